@@ -1,12 +1,14 @@
 use drone_macros_core::parse_error;
 use if_chain::if_chain;
+use inflector::Inflector;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::{format_ident, quote};
 use syn::{
     parenthesized,
     parse::{Parse, ParseStream, Result},
-    parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt, LitStr, PathArguments, Token,
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitInt, LitStr, PathArguments,
+    Token,
 };
 
 #[derive(Default)]
@@ -14,6 +16,10 @@ struct Input {
     fields: Vec<Field>,
 }
 
+struct Flags {
+    ident: Ident,
+}
+
 struct Field {
     ident: Ident,
     mode: Mode,
@@ -42,6 +48,15 @@ impl Parse for Input {
     }
 }
 
+impl Parse for Flags {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        let ident = content.parse()?;
+        Ok(Self { ident })
+    }
+}
+
 impl Parse for Field {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
         let ident = input.parse()?;
@@ -99,23 +114,21 @@ impl Mode {
 
 #[allow(clippy::too_many_lines)]
 pub fn proc_macro_derive(input: TokenStream) -> TokenStream {
-    let DeriveInput { attrs, ident, data, .. } = parse_macro_input!(input);
-    let bitfield = attrs.into_iter().find(|attr| {
-        if_chain! {
-            if attr.path.leading_colon.is_none();
-            if attr.path.segments.len() <= 1;
-            if let Some(x) = attr.path.segments.iter().next();
-            if let PathArguments::None = x.arguments;
-            then { x.ident == "bitfield" } else { false }
-        }
-    });
-    let Input { fields } = match bitfield {
+    let DeriveInput { attrs, vis, ident, data, .. } = parse_macro_input!(input);
+    let Input { fields } = match find_attr(&attrs, "bitfield") {
         Some(attr) => {
-            let input = attr.tokens.into();
+            let input = attr.tokens.clone().into();
             parse_macro_input!(input)
         }
         None => Input::default(),
     };
+    let flags = match find_attr(&attrs, "bitfield_flags") {
+        Some(attr) => {
+            let input = attr.tokens.clone().into();
+            Some(parse_macro_input!(input as Flags))
+        }
+        None => None,
+    };
     let bits = if_chain! {
         if let Data::Struct(x) = data;
         if let Fields::Unnamed(x) = x.fields;
@@ -128,6 +141,55 @@ pub fn proc_macro_derive(input: TokenStream) -> TokenStream {
         }
     };
 
+    let flags_tokens = flags.map(|flags| {
+        let flags = flags.ident;
+        let flag_fields = fields
+            .iter()
+            .filter(|field| {
+                field.mode.is_read()
+                    && field.width.as_ref().map_or(true, |width| width.base10_digits() == "1")
+            })
+            .collect::<Vec<_>>();
+        let variants = flag_fields
+            .iter()
+            .map(|field| format_ident!("{}", field.ident.to_string().to_pascal_case()))
+            .collect::<Vec<_>>();
+        let offsets = flag_fields.iter().map(|field| &field.offset).collect::<Vec<_>>();
+        let docs = flag_fields
+            .iter()
+            .map(|field| field.doc.as_ref().map(|doc| quote!(#[doc = #doc])))
+            .collect::<Vec<_>>();
+        let flags_doc = format!("Readable single-bit fields of [`{}`].", ident);
+        quote! {
+            #[doc = #flags_doc]
+            #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+            #vis enum #flags {
+                #(
+                    #docs
+                    #variants,
+                )*
+            }
+
+            impl #ident {
+                /// Returns an iterator over the set single-bit fields in the
+                /// order of declaration.
+                #[allow(clippy::unnecessary_cast)]
+                #[inline]
+                #vis fn iter_set(&self) -> impl ::core::iter::Iterator<Item = #flags> {
+                    const FLAGS: &[(#flags, #bits)] =
+                        &[#((#flags::#variants, #offsets as #bits)),*];
+                    let value = *self;
+                    FLAGS
+                        .iter()
+                        .filter(move |&&(_, offset)| unsafe {
+                            ::drone_core::bitfield::Bitfield::read_bit(&value, offset)
+                        })
+                        .map(|&(flag, _)| flag)
+                }
+            }
+        }
+    });
+
     let field_tokens = fields
         .into_iter()
         .flat_map(|field| {
@@ -248,9 +310,47 @@ pub fn proc_macro_derive(input: TokenStream) -> TokenStream {
             }
         }
 
+        impl ::drone_core::bitfield::Packed for #ident {
+            const SIZE: usize = <#bits as ::drone_core::bitfield::Packed>::SIZE;
+
+            #[inline]
+            fn write_le_bytes(&self, bytes: &mut [u8]) {
+                ::drone_core::bitfield::Packed::write_le_bytes(&self.0, bytes);
+            }
+
+            #[inline]
+            fn write_be_bytes(&self, bytes: &mut [u8]) {
+                ::drone_core::bitfield::Packed::write_be_bytes(&self.0, bytes);
+            }
+
+            #[inline]
+            fn read_le_bytes(bytes: &[u8]) -> Self {
+                Self(::drone_core::bitfield::Packed::read_le_bytes(bytes))
+            }
+
+            #[inline]
+            fn read_be_bytes(bytes: &[u8]) -> Self {
+                Self(::drone_core::bitfield::Packed::read_be_bytes(bytes))
+            }
+        }
+
         impl #ident {
             #(#field_tokens)*
         }
+
+        #flags_tokens
     };
     expanded.into()
 }
+
+pub(crate) fn find_attr<'a>(attrs: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attrs.iter().find(|attr| {
+        if_chain! {
+            if attr.path.leading_colon.is_none();
+            if attr.path.segments.len() <= 1;
+            if let Some(x) = attr.path.segments.iter().next();
+            if let PathArguments::None = x.arguments;
+            then { x.ident == name } else { false }
+        }
+    })
+}
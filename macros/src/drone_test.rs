@@ -0,0 +1,59 @@
+use drone_macros_core::parse_ident;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    parse_macro_input, Error, ItemFn,
+};
+
+struct Args {
+    ignore: bool,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let ignore = if input.is_empty() {
+            false
+        } else {
+            parse_ident!(input, "ignore");
+            true
+        };
+        Ok(Self { ignore })
+    }
+}
+
+pub fn proc_macro_attribute(args: TokenStream, item: TokenStream) -> TokenStream {
+    let Args { ignore } = parse_macro_input!(args);
+    let item = parse_macro_input!(item as ItemFn);
+    let ident = &item.sig.ident;
+    if !item.sig.inputs.is_empty() || !item.sig.generics.params.is_empty() {
+        return Error::new_spanned(&item.sig, "test functions must not have arguments")
+            .to_compile_error()
+            .into();
+    }
+    let run = if item.sig.asyncness.is_some() {
+        quote! {
+            ::drone_core::testing::TestFn::Async(|| ::drone_core::testing::box_async(#ident()))
+        }
+    } else {
+        quote! {
+            ::drone_core::testing::TestFn::Sync(|| {
+                ::drone_core::testing::TestResult::into_outcome(#ident())
+            })
+        }
+    };
+    let expanded = quote! {
+        #item
+
+        const _: () = {
+            #[used]
+            #[link_section = ".drone_tests"]
+            static TEST: ::drone_core::testing::Test = ::drone_core::testing::Test {
+                name: ::core::concat!(::core::module_path!(), "::", ::core::stringify!(#ident)),
+                ignore: #ignore,
+                run: #run,
+            };
+        };
+    };
+    expanded.into()
+}
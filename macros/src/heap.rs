@@ -4,15 +4,23 @@ use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
 use syn::{
+    braced,
     parse::{Parse, ParseStream, Result},
-    parse_macro_input, Attribute, Ident, LitBool, LitInt, Token, Visibility,
+    parse_macro_input, Attribute, Error, Ident, LitBool, LitInt, Token, Visibility,
 };
 
+/// Block sizes must keep every block word-aligned.
+const BLOCK_ALIGN: u32 = 4;
+
 struct Input {
     config: Ident,
     metadata: Metadata,
     trace_port: Option<LitInt>,
     global: Option<LitBool>,
+    dma: Option<LitBool>,
+    tags: Option<LitBool>,
+    statistics: Option<Statistics>,
+    placement: Option<Placement>,
 }
 
 struct Metadata {
@@ -21,12 +29,25 @@ struct Metadata {
     ident: Ident,
 }
 
+enum Statistics {
+    Enabled,
+    Disabled,
+    Debug,
+}
+
+/// Pairs of a block size and the origin address of its pool.
+struct Placement(Vec<(u32, u32)>);
+
 impl Parse for Input {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
         let mut config = None;
         let mut metadata = None;
         let mut trace_port = None;
         let mut global = None;
+        let mut dma = None;
+        let mut tags = None;
+        let mut statistics = None;
+        let mut placement = None;
         while !input.is_empty() {
             let attrs = input.call(Attribute::parse_outer)?;
             let ident = input.parse::<Ident>()?;
@@ -55,6 +76,30 @@ impl Parse for Input {
                 } else {
                     return Err(input.error("multiple `global` specifications"));
                 }
+            } else if attrs.is_empty() && ident == "dma" {
+                if dma.is_none() {
+                    dma = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `dma` specifications"));
+                }
+            } else if attrs.is_empty() && ident == "tags" {
+                if tags.is_none() {
+                    tags = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `tags` specifications"));
+                }
+            } else if attrs.is_empty() && ident == "statistics" {
+                if statistics.is_none() {
+                    statistics = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `statistics` specifications"));
+                }
+            } else if attrs.is_empty() && ident == "placement" {
+                if placement.is_none() {
+                    placement = Some(input.parse()?);
+                } else {
+                    return Err(input.error("multiple `placement` specifications"));
+                }
             } else {
                 return Err(input.error(format!("unknown key: `{}`", ident)));
             }
@@ -67,10 +112,70 @@ impl Parse for Input {
             metadata: metadata.ok_or_else(|| input.error("missing `metadata` specification"))?,
             trace_port,
             global,
+            dma,
+            tags,
+            statistics,
+            placement,
         })
     }
 }
 
+impl Parse for Statistics {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        if input.peek(LitBool) {
+            let LitBool { value, .. } = input.parse()?;
+            return Ok(if value { Self::Enabled } else { Self::Disabled });
+        }
+        let ident = input.parse::<Ident>()?;
+        if ident == "debug" {
+            Ok(Self::Debug)
+        } else {
+            Err(Error::new(ident.span(), "expected `true`, `false`, or `debug`"))
+        }
+    }
+}
+
+impl Parse for Placement {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let content;
+        braced!(content in input);
+        let mut entries = Vec::new();
+        while !content.is_empty() {
+            let block = content.parse::<LitInt>()?;
+            content.parse::<Token![=>]>()?;
+            let origin = content.parse::<LitInt>()?;
+            let block = block.base10_parse::<u32>()?;
+            if entries.iter().any(|&(other, _)| other == block) {
+                return Err(content.error(format!("multiple placements of block size {}", block)));
+            }
+            let origin_value = origin.base10_parse::<u32>()?;
+            if origin_value % BLOCK_ALIGN != 0 {
+                return Err(Error::new(
+                    origin.span(),
+                    format!("pool origin is not a multiple of {}", BLOCK_ALIGN),
+                ));
+            }
+            entries.push((block, origin_value));
+            if !content.is_empty() {
+                content.parse::<Token![;]>()?;
+            }
+        }
+        Ok(Self(entries))
+    }
+}
+
+impl Statistics {
+    /// Returns the `cfg` attribute for the counters, or `None` if the counters
+    /// are disabled.
+    fn cfg(&self) -> Option<TokenStream2> {
+        match self {
+            Self::Enabled => Some(quote!()),
+            Self::Disabled => None,
+            Self::Debug => Some(quote!(#[cfg(debug_assertions)])),
+        }
+    }
+}
+
 impl Metadata {
     fn parse(input: ParseStream<'_>, attrs: Vec<Attribute>) -> Result<Self> {
         let vis = input.parse()?;
@@ -81,21 +186,42 @@ impl Metadata {
 
 #[allow(clippy::too_many_lines)]
 pub fn proc_macro(input: TokenStream) -> TokenStream {
-    let Input { config: heap_config, metadata, trace_port, global } = parse_macro_input!(input);
+    let Input {
+        config: heap_config,
+        metadata,
+        trace_port,
+        global,
+        dma,
+        tags,
+        statistics,
+        placement,
+    } = parse_macro_input!(input);
+    let placement = placement.map_or_else(Vec::new, |Placement(entries)| entries);
     let Metadata { attrs: metadata_attrs, vis: metadata_vis, ident: metadata_ident } = &metadata;
     let mut config = match Config::read_from_cargo_manifest_dir() {
         Ok(config) => config,
         Err(err) => parse_error!("{}: {}", drone_config::CONFIG_NAME, err),
     };
 
-    let (mut pointer, pools) = if heap_config == "main" {
+    let (mut pointer, size, pools) = if heap_config == "main" {
         (
             config.memory.ram.origin + config.memory.ram.size - config.heap.main.size,
+            config.heap.main.size,
             &mut config.heap.main.pools,
         )
     } else {
         match config.heap.extra.get_mut(&heap_config.to_string()) {
-            Some(heap) => (heap.origin, &mut heap.block.pools),
+            Some(heap) => {
+                if heap.origin % BLOCK_ALIGN != 0 {
+                    parse_error!(
+                        "`heap.{}` origin {:#x} is not a multiple of {}",
+                        heap_config,
+                        heap.origin,
+                        BLOCK_ALIGN
+                    );
+                }
+                (heap.origin, heap.block.size, &mut heap.block.pools)
+            }
             None => {
                 parse_error!(
                     "Missing `{}` heap configuration in {}",
@@ -107,19 +233,122 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
     };
 
     pools.sort_by_key(|pool| pool.block);
+    for &(block, _) in &placement {
+        if pools.iter().all(|pool| pool.block != block) {
+            parse_error!("`heap.{}` has no pool with block size {} to place", heap_config, block);
+        }
+    }
     let mut pools_tokens = Vec::new();
+    let mut counters_tokens = Vec::new();
+    let origin = pointer;
+    let mut prev_block = 0;
+    let mut prev_edge = 0;
+    let mut placed = Vec::new();
+    let mut tag_offsets = Vec::new();
+    let mut blocks = 0;
     for pool in pools.iter() {
+        if pool.block == 0 || pool.block % BLOCK_ALIGN != 0 {
+            parse_error!(
+                "`heap.{}` block size {} is not a multiple of {}",
+                heap_config,
+                pool.block,
+                BLOCK_ALIGN
+            );
+        }
+        if pool.block == prev_block {
+            parse_error!(
+                "`heap.{}` has multiple pools with block size {}",
+                heap_config,
+                pool.block
+            );
+        }
+        prev_block = pool.block;
+        let pool_origin = match placement.iter().find(|&&(block, _)| block == pool.block) {
+            Some(&(_, pool_origin)) => {
+                placed.push(true);
+                pool_origin
+            }
+            None => {
+                placed.push(false);
+                let pool_origin = pointer;
+                pointer += pool.block * pool.capacity;
+                pool_origin
+            }
+        };
+        if pool_origin < prev_edge {
+            parse_error!(
+                "`heap.{}` pool with block size {} at {:#x} overlaps or precedes a pool with \
+                 smaller blocks; pools must be placed in the order of block sizes",
+                heap_config,
+                pool.block,
+                pool_origin
+            );
+        }
+        prev_edge = pool_origin + pool.block * pool.capacity;
         let block = LitInt::new(&pool.block.to_string(), Span::call_site());
         let capacity = LitInt::new(&pool.capacity.to_string(), Span::call_site());
-        let address = LitInt::new(&pointer.to_string(), Span::call_site());
+        let address = LitInt::new(&pool_origin.to_string(), Span::call_site());
         pools_tokens.push(quote! {
             ::drone_core::heap::Pool::new(#address, #block, #capacity)
         });
-        pointer += pool.block * pool.capacity;
+        counters_tokens.push(quote! {
+            ::drone_core::heap::PoolCounters::new(#capacity)
+        });
+        tag_offsets.push(blocks);
+        blocks += pool.capacity as usize;
+    }
+    if pointer - origin != size {
+        parse_error!(
+            "`heap.{}` size is {} bytes, but the pools take {} bytes",
+            heap_config,
+            size,
+            pointer - origin
+        );
     }
     let pools_len = pools.len();
 
-    let drone_allocator = def_drone_allocator(&metadata, trace_port, pools_len);
+    let tags = tags.map_or(false, |LitBool { value, .. }| value);
+    let (tags_field, tags_init) = if tags {
+        (quote!(tags: [::core::sync::atomic::AtomicU8; #blocks],), quote! {
+            tags: {
+                #[allow(clippy::declare_interior_mutable_const)]
+                const UNTAGGED: ::core::sync::atomic::AtomicU8 =
+                    ::core::sync::atomic::AtomicU8::new(::drone_core::heap::UNTAGGED);
+                [UNTAGGED; #blocks]
+            },
+        })
+    } else {
+        (quote!(), quote!())
+    };
+    let tag_offsets = if tags { Some(tag_offsets) } else { None };
+
+    let statistics = statistics.unwrap_or(Statistics::Enabled);
+    let (counters_field, counters_init) = if let Some(cfg) = statistics.cfg() {
+        (
+            quote! {
+                #cfg
+                counters: [::drone_core::heap::PoolCounters; #pools_len],
+            },
+            quote! {
+                #cfg
+                counters: [#(#counters_tokens),*],
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    let placed = if placed.contains(&true) { Some(placed) } else { None };
+
+    let drone_allocator = def_drone_allocator(
+        &metadata,
+        trace_port,
+        dma,
+        tag_offsets,
+        placed,
+        &statistics,
+        pools_len,
+    );
     let core_allocator = def_core_allocator(&metadata);
     let global_alloc = match global {
         Some(LitBool { value, .. }) if value => Some(def_global_alloc(&metadata)),
@@ -130,6 +359,8 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
         #(#metadata_attrs)*
         #metadata_vis struct #metadata_ident {
             pools: [::drone_core::heap::Pool; #pools_len],
+            #tags_field
+            #counters_field
         }
 
         impl #metadata_ident {
@@ -137,6 +368,8 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
             pub const fn new() -> Self {
                 Self {
                     pools: [#(#pools_tokens),*],
+                    #tags_init
+                    #counters_init
                 }
             }
         }
@@ -151,6 +384,10 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
 fn def_drone_allocator(
     metadata: &Metadata,
     trace_port: Option<LitInt>,
+    dma: Option<LitBool>,
+    tag_offsets: Option<Vec<usize>>,
+    placed: Option<Vec<bool>>,
+    statistics: &Statistics,
     pools_len: usize,
 ) -> TokenStream2 {
     let Metadata { ident: metadata_ident, .. } = metadata;
@@ -159,9 +396,44 @@ fn def_drone_allocator(
     } else {
         quote!(::core::option::Option::None)
     };
+    let dma = dma.map_or(false, |LitBool { value, .. }| value);
+    let placed = placed.map(|placed| {
+        quote! {
+            const PLACED: [bool; #pools_len] = [#(#placed),*];
+        }
+    });
+    let get_tag_unchecked = tag_offsets.map(|tag_offsets| {
+        quote! {
+            #[inline]
+            unsafe fn get_tag_unchecked(
+                &self,
+                pool_idx: usize,
+                block_idx: usize,
+            ) -> ::core::option::Option<&::core::sync::atomic::AtomicU8> {
+                const TAG_OFFSETS: [usize; #pools_len] = [#(#tag_offsets),*];
+                ::core::option::Option::Some(
+                    self.tags.get_unchecked(TAG_OFFSETS.get_unchecked(pool_idx) + block_idx),
+                )
+            }
+        }
+    });
+    let get_counters_unchecked = statistics.cfg().map(|cfg| {
+        quote! {
+            #cfg
+            #[inline]
+            unsafe fn get_counters_unchecked(
+                &self,
+                pool_idx: usize,
+            ) -> ::core::option::Option<&::drone_core::heap::PoolCounters> {
+                ::core::option::Option::Some(self.counters.get_unchecked(pool_idx))
+            }
+        }
+    });
     quote! {
         impl ::drone_core::heap::Allocator<#pools_len> for #metadata_ident {
             const TRACE_PORT: ::core::option::Option<u8> = #trace_port;
+            const DMA: bool = #dma;
+            #placed
 
             #[inline]
             unsafe fn get_pool_unchecked<I>(&self, index: I) -> &I::Output
@@ -170,6 +442,9 @@ fn def_drone_allocator(
             {
                 self.pools.get_unchecked(index)
             }
+
+            #get_tag_unchecked
+            #get_counters_unchecked
         }
     }
 }
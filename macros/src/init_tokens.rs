@@ -0,0 +1,158 @@
+use inflector::Inflector;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Attribute, Ident, Token, Visibility,
+};
+
+const TOKEN_SUFFIX: &str = "Token";
+
+struct Input {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    group: Group,
+}
+
+struct Group {
+    ident: Ident,
+    nodes: Vec<Node>,
+}
+
+enum Node {
+    Leaf { name: String },
+    Group { attrs: Vec<Attribute>, group: Group },
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let group = input.parse()?;
+        Ok(Self { attrs, vis, group })
+    }
+}
+
+impl Parse for Group {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let ident = input.parse()?;
+        let content;
+        braced!(content in input);
+        let nodes =
+            content.call(Punctuated::<_, Token![,]>::parse_terminated)?.into_iter().collect();
+        Ok(Self { ident, nodes })
+    }
+}
+
+impl Parse for Node {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        if input.peek2(syn::token::Brace) {
+            let group = input.parse()?;
+            return Ok(Self::Group { attrs, group });
+        }
+        if !attrs.is_empty() {
+            return Err(input.error("Attributes are allowed only for groups"));
+        }
+        let mut name = input.parse::<Ident>()?.to_string();
+        if name.ends_with(TOKEN_SUFFIX) {
+            name.truncate(name.len() - TOKEN_SUFFIX.len());
+        } else {
+            return Err(
+                input.error(format!("Expected an ident which ends with `{}`", TOKEN_SUFFIX))
+            );
+        }
+        Ok(Self::Leaf { name })
+    }
+}
+
+pub fn proc_macro(input: TokenStream) -> TokenStream {
+    let Input { attrs, vis, group } = parse_macro_input!(input);
+    let wrapper = format_ident!("__{}_init_tokens", group.ident.to_string().to_snake_case());
+    let mut defs = Vec::new();
+    let mut idents = Vec::new();
+    def_group(&attrs, &group, &mut defs, &mut idents);
+    let expanded = quote! {
+        mod #wrapper {
+            use super::*;
+
+            #(#defs)*
+        }
+
+        #vis use #wrapper::{#(#idents),*};
+    };
+    expanded.into()
+}
+
+fn def_group(
+    attrs: &[Attribute],
+    group: &Group,
+    defs: &mut Vec<TokenStream2>,
+    idents: &mut Vec<Ident>,
+) {
+    let Group { ident, nodes } = group;
+    let mut def_tokens = Vec::new();
+    let mut ctor_tokens = Vec::new();
+    let mut field_idents = Vec::new();
+    let mut field_types = Vec::new();
+    for node in nodes {
+        let (field_ident, struct_ident) = match node {
+            Node::Leaf { name } => {
+                (format_ident!("{}", name.to_snake_case()), format_ident!("{}Token", name))
+            }
+            Node::Group { attrs, group } => {
+                def_group(attrs, group, defs, idents);
+                (format_ident!("{}", group.ident.to_string().to_snake_case()), group.ident.clone())
+            }
+        };
+        def_tokens.push(quote! {
+            #[allow(missing_docs)]
+            pub #field_ident: #struct_ident,
+        });
+        ctor_tokens.push(quote! {
+            #field_ident: ::drone_core::token::Token::take(),
+        });
+        field_idents.push(field_ident);
+        field_types.push(struct_ident);
+    }
+    defs.push(quote! {
+        #(#attrs)*
+        pub struct #ident {
+            #(#def_tokens)*
+            __priv: (),
+        }
+
+        unsafe impl ::drone_core::token::Token for #ident {
+            #[inline]
+            unsafe fn take() -> Self {
+                Self {
+                    #(#ctor_tokens)*
+                    __priv: (),
+                }
+            }
+        }
+
+        impl ::drone_core::token::Split for #ident {
+            type Parts = (#(#field_types,)*);
+
+            #[inline]
+            fn split(self) -> Self::Parts {
+                (#(self.#field_idents,)*)
+            }
+
+            #[inline]
+            fn join((#(#field_idents,)*): Self::Parts) -> Self {
+                Self {
+                    #(#field_idents,)*
+                    __priv: (),
+                }
+            }
+        }
+    });
+    idents.push(ident.clone());
+}
@@ -10,15 +10,20 @@ extern crate proc_macro;
 
 mod bitfield;
 mod config_override;
+mod drone_test;
 mod heap;
+mod init_tokens;
 mod log_baud_rate;
+mod packed;
 mod periph;
 mod periph_map;
 mod periph_singular;
+mod plugin_registry;
 mod reg;
 mod reg_assert_taken;
 mod reg_tokens;
 mod reg_tokens_inner;
+mod register;
 mod simple_token;
 mod simple_tokens;
 mod static_tokens;
@@ -27,21 +32,36 @@ mod thr_soft;
 
 use proc_macro::TokenStream;
 
-#[proc_macro_derive(Bitfield, attributes(bitfield))]
+#[proc_macro_derive(Bitfield, attributes(bitfield, bitfield_flags))]
 pub fn derive_bitfield(input: TokenStream) -> TokenStream {
     bitfield::proc_macro_derive(input)
 }
 
+#[proc_macro_derive(Packed, attributes(packed))]
+pub fn derive_packed(input: TokenStream) -> TokenStream {
+    packed::proc_macro_derive(input)
+}
+
 #[proc_macro]
 pub fn config_override(input: TokenStream) -> TokenStream {
     config_override::proc_macro(input)
 }
 
+#[proc_macro_attribute]
+pub fn drone_test(args: TokenStream, item: TokenStream) -> TokenStream {
+    drone_test::proc_macro_attribute(args, item)
+}
+
 #[proc_macro]
 pub fn heap(input: TokenStream) -> TokenStream {
     heap::proc_macro(input)
 }
 
+#[proc_macro]
+pub fn unsafe_init_tokens(input: TokenStream) -> TokenStream {
+    init_tokens::proc_macro(input)
+}
+
 #[proc_macro]
 pub fn log_baud_rate(input: TokenStream) -> TokenStream {
     log_baud_rate::proc_macro(input)
@@ -62,6 +82,11 @@ pub fn periph_singular(input: TokenStream) -> TokenStream {
     periph_singular::proc_macro(input)
 }
 
+#[proc_macro]
+pub fn plugin_registry(input: TokenStream) -> TokenStream {
+    plugin_registry::proc_macro(input)
+}
+
 #[proc_macro]
 pub fn reg(input: TokenStream) -> TokenStream {
     reg::proc_macro(input)
@@ -82,6 +107,11 @@ pub fn reg_tokens_inner(input: TokenStream) -> TokenStream {
     reg_tokens_inner::proc_macro(input)
 }
 
+#[proc_macro_attribute]
+pub fn register(args: TokenStream, item: TokenStream) -> TokenStream {
+    register::proc_macro_attribute(args, item)
+}
+
 #[proc_macro]
 pub fn simple_token(input: TokenStream) -> TokenStream {
     simple_token::proc_macro(input)
@@ -0,0 +1,157 @@
+use crate::bitfield::find_attr;
+use drone_macros_core::parse_error;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream, Result},
+    parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt, Member, Token,
+};
+
+struct Input {
+    size: LitInt,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        let ident = content.parse::<Ident>()?;
+        if ident != "size" {
+            return Err(content.error(format!("Unknown key: `{}`", ident)));
+        }
+        content.parse::<Token![=]>()?;
+        let size = content.parse()?;
+        Ok(Self { size })
+    }
+}
+
+pub fn proc_macro_derive(input: TokenStream) -> TokenStream {
+    let DeriveInput { attrs, vis, ident, generics, data } = parse_macro_input!(input);
+    let size = match find_attr(&attrs, "packed") {
+        Some(attr) => {
+            let input = attr.tokens.clone().into();
+            Some(parse_macro_input!(input as Input).size)
+        }
+        None => None,
+    };
+    if !generics.params.is_empty() {
+        parse_error!("Packed can't be derived for a generic struct");
+    }
+    let fields = match data {
+        Data::Struct(x) => match x.fields {
+            Fields::Named(x) => x.named,
+            Fields::Unnamed(x) => x.unnamed,
+            Fields::Unit => parse_error!("Packed can't be derived for a unit struct"),
+        },
+        _ => parse_error!("Packed can be derived only from a struct"),
+    };
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| field.ident.clone().map_or_else(|| Member::from(i), Member::Named))
+        .collect::<Vec<_>>();
+    let sizes = fields
+        .iter()
+        .map(|field| {
+            let ty = &field.ty;
+            quote!(<#ty as ::drone_core::bitfield::Packed>::SIZE)
+        })
+        .collect::<Vec<_>>();
+    let offsets = (0..sizes.len())
+        .map(|i| &sizes[..i])
+        .map(|sizes| quote!(0 #(+ #sizes)*))
+        .collect::<Vec<_>>();
+    let size_check = size.map(|size| {
+        quote! {
+            const _: [(); #size] = [(); <#ident as ::drone_core::bitfield::Packed>::SIZE];
+        }
+    });
+
+    let expanded = quote! {
+        impl ::drone_core::bitfield::Packed for #ident {
+            const SIZE: usize = 0 #(+ #sizes)*;
+
+            #[inline]
+            fn write_le_bytes(&self, bytes: &mut [u8]) {
+                #(
+                    ::drone_core::bitfield::Packed::write_le_bytes(
+                        &self.#members,
+                        &mut bytes[#offsets..],
+                    );
+                )*
+            }
+
+            #[inline]
+            fn write_be_bytes(&self, bytes: &mut [u8]) {
+                #(
+                    ::drone_core::bitfield::Packed::write_be_bytes(
+                        &self.#members,
+                        &mut bytes[#offsets..],
+                    );
+                )*
+            }
+
+            #[inline]
+            fn read_le_bytes(bytes: &[u8]) -> Self {
+                Self {
+                    #(
+                        #members: ::drone_core::bitfield::Packed::read_le_bytes(
+                            &bytes[#offsets..],
+                        ),
+                    )*
+                }
+            }
+
+            #[inline]
+            fn read_be_bytes(bytes: &[u8]) -> Self {
+                Self {
+                    #(
+                        #members: ::drone_core::bitfield::Packed::read_be_bytes(
+                            &bytes[#offsets..],
+                        ),
+                    )*
+                }
+            }
+        }
+
+        impl #ident {
+            /// Returns the byte representation in little-endian byte order.
+            #[inline]
+            #vis fn to_le_bytes(&self) -> [u8; <#ident as ::drone_core::bitfield::Packed>::SIZE] {
+                let mut bytes = [0; <#ident as ::drone_core::bitfield::Packed>::SIZE];
+                ::drone_core::bitfield::Packed::write_le_bytes(self, &mut bytes);
+                bytes
+            }
+
+            /// Returns the byte representation in big-endian byte order.
+            #[inline]
+            #vis fn to_be_bytes(&self) -> [u8; <#ident as ::drone_core::bitfield::Packed>::SIZE] {
+                let mut bytes = [0; <#ident as ::drone_core::bitfield::Packed>::SIZE];
+                ::drone_core::bitfield::Packed::write_be_bytes(self, &mut bytes);
+                bytes
+            }
+
+            /// Creates a value from its byte representation in little-endian
+            /// byte order.
+            #[inline]
+            #vis fn from_le_bytes(
+                bytes: [u8; <#ident as ::drone_core::bitfield::Packed>::SIZE],
+            ) -> Self {
+                ::drone_core::bitfield::Packed::read_le_bytes(&bytes)
+            }
+
+            /// Creates a value from its byte representation in big-endian byte
+            /// order.
+            #[inline]
+            #vis fn from_be_bytes(
+                bytes: [u8; <#ident as ::drone_core::bitfield::Packed>::SIZE],
+            ) -> Self {
+                ::drone_core::bitfield::Packed::read_be_bytes(&bytes)
+            }
+        }
+
+        #size_check
+    };
+    expanded.into()
+}
@@ -8,6 +8,8 @@ use syn::{
     parse_macro_input, Attribute, Ident, LitInt, Token, TraitItem,
 };
 
+const DUMP: bool = cfg!(feature = "periph-dump");
+
 struct Input {
     trait_attrs: Vec<Attribute>,
     trait_ident: Ident,
@@ -136,6 +138,8 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
     let mut periph_bounds = Vec::new();
     let mut periph_fields = Vec::new();
     let mut traits_export = Vec::new();
+    let mut dump_tokens = Vec::new();
+    let mut view_tokens = Vec::new();
     let marker_bounds = quote! {
         ::core::marker::Sized
             + ::core::marker::Send
@@ -159,6 +163,10 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                     (reg_snk.clone(), reg_psc.clone())
                 };
                 let block_var_snk = format_ident!("{}_{}", block_snk, var_snk);
+                let reg_present = format_ident!(
+                    "{}_PRESENT",
+                    block_var_snk.to_string().to_screaming_snake_case()
+                );
                 let val_ty = format_ident!("u{}", size);
                 let reg_trait = format_ident!("{}{}", block_psc, var_psc);
                 let reg_trait_opt = format_ident!("{}{}Opt", block_psc, var_psc);
@@ -174,6 +182,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                 let s_fields = format_ident!("S{}{}Fields", block_psc, var_psc);
                 let c_fields = format_ident!("C{}{}Fields", block_psc, var_psc);
                 let reg_attrs = reg_features.attrs();
+                let reg_name = format!("{}_{}", block_ident, reg_ident);
                 let mut u_traits = Vec::new();
                 let mut s_traits = Vec::new();
                 let mut c_traits = Vec::new();
@@ -195,6 +204,12 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                 if variants.len() > 1 && reg_shared {
                     parse_error!("`Shared` can't be used with multiple variants");
                 }
+                let reg_dump = DUMP && traits.iter().any(|ident| ident == "RReg");
+                let s_reg_opt_bounds = if reg_dump {
+                    quote!(#marker_bounds + ::drone_core::periph::RegDump)
+                } else {
+                    marker_bounds.clone()
+                };
                 if reg_option && !variants.iter().all(|v| v.traits.iter().any(|t| t == "Option")) {
                     parse_error!("`Option` should be defined for all variants");
                 }
@@ -211,6 +226,10 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                     let field_ident = format_ident!("{}", unkeywordize(&field_snk));
                     let block_reg_field_snk =
                         format_ident!("{}_{}_{}", block_snk, var_snk, field_snk);
+                    let field_present = format_ident!(
+                        "{}_PRESENT",
+                        block_reg_field_snk.to_string().to_screaming_snake_case()
+                    );
                     let field_trait = format_ident!("{}{}{}", block_psc, var_psc, field_psc);
                     let field_trait_opt = format_ident!("{}{}{}Opt", block_psc, var_psc, field_psc);
                     let field_trait_ext = format_ident!("{}{}{}Ext", block_psc, var_psc, field_psc);
@@ -279,6 +298,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                     type #u_field_opt: #marker_bounds;
                                     type #s_field_opt: #marker_bounds;
                                     type #c_field_opt: #marker_bounds;
+                                    const #field_present: bool;
                                 }
                             });
                             tokens.push(quote! {
@@ -314,6 +334,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                         type #u_field_opt: #marker_bounds;
                                         type #s_field_opt: #marker_bounds;
                                         type #c_field_opt: #marker_bounds;
+                                        const #field_present: bool;
                                     }
                                 });
                                 tokens.push(quote! {
@@ -347,6 +368,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                         type #u_field_opt: #marker_bounds;
                                         type #s_field_opt: #marker_bounds;
                                         type #c_field_opt: #marker_bounds;
+                                        const #field_present: bool;
                                     }
                                 });
                                 tokens.push(quote! {
@@ -502,14 +524,30 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                             #reg_attrs
                             pub #block_var_snk: T::#s_reg_opt,
                         });
+                        if reg_dump {
+                            dump_tokens.push(quote! {
+                                #reg_attrs
+                                ::drone_core::periph::RegDump::dump(
+                                    &self.#block_var_snk,
+                                    #reg_name,
+                                    port,
+                                );
+                            });
+                            view_tokens.push((reg_attrs.clone(), quote! {
+                                unsafe {
+                                    <T::#s_reg_opt as ::drone_core::periph::RegDump>::view(#reg_name)
+                                }
+                            }));
+                        }
                     }
                     tokens.push(quote! {
                         #reg_attrs
                         #[allow(missing_docs)]
                         pub trait #reg_trait_opt {
                             type #u_reg_opt: #marker_bounds;
-                            type #s_reg_opt: #marker_bounds;
+                            type #s_reg_opt: #s_reg_opt_bounds;
                             type #c_reg_opt: #marker_bounds;
+                            const #reg_present: bool;
                         }
                     });
                     tokens.push(quote! {
@@ -649,6 +687,21 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                             #reg_attrs
                             pub #block_var_snk: T::#s_reg,
                         });
+                        if reg_dump {
+                            dump_tokens.push(quote! {
+                                #reg_attrs
+                                ::drone_core::periph::RegDump::dump(
+                                    &self.#block_var_snk,
+                                    #reg_name,
+                                    port,
+                                );
+                            });
+                            view_tokens.push((reg_attrs.clone(), quote! {
+                                unsafe {
+                                    <T::#s_reg as ::drone_core::periph::RegDump>::view(#reg_name)
+                                }
+                            }));
+                        }
                     }
                     for (variant_j, variant) in variants.iter().enumerate() {
                         if variant_i == variant_j {
@@ -779,6 +832,47 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
             pub _marker: ::core::marker::PhantomData<T>,
         });
     }
+    if DUMP {
+        let view_len = view_tokens.len();
+        let view_tokens = view_tokens.into_iter().enumerate().map(|(i, (attrs, view))| {
+            quote! {
+                #attrs
+                view[#i] = #view;
+            }
+        });
+        tokens.push(quote! {
+            impl<T: #trait_ident> #struct_ident<T> {
+                /// Writes the current values of all readable registers owned by
+                /// the peripheral to `port`.
+                #[allow(unused_variables)]
+                pub fn dump(&self, port: ::drone_core::log::Port) {
+                    #(#dump_tokens)*
+                }
+
+                /// Captures the current values of all readable registers owned
+                /// by the peripheral, without the peripheral tokens.
+                ///
+                /// Registers excluded by `cfg` attributes, absent optional
+                /// registers, and registers with read side effects are not
+                /// read.
+                ///
+                /// # Safety
+                ///
+                /// The reads bypass the token ownership. They must happen only
+                /// when the owners of the peripheral tokens can't run, like in
+                /// a panic or fault handler.
+                #[allow(unused_mut)]
+                pub unsafe fn freeze_view() -> [
+                    ::core::option::Option<::drone_core::periph::RegView>;
+                    #view_len
+                ] {
+                    let mut view = [::core::option::Option::None; #view_len];
+                    #(#view_tokens)*
+                    view
+                }
+            }
+        });
+    }
 
     let expanded = quote! {
         #(#tokens)*
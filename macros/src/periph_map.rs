@@ -18,11 +18,17 @@ struct Input {
     struct_ident: Ident,
     trait_ident: Ident,
     items: Vec<ImplItem>,
+    capabilities: Vec<Capability>,
     root_path: Path,
     macro_root_path: Option<Path>,
     blocks: Vec<Block>,
 }
 
+struct Capability {
+    path: Path,
+    items: Vec<ImplItem>,
+}
+
 struct Block {
     ident: Ident,
     path: Option<Ident>,
@@ -78,6 +84,24 @@ impl Parse for Input {
         while !content.is_empty() {
             items.push(content.parse()?);
         }
+        let mut capabilities = Vec::new();
+        while input.peek(Token![impl]) {
+            input.parse::<Token![impl]>()?;
+            let path = input.parse()?;
+            input.parse::<Token![for]>()?;
+            parse_ident!(input, struct_ident);
+            let mut items = Vec::new();
+            if input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+            } else {
+                let content;
+                braced!(content in input);
+                while !content.is_empty() {
+                    items.push(content.parse()?);
+                }
+            }
+            capabilities.push(Capability { path, items });
+        }
         let root_path = input.parse()?;
         input.parse::<Token![;]>()?;
         input.parse::<Token![crate]>()?;
@@ -100,6 +124,7 @@ impl Parse for Input {
             struct_ident,
             trait_ident,
             items,
+            capabilities,
             root_path,
             macro_root_path,
             blocks,
@@ -201,6 +226,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
         struct_ident: periph_ty,
         trait_ident: periph_trait,
         items: periph_items,
+        capabilities,
         root_path,
         macro_root_path,
         blocks,
@@ -239,6 +265,10 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                     .as_ref()
                     .map(|ident| format_ident!("{}", unkeywordize(ident.to_string().as_str())));
                 let block_var_snk = format_ident!("{}_{}", block_snk, var_snk);
+                let reg_present = format_ident!(
+                    "{}_PRESENT",
+                    block_var_snk.to_string().to_screaming_snake_case()
+                );
                 let block_var_path_snk = var_path_snk
                     .as_ref()
                     .map(|var_path_snk| format_ident!("{}_{}", block_path_snk, var_path_snk));
@@ -307,6 +337,10 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                     let field_ident = format_ident!("{}", unkeywordize(field_snk.clone().as_str()));
                     let block_reg_field_snk =
                         format_ident!("{}_{}_{}", block_snk, var_snk, field_snk);
+                    let field_present = format_ident!(
+                        "{}_PRESENT",
+                        block_reg_field_snk.to_string().to_screaming_snake_case()
+                    );
                     let field_trait = format_ident!("{}{}{}", block_psc, var_psc, field_psc);
                     let field_trait_opt = format_ident!("{}{}{}Opt", block_psc, var_psc, field_psc);
                     let field_trait_ext = format_ident!("{}{}{}Ext", block_psc, var_psc, field_psc);
@@ -337,6 +371,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                     type #u_field_opt = ();
                                     type #s_field_opt = ();
                                     type #c_field_opt = ();
+                                    const #field_present: bool = false;
                                 }
                             });
                             macro_tokens.push((features, quote!(#block_reg_field_snk: ())));
@@ -347,6 +382,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                     type #u_field_opt = ();
                                     type #s_field_opt = ();
                                     type #c_field_opt = ();
+                                    const #field_present: bool = false;
                                 }
                             });
                         }
@@ -377,6 +413,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                     type #u_field_opt = #reg_root::#field_path_psc<#core_urt>;
                                     type #s_field_opt = #reg_root::#field_path_psc<#core_srt>;
                                     type #c_field_opt = #reg_root::#field_path_psc<#core_crt>;
+                                    const #field_present: bool = true;
                                 }
                             });
                             tokens.push(quote! {
@@ -412,6 +449,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                     type #u_field_opt = #reg_root::#field_path_psc<#core_urt>;
                                     type #s_field_opt = #reg_root::#field_path_psc<#core_srt>;
                                     type #c_field_opt = #reg_root::#field_path_psc<#core_crt>;
+                                    const #field_present: bool = true;
                                 }
                             });
                             tokens.push(quote! {
@@ -477,6 +515,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                             type #u_reg_opt = ();
                             type #s_reg_opt = ();
                             type #c_reg_opt = ();
+                            const #reg_present: bool = false;
                         }
                     });
                     if !reg_shared && variant_i == 0 {
@@ -502,6 +541,7 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
                                 type #u_reg_opt = #reg_root::Reg<#core_urt>;
                                 type #s_reg_opt = #reg_root::Reg<#core_srt>;
                                 type #c_reg_opt = #reg_root::Reg<#core_crt>;
+                                const #reg_present: bool = true;
                             }
                         });
                         tokens.push(quote! {
@@ -673,6 +713,13 @@ pub fn proc_macro(input: TokenStream) -> TokenStream {
             }
         });
     }
+    for Capability { path, items } in capabilities {
+        tokens.push(quote! {
+            impl #path for #periph_ty {
+                #(#items)*
+            }
+        });
+    }
 
     let expanded = quote! {
         #(#periph_ty_attrs)*
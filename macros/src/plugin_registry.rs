@@ -0,0 +1,54 @@
+use inflector::Inflector;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    parse_macro_input, Attribute, Ident, Token, Type, Visibility,
+};
+
+struct Input {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    ident: Ident,
+    ty: Type,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        input.parse::<Option<Token![;]>>()?;
+        Ok(Self { attrs, vis, ident, ty })
+    }
+}
+
+pub fn proc_macro(input: TokenStream) -> TokenStream {
+    let Input { attrs, vis, ident, ty } = parse_macro_input!(input);
+    let symbol = format!("DRONE_PLUGINS_{}", ident.to_string().to_screaming_snake_case());
+    let start = format!("{}_START", symbol);
+    let end = format!("{}_END", symbol);
+    let expanded = quote! {
+        #(#attrs)*
+        #vis struct #ident;
+
+        impl ::drone_core::inventory::PluginRegistry for #ident {
+            type Plugin = #ty;
+
+            #[inline]
+            fn plugins() -> &'static [#ty] {
+                extern "C" {
+                    #[link_name = #start]
+                    static START: ::core::cell::UnsafeCell<usize>;
+                    #[link_name = #end]
+                    static END: ::core::cell::UnsafeCell<usize>;
+                }
+                unsafe { ::drone_core::inventory::section(&START, &END) }
+            }
+        }
+    };
+    expanded.into()
+}
@@ -7,7 +7,7 @@ use std::collections::HashSet;
 use syn::{
     braced,
     parse::{Parse, ParseStream, Result},
-    parse_macro_input, Attribute, Ident, LitInt, LitStr, Token, Visibility,
+    parse_macro_input, Attribute, Error, Ident, LitBool, LitInt, LitStr, Token, Visibility,
 };
 
 struct Input {
@@ -22,8 +22,11 @@ struct Variant {
     address: LitInt,
     size: u8,
     reset: LitInt,
+    read_side_effects: bool,
+    shared: bool,
     traits: Vec<Ident>,
     fields: Vec<Field>,
+    asserts: Vec<Assert>,
 }
 
 struct Field {
@@ -32,6 +35,12 @@ struct Field {
     offset: LitInt,
     width: LitInt,
     traits: Vec<Ident>,
+    asserts: Vec<Assert>,
+}
+
+struct Assert {
+    key: Ident,
+    value: LitInt,
 }
 
 impl Parse for Input {
@@ -59,8 +68,11 @@ impl Parse for Variant {
         let mut address = None;
         let mut size = None;
         let mut reset = None;
+        let mut read_side_effects = None;
+        let mut shared = None;
         let mut traits = Vec::new();
         let mut fields = Vec::new();
+        let mut asserts = Vec::new();
         while !input2.is_empty() {
             let ident = input2.parse::<Ident>()?;
             input2.parse::<Token![=>]>()?;
@@ -82,10 +94,24 @@ impl Parse for Variant {
                 } else {
                     return Err(input2.error("multiple `reset` specifications"));
                 }
+            } else if ident == "read_side_effects" {
+                if read_side_effects.is_none() {
+                    read_side_effects = Some(input2.parse::<LitBool>()?.value);
+                } else {
+                    return Err(input2.error("multiple `read_side_effects` specifications"));
+                }
+            } else if ident == "shared" {
+                if shared.is_none() {
+                    shared = Some(input2.parse::<LitBool>()?.value);
+                } else {
+                    return Err(input2.error("multiple `shared` specifications"));
+                }
             } else if ident == "traits" {
                 traits.extend(parse_traits(&input2)?);
             } else if ident == "fields" {
                 fields.extend(Field::parse_list(&input2)?);
+            } else if ident == "assert" {
+                asserts.extend(Assert::parse_list(&input2)?);
             } else {
                 return Err(input2.error(format!("unknown key: `{}`", ident)));
             }
@@ -101,8 +127,11 @@ impl Parse for Variant {
             address: address.ok_or_else(|| input2.error("missing `address` specification"))?,
             size: size.ok_or_else(|| input2.error("missing `size` specification"))?,
             reset: reset.ok_or_else(|| input2.error("missing `reset` specification"))?,
+            read_side_effects: read_side_effects.unwrap_or(false),
+            shared: shared.unwrap_or(false),
             traits,
             fields,
+            asserts,
         })
     }
 }
@@ -132,6 +161,7 @@ impl Parse for Field {
         let mut offset = None;
         let mut width = None;
         let mut traits = Vec::new();
+        let mut asserts = Vec::new();
         while !input2.is_empty() {
             let ident = input2.parse::<Ident>()?;
             input2.parse::<Token![=>]>()?;
@@ -149,6 +179,8 @@ impl Parse for Field {
                 }
             } else if ident == "traits" {
                 traits.extend(parse_traits(&input2)?);
+            } else if ident == "assert" {
+                asserts.extend(Assert::parse_list(&input2)?);
             } else {
                 return Err(input2.error(format!("unknown key: `{}`", ident)));
             }
@@ -162,10 +194,44 @@ impl Parse for Field {
             offset: offset.ok_or_else(|| input2.error("missing `offset` specification"))?,
             width: width.ok_or_else(|| input2.error("missing `width` specification"))?,
             traits,
+            asserts,
         })
     }
 }
 
+impl Assert {
+    fn parse_list(input: ParseStream<'_>) -> Result<Vec<Self>> {
+        let mut asserts = Vec::new();
+        let input2;
+        braced!(input2 in input);
+        while !input2.is_empty() {
+            let key = input2.parse()?;
+            input2.parse::<Token![=>]>()?;
+            let value = input2.parse()?;
+            asserts.push(Self { key, value });
+            if !input2.is_empty() {
+                input2.parse::<Token![;]>()?;
+            }
+        }
+        Ok(asserts)
+    }
+
+    fn check(&self, subject: &str, actual: u128) -> Result<()> {
+        let expected = self.value.base10_parse::<u128>()?;
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(Error::new(
+                self.value.span(),
+                format!(
+                    "`{}` of {} is {:#x}, but {:#x} is asserted",
+                    self.key, subject, actual, expected
+                ),
+            ))
+        }
+    }
+}
+
 impl Variant {
     #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
     fn generate(&self) -> TokenStream2 {
@@ -175,7 +241,7 @@ impl Variant {
         let mut tokens = Vec::new();
         let mut struct_tokens = Vec::new();
         let mut ctor_tokens = Vec::new();
-        for Field { attrs, ident, offset, width, traits } in &self.fields {
+        for Field { attrs, ident, offset, width, traits, .. } in &self.fields {
             let field_snk = ident.to_string().to_snake_case();
             let mut field_psc = ident.to_string().to_pascal_case();
             if field_psc == "Val" {
@@ -344,8 +410,52 @@ impl Variant {
             let imports = imports.iter();
             quote!(use super::{#(#imports),*};)
         };
-        let Variant { attrs, vis, address, reset, .. } = self;
+        if self.traits.iter().any(|name| name == "RReg") {
+            tokens.push(quote! {
+                /// Reads the register for a debug view, without a token.
+                ///
+                /// See [`RReg::read_debug`](::drone_core::reg::RReg::read_debug).
+                ///
+                /// # Safety
+                ///
+                /// The read bypasses the token ownership. It must happen only
+                /// when the owner of the register token can't run, like in a
+                /// panic or fault handler.
+                #[inline]
+                pub unsafe fn read_debug() -> ::core::option::Option<#val_ty> {
+                    let reg = unsafe {
+                        <Reg<::drone_core::reg::tag::Crt> as ::drone_core::token::Token>::take()
+                    };
+                    ::drone_core::reg::RReg::read_debug(&reg)
+                }
+            });
+        }
+        let Variant { attrs, vis, address, reset, read_side_effects, .. } = self;
         let reg_full = self.reg_full();
+        let index_entry = if self.shared {
+            quote! {
+                #(#attrs)*
+                #[derive(Clone, Copy)]
+                pub struct Fields<#t: ::drone_core::reg::tag::RegTag> {
+                    #(#struct_tokens),*
+                }
+
+                unsafe impl<#t: ::drone_core::reg::tag::RegTag> ::drone_core::token::Token for Fields<#t> {
+                    #[inline]
+                    unsafe fn take() -> Self {
+                        Self { #(#ctor_tokens,)* }
+                    }
+                }
+
+                /// The type of the register entry in a register tokens index.
+                pub type IndexEntry<#t> = Fields<#t>;
+            }
+        } else {
+            quote! {
+                /// The type of the register entry in a register tokens index.
+                pub type IndexEntry<#t> = Reg<#t>;
+            }
+        };
 
         quote! {
             #(#attrs)*
@@ -378,6 +488,7 @@ impl Variant {
 
                     const ADDRESS: usize = #address;
                     const RESET: #val_ty = #reset;
+                    const READ_SIDE_EFFECTS: bool = #read_side_effects;
 
                     #[inline]
                     unsafe fn val_from(bits: #val_ty) -> Val {
@@ -418,11 +529,37 @@ impl Variant {
                     }
                 }
 
+                #index_entry
+
                 #(#tokens)*
             }
         }
     }
 
+    fn check(&self) -> Result<()> {
+        if self.shared && self.fields.is_empty() {
+            return Err(Error::new(
+                self.ident.span(),
+                format!("shared register `{}` has no fields", self.ident),
+            ));
+        }
+        let mut mask = 0;
+        for field in &self.fields {
+            mask |= field.check(self.size, !self.asserts.is_empty())?;
+        }
+        for assert in &self.asserts {
+            if assert.key == "mask" {
+                assert.check(&format!("register `{}`", self.ident), mask)?;
+            } else {
+                return Err(Error::new(
+                    assert.key.span(),
+                    format!("unknown assertion: `{}`", assert.key),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn reg_full(&self) -> Ident {
         format_ident!(
             "{}_{}",
@@ -432,6 +569,42 @@ impl Variant {
     }
 }
 
+impl Field {
+    /// Checks the assertions and returns the mask of the field.
+    fn check(&self, size: u8, strict: bool) -> Result<u128> {
+        let offset = self.offset.base10_parse::<u32>()?;
+        let width = self.width.base10_parse::<u32>()?;
+        if (strict || !self.asserts.is_empty()) && offset + width > u32::from(size) {
+            return Err(Error::new(
+                self.offset.span(),
+                format!("field `{}` exceeds the register size of {} bits", self.ident, size),
+            ));
+        }
+        let mask = u128::MAX
+            .checked_shr(128_u32.saturating_sub(width))
+            .unwrap_or(0)
+            .checked_shl(offset)
+            .unwrap_or(0);
+        let subject = format!("field `{}`", self.ident);
+        for assert in &self.asserts {
+            let actual = if assert.key == "mask" {
+                mask
+            } else if assert.key == "offset" {
+                offset.into()
+            } else if assert.key == "width" {
+                width.into()
+            } else {
+                return Err(Error::new(
+                    assert.key.span(),
+                    format!("unknown assertion: `{}`", assert.key),
+                ));
+            };
+            assert.check(&subject, actual)?;
+        }
+        Ok(mask)
+    }
+}
+
 fn parse_traits(input: ParseStream<'_>) -> Result<Vec<Ident>> {
     let mut traits = Vec::new();
     let input2;
@@ -444,6 +617,9 @@ fn parse_traits(input: ParseStream<'_>) -> Result<Vec<Ident>> {
 
 pub fn proc_macro(input: TokenStream) -> TokenStream {
     let Input { variants } = parse_macro_input!(input);
+    if let Err(err) = variants.iter().try_for_each(Variant::check) {
+        return err.to_compile_error().into();
+    }
     let reg_tokens = variants.iter().map(Variant::generate).collect::<Vec<_>>();
     let mut variant_tokens = Vec::new();
     for (i, reg_src) in variants.iter().enumerate() {
@@ -184,7 +184,7 @@ fn make_macro(
                 let macro_root_path = macro_root_path.iter();
                 defs.push(quote! {
                     #(#block_attrs_non_cfg)* #(#reg_attrs)*
-                    #reg_long $crate#(#macro_root_path)*::#block_name::#reg_psc;
+                    #reg_long $crate#(#macro_root_path)*::#block_name::#reg_short::IndexEntry;
                 });
             }
         }
@@ -0,0 +1,31 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Error, ItemStatic, Path};
+
+pub fn proc_macro_attribute(args: TokenStream, item: TokenStream) -> TokenStream {
+    let registry = parse_macro_input!(args as Path);
+    let item = parse_macro_input!(item as ItemStatic);
+    if item.mutability.is_some() {
+        return Error::new_spanned(&item.mutability, "registered statics must be immutable")
+            .to_compile_error()
+            .into();
+    }
+    let registry_ident = match registry.segments.last() {
+        Some(segment) => &segment.ident,
+        None => {
+            return Error::new_spanned(&registry, "expected a registry name")
+                .to_compile_error()
+                .into();
+        }
+    };
+    let section = format!(".drone_plugins.{}", registry_ident);
+    let ty = &item.ty;
+    let expanded = quote! {
+        #[used]
+        #[link_section = #section]
+        #item
+
+        const _: fn() = ::drone_core::inventory::assert_plugin::<#registry, #ty>;
+    };
+    expanded.into()
+}
@@ -8,6 +8,8 @@ use syn::{
     parse_macro_input, Attribute, Expr, ExprPath, Ident, LitInt, Token, Type, Visibility,
 };
 
+const STATS: bool = cfg!(feature = "thr-stats");
+
 struct Input {
     thr: Thr,
     local: Local,
@@ -229,11 +231,23 @@ fn def_thr(
         thr_tokens.push(quote!(#(#attrs)* #vis #ident: #ty));
         thr_ctor_tokens.push(quote!(#ident: #init));
     }
+    let mut stats_tokens = Vec::new();
+    if STATS {
+        thr_tokens.push(quote!(stats: ::drone_core::thr::ThrStats));
+        thr_ctor_tokens.push(quote!(stats: ::drone_core::thr::ThrStats::new()));
+        stats_tokens.push(quote! {
+            #[inline]
+            fn stats(&self) -> &::drone_core::thr::ThrStats {
+                &self.stats
+            }
+        });
+    }
     quote! {
         #(#thr_attrs)*
         #thr_vis struct #thr_ident {
             fib_chain: ::drone_core::fib::Chain,
             local: ::drone_core::thr::LocalOpaque<Self>,
+            local_cells: ::drone_core::thr::LocalCells,
             #(#thr_tokens,)*
         }
 
@@ -243,6 +257,7 @@ fn def_thr(
                 Self {
                     fib_chain: ::drone_core::fib::Chain::new(),
                     local: ::drone_core::thr::LocalOpaque::new(#local_ident::new(index)),
+                    local_cells: ::drone_core::thr::LocalCells::new(),
                     #(#thr_ctor_tokens,)*
                 }
             }
@@ -276,6 +291,13 @@ fn def_thr(
                 &self.local
             }
 
+            #[inline]
+            fn local_cells(&self) -> &::drone_core::thr::LocalCells {
+                &self.local_cells
+            }
+
+            #(#stats_tokens)*
+
             #resume
         }
     }
@@ -0,0 +1,281 @@
+//! On-target microbenchmarks.
+//!
+//! Benchmarks are registered from any crate with the
+//! [`bench::benchmark!`](crate::bench::benchmark) macro, and executed on the
+//! target with [`run_all`]. A benchmark function receives a [`Bencher`] and
+//! passes the code under test to [`Bencher::iter`]. The bencher calls the code
+//! a number of times to warm up caches and branch predictors, then collects
+//! samples of the elapsed cycles, and rejects outliers caused by interrupts.
+//!
+//! Cycles are measured with the counter registered with
+//! [`set_cycle_counter!`](crate::set_cycle_counter), the same one used for
+//! thread statistics. The overhead of reading the counter is measured and
+//! subtracted from the samples.
+//!
+//! The linker script must keep the `.drone_benches` section and surround it
+//! with `DRONE_BENCHES_START` and `DRONE_BENCHES_END` symbols.
+//!
+//! ```
+//! use drone_core::{
+//!     bench::{self, Bencher},
+//!     sync::spsc::ring,
+//! };
+//!
+//! fn ring_send(b: &mut Bencher) {
+//!     let (mut tx, mut rx) = ring::channel::<u32, ()>(16);
+//!     b.iter(|| {
+//!         tx.send(1).ok();
+//!         rx.try_next()
+//!     });
+//! }
+//!
+//! bench::benchmark!("ring_send", ring_send);
+//! # fn main() {}
+//! ```
+//!
+//! # Reports
+//!
+//! Each result is written to the log port [`PORT`] as a line of whitespace
+//! separated fields, where all values are in cycles per iteration:
+//!
+//! ```text
+//! bench ring_send min=41 median=43 mean=43 max=47 samples=62 rejected=2
+//! ```
+//!
+//! Benchmark names must not contain whitespace.
+
+use crate::{log::Port, thr};
+use core::{cell::UnsafeCell, fmt::Write, mem, mem::size_of, ptr, slice};
+
+extern "C" {
+    static DRONE_BENCHES_START: UnsafeCell<usize>;
+    static DRONE_BENCHES_END: UnsafeCell<usize>;
+}
+
+/// The log port number for benchmark reports.
+pub const PORT: u8 = 28;
+
+/// The maximum number of samples per benchmark.
+pub const MAX_SAMPLES: usize = 64;
+
+/// A benchmark registered with [`bench::benchmark!`](crate::bench::benchmark).
+pub struct Bench {
+    /// The benchmark name.
+    pub name: &'static str,
+    /// The benchmark function.
+    pub run: fn(&mut Bencher),
+}
+
+/// Benchmark parameters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// The number of calls before collecting samples.
+    pub warmup: u32,
+    /// The number of samples, at most [`MAX_SAMPLES`].
+    pub samples: usize,
+    /// The number of calls per sample. Larger batches improve resolution for
+    /// very short code.
+    pub batch: u32,
+}
+
+/// A benchmark driver passed to benchmark functions.
+pub struct Bencher {
+    config: Config,
+    summary: Option<Summary>,
+}
+
+/// Statistics of a benchmark in cycles per iteration.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Summary {
+    /// The minimum.
+    pub min: u32,
+    /// The median.
+    pub median: u32,
+    /// The arithmetic mean.
+    pub mean: u32,
+    /// The maximum.
+    pub max: u32,
+    /// The number of samples after the outlier rejection.
+    pub samples: u32,
+    /// The number of rejected outliers.
+    pub rejected: u32,
+}
+
+/// Registers a benchmark.
+///
+/// See [the module-level documentation](self) for details.
+#[doc(inline)]
+pub use crate::__bench_benchmark as benchmark;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bench_benchmark {
+    ($name:expr, $run:path $(,)?) => {
+        const _: () = {
+            #[used]
+            #[link_section = ".drone_benches"]
+            static BENCH: $crate::bench::Bench = $crate::bench::Bench { name: $name, run: $run };
+        };
+    };
+}
+
+/// Returns an iterator over all registered benchmarks.
+pub fn benches() -> impl Iterator<Item = &'static Bench> {
+    let benches = unsafe {
+        let count = (DRONE_BENCHES_END.get() as usize - DRONE_BENCHES_START.get() as usize)
+            / size_of::<Bench>();
+        slice::from_raw_parts(DRONE_BENCHES_START.get().cast::<Bench>(), count)
+    };
+    benches.iter()
+}
+
+/// Runs all registered benchmarks with the `config`, and reports the results.
+/// Returns the number of benchmarks run.
+pub fn run_all(config: Config) -> usize {
+    let mut count = 0;
+    for bench in benches() {
+        run(bench, config);
+        count += 1;
+    }
+    count
+}
+
+/// Runs the `bench` with the `config`, and reports the result.
+///
+/// Returns `None` if the benchmark function didn't call [`Bencher::iter`].
+pub fn run(bench: &Bench, config: Config) -> Option<Summary> {
+    let mut bencher = Bencher::new(config);
+    (bench.run)(&mut bencher);
+    if let Some(summary) = &bencher.summary {
+        report(bench.name, summary);
+    }
+    bencher.summary
+}
+
+/// Writes the `summary` of the benchmark `name` to the log port [`PORT`].
+pub fn report(name: &str, summary: &Summary) {
+    let mut port = Port::new(PORT);
+    if port.is_enabled() {
+        let Summary { min, median, mean, max, samples, rejected } = summary;
+        writeln!(
+            port,
+            "bench {} min={} median={} mean={} max={} samples={} rejected={}",
+            name, min, median, mean, max, samples, rejected
+        )
+        .ok();
+    }
+}
+
+impl Config {
+    /// Creates a new configuration with the default parameters.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { warmup: 16, samples: MAX_SAMPLES, batch: 1 }
+    }
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bencher {
+    /// Creates a new bencher with the `config`.
+    #[inline]
+    pub const fn new(config: Config) -> Self {
+        Self { config, summary: None }
+    }
+
+    /// Measures the execution of `f`.
+    ///
+    /// # Panics
+    ///
+    /// If `batch` parameter is zero.
+    pub fn iter<R, F: FnMut() -> R>(&mut self, mut f: F) {
+        let Config { warmup, samples, batch } = self.config;
+        assert!(batch > 0, "batch must be non-zero");
+        for _ in 0..warmup {
+            black_box(f());
+        }
+        let overhead = (0..8)
+            .map(|_| {
+                let start = thr::cycles();
+                thr::cycles().wrapping_sub(start)
+            })
+            .min()
+            .unwrap_or(0);
+        let mut buffer = [0; MAX_SAMPLES];
+        let buffer = &mut buffer[..samples.clamp(1, MAX_SAMPLES)];
+        for sample in buffer.iter_mut() {
+            let start = thr::cycles();
+            for _ in 0..batch {
+                black_box(f());
+            }
+            *sample = thr::cycles().wrapping_sub(start).saturating_sub(overhead) / batch;
+        }
+        self.summary = Some(Summary::from_samples(buffer));
+    }
+
+    /// Returns the result of the last [`Bencher::iter`] call.
+    #[inline]
+    pub fn summary(&self) -> Option<Summary> {
+        self.summary
+    }
+}
+
+impl Summary {
+    /// Computes statistics of the `samples`, rejecting outliers beyond 1.5
+    /// interquartile ranges from the quartiles. Sorts the `samples` in place.
+    ///
+    /// # Panics
+    ///
+    /// If `samples` is empty.
+    pub fn from_samples(samples: &mut [u32]) -> Self {
+        assert!(!samples.is_empty(), "no samples");
+        samples.sort_unstable();
+        let len = samples.len();
+        let (q1, q3) = (samples[len / 4], samples[len * 3 / 4]);
+        let fence = (q3 - q1).saturating_mul(3) / 2;
+        let (low, high) = (q1.saturating_sub(fence), q3.saturating_add(fence));
+        let start = samples.partition_point(|&x| x < low);
+        let end = samples.partition_point(|&x| x <= high);
+        let kept = &samples[start..end];
+        let sum = kept.iter().map(|&x| u64::from(x)).sum::<u64>();
+        Self {
+            min: kept[0],
+            median: kept[kept.len() / 2],
+            mean: (sum / kept.len() as u64) as u32,
+            max: kept[kept.len() - 1],
+            samples: kept.len() as u32,
+            rejected: (len - kept.len()) as u32,
+        }
+    }
+}
+
+/// Prevents the optimizer from removing the computation of `value`.
+fn black_box<T>(value: T) -> T {
+    let result = unsafe { ptr::read_volatile(&value) };
+    mem::forget(value);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outliers() {
+        let mut samples = [43, 41, 44, 43, 42, 1200, 43, 45, 44, 42, 43, 3];
+        let summary = Summary::from_samples(&mut samples);
+        assert_eq!(summary, Summary {
+            min: 41,
+            median: 43,
+            mean: 43,
+            max: 45,
+            samples: 10,
+            rejected: 2,
+        });
+    }
+}
@@ -6,6 +6,9 @@ use core::{
 
 /// An integer interface for [`Bitfield`](super::Bitfield).
 ///
+/// The bit counting methods have generic default implementations, which the
+/// primitive integers override with their native methods.
+///
 /// See [the module level documentation](super) for details.
 pub trait Bits
 where
@@ -29,6 +32,42 @@ where
 
     /// Returns `true` if all bits of the value are cleared.
     fn is_zero(self) -> bool;
+
+    /// Returns the number of set bits.
+    fn count_ones(self) -> u32 {
+        let one = Self::from_usize(1);
+        let mut bits = self;
+        let mut count = 0;
+        while !bits.is_zero() {
+            bits = bits & (bits - one);
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the number of cleared bits before the most significant set bit.
+    fn leading_zeros(self) -> u32 {
+        let one = Self::from_usize(1);
+        let mut mask = one << (Self::width() - one);
+        let mut count = 0;
+        while !mask.is_zero() && (self & mask).is_zero() {
+            mask = mask >> one;
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the number of cleared bits after the least significant set bit.
+    fn trailing_zeros(self) -> u32 {
+        let one = Self::from_usize(1);
+        let mut mask = one;
+        let mut count = 0;
+        while !mask.is_zero() && (self & mask).is_zero() {
+            mask = mask << one;
+            count += 1;
+        }
+        count
+    }
 }
 
 macro_rules! bits {
@@ -48,6 +87,21 @@ macro_rules! bits {
             fn is_zero(self) -> bool {
                 self == 0
             }
+
+            #[inline]
+            fn count_ones(self) -> u32 {
+                <$type>::count_ones(self)
+            }
+
+            #[inline]
+            fn leading_zeros(self) -> u32 {
+                <$type>::leading_zeros(self)
+            }
+
+            #[inline]
+            fn trailing_zeros(self) -> u32 {
+                <$type>::trailing_zeros(self)
+            }
         }
     };
 }
@@ -49,8 +49,80 @@
 //!
 //! assert_eq!(value.0, 0b0001_0100);
 //! ```
+//!
+//! For flag words, the optional `bitfield_flags` attribute additionally
+//! defines an `enum` of the readable single-bit fields, and an `iter_set`
+//! method, which yields the set fields in the order of declaration:
+//!
+//! ```
+//! use drone_core::bitfield::Bitfield;
+//!
+//! #[derive(Clone, Copy, Bitfield)]
+//! #[bitfield(
+//!     overrun(r, 3, "Overrun error"),
+//!     rx_ready(r, 5, "Receive data ready"),
+//!     tx_ready(r, 7, "Transmit data ready")
+//! )]
+//! #[bitfield_flags(Status)]
+//! struct StatusValue(u8);
+//!
+//! let value = StatusValue(0b1000_1000);
+//! assert!(value.any(0b1010_0000));
+//! assert!(!value.all(0b1010_0000));
+//! assert_eq!(value.count_ones(), 2);
+//! let set = value.iter_set().collect::<Vec<_>>();
+//! assert_eq!(set, [Status::Overrun, Status::TxReady]);
+//! ```
+//!
+//! # Packed Structs
+//!
+//! A protocol packet made of bit-fields and plain integers can be declared
+//! once with the [`Packed`] derive. The struct gets `to_le_bytes`,
+//! `from_le_bytes`, `to_be_bytes`, and `from_be_bytes` methods, which convert
+//! the fields in the order of declaration without padding. Each field is
+//! converted with its own byte order, so a multi-byte bit-field keeps its bit
+//! numbering. The optional `packed(size = ...)` attribute checks the total
+//! size at compile-time.
+//!
+//! ```
+//! use drone_core::bitfield::{Bitfield, Packed};
+//!
+//! #[derive(Clone, Copy, Bitfield)]
+//! #[bitfield(kind(rw, 0, 4), ack(rw, 7))]
+//! struct Header(u8);
+//!
+//! #[derive(Packed)]
+//! #[packed(size = 5)]
+//! struct Frame {
+//!     header: Header,
+//!     address: u16,
+//!     length: u16,
+//! }
+//!
+//! let frame = Frame { header: Header(0x83), address: 0x1234, length: 2 };
+//! assert_eq!(frame.to_be_bytes(), [0x83, 0x12, 0x34, 0x00, 0x02]);
+//! let frame = Frame::from_le_bytes([0x83, 0x34, 0x12, 0x02, 0x00]);
+//! assert!(frame.header.ack());
+//! assert_eq!(frame.header.kind(), 3);
+//! assert_eq!(frame.address, 0x1234);
+//! assert_eq!(frame.length, 2);
+//! ```
+//!
+//! A size mismatch is a compile error:
+//!
+//! ```compile_fail
+//! use drone_core::bitfield::Packed;
+//!
+//! #[derive(Packed)]
+//! #[packed(size = 4)]
+//! struct Frame {
+//!     address: u16,
+//!     length: u8,
+//! }
+//! ```
 
 mod bits;
+mod packed;
 
 /// Defines a new [`Bitfield`].
 ///
@@ -58,7 +130,13 @@ mod bits;
 #[doc(inline)]
 pub use drone_core_macros::Bitfield;
 
-pub use self::bits::Bits;
+/// Defines byte conversions for a struct of [`Packed`] fields.
+///
+/// See [the module level documentation](self#packed-structs) for details.
+#[doc(inline)]
+pub use drone_core_macros::Packed;
+
+pub use self::{bits::Bits, packed::Packed};
 
 /// An integer value treated as a sequence of bits, which can be toggled
 /// individually.
@@ -141,6 +219,30 @@ pub trait Bitfield: Sized + Send + Sync + Clone + Copy + 'static {
             self.bits() & !(bit_mask(width) << offset) | (bits & bit_mask(width)) << offset
         };
     }
+
+    /// Returns `true` if any of the bits set in `mask` is set.
+    #[inline]
+    fn any(&self, mask: Self::Bits) -> bool {
+        !(self.bits() & mask).is_zero()
+    }
+
+    /// Returns `true` if all of the bits set in `mask` are set.
+    #[inline]
+    fn all(&self, mask: Self::Bits) -> bool {
+        (self.bits() & mask) == mask
+    }
+
+    /// Returns the number of set bits.
+    #[inline]
+    fn count_ones(&self) -> u32 {
+        self.bits().count_ones()
+    }
+
+    /// Returns the number of cleared bits before the most significant set bit.
+    #[inline]
+    fn leading_zeros(&self) -> u32 {
+        self.bits().leading_zeros()
+    }
 }
 
 fn bit_at<T: Bits>(offset: T) -> T {
@@ -0,0 +1,83 @@
+use core::mem::size_of;
+
+/// A value with a fixed-size byte representation without padding.
+///
+/// Implemented for the unsigned integers and for all [`Bitfield`]s. Can be
+/// derived for structs of packed fields, see
+/// [the module level documentation](super) for details.
+///
+/// [`Bitfield`]: super::Bitfield
+pub trait Packed: Sized {
+    /// The size of the byte representation.
+    const SIZE: usize;
+
+    /// Writes the byte representation in little-endian byte order to the
+    /// beginning of `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is shorter than [`Packed::SIZE`].
+    fn write_le_bytes(&self, bytes: &mut [u8]);
+
+    /// Writes the byte representation in big-endian byte order to the
+    /// beginning of `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is shorter than [`Packed::SIZE`].
+    fn write_be_bytes(&self, bytes: &mut [u8]);
+
+    /// Reads a value from its byte representation in little-endian byte order
+    /// at the beginning of `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is shorter than [`Packed::SIZE`].
+    fn read_le_bytes(bytes: &[u8]) -> Self;
+
+    /// Reads a value from its byte representation in big-endian byte order at
+    /// the beginning of `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is shorter than [`Packed::SIZE`].
+    fn read_be_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! packed {
+    ($type:ty) => {
+        impl Packed for $type {
+            const SIZE: usize = size_of::<Self>();
+
+            #[inline]
+            fn write_le_bytes(&self, bytes: &mut [u8]) {
+                bytes[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+            }
+
+            #[inline]
+            fn write_be_bytes(&self, bytes: &mut [u8]) {
+                bytes[..Self::SIZE].copy_from_slice(&self.to_be_bytes());
+            }
+
+            #[inline]
+            fn read_le_bytes(bytes: &[u8]) -> Self {
+                let mut array = [0; size_of::<Self>()];
+                array.copy_from_slice(&bytes[..Self::SIZE]);
+                Self::from_le_bytes(array)
+            }
+
+            #[inline]
+            fn read_be_bytes(bytes: &[u8]) -> Self {
+                let mut array = [0; size_of::<Self>()];
+                array.copy_from_slice(&bytes[..Self::SIZE]);
+                Self::from_be_bytes(array)
+            }
+        }
+    };
+}
+
+packed!(u8);
+packed!(u16);
+packed!(u32);
+packed!(u64);
+packed!(u128);
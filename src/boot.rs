@@ -0,0 +1,460 @@
+//! Bootloader support.
+//!
+//! This module defines the protocol shared by a bootloader and the application
+//! images it starts:
+//!
+//! - [`ImageHeader`] is placed at the beginning of each image, and describes
+//!   the image version, size, and CRC-32. A bootloader checks the image with
+//!   [`verify`] or [`verify_at`] before jumping to it.
+//! - The A/B slot metadata survives resets in RAM. The application requests a
+//!   trial boot of a freshly written image with [`request_trial`], the
+//!   bootloader picks the slot with [`select`], and the new image makes itself
+//!   permanent with [`confirm`] once it is healthy. If the new image fails to
+//!   confirm within the given number of attempts, the bootloader falls back to
+//!   the previous slot. The metadata is not synchronized, so these functions
+//!   are `unsafe`, and must be called from a single context, e.g. the
+//!   bootloader or the application initialization.
+//!
+//! The metadata is placed into the `.drone_boot_meta` linker section. Both the
+//! bootloader and the application linker scripts must place this section at
+//! the same fixed address, and exclude it from the BSS/DATA initialization:
+//!
+//! ```text
+//! SECTIONS
+//! {
+//!     .drone_boot_meta 0x2001FFE0 (NOLOAD) :
+//!     {
+//!         KEEP(*(.drone_boot_meta))
+//!     }
+//! }
+//! ```
+//!
+//! The RAM metadata is lost on power-off. The application must therefore
+//! persist the confirmed slot to flash, and the bootloader must pass it to
+//! [`select`], which uses it whenever the metadata is invalid.
+//!
+//! ```no_run
+//! use drone_core::boot::{self, Slot};
+//!
+//! const SLOT_A: usize = 0x0800_4000;
+//! const SLOT_B: usize = 0x0804_0000;
+//!
+//! // In the bootloader, with the slot persisted by the application:
+//! # let persisted = Slot::A;
+//! let slot = unsafe { boot::select(persisted) };
+//! let addr = match slot {
+//!     Slot::A => SLOT_A,
+//!     Slot::B => SLOT_B,
+//! };
+//! match unsafe { boot::verify_at(addr) } {
+//!     Ok(header) => { /* Jump to `addr + header.header_size`. */ }
+//!     Err(_) => { /* Try the other slot. */ }
+//! }
+//!
+//! // In the application, after a successful self-test:
+//! unsafe { boot::confirm() };
+//! // Then persist `boot::active()` to flash.
+//! ```
+
+use crate::crc::{Crc32, CRC32_ISO_HDLC};
+use core::{convert::TryInto, fmt, ptr, slice};
+
+/// The magic number of [`ImageHeader`].
+pub const IMAGE_MAGIC: u32 = 0x4752_4D49;
+
+/// The size of a serialized [`ImageHeader`] in bytes.
+pub const HEADER_SIZE: usize = 32;
+
+const HEADER_VERSION: u16 = 1;
+const META_MAGIC: u32 = 0xB007_AB5E;
+const NO_SLOT: u8 = 0xFF;
+const CRC: Crc32 = Crc32::new(CRC32_ISO_HDLC);
+
+#[cfg_attr(not(feature = "std"), link_section = ".drone_boot_meta")]
+static mut META: BootMeta = BootMeta::ZERO;
+
+/// An image version.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Version {
+    /// The major version.
+    pub major: u8,
+    /// The minor version.
+    pub minor: u8,
+    /// The patch version.
+    pub patch: u16,
+}
+
+/// An image header.
+///
+/// The header is serialized in little-endian byte order with
+/// [`ImageHeader::to_bytes`], and is followed by the image payload, possibly
+/// after padding up to [`ImageHeader::header_size`] bytes (e.g. to align the
+/// vector table).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ImageHeader {
+    /// The size of the header including padding, i.e. the offset of the
+    /// payload.
+    pub header_size: u16,
+    /// The image version.
+    pub version: Version,
+    /// The size of the payload in bytes.
+    pub image_size: u32,
+    /// The CRC-32/ISO-HDLC of the payload.
+    pub image_crc: u32,
+    /// Application-defined flags.
+    pub flags: u32,
+}
+
+/// An image verification error.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageError {
+    /// The header magic number doesn't match.
+    BadMagic,
+    /// The header format version is not supported.
+    UnsupportedHeader,
+    /// The header is corrupted.
+    BadHeaderCrc,
+    /// The payload is shorter than declared in the header.
+    Truncated,
+    /// The payload is corrupted.
+    BadImageCrc,
+}
+
+/// A boot slot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    /// The first slot.
+    A,
+    /// The second slot.
+    B,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BootMeta {
+    magic: u32,
+    active: u8,
+    pending: u8,
+    attempts: u8,
+    max_attempts: u8,
+    booted: u8,
+    checksum: u32,
+}
+
+impl Version {
+    /// Creates a new version.
+    #[inline]
+    pub const fn new(major: u8, minor: u8, patch: u16) -> Self {
+        Self { major, minor, patch }
+    }
+}
+
+impl ImageHeader {
+    /// Creates a new header for the `payload`.
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn new(version: Version, payload: &[u8]) -> Self {
+        Self {
+            header_size: HEADER_SIZE as u16,
+            version,
+            image_size: payload.len() as u32,
+            image_crc: CRC.checksum(payload),
+            flags: 0,
+        }
+    }
+
+    /// Serializes the header.
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&IMAGE_MAGIC.to_le_bytes());
+        bytes[4..6].copy_from_slice(&HEADER_VERSION.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.header_size.to_le_bytes());
+        bytes[8] = self.version.major;
+        bytes[9] = self.version.minor;
+        bytes[10..12].copy_from_slice(&self.version.patch.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.image_size.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.image_crc.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.flags.to_le_bytes());
+        let header_crc = CRC.checksum(&bytes[..28]);
+        bytes[28..32].copy_from_slice(&header_crc.to_le_bytes());
+        bytes
+    }
+
+    /// Deserializes and checks a header from the beginning of `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        let bytes = bytes.get(..HEADER_SIZE).ok_or(ImageError::Truncated)?;
+        let u16_at = |i: usize| u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        if u32_at(0) != IMAGE_MAGIC {
+            return Err(ImageError::BadMagic);
+        }
+        if u16_at(4) != HEADER_VERSION {
+            return Err(ImageError::UnsupportedHeader);
+        }
+        if u32_at(28) != CRC.checksum(&bytes[..28]) {
+            return Err(ImageError::BadHeaderCrc);
+        }
+        let header = Self {
+            header_size: u16_at(6),
+            version: Version::new(bytes[8], bytes[9], u16_at(10)),
+            image_size: u32_at(12),
+            image_crc: u32_at(16),
+            flags: u32_at(20),
+        };
+        if usize::from(header.header_size) < HEADER_SIZE {
+            return Err(ImageError::UnsupportedHeader);
+        }
+        Ok(header)
+    }
+}
+
+/// Verifies an `image`, which starts with a serialized [`ImageHeader`].
+pub fn verify(image: &[u8]) -> Result<ImageHeader, ImageError> {
+    let header = ImageHeader::from_bytes(image)?;
+    let start = usize::from(header.header_size);
+    let payload = start
+        .checked_add(header.image_size as usize)
+        .and_then(|end| image.get(start..end))
+        .ok_or(ImageError::Truncated)?;
+    if CRC.checksum(payload) != header.image_crc {
+        return Err(ImageError::BadImageCrc);
+    }
+    Ok(header)
+}
+
+/// Verifies a memory-mapped image at `addr`.
+///
+/// # Safety
+///
+/// The memory at `addr` must be readable for the image size declared in the
+/// header, or for [`HEADER_SIZE`] bytes if the header is invalid.
+pub unsafe fn verify_at(addr: usize) -> Result<ImageHeader, ImageError> {
+    let header =
+        ImageHeader::from_bytes(unsafe { slice::from_raw_parts(addr as *const u8, HEADER_SIZE) })?;
+    let len = usize::from(header.header_size) + header.image_size as usize;
+    verify(unsafe { slice::from_raw_parts(addr as *const u8, len) })
+}
+
+/// Chooses the slot to boot. This function is intended for the bootloader.
+///
+/// If a trial boot was requested with [`request_trial`], and the trial slot has
+/// attempts left, the trial slot is chosen and an attempt is consumed.
+/// Otherwise the trial is canceled, and the active slot is chosen.
+///
+/// If the metadata is invalid, e.g. after a power-on, it is reset with
+/// `persisted_active` as the active slot. This should be the slot last
+/// confirmed by the application and persisted to flash.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with other functions of this
+/// module.
+pub unsafe fn select(persisted_active: Slot) -> Slot {
+    let mut meta = BootMeta::load().unwrap_or_else(|| BootMeta::new(persisted_active));
+    let slot = if meta.pending != NO_SLOT && meta.attempts < meta.max_attempts {
+        meta.attempts += 1;
+        meta.pending
+    } else {
+        meta.pending = NO_SLOT;
+        meta.active
+    };
+    meta.booted = slot;
+    meta.store();
+    Slot::from_raw(slot)
+}
+
+/// Requests a trial boot of the `slot` for up to `max_attempts` resets.
+///
+/// If the metadata is invalid, the other slot becomes active.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with other functions of this
+/// module.
+pub unsafe fn request_trial(slot: Slot, max_attempts: u8) {
+    let mut meta = BootMeta::load().unwrap_or_else(|| BootMeta::new(slot.other()));
+    meta.pending = slot as u8;
+    meta.attempts = 0;
+    meta.max_attempts = max_attempts;
+    meta.store();
+}
+
+/// Makes the currently booted slot active, if it was booted for trial.
+///
+/// The RAM metadata doesn't survive a power-off, so the application should
+/// persist the new [`active`] slot to flash afterwards.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with other functions of this
+/// module.
+pub unsafe fn confirm() {
+    if let Some(mut meta) = BootMeta::load() {
+        if meta.pending != NO_SLOT && meta.pending == meta.booted {
+            meta.active = meta.pending;
+            meta.pending = NO_SLOT;
+            meta.store();
+        }
+    }
+}
+
+/// Returns the active slot, which is booted when no trial is pending.
+///
+/// Returns `None` if the metadata is invalid.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with other functions of this
+/// module.
+pub unsafe fn active() -> Option<Slot> {
+    BootMeta::load().map(|meta| Slot::from_raw(meta.active))
+}
+
+/// Returns the slot chosen by the last [`select`] call.
+///
+/// Returns `None` if the metadata is invalid.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with other functions of this
+/// module.
+pub unsafe fn booted() -> Option<Slot> {
+    BootMeta::load().map(|meta| Slot::from_raw(meta.booted))
+}
+
+/// Returns the slot pending for a trial boot, if any.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with other functions of this
+/// module.
+pub unsafe fn pending() -> Option<Slot> {
+    BootMeta::load()
+        .map(|meta| meta.pending)
+        .filter(|&pending| pending != NO_SLOT)
+        .map(Slot::from_raw)
+}
+
+impl Slot {
+    /// Returns the other slot.
+    #[inline]
+    #[must_use]
+    pub fn other(self) -> Self {
+        match self {
+            Self::A => Self::B,
+            Self::B => Self::A,
+        }
+    }
+
+    fn from_raw(raw: u8) -> Self {
+        if raw == Self::B as u8 {
+            Self::B
+        } else {
+            Self::A
+        }
+    }
+}
+
+impl BootMeta {
+    const ZERO: Self = Self {
+        magic: 0,
+        active: 0,
+        pending: 0,
+        attempts: 0,
+        max_attempts: 0,
+        booted: 0,
+        checksum: 0,
+    };
+
+    fn new(active: Slot) -> Self {
+        Self {
+            magic: META_MAGIC,
+            active: active as u8,
+            pending: NO_SLOT,
+            attempts: 0,
+            max_attempts: 0,
+            booted: active as u8,
+            checksum: 0,
+        }
+    }
+
+    fn load() -> Option<Self> {
+        let meta = unsafe { ptr::read_volatile(ptr::addr_of!(META)) };
+        if meta.magic == META_MAGIC && meta.checksum == meta.compute_checksum() {
+            Some(meta)
+        } else {
+            None
+        }
+    }
+
+    fn store(mut self) {
+        self.checksum = self.compute_checksum();
+        unsafe { ptr::write_volatile(ptr::addr_of_mut!(META), self) };
+    }
+
+    fn compute_checksum(&self) -> u32 {
+        let fields =
+            u32::from_le_bytes([self.active, self.pending, self.attempts, self.max_attempts]);
+        !(self.magic ^ fields ^ u32::from(self.booted).rotate_left(16))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => f.write_str("bad image magic"),
+            Self::UnsupportedHeader => f.write_str("unsupported image header"),
+            Self::BadHeaderCrc => f.write_str("corrupted image header"),
+            Self::Truncated => f.write_str("truncated image"),
+            Self::BadImageCrc => f.write_str("corrupted image payload"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image() {
+        let payload = [0x55; 100];
+        let header = ImageHeader::new(Version::new(1, 2, 3), &payload);
+        let mut image = header.to_bytes().to_vec();
+        image.extend_from_slice(&payload);
+        assert_eq!(verify(&image), Ok(header));
+        image[HEADER_SIZE + 10] = 0;
+        assert_eq!(verify(&image), Err(ImageError::BadImageCrc));
+        image.truncate(HEADER_SIZE + 50);
+        assert_eq!(verify(&image), Err(ImageError::Truncated));
+        image[9] = 0;
+        assert_eq!(verify(&image), Err(ImageError::BadHeaderCrc));
+        image[0] = 0;
+        assert_eq!(verify(&image), Err(ImageError::BadMagic));
+    }
+
+    #[test]
+    fn trial_boot() {
+        unsafe {
+            assert_eq!(active(), None);
+            assert_eq!(select(Slot::B), Slot::B);
+            assert_eq!(active(), Some(Slot::B));
+            request_trial(Slot::A, 2);
+            assert_eq!(select(Slot::B), Slot::A);
+            assert_eq!(select(Slot::B), Slot::A);
+            assert_eq!(select(Slot::B), Slot::B);
+            assert_eq!(pending(), None);
+            request_trial(Slot::A, 2);
+            assert_eq!(select(Slot::B), Slot::A);
+            confirm();
+            assert_eq!(active(), Some(Slot::A));
+            assert_eq!(select(Slot::B), Slot::A);
+            assert_eq!(booted(), Some(Slot::A));
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! A lightweight typed publish-subscribe bus.
+//!
+//! A [`Topic`] decouples publishers of events from their consumers: publishers
+//! don't need to know who is listening. Each subscriber receives its own copy
+//! of every published value through a dedicated [ring
+//! channel](crate::sync::spsc::ring), and chooses an [`Overflow`] policy for
+//! the case when it can't keep up.
+//!
+//! ```
+//! use drone_core::bus::{Overflow, Topic};
+//!
+//! #[derive(Clone, Debug)]
+//! pub enum Battery {
+//!     Low,
+//!     Charging,
+//! }
+//!
+//! static BATTERY: Topic<Battery, 4> = Topic::new();
+//!
+//! let mut display = BATTERY.subscribe(8, Overflow::DropOldest).unwrap();
+//! BATTERY.publish(Battery::Low);
+//! assert!(matches!(display.try_next(), Some(Battery::Low)));
+//! ```
+
+use crate::sync::spsc::ring::{self, SendErrorKind};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::MaybeUninit,
+    pin::Pin,
+    ptr,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+use futures::stream::Stream;
+
+const FREE: u8 = 0;
+const CLAIMED: u8 = 1;
+const ACTIVE: u8 = 2;
+
+/// A topic with up to `N` subscribers.
+///
+/// Publishing is lock-free and can be done from any context. If a subscriber
+/// is being published to from a preempted context, a concurrent publication
+/// skips it and counts the value as dropped.
+pub struct Topic<T, const N: usize> {
+    states: [AtomicU8; N],
+    slots: UnsafeCell<MaybeUninit<[Slot<T>; N]>>,
+    dropped: AtomicUsize,
+}
+
+/// A subscriber policy for the case when its channel is full.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Overflow {
+    /// Drop the newly published value.
+    DropNewest,
+    /// Drop the oldest value in the channel to make room for the new one.
+    DropOldest,
+}
+
+/// A subscription to a [`Topic`]. A [`Stream`] of the published values.
+///
+/// The subscriber slot is freed on the next publication after the
+/// subscription is dropped.
+pub struct Subscription<T> {
+    rx: ring::Receiver<T, !>,
+}
+
+/// The error type returned from [`Topic::subscribe`] when all subscriber slots
+/// are taken.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TopicFull;
+
+struct Slot<T> {
+    tx: ring::Sender<T, !>,
+    overflow: Overflow,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for Topic<T, N> {}
+
+impl<T, const N: usize> Topic<T, N> {
+    /// Creates a new topic without subscribers.
+    #[inline]
+    pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const FREE_STATE: AtomicU8 = AtomicU8::new(FREE);
+        Self {
+            states: [FREE_STATE; N],
+            slots: UnsafeCell::new(MaybeUninit::uninit()),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Subscribes to the topic with a channel of `capacity` values and the
+    /// `overflow` policy.
+    pub fn subscribe(
+        &self,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> Result<Subscription<T>, TopicFull> {
+        let index = self
+            .states
+            .iter()
+            .position(|state| {
+                state.compare_exchange(FREE, CLAIMED, Ordering::Acquire, Ordering::Relaxed).is_ok()
+            })
+            .ok_or(TopicFull)?;
+        let (tx, rx) = ring::channel(capacity);
+        unsafe { ptr::write(self.slot(index), Slot { tx, overflow }) };
+        self.states[index].store(ACTIVE, Ordering::Release);
+        Ok(Subscription { rx })
+    }
+
+    /// Publishes the `value` to all subscribers.
+    pub fn publish(&self, value: T)
+    where
+        T: Clone,
+    {
+        for (index, state) in self.states.iter().enumerate() {
+            match state.compare_exchange(ACTIVE, CLAIMED, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => {}
+                Err(CLAIMED) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                Err(_) => continue,
+            }
+            let slot = unsafe { &mut *self.slot(index) };
+            let canceled = match slot.overflow {
+                Overflow::DropNewest => match slot.tx.send(value.clone()) {
+                    Ok(()) => false,
+                    Err(err) => {
+                        if err.kind == SendErrorKind::Overflow {
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        err.kind == SendErrorKind::Canceled
+                    }
+                },
+                Overflow::DropOldest => slot.tx.send_overwrite(value.clone()).is_err(),
+            };
+            if canceled {
+                unsafe { ptr::drop_in_place(slot) };
+                state.store(FREE, Ordering::Release);
+            } else {
+                state.store(ACTIVE, Ordering::Release);
+            }
+        }
+    }
+
+    /// Returns the number of values dropped because of subscriber overflows.
+    #[inline]
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn slot(&self, index: usize) -> *mut Slot<T> {
+        unsafe { self.slots.get().cast::<Slot<T>>().add(index) }
+    }
+}
+
+impl<T, const N: usize> Default for Topic<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Topic<T, N> {
+    fn drop(&mut self) {
+        for (index, state) in self.states.iter().enumerate() {
+            if state.load(Ordering::Relaxed) == ACTIVE {
+                unsafe { ptr::drop_in_place(self.slot(index)) };
+            }
+        }
+    }
+}
+
+impl<T> Subscription<T> {
+    /// Attempts to receive the next value without waiting.
+    #[inline]
+    pub fn try_next(&mut self) -> Option<T> {
+        let Ok(value) = self.rx.try_next();
+        value
+    }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.rx).poll_next(cx).map(|value| value.map(|Ok(value)| value))
+    }
+}
+
+impl fmt::Display for TopicFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no free topic subscriber slots")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow() {
+        let topic = Topic::<u32, 2>::new();
+        let mut newest = topic.subscribe(2, Overflow::DropNewest).unwrap();
+        let mut oldest = topic.subscribe(2, Overflow::DropOldest).unwrap();
+        assert!(topic.subscribe(2, Overflow::DropOldest).is_err());
+        for value in 0..3 {
+            topic.publish(value);
+        }
+        assert_eq!(topic.dropped(), 1);
+        assert_eq!(newest.try_next(), Some(0));
+        assert_eq!(newest.try_next(), Some(1));
+        assert_eq!(newest.try_next(), None);
+        assert_eq!(oldest.try_next(), Some(1));
+        assert_eq!(oldest.try_next(), Some(2));
+        drop(oldest);
+        topic.publish(3);
+        assert!(topic.subscribe(2, Overflow::DropOldest).is_ok());
+    }
+}
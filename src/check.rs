@@ -0,0 +1,275 @@
+//! Runtime checks and fault injection.
+//!
+//! # Invariant Checks
+//!
+//! Unlike [`assert!`], a failed [`check::ensure!`](crate::check::ensure) doesn't
+//! panic. It increments the failure counter, remembers the failure site, and
+//! writes a structured record to the log port [`PORT`] if a debug probe is
+//! listening. The record consists of three big-endian `u32` words: the
+//! [`crash::location_hash`](crate::crash::location_hash) of the failure site,
+//! the line number, and an application-defined code.
+//!
+//! ```
+//! use drone_core::check;
+//!
+//! fn on_packet(len: usize) {
+//!     check::ensure!(len <= 64, 0x10);
+//!     // Handle the packet.
+//! }
+//!
+//! on_packet(65);
+//! assert_eq!(check::failures(), 1);
+//! assert_eq!(check::last_failure().unwrap().code, 0x10);
+//! ```
+//!
+//! # Fault Injection
+//!
+//! A [`check::inject!`](crate::check::inject) call site evaluates to `true` when
+//! a fault of the given kind is armed with `check::arm`. The heap allocator and
+//! [`ring`](crate::sync::spsc::ring) senders have built-in injection sites for
+//! [`Fault::Alloc`] and [`Fault::Overflow`] respectively.
+//!
+//! Arming is available only with `fault-injection` feature. Without the feature
+//! `check::inject!` always evaluates to `false`, and is optimized out.
+//!
+//! ```
+//! use drone_core::check::{self, Fault};
+//!
+//! fn wait_ready() -> Result<(), ()> {
+//!     if check::inject!(Fault::Timeout) {
+//!         return Err(());
+//!     }
+//!     // Poll the device.
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{crash::location_hash, log::Port};
+use core::{
+    panic::Location,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// The log port number for check failure records.
+pub const PORT: u8 = 30;
+
+static FAILURES: AtomicU32 = AtomicU32::new(0);
+static LAST_LOCATION: AtomicU32 = AtomicU32::new(0);
+static LAST_LINE: AtomicU32 = AtomicU32::new(0);
+static LAST_CODE: AtomicU32 = AtomicU32::new(0);
+
+/// A failed check.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Failure {
+    /// The [`crash::location_hash`](crate::crash::location_hash) of the
+    /// failure site.
+    pub location: u32,
+    /// The line number of the failure site.
+    pub line: u32,
+    /// The application-defined code.
+    pub code: u32,
+}
+
+/// A kind of injected fault.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fault {
+    /// A memory allocation failure.
+    Alloc,
+    /// A channel overflow.
+    Overflow,
+    /// A timeout.
+    Timeout,
+}
+
+/// Checks that a boolean expression is `true` at runtime, and reports a
+/// failure otherwise.
+///
+/// An optional second argument is an application-defined `u32` code included
+/// into the failure record. See [the module-level documentation](self) for
+/// details.
+#[doc(inline)]
+pub use crate::__check_ensure as ensure;
+
+/// Evaluates to `true` if a fault of the given kind should be injected at this
+/// call site.
+///
+/// See [the module-level documentation](self) for details.
+#[doc(inline)]
+pub use crate::__check_inject as inject;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_ensure {
+    ($cond:expr $(,)?) => {
+        $crate::check::ensure!($cond, 0)
+    };
+    ($cond:expr, $code:expr $(,)?) => {
+        if !$cond {
+            $crate::check::report(::core::panic::Location::caller(), $code);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __check_inject {
+    ($fault:expr $(,)?) => {
+        $crate::check::should_fail($fault, ::core::panic::Location::caller())
+    };
+}
+
+/// Returns the number of failed checks since startup.
+#[inline]
+pub fn failures() -> u32 {
+    FAILURES.load(Ordering::Relaxed)
+}
+
+/// Returns the last failed check, if any.
+pub fn last_failure() -> Option<Failure> {
+    (failures() > 0).then(|| Failure {
+        location: LAST_LOCATION.load(Ordering::Relaxed),
+        line: LAST_LINE.load(Ordering::Relaxed),
+        code: LAST_CODE.load(Ordering::Relaxed),
+    })
+}
+
+#[doc(hidden)]
+#[cold]
+#[inline(never)]
+pub fn report(location: &Location<'_>, code: u32) {
+    let hash = location_hash(location);
+    LAST_LOCATION.store(hash, Ordering::Relaxed);
+    LAST_LINE.store(location.line(), Ordering::Relaxed);
+    LAST_CODE.store(code, Ordering::Relaxed);
+    FAILURES.fetch_add(1, Ordering::Relaxed);
+    let port = Port::new(PORT);
+    if port.is_enabled() {
+        port.write::<u32>(hash).write::<u32>(location.line()).write::<u32>(code);
+    }
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn should_fail(fault: Fault, location: &Location<'_>) -> bool {
+    #[cfg(feature = "fault-injection")]
+    return injection::should_fail(fault, location);
+    #[cfg(not(feature = "fault-injection"))]
+    {
+        let _ = (fault, location);
+        false
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+pub use self::injection::{arm, disarm, site, Trigger};
+
+#[cfg(feature = "fault-injection")]
+mod injection {
+    use super::Fault;
+    use crate::fnv::Fnv1a;
+    use core::{
+        hash::Hasher,
+        panic::Location,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    const ANY_SITE: u32 = 0;
+
+    struct Rule {
+        skip: AtomicU32,
+        count: AtomicU32,
+        site: AtomicU32,
+    }
+
+    /// A rule for injecting faults, see [`arm`].
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct Trigger {
+        /// The number of matching hits to pass before injecting faults.
+        pub skip: u32,
+        /// The number of faults to inject.
+        pub count: u32,
+        /// The call site identifier returned by [`site`], or `None` to match
+        /// any call site.
+        pub site: Option<u32>,
+    }
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const RULE: Rule =
+        Rule { skip: AtomicU32::new(0), count: AtomicU32::new(0), site: AtomicU32::new(ANY_SITE) };
+
+    static RULES: [Rule; 3] = [RULE; 3];
+
+    /// Arms injection of the `fault` according to the `trigger`. Replaces the
+    /// previous trigger for the same fault kind.
+    pub fn arm(fault: Fault, trigger: Trigger) {
+        let rule = &RULES[fault as usize];
+        rule.count.store(0, Ordering::Relaxed);
+        rule.skip.store(trigger.skip, Ordering::Relaxed);
+        rule.site.store(trigger.site.unwrap_or(ANY_SITE), Ordering::Relaxed);
+        rule.count.store(trigger.count, Ordering::Release);
+    }
+
+    /// Disarms injection of the `fault`.
+    pub fn disarm(fault: Fault) {
+        RULES[fault as usize].count.store(0, Ordering::Relaxed);
+    }
+
+    /// Returns an identifier of the call site at `file` and `line` for
+    /// [`Trigger::site`].
+    pub fn site(file: &str, line: u32) -> u32 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(file.as_bytes());
+        hasher.write(&line.to_le_bytes());
+        hasher.value() | 1
+    }
+
+    pub(super) fn should_fail(fault: Fault, location: &Location<'_>) -> bool {
+        let rule = &RULES[fault as usize];
+        if rule.count.load(Ordering::Acquire) == 0 {
+            return false;
+        }
+        let target = rule.site.load(Ordering::Relaxed);
+        if target != ANY_SITE && target != site(location.file(), location.line()) {
+            return false;
+        }
+        if rule
+            .skip
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_sub(1))
+            .is_ok()
+        {
+            return false;
+        }
+        rule.count.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_sub(1)).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure() {
+        ensure!(1 + 1 == 2);
+        assert_eq!(last_failure(), None);
+        let line = line!() + 1;
+        ensure!(1 + 1 == 3, 42);
+        let failure = last_failure().unwrap();
+        assert_eq!(failures(), 1);
+        assert_eq!(failure.line, line);
+        assert_eq!(failure.code, 42);
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[test]
+    fn inject() {
+        assert!(!inject!(Fault::Timeout));
+        arm(Fault::Timeout, Trigger { skip: 1, count: 2, site: None });
+        let hits = (0..5).map(|_| inject!(Fault::Timeout)).collect::<Vec<_>>();
+        assert_eq!(hits, [false, true, true, false, false]);
+        let line = line!() + 3;
+        arm(Fault::Timeout, Trigger { skip: 0, count: 1, site: Some(site(file!(), line)) });
+        assert!(!inject!(Fault::Timeout));
+        assert!(inject!(Fault::Timeout));
+        disarm(Fault::Timeout);
+    }
+}
@@ -0,0 +1,240 @@
+//! Clock tree management.
+//!
+//! The platform crate describes the clock tree of the MCU by implementing the
+//! [`ClockNode`] trait for oscillators, PLLs, bus prescalers, and peripheral
+//! clock gates, and registers the nodes with [`register`]. Drivers then query
+//! their bus clock frequency at runtime instead of hard-coding constants, and
+//! hold a [`ClockGuard`] while they need the clock running:
+//!
+//! ```
+//! use drone_core::clock::{self, ClockNode, ClockUsage};
+//!
+//! struct Hse;
+//!
+//! impl ClockNode for Hse {
+//!     fn freq(&self) -> u32 {
+//!         8_000_000
+//!     }
+//!
+//!     fn usage(&self) -> &ClockUsage {
+//!         static USAGE: ClockUsage = ClockUsage::new();
+//!         &USAGE
+//!     }
+//! }
+//!
+//! struct Apb1;
+//!
+//! impl ClockNode for Apb1 {
+//!     fn parent(&self) -> Option<&'static dyn ClockNode> {
+//!         Some(&Hse)
+//!     }
+//!
+//!     fn freq(&self) -> u32 {
+//!         self.parent().map_or(0, |parent| parent.freq() / 2)
+//!     }
+//!
+//!     fn usage(&self) -> &ClockUsage {
+//!         static USAGE: ClockUsage = ClockUsage::new();
+//!         &USAGE
+//!     }
+//! }
+//!
+//! clock::register("apb1", &Apb1).unwrap();
+//!
+//! // In a UART driver.
+//! let apb1 = clock::get("apb1").unwrap();
+//! let _clock = clock::enable(apb1);
+//! let brr = apb1.freq() / 115_200;
+//! assert_eq!(brr, 34);
+//! ```
+
+use crate::{
+    inventory::{Registry, RegistryFull},
+    thr::critical,
+};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The maximum number of registered [`ClockNode`]s.
+pub const MAX_NODES: usize = 32;
+
+static NODES: Registry<dyn ClockNode, MAX_NODES> = Registry::new();
+
+/// A node of the clock tree.
+pub trait ClockNode: Sync {
+    /// Returns the node this clock is derived from, or `None` for a root
+    /// clock.
+    #[inline]
+    fn parent(&self) -> Option<&'static dyn ClockNode> {
+        None
+    }
+
+    /// Returns the current output frequency in Hz.
+    fn freq(&self) -> u32;
+
+    /// Returns the usage counter of the node.
+    fn usage(&self) -> &ClockUsage;
+
+    /// Turns the clock on.
+    ///
+    /// Called by [`enable`] when the first user appears. The parent is already
+    /// on at this point.
+    #[inline]
+    fn on(&self) {}
+
+    /// Turns the clock off.
+    ///
+    /// Called when the last [`ClockGuard`] is dropped. The parent is still on
+    /// at this point.
+    #[inline]
+    fn off(&self) {}
+}
+
+/// A usage counter of a [`ClockNode`].
+pub struct ClockUsage(AtomicUsize);
+
+/// A guard, which keeps the clock node and all its ancestors on while held.
+#[must_use = "the clock is released immediately if unused"]
+pub struct ClockGuard {
+    node: &'static dyn ClockNode,
+}
+
+impl ClockUsage {
+    /// Creates a new zeroed usage counter.
+    #[inline]
+    pub const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    /// Returns the number of active users.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl Default for ClockUsage {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockGuard {
+    /// Returns the clock node.
+    #[inline]
+    pub fn node(&self) -> &'static dyn ClockNode {
+        self.node
+    }
+
+    /// Returns the current output frequency of the clock node in Hz.
+    #[inline]
+    pub fn freq(&self) -> u32 {
+        self.node.freq()
+    }
+}
+
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        critical(|_| release(self.node));
+    }
+}
+
+/// Registers a [`ClockNode`] under the `id`.
+///
+/// Returns an error if [`MAX_NODES`] nodes are already registered.
+#[inline]
+pub fn register(id: &'static str, node: &'static dyn ClockNode) -> Result<(), RegistryFull> {
+    NODES.register(id, node)
+}
+
+/// Returns the clock node registered under the `id`.
+#[inline]
+pub fn get(id: &str) -> Option<&'static dyn ClockNode> {
+    NODES.get(id)
+}
+
+/// Returns the current frequency in Hz of the clock node registered under the
+/// `id`.
+#[inline]
+pub fn freq(id: &str) -> Option<u32> {
+    get(id).map(ClockNode::freq)
+}
+
+/// Turns `node` on together with all its ancestors, and returns a guard, which
+/// turns them off when the last user is gone.
+pub fn enable(node: &'static dyn ClockNode) -> ClockGuard {
+    critical(|_| acquire(node));
+    ClockGuard { node }
+}
+
+fn acquire(node: &dyn ClockNode) {
+    if node.usage().0.fetch_add(1, Ordering::AcqRel) == 0 {
+        if let Some(parent) = node.parent() {
+            acquire(parent);
+        }
+        node.on();
+    }
+}
+
+fn release(node: &dyn ClockNode) {
+    if node.usage().0.fetch_sub(1, Ordering::AcqRel) == 1 {
+        node.off();
+        if let Some(parent) = node.parent() {
+            release(parent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+
+    struct Node {
+        parent: Option<&'static Node>,
+        usage: ClockUsage,
+        on: AtomicBool,
+    }
+
+    impl ClockNode for Node {
+        fn parent(&self) -> Option<&'static dyn ClockNode> {
+            self.parent.map(|parent| parent as _)
+        }
+
+        fn freq(&self) -> u32 {
+            self.parent.map_or(16_000_000, |parent| parent.freq() / 4)
+        }
+
+        fn usage(&self) -> &ClockUsage {
+            &self.usage
+        }
+
+        fn on(&self) {
+            assert!(self.parent.map_or(true, |parent| parent.on.load(Ordering::Relaxed)));
+            self.on.store(true, Ordering::Relaxed);
+        }
+
+        fn off(&self) {
+            self.on.store(false, Ordering::Relaxed);
+        }
+    }
+
+    static ROOT: Node = Node { parent: None, usage: ClockUsage::new(), on: AtomicBool::new(false) };
+    static BUS: Node =
+        Node { parent: Some(&ROOT), usage: ClockUsage::new(), on: AtomicBool::new(false) };
+
+    #[test]
+    fn usage_counting() {
+        register("bus", &BUS).unwrap();
+        assert_eq!(freq("bus"), Some(4_000_000));
+        let a = enable(get("bus").unwrap());
+        let b = enable(&BUS);
+        assert!(ROOT.on.load(Ordering::Relaxed) && BUS.on.load(Ordering::Relaxed));
+        assert_eq!((ROOT.usage.count(), BUS.usage.count()), (1, 2));
+        drop(a);
+        assert!(BUS.on.load(Ordering::Relaxed));
+        drop(b);
+        assert!(!ROOT.on.load(Ordering::Relaxed) && !BUS.on.load(Ordering::Relaxed));
+        assert_eq!((ROOT.usage.count(), BUS.usage.count()), (0, 0));
+    }
+}
@@ -0,0 +1,259 @@
+use core::{fmt, iter::FusedIterator, mem::MaybeUninit, ptr};
+
+/// A double-ended queue with a fixed capacity of `N` elements, implemented as a
+/// ring buffer stored inline.
+pub struct ArrayDeque<T, const N: usize> {
+    buf: MaybeUninit<[T; N]>,
+    head: usize,
+    len: usize,
+}
+
+/// An iterator over the elements of an [`ArrayDeque`].
+///
+/// This struct is created by [`ArrayDeque::iter`].
+pub struct Iter<'a, T, const N: usize> {
+    deque: &'a ArrayDeque<T, N>,
+    front: usize,
+    back: usize,
+}
+
+impl<T, const N: usize> ArrayDeque<T, N> {
+    /// Creates a new empty deque.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { buf: MaybeUninit::uninit(), head: 0, len: 0 }
+    }
+
+    /// Returns the number of elements in the deque.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the deque contains no elements.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the deque is at its full capacity.
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the capacity of the deque.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Appends the `value` to the back of the deque.
+    ///
+    /// # Errors
+    ///
+    /// If the deque is full, the `value` is returned back.
+    #[inline]
+    pub fn push_back(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        unsafe { ptr::write(self.slot_mut(self.len), value) };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Prepends the `value` to the front of the deque.
+    ///
+    /// # Errors
+    ///
+    /// If the deque is full, the `value` is returned back.
+    #[inline]
+    pub fn push_front(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        self.head = (self.head + N - 1) % N;
+        self.len += 1;
+        unsafe { ptr::write(self.slot_mut(0), value) };
+        Ok(())
+    }
+
+    /// Removes the first element and returns it, or `None` if the deque is
+    /// empty.
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let value = unsafe { ptr::read(self.slot(0)) };
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Removes the last element and returns it, or `None` if the deque is
+    /// empty.
+    #[inline]
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { ptr::read(self.slot(self.len)) })
+    }
+
+    /// Returns a reference to the element at position `index` counting from
+    /// the front, or `None` if `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        (index < self.len).then(|| unsafe { &*self.slot(index) })
+    }
+
+    /// Returns a mutable reference to the element at position `index` counting
+    /// from the front, or `None` if `index` is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        (index < self.len).then(move || unsafe { &mut *self.slot_mut(index) })
+    }
+
+    /// Returns a reference to the front element, or `None` if the deque is
+    /// empty.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns a reference to the back element, or `None` if the deque is
+    /// empty.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// Returns a front-to-back iterator.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter { deque: self, front: 0, back: self.len }
+    }
+
+    /// Returns a pair of slices which contain, in order, the contents of the
+    /// deque.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let base = self.buf.as_ptr().cast::<T>();
+        let first = self.len.min(N - self.head);
+        unsafe {
+            (
+                &*ptr::slice_from_raw_parts(base.add(self.head), first),
+                &*ptr::slice_from_raw_parts(base, self.len - first),
+            )
+        }
+    }
+
+    /// Removes all elements from the deque.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    fn slot(&self, index: usize) -> *const T {
+        unsafe { self.buf.as_ptr().cast::<T>().add((self.head + index) % N) }
+    }
+
+    fn slot_mut(&mut self, index: usize) -> *mut T {
+        let offset = (self.head + index) % N;
+        unsafe { self.buf.as_mut_ptr().cast::<T>().add(offset) }
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayDeque<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for ArrayDeque<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayDeque<T, N> {
+    fn clone(&self) -> Self {
+        let mut deque = Self::new();
+        for value in self {
+            deque.push_back(value.clone()).ok();
+        }
+        deque
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayDeque<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayDeque<T, N> {
+    type IntoIter = Iter<'a, T, N>;
+    type Item = &'a T;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(unsafe { &*self.deque.slot(self.front - 1) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(unsafe { &*self.deque.slot(self.back) })
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Iter<'_, T, N> {}
+
+impl<T, const N: usize> FusedIterator for Iter<'_, T, N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_around() {
+        let mut deque = ArrayDeque::<u8, 3>::new();
+        assert_eq!(deque.push_back(2), Ok(()));
+        assert_eq!(deque.push_front(1), Ok(()));
+        assert_eq!(deque.push_back(3), Ok(()));
+        assert_eq!(deque.push_back(4), Err(4));
+        assert_eq!(deque.as_slices(), (&[1][..], &[2, 3][..]));
+        assert_eq!(deque.iter().rev().copied().collect::<Vec<_>>(), [3, 2, 1]);
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.front(), Some(&2));
+        assert_eq!(deque.back(), Some(&2));
+    }
+}
@@ -0,0 +1,174 @@
+use super::{ArrayVec, CapacityError};
+use core::{fmt, ops::Deref, str};
+
+/// A UTF-8 string with a fixed capacity of `N` bytes, stored inline.
+///
+/// Writing through [`core::fmt::Write`] fails with [`fmt::Error`] when the
+/// string is full. The part that fitted into the capacity is kept.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ArrayString<const N: usize> {
+    vec: ArrayVec<u8, N>,
+}
+
+impl<const N: usize> ArrayString<N> {
+    /// Creates a new empty string.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { vec: ArrayVec::new() }
+    }
+
+    /// Creates a new string with a copy of `s`.
+    ///
+    /// # Errors
+    ///
+    /// If `s` is longer than `N` bytes.
+    #[inline]
+    pub fn try_from_str(s: &str) -> Result<Self, CapacityError> {
+        let mut string = Self::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+
+    /// Returns the length of the string in bytes.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Returns `true` if the string has zero length.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Returns the capacity of the string in bytes.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Appends the `s` string slice to the end of the string.
+    ///
+    /// # Errors
+    ///
+    /// If there is not enough capacity for `s`, the string is not modified.
+    #[inline]
+    pub fn push_str(&mut self, s: &str) -> Result<(), CapacityError> {
+        self.vec.extend_from_slice(s.as_bytes())
+    }
+
+    /// Appends the `ch` character to the end of the string.
+    ///
+    /// # Errors
+    ///
+    /// If there is not enough capacity for `ch`, the string is not modified.
+    #[inline]
+    pub fn push(&mut self, ch: char) -> Result<(), CapacityError> {
+        self.push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Removes the last character from the string and returns it, or `None` if
+    /// it is empty.
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.vec.truncate(self.len() - ch.len_utf8());
+        Some(ch)
+    }
+
+    /// Shortens the string to `len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `len` doesn't lie on a character boundary.
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        assert!(self.as_str().is_char_boundary(len), "truncation length is not a char boundary");
+        self.vec.truncate(len);
+    }
+
+    /// Removes all contents of the string.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.vec.clear();
+    }
+
+    /// Returns a string slice of the entire string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.vec.as_slice()) }
+    }
+
+    /// Returns a byte slice of the string contents.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.vec.as_slice()
+    }
+}
+
+impl<const N: usize> Deref for ArrayString<N> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> fmt::Write for ArrayString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.push_str(s).is_ok() {
+            return Ok(());
+        }
+        let mut len = N - self.len();
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.push_str(&s[..len]).ok();
+        Err(fmt::Error)
+    }
+}
+
+impl<const N: usize> fmt::Display for ArrayString<N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayString<N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> PartialEq<str> for ArrayString<N> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn write_overflow() {
+        let mut string = ArrayString::<6>::new();
+        assert_eq!(string.push('a'), Ok(()));
+        assert_eq!(write!(string, "{}", "bcd"), Ok(()));
+        assert_eq!(write!(string, "é€"), Err(fmt::Error));
+        assert_eq!(string, "abcdé");
+        assert_eq!(string.pop(), Some('é'));
+        assert_eq!(string, "abcd");
+    }
+}
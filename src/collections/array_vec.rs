@@ -0,0 +1,366 @@
+use super::CapacityError;
+use core::{
+    fmt,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr, slice,
+};
+
+/// A vector with a fixed capacity of `N` elements, stored inline.
+pub struct ArrayVec<T, const N: usize> {
+    buf: MaybeUninit<[T; N]>,
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    /// Creates a new empty vector.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { buf: MaybeUninit::uninit(), len: 0 }
+    }
+
+    /// Returns the number of elements in the vector.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the vector is at its full capacity.
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the capacity of the vector.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Appends the `value` to the back of the vector.
+    ///
+    /// # Errors
+    ///
+    /// If the vector is full, the `value` is returned back.
+    #[inline]
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        unsafe { ptr::write(self.as_mut_ptr().add(self.len), value) };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the last element from the vector and returns it, or `None` if
+    /// it is empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { ptr::read(self.as_ptr().add(self.len)) })
+    }
+
+    /// Inserts the `value` at position `index`, shifting all elements after it
+    /// to the right.
+    ///
+    /// # Errors
+    ///
+    /// If the vector is full, the `value` is returned back.
+    ///
+    /// # Panics
+    ///
+    /// If `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), T> {
+        assert!(index <= self.len, "insertion index is out of bounds");
+        if self.is_full() {
+            return Err(value);
+        }
+        unsafe {
+            let ptr = self.as_mut_ptr().add(index);
+            ptr::copy(ptr, ptr.add(1), self.len - index);
+            ptr::write(ptr, value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `index`, shifting all
+    /// elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "removal index is out of bounds");
+        self.len -= 1;
+        unsafe {
+            let ptr = self.as_mut_ptr().add(index);
+            let value = ptr::read(ptr);
+            ptr::copy(ptr.add(1), ptr, self.len - index);
+            value
+        }
+    }
+
+    /// Removes and returns the element at position `index`, replacing it with
+    /// the last element.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "removal index is out of bounds");
+        let last = self.len - 1;
+        self.swap(index, last);
+        self.len = last;
+        unsafe { ptr::read(self.as_ptr().add(last)) }
+    }
+
+    /// Retains only the elements specified by the predicate `f`.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let len = self.len;
+        // The elements are dropped and moved while the predicate runs, so the
+        // length is restored by the guard, even if the predicate panics.
+        self.len = 0;
+        let mut guard = RetainGuard { vec: self, len, processed: 0, kept: 0 };
+        while guard.processed < guard.len {
+            unsafe {
+                let ptr = guard.vec.as_mut_ptr().add(guard.processed);
+                let keep = f(&*ptr);
+                guard.processed += 1;
+                if keep {
+                    ptr::copy(ptr, guard.vec.as_mut_ptr().add(guard.kept), 1);
+                    guard.kept += 1;
+                } else {
+                    ptr::drop_in_place(ptr);
+                }
+            }
+        }
+    }
+
+    /// Shortens the vector to `len` elements, dropping the rest.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            let tail = ptr::slice_from_raw_parts_mut(
+                unsafe { self.as_mut_ptr().add(len) },
+                self.len - len,
+            );
+            self.len = len;
+            unsafe { ptr::drop_in_place(tail) };
+        }
+    }
+
+    /// Removes all elements from the vector.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Clones and appends all elements of `other` to the vector.
+    ///
+    /// # Errors
+    ///
+    /// If there is not enough capacity for all elements of `other`, the vector
+    /// is not modified.
+    pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), CapacityError>
+    where
+        T: Clone,
+    {
+        if other.len() > N - self.len {
+            return Err(CapacityError);
+        }
+        for value in other {
+            unsafe { ptr::write(self.as_mut_ptr().add(self.len), value.clone()) };
+            self.len += 1;
+        }
+        Ok(())
+    }
+
+    /// Returns a slice of all elements.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    /// Returns a mutable slice of all elements.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    /// Returns a raw pointer to the vector's buffer.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        self.buf.as_ptr().cast()
+    }
+
+    /// Returns a raw mutable pointer to the vector's buffer.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.buf.as_mut_ptr().cast()
+    }
+
+    /// Sets the length of the vector.
+    ///
+    /// # Safety
+    ///
+    /// `len` must not exceed the capacity, and the elements up to `len` must be
+    /// initialized.
+    #[inline]
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut vec = Self::new();
+        vec.extend_from_slice(self).ok();
+        vec
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for ArrayVec<T, N> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for ArrayVec<T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type IntoIter = slice::Iter<'a, T>;
+    type Item = &'a T;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayVec<T, N> {
+    type IntoIter = slice::IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+struct RetainGuard<'a, T, const N: usize> {
+    vec: &'a mut ArrayVec<T, N>,
+    len: usize,
+    processed: usize,
+    kept: usize,
+}
+
+impl<T, const N: usize> Drop for RetainGuard<'_, T, N> {
+    fn drop(&mut self) {
+        // Move the unprocessed elements after the kept ones.
+        let tail = self.len - self.processed;
+        unsafe {
+            let ptr = self.vec.as_mut_ptr();
+            ptr::copy(ptr.add(self.processed), ptr.add(self.kept), tail);
+        }
+        self.vec.len = self.kept + tail;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_insert_remove() {
+        let mut vec = ArrayVec::<u8, 4>::new();
+        assert_eq!(vec.push(1), Ok(()));
+        assert_eq!(vec.push(3), Ok(()));
+        assert_eq!(vec.insert(1, 2), Ok(()));
+        assert_eq!(vec.push(4), Ok(()));
+        assert_eq!(vec.push(5), Err(5));
+        assert_eq!(vec.as_slice(), &[1, 2, 3, 4]);
+        assert_eq!(vec.remove(0), 1);
+        assert_eq!(vec.swap_remove(0), 2);
+        assert_eq!(vec.as_slice(), &[4, 3]);
+        vec.retain(|&x| x != 4);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn drop_elements() {
+        let rc = std::rc::Rc::new(());
+        let mut vec = ArrayVec::<_, 3>::new();
+        vec.extend_from_slice(&[rc.clone(), rc.clone(), rc.clone()]).unwrap();
+        assert_eq!(std::rc::Rc::strong_count(&rc), 4);
+        vec.truncate(1);
+        assert_eq!(std::rc::Rc::strong_count(&rc), 2);
+        drop(vec);
+        assert_eq!(std::rc::Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn retain_panic() {
+        let rc = std::rc::Rc::new(());
+        let mut vec = ArrayVec::<_, 4>::new();
+        vec.extend_from_slice(&[rc.clone(), rc.clone(), rc.clone(), rc.clone()]).unwrap();
+        let mut calls = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vec.retain(|_| {
+                calls += 1;
+                assert!(calls < 3);
+                calls != 1
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.len(), 3);
+        assert_eq!(std::rc::Rc::strong_count(&rc), 4);
+        drop(vec);
+        assert_eq!(std::rc::Rc::strong_count(&rc), 1);
+    }
+}
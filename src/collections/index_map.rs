@@ -0,0 +1,262 @@
+use super::ArrayVec;
+use crate::fnv::Fnv1a;
+use core::{borrow::Borrow, fmt, hash::Hash, iter::FusedIterator, slice};
+
+/// A hash map with a fixed capacity of `N` entries, stored inline.
+///
+/// Entries are kept in insertion order and can be accessed by their index.
+/// Lookups compare cached 32-bit FNV-1a hashes before comparing keys, which is
+/// efficient for the small capacities typical for embedded systems.
+///
+/// ```
+/// use drone_core::collections::IndexMap;
+///
+/// let mut map = IndexMap::<&str, u32, 4>::new();
+/// assert_eq!(map.insert("uart", 115_200), Ok(None));
+/// assert_eq!(map.insert("spi", 8_000_000), Ok(None));
+/// assert_eq!(map.get("uart"), Some(&115_200));
+/// assert_eq!(map.get_index(1), Some((&"spi", &8_000_000)));
+/// ```
+pub struct IndexMap<K, V, const N: usize> {
+    entries: ArrayVec<Bucket<K, V>, N>,
+}
+
+/// An iterator over the entries of an [`IndexMap`].
+///
+/// This struct is created by [`IndexMap::iter`].
+pub struct Iter<'a, K, V> {
+    iter: slice::Iter<'a, Bucket<K, V>>,
+}
+
+#[derive(Clone)]
+struct Bucket<K, V> {
+    hash: u32,
+    key: K,
+    value: V,
+}
+
+impl<K, V, const N: usize> IndexMap<K, V, N> {
+    /// Creates a new empty map.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { entries: ArrayVec::new() }
+    }
+
+    /// Returns the number of entries in the map.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the capacity of the map.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the key-value pair at position `index`, or `None` if `index` is
+    /// out of bounds.
+    #[inline]
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    /// Returns an iterator over the entries in insertion order.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { iter: self.entries.iter() }
+    }
+
+    /// Returns an iterator over the keys in insertion order.
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over the values in insertion order.
+    #[inline]
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Returns an iterator over mutable references to the values in insertion
+    /// order.
+    #[inline]
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.iter_mut().map(|bucket| &mut bucket.value)
+    }
+
+    /// Removes all entries from the map.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> IndexMap<K, V, N> {
+    /// Inserts a key-value pair into the map. If the map already had the `key`,
+    /// the value is replaced, and the old value is returned.
+    ///
+    /// # Errors
+    ///
+    /// If the map is full and doesn't contain the `key`, the pair is returned
+    /// back.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        let hash = hash(&key);
+        if let Some(index) = self.find(hash, &key) {
+            return Ok(Some(core::mem::replace(&mut self.entries[index].value, value)));
+        }
+        self.entries
+            .push(Bucket { hash, key, value })
+            .map(|()| None)
+            .map_err(|bucket| (bucket.key, bucket.value))
+    }
+
+    /// Returns a reference to the value corresponding to the `key`.
+    #[inline]
+    pub fn get<Q: ?Sized + Hash + Eq>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.get_index_of(key).map(|index| &self.entries[index].value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `key`.
+    #[inline]
+    pub fn get_mut<Q: ?Sized + Hash + Eq>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        self.get_index_of(key).map(move |index| &mut self.entries[index].value)
+    }
+
+    /// Returns the position of the `key` in insertion order.
+    #[inline]
+    pub fn get_index_of<Q: ?Sized + Hash + Eq>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        self.find(hash(key), key)
+    }
+
+    /// Returns `true` if the map contains the `key`.
+    #[inline]
+    pub fn contains_key<Q: ?Sized + Hash + Eq>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.get_index_of(key).is_some()
+    }
+
+    /// Removes the `key` from the map, returning its value. The last entry
+    /// takes the place of the removed one, which changes the insertion order.
+    pub fn swap_remove<Q: ?Sized + Hash + Eq>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.get_index_of(key).map(|index| self.entries.swap_remove(index).value)
+    }
+
+    /// Removes the `key` from the map, returning its value. The following
+    /// entries are shifted, which preserves the insertion order.
+    pub fn shift_remove<Q: ?Sized + Hash + Eq>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.get_index_of(key).map(|index| self.entries.remove(index).value)
+    }
+
+    fn find<Q: ?Sized + Eq>(&self, hash: u32, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+    {
+        self.entries.iter().position(|bucket| bucket.hash == hash && bucket.key.borrow() == key)
+    }
+}
+
+impl<K, V, const N: usize> Default for IndexMap<K, V, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone, V: Clone, const N: usize> Clone for IndexMap<K, V, N> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { entries: self.entries.clone() }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for IndexMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a IndexMap<K, V, N> {
+    type IntoIter = Iter<'a, K, V>;
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|bucket| (&bucket.key, &bucket.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+fn hash<Q: ?Sized + Hash>(key: &Q) -> u32 {
+    let mut hasher = Fnv1a::new();
+    key.hash(&mut hasher);
+    hasher.value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_remove() {
+        let mut map = IndexMap::<u32, char, 3>::new();
+        assert_eq!(map.insert(1, 'a'), Ok(None));
+        assert_eq!(map.insert(2, 'b'), Ok(None));
+        assert_eq!(map.insert(3, 'c'), Ok(None));
+        assert_eq!(map.insert(4, 'd'), Err((4, 'd')));
+        assert_eq!(map.insert(2, 'B'), Ok(Some('b')));
+        assert_eq!(map.swap_remove(&1), Some('a'));
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), [3, 2]);
+        assert_eq!(map.shift_remove(&3), Some('c'));
+        assert_eq!(map.get(&2), Some(&'B'));
+        assert_eq!(map.get(&3), None);
+    }
+}
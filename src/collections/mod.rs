@@ -0,0 +1,55 @@
+//! Fixed-capacity collections.
+//!
+//! The collections in this module store their elements inline, never allocate,
+//! and can be created in `const` context, so they can be placed in statics or
+//! used before the heap is initialized:
+//!
+//! - [`ArrayVec`] - a vector with a fixed capacity.
+//! - [`ArrayString`] - a UTF-8 string with a fixed capacity.
+//! - [`ArrayDeque`] - a double-ended ring buffer with a fixed capacity.
+//! - [`IndexMap`] - a hash map with a fixed capacity, which preserves insertion
+//!   order.
+//!
+//! When a collection is full, an insertion returns an error instead of
+//! panicking. Operations don't lock or allocate, so they are safe to use from
+//! interrupt handlers, given that the collection itself is accessed by a single
+//! context at a time. For passing values between contexts see
+//! [`thr::WorkQueue`](crate::thr::WorkQueue) and [`sync::spsc`](crate::sync::spsc).
+//!
+//! ```
+//! use core::fmt::Write;
+//! use drone_core::collections::{ArrayString, ArrayVec};
+//!
+//! let mut vec = ArrayVec::<u32, 4>::new();
+//! vec.push(1).unwrap();
+//! vec.push(2).unwrap();
+//! assert_eq!(vec.as_slice(), &[1, 2]);
+//!
+//! let mut string = ArrayString::<16>::new();
+//! write!(string, "{}:{}", vec[0], vec[1]).unwrap();
+//! assert_eq!(string.as_str(), "1:2");
+//! ```
+
+mod array_deque;
+mod array_string;
+mod array_vec;
+mod index_map;
+
+pub use self::{
+    array_deque::{ArrayDeque, Iter as ArrayDequeIter},
+    array_string::ArrayString,
+    array_vec::ArrayVec,
+    index_map::{IndexMap, Iter as IndexMapIter},
+};
+
+use core::fmt;
+
+/// The error type returned when a fixed-capacity collection is full.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
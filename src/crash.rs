@@ -0,0 +1,413 @@
+//! Persistent crash records.
+//!
+//! Before the device is reset by a panic or an out-of-memory condition, a
+//! compact crash record is stored in a memory region which survives the reset.
+//! On the next boot the application calls [`init`] and then can query
+//! [`last_reason`] to report why the device was last rebooted.
+//!
+//! By default the record is stored in a static placed into the `.noinit` linker
+//! section, which must be excluded from the BSS/DATA initialization by the
+//! linker script. An application may provide its own storage (e.g. backup
+//! registers or an RTC domain) by implementing the [`CrashBackend`] trait and
+//! registering it with the [`set_crash_backend!`](crate::set_crash_backend)
+//! macro.
+//!
+//! ```no_run
+//! use drone_core::crash;
+//!
+//! unsafe { crash::init() };
+//! if let Some(reason) = crash::last_reason() {
+//!     drone_core::eprintln!("rebooted after {:?}, boot #{}", reason, crash::boot_count());
+//! }
+//! ```
+//!
+//! # Backtraces
+//!
+//! With `panic-backtrace` feature, a panic record also holds up to
+//! `BACKTRACE_DEPTH` return addresses collected by following the chain of
+//! frame records, each of which is a pair of the caller's frame pointer and
+//! the return address. The chain is followed while it stays within the
+//! [stack](crate::mem::stack) and goes upwards. After the reboot the addresses
+//! are available with `last_backtrace`, and can be symbolicated on the host
+//! with `addr2line` against the application ELF file.
+//!
+//! The application must be built with `-C force-frame-pointers=yes`, and the
+//! platform crate must provide `drone_frame_pointer` function, which returns
+//! the frame pointer of its caller.
+
+use crate::fnv::Fnv1a;
+#[cfg(feature = "panic-backtrace")]
+use crate::mem::stack;
+use core::{
+    hash::Hasher,
+    mem,
+    panic::Location,
+    ptr,
+    sync::atomic::{AtomicU32, AtomicU8, Ordering},
+};
+#[cfg(feature = "panic-backtrace")]
+use core::{mem::size_of, ops::Range};
+
+/// The maximum number of return addresses in a panic backtrace.
+#[cfg(feature = "panic-backtrace")]
+pub const BACKTRACE_DEPTH: usize = 8;
+
+const MAGIC: u32 = 0xDEAD_B007;
+
+const KIND_NONE: u8 = 0;
+const KIND_PANIC: u8 = 1;
+const KIND_OOM: u8 = 2;
+
+static LAST_KIND: AtomicU8 = AtomicU8::new(KIND_NONE);
+static LAST_VALUE: AtomicU32 = AtomicU32::new(0);
+static BOOT_COUNT: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(feature = "panic-backtrace")]
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(feature = "panic-backtrace")]
+static PENDING_BACKTRACE: [AtomicU32; BACKTRACE_DEPTH] = [ZERO; BACKTRACE_DEPTH];
+#[cfg(feature = "panic-backtrace")]
+static LAST_BACKTRACE: [AtomicU32; BACKTRACE_DEPTH] = [ZERO; BACKTRACE_DEPTH];
+
+#[cfg_attr(not(feature = "std"), link_section = ".noinit")]
+static mut RECORD: CrashRecord = CrashRecord::ZERO;
+
+/// The reason of a device reset.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reason {
+    /// The device was reset by a panic.
+    Panic {
+        /// A hash of the panic source location. See [`location_hash`].
+        location: u32,
+    },
+    /// The device was reset by an out-of-memory condition.
+    Oom {
+        /// The size of the failed allocation.
+        size: u32,
+    },
+}
+
+/// The size of a [`CrashRecord`] serialized with [`CrashRecord::to_bytes`].
+pub const RECORD_SIZE: usize = mem::size_of::<CrashRecord>();
+
+/// A compact raw crash record as it is stored in memory.
+///
+/// A [`CrashBackend`] storing the record outside of the RAM, e.g. in backup
+/// registers, can serialize it with [`to_bytes`](CrashRecord::to_bytes) and
+/// [`from_bytes`](CrashRecord::from_bytes).
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CrashRecord {
+    magic: u32,
+    boot_count: u32,
+    kind: u32,
+    value: u32,
+    checksum: u32,
+    #[cfg(feature = "panic-backtrace")]
+    backtrace: [u32; BACKTRACE_DEPTH],
+}
+
+/// A storage for crash records, which survives a device reset.
+pub trait CrashBackend {
+    /// Reads the stored record.
+    fn load() -> CrashRecord;
+
+    /// Stores the `record`.
+    fn store(record: &CrashRecord);
+}
+
+/// The default crash backend, which keeps the record in the `.noinit` linker
+/// section.
+pub struct NoInit;
+
+impl CrashBackend for NoInit {
+    fn load() -> CrashRecord {
+        unsafe { ptr::read_volatile(ptr::addr_of!(RECORD)) }
+    }
+
+    fn store(record: &CrashRecord) {
+        unsafe { ptr::write_volatile(ptr::addr_of_mut!(RECORD), *record) };
+    }
+}
+
+/// Registers `$backend` type as the crash record storage.
+///
+/// The type must implement [`CrashBackend`](crate::crash::CrashBackend). See
+/// [the module-level documentation](crate::crash) for details.
+#[macro_export]
+macro_rules! set_crash_backend {
+    ($backend:ty) => {
+        #[no_mangle]
+        fn drone_crash_load() -> $crate::crash::CrashRecord {
+            <$backend as $crate::crash::CrashBackend>::load()
+        }
+
+        #[no_mangle]
+        fn drone_crash_store(record: &$crate::crash::CrashRecord) {
+            <$backend as $crate::crash::CrashBackend>::store(record)
+        }
+    };
+}
+
+impl CrashRecord {
+    const ZERO: Self = Self {
+        magic: 0,
+        boot_count: 0,
+        kind: 0,
+        value: 0,
+        checksum: 0,
+        #[cfg(feature = "panic-backtrace")]
+        backtrace: [0; BACKTRACE_DEPTH],
+    };
+
+    fn new(boot_count: u32, kind: u8, value: u32) -> Self {
+        let mut record =
+            Self { magic: MAGIC, boot_count, kind: u32::from(kind), value, ..Self::ZERO };
+        #[cfg(feature = "panic-backtrace")]
+        if kind == KIND_PANIC {
+            for (address, pending) in record.backtrace.iter_mut().zip(&PENDING_BACKTRACE) {
+                *address = pending.load(Ordering::Relaxed);
+            }
+        }
+        record.checksum = record.checksum();
+        record
+    }
+
+    /// Serializes the record as little-endian 32-bit words.
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        let header = [self.magic, self.boot_count, self.kind, self.value, self.checksum];
+        #[cfg(not(feature = "panic-backtrace"))]
+        let words = header.iter();
+        #[cfg(feature = "panic-backtrace")]
+        let words = header.iter().chain(&self.backtrace);
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserializes a record produced by [`to_bytes`](CrashRecord::to_bytes).
+    ///
+    /// The record is not validated here. A corrupted record is ignored by
+    /// [`init`].
+    pub fn from_bytes(bytes: &[u8; RECORD_SIZE]) -> Self {
+        let mut words = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        let mut next = || words.next().unwrap_or(0);
+        Self {
+            magic: next(),
+            boot_count: next(),
+            kind: next(),
+            value: next(),
+            checksum: next(),
+            #[cfg(feature = "panic-backtrace")]
+            backtrace: {
+                let mut backtrace = [0; BACKTRACE_DEPTH];
+                for address in &mut backtrace {
+                    *address = next();
+                }
+                backtrace
+            },
+        }
+    }
+
+    /// Returns `true` if the record has a valid signature and checksum.
+    pub fn is_valid(&self) -> bool {
+        self.magic == MAGIC && self.checksum == self.checksum()
+    }
+
+    fn checksum(&self) -> u32 {
+        let checksum =
+            !(MAGIC ^ self.boot_count ^ self.kind.rotate_left(8) ^ self.value.rotate_left(16));
+        #[cfg(feature = "panic-backtrace")]
+        let checksum = self
+            .backtrace
+            .iter()
+            .fold(checksum, |checksum, &address| checksum.rotate_left(5) ^ address);
+        checksum
+    }
+}
+
+/// Reads the crash record left by the previous run and prepares a new one.
+///
+/// This function should be called once early at startup, after the BSS and
+/// DATA segments are initialized.
+///
+/// # Safety
+///
+/// This function must not be called concurrently with [`store`].
+pub unsafe fn init() {
+    let record = drone_crash_load();
+    if record.is_valid() {
+        LAST_KIND.store(record.kind as u8, Ordering::Relaxed);
+        LAST_VALUE.store(record.value, Ordering::Relaxed);
+        #[cfg(feature = "panic-backtrace")]
+        for (last, &address) in LAST_BACKTRACE.iter().zip(&record.backtrace) {
+            last.store(address, Ordering::Relaxed);
+        }
+        let boot_count = record.boot_count.wrapping_add(1);
+        BOOT_COUNT.store(boot_count, Ordering::Relaxed);
+        drone_crash_store(&CrashRecord::new(boot_count, KIND_NONE, 0));
+    } else {
+        drone_crash_store(&CrashRecord::new(0, KIND_NONE, 0));
+    }
+}
+
+/// Returns the reason of the last reset, if it was caused by a crash.
+///
+/// Returns `None` after a power-on or a regular reset, or if [`init`] was not
+/// called.
+pub fn last_reason() -> Option<Reason> {
+    let value = LAST_VALUE.load(Ordering::Relaxed);
+    match LAST_KIND.load(Ordering::Relaxed) {
+        KIND_PANIC => Some(Reason::Panic { location: value }),
+        KIND_OOM => Some(Reason::Oom { size: value }),
+        _ => None,
+    }
+}
+
+/// Returns the return addresses of the panic which caused the last reset,
+/// innermost first. Unused trailing entries are zero.
+///
+/// All entries are zero if the last reset wasn't caused by a panic. See
+/// [the module-level documentation](self#backtraces) for details.
+#[cfg(feature = "panic-backtrace")]
+pub fn last_backtrace() -> [u32; BACKTRACE_DEPTH] {
+    let mut backtrace = [0; BACKTRACE_DEPTH];
+    for (address, last) in backtrace.iter_mut().zip(&LAST_BACKTRACE) {
+        *address = last.load(Ordering::Relaxed);
+    }
+    backtrace
+}
+
+/// Returns the number of resets since the last power-on.
+pub fn boot_count() -> u32 {
+    BOOT_COUNT.load(Ordering::Relaxed)
+}
+
+/// Stores the crash `reason` to survive the upcoming reset.
+pub fn store(reason: Reason) {
+    let (kind, value) = match reason {
+        Reason::Panic { location } => (KIND_PANIC, location),
+        Reason::Oom { size } => (KIND_OOM, size),
+    };
+    drone_crash_store(&CrashRecord::new(boot_count(), kind, value));
+}
+
+/// Computes a compact 32-bit hash (FNV-1a) of the source `location`.
+pub fn location_hash(location: &Location<'_>) -> u32 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(location.file().as_bytes());
+    hasher.write(&location.line().to_le_bytes());
+    hasher.write(&location.column().to_le_bytes());
+    hasher.value()
+}
+
+/// Collects the return addresses starting from the frame pointer `fp` to be
+/// stored with the next panic record.
+///
+/// # Safety
+///
+/// `fp` must be zero or the frame pointer of a live frame in the stack.
+#[cfg(feature = "panic-backtrace")]
+#[cfg_attr(feature = "std", allow(dead_code))]
+pub(crate) unsafe fn capture_backtrace(fp: usize) {
+    let mut backtrace = [0; BACKTRACE_DEPTH];
+    unsafe { walk(fp, stack::range(), &mut backtrace) };
+    for (pending, &address) in PENDING_BACKTRACE.iter().zip(&backtrace) {
+        pending.store(address, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "panic-backtrace")]
+#[allow(clippy::cast_possible_truncation)]
+unsafe fn walk(mut fp: usize, stack: Range<usize>, backtrace: &mut [u32]) {
+    for address in backtrace {
+        if fp < stack.start
+            || fp.saturating_add(2 * size_of::<usize>()) > stack.end
+            || fp % size_of::<usize>() != 0
+        {
+            break;
+        }
+        let frame = fp as *const usize;
+        let (next, ret) = unsafe { (frame.read_volatile(), frame.add(1).read_volatile()) };
+        if ret == 0 {
+            break;
+        }
+        *address = ret as u32;
+        if next <= fp {
+            break;
+        }
+        fp = next;
+    }
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_crash_load() -> CrashRecord {
+    NoInit::load()
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_crash_store(record: &CrashRecord) {
+    NoInit::store(record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes() {
+        let record = CrashRecord::new(7, KIND_OOM, 0x1234);
+        let bytes = record.to_bytes();
+        assert_eq!(bytes[..4], MAGIC.to_le_bytes());
+        assert_eq!(bytes[4..8], 7_u32.to_le_bytes());
+        let decoded = CrashRecord::from_bytes(&bytes);
+        assert_eq!(decoded, record);
+        assert!(decoded.is_valid());
+        let mut bytes = bytes;
+        bytes[12] ^= 1;
+        assert!(!CrashRecord::from_bytes(&bytes).is_valid());
+    }
+
+    #[cfg(feature = "panic-backtrace")]
+    #[test]
+    fn walk_chain() {
+        let mut stack = [0_usize; 8];
+        let base = stack.as_ptr() as usize;
+        let word = size_of::<usize>();
+        stack[0] = base + 2 * word;
+        stack[1] = 0x100;
+        stack[2] = base + 4 * word;
+        stack[3] = 0x200;
+        stack[4] = base + 2 * word;
+        stack[5] = 0x300;
+        let mut backtrace = [0; 4];
+        unsafe { walk(base, base..base + 8 * word, &mut backtrace) };
+        assert_eq!(backtrace, [0x100, 0x200, 0x300, 0]);
+        let mut backtrace = [0; 4];
+        unsafe { walk(base + 4 * word, base..base + 5 * word, &mut backtrace) };
+        assert_eq!(backtrace, [0; 4]);
+        let mut backtrace = [0; 2];
+        unsafe { walk(base, base..base + 8 * word, &mut backtrace) };
+        assert_eq!(backtrace, [0x100, 0x200]);
+    }
+
+    #[cfg(feature = "panic-backtrace")]
+    #[test]
+    fn record_checksum() {
+        PENDING_BACKTRACE[0].store(0x0800_1235, Ordering::Relaxed);
+        let mut record = CrashRecord::new(3, KIND_PANIC, 42);
+        assert_eq!(record.backtrace[0], 0x0800_1235);
+        assert!(record.is_valid());
+        record.backtrace[1] = 1;
+        assert!(!record.is_valid());
+        assert_eq!(CrashRecord::new(3, KIND_OOM, 42).backtrace, [0; BACKTRACE_DEPTH]);
+    }
+}
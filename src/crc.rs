@@ -0,0 +1,335 @@
+//! Cyclic redundancy checks.
+//!
+//! This module provides CRC-8, CRC-16, and CRC-32 with configurable
+//! parameters. Each width comes in two flavors: a table-free one ([`Crc8`],
+//! [`Crc16`], [`Crc32`]), which is small and suitable for bootloaders, and a
+//! table-based one ([`Crc8Table`], [`Crc16Table`], [`Crc32Table`]), which is
+//! faster at the cost of a 256-entry lookup table. All constructors and
+//! computations are `const fn`, so the tables can be generated at compile
+//! time:
+//!
+//! ```
+//! use drone_core::crc::{Crc32Table, CRC32_ISO_HDLC};
+//!
+//! static CRC: Crc32Table = Crc32Table::new(CRC32_ISO_HDLC);
+//!
+//! assert_eq!(CRC.checksum(b"123456789"), 0xCBF4_3926);
+//! ```
+//!
+//! Data can also be processed incrementally:
+//!
+//! ```
+//! use drone_core::crc::{Crc16, CRC16_MODBUS};
+//!
+//! const CRC: Crc16 = Crc16::new(CRC16_MODBUS);
+//!
+//! let mut crc = CRC.init();
+//! crc = CRC.update(crc, b"1234");
+//! crc = CRC.update(crc, b"56789");
+//! assert_eq!(CRC.finalize(crc), 0x4B37);
+//! ```
+
+/// CRC algorithm parameters in the Rocksoft model.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Params<W> {
+    /// Generator polynomial in the normal (non-reflected) form, without the top
+    /// bit.
+    pub poly: W,
+    /// Initial register value.
+    pub init: W,
+    /// Whether input bytes are reflected.
+    pub refin: bool,
+    /// Whether the final register value is reflected.
+    pub refout: bool,
+    /// Value XORed with the final register value.
+    pub xorout: W,
+}
+
+/// CRC-8/SMBUS parameters.
+pub const CRC8_SMBUS: Params<u8> =
+    Params { poly: 0x07, init: 0x00, refin: false, refout: false, xorout: 0x00 };
+
+/// CRC-8/MAXIM-DOW (Dallas 1-Wire) parameters.
+pub const CRC8_MAXIM_DOW: Params<u8> =
+    Params { poly: 0x31, init: 0x00, refin: true, refout: true, xorout: 0x00 };
+
+/// CRC-16/IBM-3740 (also known as CRC-16/CCITT-FALSE) parameters.
+pub const CRC16_IBM_3740: Params<u16> =
+    Params { poly: 0x1021, init: 0xFFFF, refin: false, refout: false, xorout: 0x0000 };
+
+/// CRC-16/XMODEM parameters.
+pub const CRC16_XMODEM: Params<u16> =
+    Params { poly: 0x1021, init: 0x0000, refin: false, refout: false, xorout: 0x0000 };
+
+/// CRC-16/MODBUS parameters.
+pub const CRC16_MODBUS: Params<u16> =
+    Params { poly: 0x8005, init: 0xFFFF, refin: true, refout: true, xorout: 0x0000 };
+
+/// CRC-32/ISO-HDLC (Ethernet, zlib) parameters.
+pub const CRC32_ISO_HDLC: Params<u32> =
+    Params { poly: 0x04C1_1DB7, init: 0xFFFF_FFFF, refin: true, refout: true, xorout: 0xFFFF_FFFF };
+
+/// CRC-32/ISCSI (Castagnoli) parameters.
+pub const CRC32_ISCSI: Params<u32> =
+    Params { poly: 0x1EDC_6F41, init: 0xFFFF_FFFF, refin: true, refout: true, xorout: 0xFFFF_FFFF };
+
+/// CRC-32/MPEG-2 parameters. This is the algorithm implemented by the CRC
+/// peripheral of many microcontrollers.
+pub const CRC32_MPEG_2: Params<u32> = Params {
+    poly: 0x04C1_1DB7,
+    init: 0xFFFF_FFFF,
+    refin: false,
+    refout: false,
+    xorout: 0x0000_0000,
+};
+
+macro_rules! crc {
+    ($(#[$bitwise_attr:meta])* $bitwise:ident, $(#[$table_attr:meta])* $table:ident, $w:ty) => {
+        $(#[$bitwise_attr])*
+        #[derive(Clone, Copy, Debug)]
+        pub struct $bitwise {
+            params: Params<$w>,
+            poly: $w,
+        }
+
+        $(#[$table_attr])*
+        #[derive(Clone)]
+        pub struct $table {
+            params: Params<$w>,
+            table: [$w; 256],
+        }
+
+        impl $bitwise {
+            /// Creates a new table-free CRC with the given parameters.
+            #[inline]
+            pub const fn new(params: Params<$w>) -> Self {
+                let poly = if params.refin { params.poly.reverse_bits() } else { params.poly };
+                Self { params, poly }
+            }
+
+            /// Returns the CRC parameters.
+            #[inline]
+            pub const fn params(&self) -> &Params<$w> {
+                &self.params
+            }
+
+            /// Computes the checksum of `data`.
+            #[inline]
+            pub const fn checksum(&self, data: &[u8]) -> $w {
+                self.finalize(self.update(self.init(), data))
+            }
+
+            /// Returns the initial register value for incremental computation.
+            #[inline]
+            pub const fn init(&self) -> $w {
+                init(&self.params)
+            }
+
+            /// Feeds `data` into the register value `crc`, and returns the new
+            /// register value.
+            pub const fn update(&self, mut crc: $w, data: &[u8]) -> $w {
+                let mut i = 0;
+                while i < data.len() {
+                    crc = if self.params.refin {
+                        shift_reflected(crc ^ data[i] as $w, self.poly)
+                    } else {
+                        shift_normal(crc ^ ((data[i] as $w) << (<$w>::BITS - 8)), self.poly)
+                    };
+                    i += 1;
+                }
+                crc
+            }
+
+            /// Converts the register value `crc` into the checksum.
+            #[inline]
+            pub const fn finalize(&self, crc: $w) -> $w {
+                finalize(&self.params, crc)
+            }
+        }
+
+        impl $table {
+            /// Creates a new table-based CRC with the given parameters. The
+            /// lookup table is computed in place, so this function should be
+            /// used in a `const` or `static` initializer.
+            #[allow(clippy::cast_possible_truncation)]
+            pub const fn new(params: Params<$w>) -> Self {
+                let poly = if params.refin { params.poly.reverse_bits() } else { params.poly };
+                let mut table = [0; 256];
+                let mut i = 0;
+                while i < 256 {
+                    table[i] = if params.refin {
+                        shift_reflected(i as $w, poly)
+                    } else {
+                        shift_normal((i as $w) << (<$w>::BITS - 8), poly)
+                    };
+                    i += 1;
+                }
+                Self { params, table }
+            }
+
+            /// Returns the CRC parameters.
+            #[inline]
+            pub const fn params(&self) -> &Params<$w> {
+                &self.params
+            }
+
+            /// Returns the lookup table.
+            #[inline]
+            pub const fn table(&self) -> &[$w; 256] {
+                &self.table
+            }
+
+            /// Computes the checksum of `data`.
+            #[inline]
+            pub const fn checksum(&self, data: &[u8]) -> $w {
+                self.finalize(self.update(self.init(), data))
+            }
+
+            /// Returns the initial register value for incremental computation.
+            #[inline]
+            pub const fn init(&self) -> $w {
+                init(&self.params)
+            }
+
+            /// Feeds `data` into the register value `crc`, and returns the new
+            /// register value.
+            #[allow(clippy::cast_possible_truncation)]
+            pub const fn update(&self, mut crc: $w, data: &[u8]) -> $w {
+                let mut i = 0;
+                while i < data.len() {
+                    // Shifting through `u64` makes 8-bit shifts valid for `u8`.
+                    crc = if self.params.refin {
+                        let index = (crc ^ data[i] as $w) as u8;
+                        self.table[index as usize] ^ ((crc as u64) >> 8) as $w
+                    } else {
+                        let index = (crc >> (<$w>::BITS - 8)) as u8 ^ data[i];
+                        self.table[index as usize] ^ ((crc as u64) << 8) as $w
+                    };
+                    i += 1;
+                }
+                crc
+            }
+
+            /// Converts the register value `crc` into the checksum.
+            #[inline]
+            pub const fn finalize(&self, crc: $w) -> $w {
+                finalize(&self.params, crc)
+            }
+        }
+
+        const fn init(params: &Params<$w>) -> $w {
+            if params.refin { params.init.reverse_bits() } else { params.init }
+        }
+
+        const fn finalize(params: &Params<$w>, mut crc: $w) -> $w {
+            if params.refin != params.refout {
+                crc = crc.reverse_bits();
+            }
+            crc ^ params.xorout
+        }
+
+        const fn shift_reflected(mut crc: $w, poly: $w) -> $w {
+            let mut i = 0;
+            while i < 8 {
+                crc = if crc & 1 == 0 { crc >> 1 } else { (crc >> 1) ^ poly };
+                i += 1;
+            }
+            crc
+        }
+
+        const fn shift_normal(mut crc: $w, poly: $w) -> $w {
+            let mut i = 0;
+            while i < 8 {
+                crc = if crc >> (<$w>::BITS - 1) == 0 { crc << 1 } else { (crc << 1) ^ poly };
+                i += 1;
+            }
+            crc
+        }
+    };
+}
+
+mod crc8 {
+    use super::Params;
+
+    crc! {
+        /// Table-free CRC-8.
+        Crc8,
+        /// Table-based CRC-8.
+        Crc8Table,
+        u8
+    }
+}
+
+mod crc16 {
+    use super::Params;
+
+    crc! {
+        /// Table-free CRC-16.
+        Crc16,
+        /// Table-based CRC-16.
+        Crc16Table,
+        u16
+    }
+}
+
+mod crc32 {
+    use super::Params;
+
+    crc! {
+        /// Table-free CRC-32.
+        Crc32,
+        /// Table-based CRC-32.
+        Crc32Table,
+        u32
+    }
+}
+
+pub use self::{
+    crc16::{Crc16, Crc16Table},
+    crc32::{Crc32, Crc32Table},
+    crc8::{Crc8, Crc8Table},
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn crc8() {
+        assert_eq!(Crc8::new(CRC8_SMBUS).checksum(CHECK), 0xF4);
+        assert_eq!(Crc8Table::new(CRC8_SMBUS).checksum(CHECK), 0xF4);
+        assert_eq!(Crc8::new(CRC8_MAXIM_DOW).checksum(CHECK), 0xA1);
+        assert_eq!(Crc8Table::new(CRC8_MAXIM_DOW).checksum(CHECK), 0xA1);
+    }
+
+    #[test]
+    fn crc16() {
+        for &(params, check) in
+            &[(CRC16_IBM_3740, 0x29B1), (CRC16_XMODEM, 0x31C3), (CRC16_MODBUS, 0x4B37)]
+        {
+            assert_eq!(Crc16::new(params).checksum(CHECK), check);
+            assert_eq!(Crc16Table::new(params).checksum(CHECK), check);
+        }
+    }
+
+    #[test]
+    fn crc32() {
+        for &(params, check) in &[
+            (CRC32_ISO_HDLC, 0xCBF4_3926),
+            (CRC32_ISCSI, 0xE306_9283),
+            (CRC32_MPEG_2, 0x0376_E6E7),
+        ] {
+            assert_eq!(Crc32::new(params).checksum(CHECK), check);
+            assert_eq!(Crc32Table::new(params).checksum(CHECK), check);
+        }
+    }
+
+    #[test]
+    fn incremental() {
+        const CRC: Crc32Table = Crc32Table::new(CRC32_ISO_HDLC);
+        let (head, tail) = CHECK.split_at(4);
+        assert_eq!(CRC.finalize(CRC.update(CRC.update(CRC.init(), head), tail)), 0xCBF4_3926);
+    }
+}
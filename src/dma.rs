@@ -0,0 +1,389 @@
+//! Direct memory access.
+//!
+//! This module defines a common interface for DMA engines. Platform crates
+//! implement [`DmaChannel`] for their channels, and portable drivers describe
+//! transfers with [`Transfer`] and [`Descriptor`] values without knowing the
+//! underlying controller.
+//!
+//! Transfer completion is usually signaled from an interrupt handler. A
+//! [`Completion`] bridges such a handler with the futures returned by
+//! [`DmaChannel::transfer`]:
+//!
+//! ```
+//! use core::{future::Future, pin::Pin};
+//! use drone_core::dma::{Completion, DmaChannel, DmaError, Transfer};
+//!
+//! static CH1_DONE: Completion = Completion::new();
+//!
+//! pub struct Ch1;
+//!
+//! impl DmaChannel for Ch1 {
+//!     type Error = DmaError;
+//!
+//!     unsafe fn transfer(
+//!         &mut self,
+//!         transfer: Transfer,
+//!     ) -> Pin<Box<dyn Future<Output = Result<(), DmaError>> + Send + '_>> {
+//!         CH1_DONE.reset();
+//!         // Program the channel registers with `transfer` and enable it.
+//!         Box::pin(CH1_DONE.wait())
+//!     }
+//! }
+//!
+//! // In the channel interrupt handler:
+//! fn ch1_handler() {
+//!     // Clear the interrupt flags.
+//!     CH1_DONE.complete(Ok(()));
+//! }
+//! ```
+
+use core::{
+    fmt,
+    future::Future,
+    iter::FusedIterator,
+    pin::Pin,
+    sync::atomic::{AtomicU8, Ordering},
+    task::{Context, Poll},
+};
+use futures::task::AtomicWaker;
+
+const IDLE: u8 = 0;
+const PENDING: u8 = 1;
+const COMPLETE: u8 = 2;
+const BUS_ERROR: u8 = 3;
+const ABORTED: u8 = 4;
+
+/// A DMA channel.
+pub trait DmaChannel {
+    /// The error type returned by the transfer futures.
+    type Error;
+
+    /// Starts the `transfer`, and returns a future which resolves when the
+    /// transfer completes.
+    ///
+    /// Dropping the returned future before its completion must abort the
+    /// transfer.
+    ///
+    /// # Safety
+    ///
+    /// The source and destination areas of the `transfer` must be valid for
+    /// reads and writes respectively, and must not be accessed otherwise, until
+    /// the returned future completes or is dropped.
+    unsafe fn transfer(
+        &mut self,
+        transfer: Transfer,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + '_>>;
+
+    /// Executes the chain of descriptors starting from `head`, and returns a
+    /// future which resolves when the last transfer completes.
+    ///
+    /// The default implementation executes the descriptors one by one with
+    /// [`DmaChannel::transfer`]. Controllers with hardware linked-list support
+    /// should override this method.
+    ///
+    /// # Safety
+    ///
+    /// See [`DmaChannel::transfer`]. The requirements apply to all transfers in
+    /// the chain.
+    unsafe fn transfer_chain(
+        &mut self,
+        head: &'static Descriptor,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + '_>>
+    where
+        Self: Send,
+        Self::Error: Send,
+    {
+        Box::pin(async move {
+            for descriptor in head.iter() {
+                unsafe { self.transfer(descriptor.transfer) }.await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// A size of a single data item.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Width {
+    /// 8 bits.
+    Byte,
+    /// 16 bits.
+    HalfWord,
+    /// 32 bits.
+    Word,
+}
+
+/// A source or destination of a transfer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Endpoint {
+    /// The start address.
+    pub addr: usize,
+    /// The size of data items.
+    pub width: Width,
+    /// Whether the address increments after each item.
+    pub increment: bool,
+}
+
+/// A single DMA transfer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Transfer {
+    /// The source.
+    pub src: Endpoint,
+    /// The destination.
+    pub dst: Endpoint,
+    /// The number of data items.
+    pub len: usize,
+}
+
+/// A node of a linked list of transfers.
+///
+/// ```
+/// use drone_core::dma::{Descriptor, Endpoint, Transfer, Width};
+///
+/// const HEADER: usize = 0x2000_0000;
+/// const PAYLOAD: usize = 0x2000_0004;
+/// const DR: usize = 0x4001_3800;
+///
+/// static PAYLOAD_TX: Descriptor = Descriptor::new(
+///     Transfer::new(
+///         Endpoint::memory(PAYLOAD, Width::Byte),
+///         Endpoint::peripheral(DR, Width::Byte),
+///         64,
+///     ),
+///     None,
+/// );
+/// static HEADER_TX: Descriptor = Descriptor::new(
+///     Transfer::new(
+///         Endpoint::memory(HEADER, Width::Byte),
+///         Endpoint::peripheral(DR, Width::Byte),
+///         4,
+///     ),
+///     Some(&PAYLOAD_TX),
+/// );
+/// # fn main() {}
+/// ```
+#[derive(Debug)]
+pub struct Descriptor {
+    /// The transfer.
+    pub transfer: Transfer,
+    /// The next descriptor in the chain.
+    pub next: Option<&'static Descriptor>,
+}
+
+/// An iterator over a chain of descriptors.
+///
+/// This struct is created by [`Descriptor::iter`].
+pub struct Descriptors {
+    next: Option<&'static Descriptor>,
+}
+
+/// A transfer completion signal.
+///
+/// The platform interrupt handler calls [`Completion::complete`], and the
+/// future returned by [`Completion::wait`] resolves with the result.
+pub struct Completion {
+    state: AtomicU8,
+    waker: AtomicWaker,
+}
+
+/// A future returned by [`Completion::wait`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct CompletionFuture<'a> {
+    completion: &'a Completion,
+}
+
+/// A generic DMA transfer error.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DmaError {
+    /// A bus error occurred during the transfer.
+    Bus,
+    /// The transfer was aborted.
+    Aborted,
+}
+
+impl Width {
+    /// Returns the number of bytes in a data item.
+    #[inline]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Self::Byte => 1,
+            Self::HalfWord => 2,
+            Self::Word => 4,
+        }
+    }
+}
+
+impl Endpoint {
+    /// Creates a memory endpoint, which increments the address after each
+    /// item.
+    #[inline]
+    pub const fn memory(addr: usize, width: Width) -> Self {
+        Self { addr, width, increment: true }
+    }
+
+    /// Creates a peripheral endpoint, which keeps the same address for all
+    /// items.
+    #[inline]
+    pub const fn peripheral(addr: usize, width: Width) -> Self {
+        Self { addr, width, increment: false }
+    }
+}
+
+impl Transfer {
+    /// Creates a new transfer of `len` items from `src` to `dst`.
+    #[inline]
+    pub const fn new(src: Endpoint, dst: Endpoint, len: usize) -> Self {
+        Self { src, dst, len }
+    }
+}
+
+impl Descriptor {
+    /// Creates a new descriptor for the `transfer` followed by `next`.
+    #[inline]
+    pub const fn new(transfer: Transfer, next: Option<&'static Descriptor>) -> Self {
+        Self { transfer, next }
+    }
+
+    /// Returns an iterator over the chain starting from this descriptor.
+    #[inline]
+    pub fn iter(&'static self) -> Descriptors {
+        Descriptors { next: Some(self) }
+    }
+}
+
+impl Iterator for Descriptors {
+    type Item = &'static Descriptor;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let descriptor = self.next?;
+        self.next = descriptor.next;
+        Some(descriptor)
+    }
+}
+
+impl FusedIterator for Descriptors {}
+
+impl Completion {
+    /// Creates a new idle completion signal.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { state: AtomicU8::new(IDLE), waker: AtomicWaker::new() }
+    }
+
+    /// Marks a new transfer as pending. Should be called before the transfer
+    /// is started.
+    #[inline]
+    pub fn reset(&self) {
+        self.state.store(PENDING, Ordering::Release);
+    }
+
+    /// Signals the transfer completion with the `result`, and wakes the waiting
+    /// task.
+    #[inline]
+    pub fn complete(&self, result: Result<(), DmaError>) {
+        let state = match result {
+            Ok(()) => COMPLETE,
+            Err(DmaError::Bus) => BUS_ERROR,
+            Err(DmaError::Aborted) => ABORTED,
+        };
+        self.state.store(state, Ordering::Release);
+        self.waker.wake();
+    }
+
+    /// Returns `true` if the transfer is pending.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.state.load(Ordering::Acquire) == PENDING
+    }
+
+    /// Returns a future, which resolves when the transfer completes.
+    ///
+    /// An idle completion, which wasn't [`reset`](Completion::reset) yet, is
+    /// treated as pending.
+    #[inline]
+    pub fn wait(&self) -> CompletionFuture<'_> {
+        CompletionFuture { completion: self }
+    }
+
+    fn poll_result(&self) -> Poll<Result<(), DmaError>> {
+        match self.state.load(Ordering::Acquire) {
+            COMPLETE => Poll::Ready(Ok(())),
+            BUS_ERROR => Poll::Ready(Err(DmaError::Bus)),
+            ABORTED => Poll::Ready(Err(DmaError::Aborted)),
+            _ => Poll::Pending,
+        }
+    }
+}
+
+impl Default for Completion {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Future for CompletionFuture<'_> {
+    type Output = Result<(), DmaError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let completion = self.completion;
+        if let Poll::Ready(result) = completion.poll_result() {
+            return Poll::Ready(result);
+        }
+        completion.waker.register(cx.waker());
+        completion.poll_result()
+    }
+}
+
+impl fmt::Display for DmaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bus => f.write_str("DMA bus error"),
+            Self::Aborted => f.write_str("DMA transfer aborted"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{pin_mut, task::ArcWake};
+    use std::sync::{atomic::AtomicUsize, Arc};
+
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn completion() {
+        let completion = Completion::new();
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = futures::task::waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        let future = completion.wait();
+        pin_mut!(future);
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        completion.reset();
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        completion.complete(Err(DmaError::Bus));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(Err(DmaError::Bus)));
+    }
+
+    #[test]
+    fn chain() {
+        const TRANSFER: Transfer = Transfer::new(
+            Endpoint::memory(0x2000_0000, Width::Word),
+            Endpoint::peripheral(0x4000_0000, Width::Word),
+            2,
+        );
+        static TAIL: Descriptor = Descriptor::new(TRANSFER, None);
+        static HEAD: Descriptor = Descriptor::new(TRANSFER, Some(&TAIL));
+        assert_eq!(HEAD.iter().count(), 2);
+    }
+}
@@ -0,0 +1,131 @@
+use core::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+enum MaybeDone<F: Future> {
+    Future(F),
+    Done(F::Output),
+    Gone,
+}
+
+impl<F: Future> MaybeDone<F> {
+    fn poll_done(self: Pin<&mut Self>, cx: &mut Context<'_>) -> bool {
+        let this = unsafe { self.get_unchecked_mut() };
+        match this {
+            Self::Future(fut) => match unsafe { Pin::new_unchecked(fut) }.poll(cx) {
+                Poll::Ready(output) => {
+                    *this = Self::Done(output);
+                    true
+                }
+                Poll::Pending => false,
+            },
+            Self::Done(_) => true,
+            Self::Gone => panic!("`Join` polled after completion"),
+        }
+    }
+
+    fn take(self: Pin<&mut Self>) -> F::Output {
+        match mem::replace(unsafe { self.get_unchecked_mut() }, Self::Gone) {
+            Self::Done(output) => output,
+            _ => unreachable!(),
+        }
+    }
+}
+
+macro_rules! join {
+    ($(#[$attr:meta])* $fn:ident, $join:ident, $doc:literal, $($fut:ident $field:ident),*) => {
+        #[doc = $doc]
+        #[must_use = "futures do nothing unless you `.await` or poll them"]
+        pub struct $join<$($fut: Future),*> {
+            $($field: MaybeDone<$fut>,)*
+        }
+
+        $(#[$attr])*
+        #[inline]
+        pub fn $fn<$($fut: Future),*>($($field: $fut),*) -> $join<$($fut),*> {
+            $join { $($field: MaybeDone::Future($field)),* }
+        }
+
+        impl<$($fut: Future),*> Future for $join<$($fut),*> {
+            type Output = ($($fut::Output,)*);
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let this = unsafe { self.get_unchecked_mut() };
+                let mut done = true;
+                $(done &= unsafe { Pin::new_unchecked(&mut this.$field) }.poll_done(cx);)*
+                if !done {
+                    return Poll::Pending;
+                }
+                Poll::Ready(($(unsafe { Pin::new_unchecked(&mut this.$field) }.take(),)*))
+            }
+        }
+    };
+}
+
+join! {
+    /// Joins the results of two futures, waiting for them both to complete.
+    ///
+    /// The futures are stored inline, and polled with the waker of the
+    /// enclosing task.
+    ///
+    /// ```
+    /// use drone_core::exec;
+    ///
+    /// # async fn f() {
+    /// let (a, b) = exec::join2(async { 1 }, async { 2 }).await;
+    /// assert_eq!(a + b, 3);
+    /// # }
+    /// ```
+    join2,
+    Join2,
+    "Future for the [`join2`] function.",
+    A a, B b
+}
+
+join! {
+    /// Joins the results of three futures, waiting for them all to complete.
+    ///
+    /// See [`join2`] for details.
+    join3,
+    Join3,
+    "Future for the [`join3`] function.",
+    A a, B b, C c
+}
+
+join! {
+    /// Joins the results of four futures, waiting for them all to complete.
+    ///
+    /// See [`join2`] for details.
+    join4,
+    Join4,
+    "Future for the [`join4`] function.",
+    A a, B b, C c, D d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future, pin_mut, task::noop_waker_ref};
+
+    #[test]
+    fn join() {
+        let mut polled = false;
+        let slow = future::poll_fn(move |cx| {
+            if polled {
+                Poll::Ready('b')
+            } else {
+                polled = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        });
+        let fut = join3(future::ready(1), slow, async { "c" });
+        pin_mut!(fut);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready((1, 'b', "c")));
+    }
+}
@@ -0,0 +1,329 @@
+//! A cooperative executor for background tasks.
+//!
+//! Unlike [`ThrExec`](crate::thr::ThrExec), which attaches each future to an
+//! interrupt-driven thread, an [`Executor`] multiplexes many futures on a
+//! single context, typically the idle thread. Woken tasks are put to a FIFO
+//! run-queue, and [`Executor::run`] polls at most `budget` tasks per call, so a
+//! busy task can't starve the others, and the idle loop keeps control between
+//! the calls.
+//!
+//! ```
+//! use drone_core::exec::Executor;
+//!
+//! static EXECUTOR: Executor<16> = Executor::new();
+//!
+//! EXECUTOR.spawn(async { /* blink a LED */ }).unwrap();
+//! EXECUTOR.spawn(async { /* poll a sensor */ }).unwrap();
+//! // In the idle loop:
+//! loop {
+//!     EXECUTOR.run(8);
+//!     if EXECUTOR.is_idle() {
+//!         // Wait for an interrupt.
+//!         # break;
+//!     }
+//! }
+//! ```
+//!
+//! The executor can also be driven by an idle hook:
+//!
+//! ```
+//! # use drone_core::exec::Executor;
+//! # static EXECUTOR: Executor<16> = Executor::new();
+//! use drone_core::thr;
+//!
+//! fn run_background() {
+//!     EXECUTOR.run(8);
+//! }
+//!
+//! thr::idle_hook!(10, run_background);
+//! # fn main() {}
+//! ```
+//!
+//! With `thr-stats` feature enabled, each task keeps `ThrStats` with the number
+//! of polls and the longest poll duration, measured with the platform cycle
+//! counter.
+//!
+//! # Combinators
+//!
+//! The [`join2`], [`join3`], [`join4`], [`select2`], and [`select3`]
+//! combinators keep their state inline without boxing, and poll the inner
+//! futures with the waker of the enclosing task. Therefore they are suitable
+//! for small tasks driven by thread tokens, as well as for the executor tasks.
+
+mod join;
+mod select;
+
+pub use self::{
+    join::{join2, join3, join4, Join2, Join3, Join4},
+    select::{select2, select3, Either2, Either3, Select2, Select3},
+};
+
+#[cfg(feature = "thr-stats")]
+use crate::thr::ThrStats;
+use crate::thr::WorkQueue;
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    future::Future,
+    mem::size_of,
+    pin::Pin,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, Ordering},
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+const FREE: u8 = 0;
+const CLAIMED: u8 = 1;
+const ACTIVE: u8 = 2;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A cooperative executor with up to `N` tasks, where `N` must be a power of
+/// two.
+pub struct Executor<const N: usize> {
+    tasks: [Task<N>; N],
+    queue: WorkQueue<u16, N>,
+    running: AtomicBool,
+    polls: AtomicU32,
+    completed: AtomicU32,
+}
+
+/// A task identifier returned by [`Executor::spawn`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TaskId(u16);
+
+/// The error type returned from [`Executor::spawn`] when all task slots are
+/// taken.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ExecutorFull;
+
+struct Task<const N: usize> {
+    state: AtomicU8,
+    queued: AtomicBool,
+    executor: AtomicPtr<Executor<N>>,
+    future: UnsafeCell<Option<BoxFuture>>,
+    #[cfg(feature = "thr-stats")]
+    stats: ThrStats,
+}
+
+unsafe impl<const N: usize> Sync for Executor<N> {}
+
+impl<const N: usize> Executor<N> {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(Self::clone, Self::wake, Self::wake, drop);
+
+    /// Creates a new executor without tasks.
+    #[inline]
+    pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const TASK: Task<N> = Task {
+            state: AtomicU8::new(FREE),
+            queued: AtomicBool::new(false),
+            executor: AtomicPtr::new(ptr::null_mut()),
+            future: UnsafeCell::new(None),
+            #[cfg(feature = "thr-stats")]
+            stats: ThrStats::new(),
+        };
+        Self {
+            tasks: [TASK; N],
+            queue: WorkQueue::new(),
+            running: AtomicBool::new(false),
+            polls: AtomicU32::new(0),
+            completed: AtomicU32::new(0),
+        }
+    }
+
+    /// Spawns the future `fut` as a new task, and schedules it for polling.
+    ///
+    /// This method can be called from any context.
+    ///
+    /// # Errors
+    ///
+    /// If all `N` task slots are taken.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn spawn<F>(&'static self, fut: F) -> Result<TaskId, ExecutorFull>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let index = self
+            .tasks
+            .iter()
+            .position(|task| {
+                task.state
+                    .compare_exchange(FREE, CLAIMED, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .ok_or(ExecutorFull)?;
+        let task = &self.tasks[index];
+        unsafe { *task.future.get() = Some(Box::pin(fut)) };
+        #[cfg(feature = "thr-stats")]
+        task.stats.reset();
+        task.executor.store(self as *const Self as *mut Self, Ordering::Relaxed);
+        task.state.store(ACTIVE, Ordering::Release);
+        self.schedule(index as u16);
+        Ok(TaskId(index as u16))
+    }
+
+    /// Polls up to `budget` scheduled tasks in their wake-up order, and returns
+    /// the number of polls made.
+    ///
+    /// A task woken during its own poll is put to the back of the run-queue. If
+    /// the executor is already running in another context, returns `0`
+    /// immediately.
+    pub fn run(&self, budget: usize) -> usize {
+        if self.running.swap(true, Ordering::Acquire) {
+            return 0;
+        }
+        let mut polls = 0;
+        while polls < budget {
+            let index = match self.queue.pop() {
+                Some(index) => index,
+                None => break,
+            };
+            polls += 1;
+            self.poll_task(index);
+        }
+        self.running.store(false, Ordering::Release);
+        polls
+    }
+
+    /// Returns `true` if there are no scheduled tasks.
+    #[inline]
+    pub fn is_idle(&self) -> bool {
+        !self.tasks.iter().any(|task| task.queued.load(Ordering::Relaxed))
+    }
+
+    /// Returns the number of live tasks.
+    pub fn tasks(&self) -> usize {
+        self.tasks.iter().filter(|task| task.state.load(Ordering::Relaxed) != FREE).count()
+    }
+
+    /// Returns `true` if the task `id` hasn't completed yet.
+    #[inline]
+    pub fn is_alive(&self, id: TaskId) -> bool {
+        self.tasks[usize::from(id.0)].state.load(Ordering::Relaxed) != FREE
+    }
+
+    /// Returns the statistics of the task `id`: the number of polls and the
+    /// longest poll duration.
+    #[cfg(feature = "thr-stats")]
+    #[inline]
+    pub fn task_stats(&self, id: TaskId) -> &ThrStats {
+        &self.tasks[usize::from(id.0)].stats
+    }
+
+    /// Returns the total number of polls since the executor creation.
+    #[inline]
+    pub fn polls(&self) -> u32 {
+        self.polls.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of completed tasks since the executor creation.
+    #[inline]
+    pub fn completed(&self) -> u32 {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    fn poll_task(&self, index: u16) {
+        let task = &self.tasks[usize::from(index)];
+        task.queued.store(false, Ordering::Relaxed);
+        if task.state.load(Ordering::Acquire) != ACTIVE {
+            return;
+        }
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        let waker = unsafe { Waker::from_raw(Self::raw(task)) };
+        let mut cx = Context::from_waker(&waker);
+        let future = unsafe { &mut *task.future.get() };
+        #[cfg(feature = "thr-stats")]
+        let start = task.stats.start();
+        let poll = future.as_mut().map_or(Poll::Ready(()), |future| future.as_mut().poll(&mut cx));
+        #[cfg(feature = "thr-stats")]
+        task.stats.finish(start);
+        if poll.is_ready() {
+            *future = None;
+            self.completed.fetch_add(1, Ordering::Relaxed);
+            task.state.store(FREE, Ordering::Release);
+        }
+    }
+
+    fn schedule(&self, index: u16) {
+        if !self.tasks[usize::from(index)].queued.swap(true, Ordering::Relaxed) {
+            // Each task occupies at most one run-queue slot, so the queue
+            // can't overflow.
+            self.queue.push(index).ok();
+        }
+    }
+
+    fn raw(task: &Task<N>) -> RawWaker {
+        RawWaker::new((task as *const Task<N>).cast(), &Self::VTABLE)
+    }
+
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        Self::raw(unsafe { &*data.cast::<Task<N>>() })
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    unsafe fn wake(data: *const ()) {
+        let task = unsafe { &*data.cast::<Task<N>>() };
+        let executor = unsafe { &*task.executor.load(Ordering::Relaxed) };
+        let index = (data as usize - executor.tasks.as_ptr() as usize) / size_of::<Task<N>>();
+        executor.schedule(index as u16);
+    }
+}
+
+impl fmt::Display for ExecutorFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("executor is full")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use futures::future;
+
+    static EXECUTOR: Executor<4> = Executor::new();
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn yield_now() -> impl Future<Output = ()> {
+        let mut yielded = false;
+        future::poll_fn(move |cx| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+    }
+
+    #[test]
+    fn round_robin() {
+        let a = EXECUTOR
+            .spawn(async {
+                for _ in 0..3 {
+                    COUNTER.fetch_add(1, Ordering::Relaxed);
+                    yield_now().await;
+                }
+            })
+            .unwrap();
+        let b = EXECUTOR
+            .spawn(async {
+                COUNTER.fetch_add(10, Ordering::Relaxed);
+            })
+            .unwrap();
+        assert_eq!(EXECUTOR.tasks(), 2);
+        assert_eq!(EXECUTOR.run(2), 2);
+        assert_eq!(COUNTER.load(Ordering::Relaxed), 11);
+        assert!(!EXECUTOR.is_alive(b));
+        assert!(!EXECUTOR.is_idle());
+        assert_eq!(EXECUTOR.run(8), 3);
+        assert!(EXECUTOR.is_idle());
+        assert!(!EXECUTOR.is_alive(a));
+        #[cfg(feature = "thr-stats")]
+        assert_eq!(EXECUTOR.task_stats(a).activations(), 4);
+        assert_eq!(EXECUTOR.completed(), 2);
+        assert_eq!(COUNTER.load(Ordering::Relaxed), 13);
+    }
+}
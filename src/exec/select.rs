@@ -0,0 +1,109 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// The output of [`select2`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Either2<A, B> {
+    /// The first future completed first.
+    First(A),
+    /// The second future completed first.
+    Second(B),
+}
+
+/// The output of [`select3`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Either3<A, B, C> {
+    /// The first future completed first.
+    First(A),
+    /// The second future completed first.
+    Second(B),
+    /// The third future completed first.
+    Third(C),
+}
+
+macro_rules! select {
+    ($(#[$attr:meta])* $fn:ident, $select:ident, $doc:literal, $either:ident, $($fut:ident $field:ident $variant:ident),*) => {
+        #[doc = $doc]
+        #[must_use = "futures do nothing unless you `.await` or poll them"]
+        pub struct $select<$($fut: Future),*> {
+            $($field: $fut,)*
+        }
+
+        $(#[$attr])*
+        #[inline]
+        pub fn $fn<$($fut: Future),*>($($field: $fut),*) -> $select<$($fut),*> {
+            $select { $($field),* }
+        }
+
+        impl<$($fut: Future),*> Future for $select<$($fut),*> {
+            type Output = $either<$($fut::Output),*>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let this = unsafe { self.get_unchecked_mut() };
+                $(
+                    if let Poll::Ready(output) = unsafe { Pin::new_unchecked(&mut this.$field) }.poll(cx) {
+                        return Poll::Ready($either::$variant(output));
+                    }
+                )*
+                Poll::Pending
+            }
+        }
+    };
+}
+
+select! {
+    /// Waits for either of two futures to complete.
+    ///
+    /// The futures are stored inline, and polled in order with the waker of the
+    /// enclosing task, so the first future has priority if both are ready. The
+    /// future which didn't complete is dropped together with the returned
+    /// future.
+    ///
+    /// ```
+    /// use drone_core::exec::{self, Either2};
+    /// use futures::future;
+    ///
+    /// # async fn f() {
+    /// match exec::select2(future::pending::<()>(), async { 2 }).await {
+    ///     Either2::First(()) => unreachable!(),
+    ///     Either2::Second(b) => assert_eq!(b, 2),
+    /// }
+    /// # }
+    /// ```
+    select2,
+    Select2,
+    "Future for the [`select2`] function.",
+    Either2,
+    A a First,
+    B b Second
+}
+
+select! {
+    /// Waits for any of three futures to complete.
+    ///
+    /// See [`select2`] for details.
+    select3,
+    Select3,
+    "Future for the [`select3`] function.",
+    Either3,
+    A a First,
+    B b Second,
+    C c Third
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future, pin_mut, task::noop_waker_ref};
+
+    #[test]
+    fn select() {
+        let fut = select3(future::pending::<u8>(), future::ready('b'), future::ready("c"));
+        pin_mut!(fut);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(Either3::Second('b')));
+    }
+}
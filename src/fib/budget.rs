@@ -0,0 +1,131 @@
+use crate::{
+    fib::{closure::ReturnNone, Fiber, FiberState, RootFiber},
+    thr,
+};
+use core::pin::Pin;
+
+/// Fiber for a step function with a cycle budget per resumption.
+///
+/// Can be created with [`fib::new_budgeted`](crate::fib::new_budgeted).
+pub struct FiberBudgeted<F, R>
+where
+    F: FnMut() -> FiberState<(), R>,
+{
+    f: Option<F>,
+    budget: u32,
+}
+
+impl<F, R> Fiber for FiberBudgeted<F, R>
+where
+    F: FnMut() -> FiberState<(), R>,
+{
+    type Input = ();
+    type Return = R;
+    type Yield = ();
+
+    fn resume(self: Pin<&mut Self>, (): ()) -> FiberState<(), R> {
+        let Self { f, budget } = unsafe { self.get_unchecked_mut() };
+        let step = f.as_mut().expect("fiber resumed after completion");
+        let start = thr::cycles();
+        loop {
+            if let FiberState::Complete(value) = step() {
+                *f = None;
+                break FiberState::Complete(value);
+            }
+            if thr::cycles().wrapping_sub(start) >= *budget {
+                break FiberState::Yielded(());
+            }
+        }
+    }
+}
+
+impl<F, R> RootFiber for FiberBudgeted<F, R>
+where
+    F: FnMut() -> FiberState<(), R>,
+    F: 'static,
+    R: ReturnNone,
+{
+    #[inline]
+    fn advance(self: Pin<&mut Self>) -> bool {
+        match self.resume(()) {
+            FiberState::Yielded(()) => false,
+            FiberState::Complete(_) => true,
+        }
+    }
+}
+
+/// Creates a fiber that calls the step function `f` repeatedly until
+/// [`FiberState::Complete`] is returned, and yields only when a resumption
+/// exceeds `cycles_budget` cycles.
+///
+/// Each [`FiberState::Yielded`] returned by `f` marks a point where the fiber
+/// may yield. This lets a long CPU-bound computation, like a checksum or
+/// compression, run in a low-priority thread without handcrafted yield points.
+/// The cycles are measured with the counter registered with
+/// [`set_cycle_counter!`](crate::set_cycle_counter). If no counter is
+/// registered, the fiber runs to completion in one resumption, unless
+/// `cycles_budget` is zero.
+///
+/// An iterator can be driven with a step function like this:
+///
+/// ```
+/// use drone_core::fib;
+///
+/// let mut bytes = (0..=255_u8).cycle().take(4096);
+/// let mut checksum = 0_u32;
+/// let fiber = fib::new_budgeted(
+///     move || match bytes.next() {
+///         Some(byte) => {
+///             checksum = checksum.wrapping_add(u32::from(byte));
+///             fib::Yielded(())
+///         }
+///         None => fib::Complete(checksum),
+///     },
+///     10_000,
+/// );
+/// ```
+#[inline]
+pub fn new_budgeted<F, R>(f: F, cycles_budget: u32) -> FiberBudgeted<F, R>
+where
+    F: FnMut() -> FiberState<(), R>,
+{
+    FiberBudgeted { f: Some(f), budget: cycles_budget }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        let mut steps = 0;
+        let mut fiber = new_budgeted(
+            || {
+                steps += 1;
+                if steps < 3 {
+                    FiberState::Yielded(())
+                } else {
+                    FiberState::Complete(steps)
+                }
+            },
+            0,
+        );
+        let mut fiber = Pin::new(&mut fiber);
+        assert_eq!(fiber.as_mut().resume(()), FiberState::Yielded(()));
+        assert_eq!(fiber.as_mut().resume(()), FiberState::Yielded(()));
+        assert_eq!(fiber.as_mut().resume(()), FiberState::Complete(3));
+        let mut steps = 0;
+        let mut fiber = new_budgeted(
+            || {
+                steps += 1;
+                if steps < 3 {
+                    FiberState::Yielded(())
+                } else {
+                    FiberState::Complete(steps)
+                }
+            },
+            u32::MAX,
+        );
+        assert_eq!(Pin::new(&mut fiber).resume(()), FiberState::Complete(3));
+    }
+}
@@ -0,0 +1,99 @@
+/// A loop iteration counter for generator fibers, which process several items
+/// per activation.
+///
+/// See [`fib::yield_every!`](crate::fib::yield_every) for details.
+#[derive(Debug, Clone)]
+pub struct YieldEvery {
+    n: usize,
+    count: usize,
+}
+
+/// Yields from the enclosing generator once every `n` invocations, where `n`
+/// is the period of the given [`YieldEvery`] counter.
+///
+/// This amortizes the loop work across activations, so that a fiber processes
+/// up to `n` items per thread activation. The second optional argument is the
+/// value to yield, which defaults to `()`.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(generators)]
+/// use core::pin::Pin;
+/// use drone_core::fib::{self, Fiber, FiberState, YieldEvery};
+///
+/// let mut fiber = fib::new(|| {
+///     let mut every = YieldEvery::new(4);
+///     for _sample in 0..10 {
+///         // Process the sample.
+///         fib::yield_every!(every);
+///     }
+/// });
+/// let mut fiber = Pin::new(&mut fiber);
+/// // Samples 0..4 are processed.
+/// assert!(matches!(fiber.as_mut().resume(()), FiberState::Yielded(())));
+/// // Samples 4..8 are processed.
+/// assert!(matches!(fiber.as_mut().resume(()), FiberState::Yielded(())));
+/// // Samples 8..10 are processed.
+/// assert!(matches!(fiber.as_mut().resume(()), FiberState::Complete(())));
+/// ```
+#[doc(inline)]
+pub use crate::__fib_yield_every as yield_every;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fib_yield_every {
+    ($every:expr $(,)?) => {
+        $crate::fib::yield_every!($every, ())
+    };
+    ($every:expr, $value:expr $(,)?) => {
+        if $crate::fib::YieldEvery::tick(&mut $every) {
+            yield $value;
+        }
+    };
+}
+
+impl YieldEvery {
+    /// Creates a new counter, which fires once every `n` ticks.
+    ///
+    /// Zero `n` is treated as one.
+    #[inline]
+    pub const fn new(n: usize) -> Self {
+        Self { n: if n == 0 { 1 } else { n }, count: 0 }
+    }
+
+    /// Counts one loop iteration, and returns `true` if it was the `n`-th
+    /// iteration since the last time the counter fired.
+    #[inline]
+    pub fn tick(&mut self) -> bool {
+        self.count += 1;
+        if self.count == self.n {
+            self.count = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Resets the counter, so that the next fire will happen after `n` ticks.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn period() {
+        let mut every = YieldEvery::new(3);
+        let fired = (0..7).map(|_| every.tick()).collect::<Vec<_>>();
+        assert_eq!(fired, [false, false, true, false, false, true, false]);
+        every.reset();
+        assert!(!every.tick());
+        let mut every = YieldEvery::new(0);
+        assert!(every.tick() && every.tick());
+    }
+}
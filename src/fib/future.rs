@@ -1,24 +1,49 @@
 use crate::{
     fib::{self, Fiber},
     sync::spsc::oneshot::{channel, Canceled, Receiver},
-    thr::prelude::*,
+    thr::{inherit, prelude::*, ThrExec},
 };
+use alloc::sync::Arc;
 use core::{
     future::Future,
     intrinsics::unreachable,
+    num::NonZeroU32,
     pin::Pin,
-    task::{Context, Poll},
+    sync::atomic::{AtomicU32, AtomicU8, Ordering},
+    task::{Context, Poll, Waker},
 };
 
 /// A future that resolves on completion of the fiber from another thread.
 ///
 /// Dropping or closing this future will remove the fiber on a next thread
 /// invocation without resuming it.
+///
+/// The fiber returns a value of type `R`, which is converted to the output
+/// type `T`. If the fiber returns a `Result`, the future can be awaited with
+/// `?` in an async function, and the error type can be converted with
+/// [`err_into`](FiberFuture::err_into).
+///
+/// A future returned by
+/// [`add_future_donate`](ThrFiberFuture::add_future_donate) donates the
+/// priority of the awaiting thread to the thread running the fiber.
 #[must_use = "futures do nothing unless you `.await` or poll them"]
-pub struct FiberFuture<T> {
-    rx: Receiver<T>,
+pub struct FiberFuture<T, R = T> {
+    rx: Receiver<R>,
+    map: fn(R) -> T,
+    donation: Option<Arc<Donation>>,
 }
 
+// The state of a priority donation from the thread awaiting a `FiberFuture` to
+// the thread running its fiber.
+struct Donation {
+    waker: Waker,
+    owner: AtomicU32,
+    priority: AtomicU8,
+    base_priority: AtomicU8,
+}
+
+const NOT_DONATED: u8 = u8::MAX;
+
 #[marker]
 pub trait YieldNone: Send + 'static {}
 
@@ -26,6 +51,57 @@ impl YieldNone for () {}
 impl YieldNone for ! {}
 
 impl<T> FiberFuture<T> {
+    #[inline]
+    fn new(rx: Receiver<T>) -> Self {
+        Self { rx, map: |value| value, donation: None }
+    }
+}
+
+impl<T, E> FiberFuture<Result<T, E>> {
+    /// Converts the error type of the fiber output with [`From`].
+    ///
+    /// ```
+    /// # #![feature(generators)]
+    /// # use drone_core::token::Token;
+    /// # drone_core::thr::pool! {
+    /// #     thread => Thr {};
+    /// #     local => ThrLocal {};
+    /// #     index => Thrs;
+    /// #     threads => { sys_tick };
+    /// # }
+    /// use drone_core::{fib, thr::prelude::*};
+    ///
+    /// struct Timeout;
+    ///
+    /// enum Error {
+    ///     Timeout,
+    /// }
+    ///
+    /// impl From<Timeout> for Error {
+    ///     fn from(Timeout: Timeout) -> Self {
+    ///         Self::Timeout
+    ///     }
+    /// }
+    ///
+    /// async fn wait(thr: SysTick) -> Result<u32, Error> {
+    ///     let value = thr
+    ///         .add_future(fib::new(|| {
+    ///             yield;
+    ///             Err::<u32, _>(Timeout)
+    ///         }))
+    ///         .err_into::<Error>()
+    ///         .await?;
+    ///     Ok(value)
+    /// }
+    /// # fn main() {}
+    /// ```
+    #[inline]
+    pub fn err_into<U: From<E>>(self) -> FiberFuture<Result<T, U>, Result<T, E>> {
+        FiberFuture { rx: self.rx, map: |value| value.map_err(U::from), donation: self.donation }
+    }
+}
+
+impl<T, R> FiberFuture<T, R> {
     /// Gracefully close this future.
     ///
     /// The fiber will be removed on a next thread invocation without resuming.
@@ -35,20 +111,25 @@ impl<T> FiberFuture<T> {
     }
 }
 
-impl<T> Future for FiberFuture<T> {
+impl<T, R> Future for FiberFuture<T, R> {
     type Output = T;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
-        let rx = unsafe { self.map_unchecked_mut(|x| &mut x.rx) };
-        rx.poll(cx).map(|value| match value {
-            Ok(value) => value,
+        let this = unsafe { self.get_unchecked_mut() };
+        let map = this.map;
+        let poll = unsafe { Pin::new_unchecked(&mut this.rx) }.poll(cx).map(|value| match value {
+            Ok(value) => map(value),
             Err(Canceled) => unsafe { unreachable() },
-        })
+        });
+        if let (Poll::Pending, Some(donation)) = (&poll, &this.donation) {
+            donation.donate();
+        }
+        poll
     }
 }
 
-/// Extends [`ThrToken`](crate::thr::ThrToken) types with `add_future` and
-/// `add_future_factory` methods.
+/// Extends [`ThrToken`](crate::thr::ThrToken) types with `add_future`,
+/// `add_future_factory`, and `add_future_donate` methods.
 pub trait ThrFiberFuture: ThrToken {
     /// Adds the fiber `fib` to the fiber chain and returns a future, which
     /// resolves on fiber completion.
@@ -60,7 +141,36 @@ pub trait ThrFiberFuture: ThrToken {
         F: Send + 'static,
         T: Send + 'static,
     {
-        FiberFuture { rx: add_rx(self, || fib) }
+        FiberFuture::new(add_rx(self, None, || fib))
+    }
+
+    /// Adds the fiber `fib` to the fiber chain and returns a future, which
+    /// resolves on fiber completion. The awaiting thread donates its priority
+    /// to this thread until the fiber completes.
+    ///
+    /// Each time the future is polled and the fiber is not yet completed, this
+    /// thread is woken up, so the fiber gets resumed without waiting for
+    /// another event. If a
+    /// [`PriorityInheritance`](crate::thr::PriorityInheritance) hook is
+    /// registered, the priority of this thread is also temporarily raised to
+    /// the priority of the awaiting thread. The original priority is restored
+    /// when the fiber completes or the future is dropped. This bounds the
+    /// latency of awaiting a fiber on a lower-priority thread.
+    ///
+    /// The thread running the fiber is known to the hook only after the first
+    /// resume, so the priority is raised starting from that point.
+    #[inline]
+    fn add_future_donate<F, Y, T>(self, fib: F) -> FiberFuture<T>
+    where
+        Self: ThrExec,
+        F: Fiber<Input = (), Yield = Y, Return = T>,
+        Y: YieldNone,
+        F: Send + 'static,
+        T: Send + 'static,
+    {
+        let donation = Arc::new(Donation::new(self.waker()));
+        let rx = add_rx(self, Some(Arc::clone(&donation)), || fib);
+        FiberFuture { donation: Some(donation), ..FiberFuture::new(rx) }
     }
 
     /// Adds the fiber returned by `factory` to the fiber chain and returns a
@@ -76,12 +186,110 @@ pub trait ThrFiberFuture: ThrToken {
         F: 'static,
         T: Send + 'static,
     {
-        FiberFuture { rx: add_rx(self, factory) }
+        FiberFuture::new(add_rx(self, None, factory))
+    }
+
+    /// Adds the fiber `fib` to the fiber chain, and after its completion runs
+    /// the fiber returned by `then` in the same chain slot. Returns a future,
+    /// which resolves on completion of the second fiber.
+    ///
+    /// The second fiber starts at the same thread invocation when the first
+    /// fiber completes.
+    #[inline]
+    fn add_future_then<F, Y, T, C, G, Z, U>(self, fib: F, then: C) -> FiberFuture<U>
+    where
+        F: Fiber<Input = (), Yield = Y, Return = T>,
+        Y: YieldNone,
+        F: Send + 'static,
+        C: FnOnce(T) -> G + Send + 'static,
+        G: Fiber<Input = (), Yield = Z, Return = U>,
+        Z: YieldNone,
+        G: 'static,
+        T: 'static,
+        U: Send + 'static,
+    {
+        FiberFuture::new(add_rx(self, None, || {
+            let mut first = fib;
+            fib::new(move || {
+                let output = loop {
+                    match unsafe { Pin::new_unchecked(&mut first) }.resume(()) {
+                        fib::Yielded(_) => yield,
+                        fib::Complete(output) => break output,
+                    }
+                };
+                let mut second = then(output);
+                loop {
+                    match unsafe { Pin::new_unchecked(&mut second) }.resume(()) {
+                        fib::Yielded(_) => yield,
+                        fib::Complete(output) => break output,
+                    }
+                }
+            })
+        }))
+    }
+}
+
+impl Donation {
+    fn new(waker: Waker) -> Self {
+        Self {
+            waker,
+            owner: AtomicU32::new(0),
+            priority: AtomicU8::new(0),
+            base_priority: AtomicU8::new(NOT_DONATED),
+        }
+    }
+
+    // Called from the awaiting thread when the fiber is not yet completed.
+    fn donate(&self) {
+        if let Some(waiter) = inherit::drone_thr_current() {
+            self.priority.fetch_max(inherit::drone_thr_priority(waiter), Ordering::Relaxed);
+            if let Some(owner) = NonZeroU32::new(self.owner.load(Ordering::Relaxed)) {
+                if owner != waiter {
+                    self.boost(owner);
+                }
+            }
+        }
+        self.waker.wake_by_ref();
+    }
+
+    // Called from the fiber thread before each fiber resume.
+    fn enter(&self) {
+        if let Some(owner) = inherit::drone_thr_current() {
+            self.owner.store(owner.get(), Ordering::Relaxed);
+            self.boost(owner);
+        }
+    }
+
+    fn boost(&self, owner: NonZeroU32) {
+        let priority = self.priority.load(Ordering::Relaxed);
+        let owner_priority = inherit::drone_thr_priority(owner);
+        if owner_priority < priority {
+            // Remember the original priority only on the first boost.
+            self.base_priority
+                .compare_exchange(NOT_DONATED, owner_priority, Ordering::Relaxed, Ordering::Relaxed)
+                .ok();
+            inherit::drone_thr_set_priority(owner, priority);
+        }
+    }
+
+    fn restore(&self) {
+        let base_priority = self.base_priority.swap(NOT_DONATED, Ordering::Relaxed);
+        if let Some(owner) = NonZeroU32::new(self.owner.load(Ordering::Relaxed)) {
+            if base_priority != NOT_DONATED {
+                inherit::drone_thr_set_priority(owner, base_priority);
+            }
+        }
+    }
+}
+
+impl Drop for Donation {
+    fn drop(&mut self) {
+        self.restore();
     }
 }
 
 #[inline]
-fn add_rx<C, H, F, Y, T>(thr: H, factory: C) -> Receiver<T>
+fn add_rx<C, H, F, Y, T>(thr: H, donation: Option<Arc<Donation>>, factory: C) -> Receiver<T>
 where
     C: FnOnce() -> F + Send + 'static,
     H: ThrToken,
@@ -96,12 +304,21 @@ where
         move || {
             loop {
                 if tx.is_canceled() {
+                    if let Some(donation) = &donation {
+                        donation.restore();
+                    }
                     break;
                 }
+                if let Some(donation) = &donation {
+                    donation.enter();
+                }
                 match unsafe { Pin::new_unchecked(&mut fib) }.resume(()) {
                     fib::Yielded(_) => {}
                     fib::Complete(complete) => {
                         drop(tx.send(complete));
+                        if let Some(donation) = &donation {
+                            donation.restore();
+                        }
                         break;
                     }
                 }
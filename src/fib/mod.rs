@@ -98,6 +98,12 @@
 //! # }
 //! ```
 //!
+//! A generator fiber, which processes a number of items per activation, can
+//! use [`fib::yield_every!`](yield_every) to yield only once every `n` loop
+//! iterations. A long CPU-bound computation can be split into steps with
+//! [`fib::new_budgeted`](new_budgeted), which yields only when a resumption
+//! exceeds a cycle budget.
+//!
 //! # Compound Fibers
 //!
 //! There is a number of useful compound fibers implemented in this module:
@@ -121,6 +127,8 @@
 //!
 //! In addition, each of the above methods has `*_factory` modification, which
 //! is useful for creating non-`Send` fibers.
+//! [`token.add_future_then(...)`](ThrFiberFuture::add_future_then) chains a
+//! second fiber after completion of the first one in the same chain slot.
 //!
 //! ## Examples
 //!
@@ -151,16 +159,20 @@
 //! # }
 //! ```
 
+mod budget;
 mod chain;
 mod closure;
+mod every;
 mod future;
 mod generator;
 mod stream_pulse;
 mod stream_ring;
 
 pub use self::{
+    budget::{new_budgeted, FiberBudgeted},
     chain::Chain,
     closure::{new_fn, new_once, FiberFn, FiberOnce, ThrFiberClosure},
+    every::{yield_every, YieldEvery},
     future::{FiberFuture, ThrFiberFuture},
     generator::{new, FiberGen, ThrFiberGen},
     stream_pulse::{FiberStreamPulse, ThrFiberStreamPulse, TryFiberStreamPulse},
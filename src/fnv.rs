@@ -0,0 +1,59 @@
+//! 32-bit FNV-1a hash.
+
+use core::hash::Hasher;
+
+const OFFSET: u32 = 0x811C_9DC5;
+const PRIME: u32 = 0x0100_0193;
+
+/// A 32-bit FNV-1a hasher.
+///
+/// The hash is small and fast for short inputs, which makes it suitable for
+/// identifiers and hash tables of embedded systems. It is not a cryptographic
+/// hash.
+pub(crate) struct Fnv1a(u32);
+
+impl Fnv1a {
+    /// Creates a new hasher with the FNV offset basis.
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self(OFFSET)
+    }
+
+    /// Returns the 32-bit hash of the bytes written so far.
+    #[inline]
+    pub(crate) fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Hasher for Fnv1a {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0.into()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u32::from(byte)).wrapping_mul(PRIME);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(bytes: &[u8]) -> u32 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(bytes);
+        hasher.value()
+    }
+
+    #[test]
+    fn check_values() {
+        assert_eq!(hash(b""), 0x811C_9DC5);
+        assert_eq!(hash(b"a"), 0xE40C_292C);
+        assert_eq!(hash(b"foobar"), 0xBF9C_F968);
+    }
+}
@@ -0,0 +1,273 @@
+//! Hierarchical state machines driven by events.
+//!
+//! A state machine is described by implementing the [`Machine`] trait. States
+//! may be nested by returning a parent from [`Machine::parent`]. An event not
+//! handled by a state is offered to its parent, and so on up to the root. On a
+//! transition the machine exits the states up to the least common ancestor of
+//! the source and the target, and then enters the states down to the target,
+//! calling [`Machine::on_exit`] and [`Machine::on_entry`] hooks.
+//!
+//! The [`Fsm`] runner dispatches events either one by one with
+//! [`Fsm::dispatch`], or from a stream (e.g. a [ring
+//! channel](crate::sync::spsc::ring)) inside a fiber with [`Fsm::run`]. The
+//! transitions can be traced to a log port.
+//!
+//! ```
+//! use drone_core::fsm::{Fsm, Handled, Machine};
+//!
+//! #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+//! enum State {
+//!     Off,
+//!     On,
+//!     Idle,
+//!     Busy,
+//! }
+//!
+//! enum Event {
+//!     Power,
+//!     Start,
+//!     Done,
+//! }
+//!
+//! struct Motor;
+//!
+//! impl Machine for Motor {
+//!     type Event = Event;
+//!     type State = State;
+//!
+//!     fn parent(state: State) -> Option<State> {
+//!         match state {
+//!             State::Idle | State::Busy => Some(State::On),
+//!             State::Off | State::On => None,
+//!         }
+//!     }
+//!
+//!     fn handle(&mut self, state: State, event: &Event) -> Handled<State> {
+//!         match (state, event) {
+//!             (State::Off, Event::Power) => Handled::Transition(State::Idle),
+//!             (State::On, Event::Power) => Handled::Transition(State::Off),
+//!             (State::Idle, Event::Start) => Handled::Transition(State::Busy),
+//!             (State::Busy, Event::Done) => Handled::Transition(State::Idle),
+//!             _ => Handled::Unhandled,
+//!         }
+//!     }
+//! }
+//!
+//! let mut fsm = Fsm::new(Motor, State::Off);
+//! fsm.dispatch(&Event::Power);
+//! fsm.dispatch(&Event::Start);
+//! assert_eq!(fsm.state(), State::Busy);
+//! // Handled by the parent state `On`.
+//! fsm.dispatch(&Event::Power);
+//! assert_eq!(fsm.state(), State::Off);
+//! ```
+
+use crate::log;
+use core::fmt;
+use futures::stream::{Stream, StreamExt};
+
+/// A description of a hierarchical state machine.
+pub trait Machine {
+    /// The state type.
+    type State: Copy + Eq + fmt::Debug;
+
+    /// The event type.
+    type Event;
+
+    /// Returns the parent of the `state`, or `None` for a top-level state.
+    #[inline]
+    fn parent(_state: Self::State) -> Option<Self::State> {
+        None
+    }
+
+    /// Called when the machine enters the `state`.
+    #[inline]
+    fn on_entry(&mut self, _state: Self::State) {}
+
+    /// Called when the machine exits the `state`.
+    #[inline]
+    fn on_exit(&mut self, _state: Self::State) {}
+
+    /// Handles the `event` in the `state`.
+    ///
+    /// Returning [`Handled::Unhandled`] offers the event to the parent state.
+    fn handle(&mut self, state: Self::State, event: &Self::Event) -> Handled<Self::State>;
+}
+
+/// A result of [`Machine::handle`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Handled<S> {
+    /// The event is handled, and the machine transitions to the given state.
+    Transition(S),
+    /// The event is handled, and the machine stays in the current state.
+    Stay,
+    /// The event is not handled by this state.
+    Unhandled,
+}
+
+/// A state machine runner.
+pub struct Fsm<M: Machine> {
+    machine: M,
+    state: M::State,
+    trace: Option<u8>,
+}
+
+impl<M: Machine> Fsm<M> {
+    /// Creates a new runner, and enters the `initial` state with all its
+    /// ancestors.
+    pub fn new(mut machine: M, initial: M::State) -> Self {
+        enter(&mut machine, None, initial);
+        Self { machine, state: initial, trace: None }
+    }
+
+    /// Enables tracing of transitions to the log `port`.
+    #[must_use]
+    pub fn with_trace(mut self, port: u8) -> Self {
+        self.trace = Some(port);
+        self
+    }
+
+    /// Returns the current state.
+    #[inline]
+    pub fn state(&self) -> M::State {
+        self.state
+    }
+
+    /// Returns a reference to the machine.
+    #[inline]
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Returns a mutable reference to the machine.
+    #[inline]
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Dispatches the `event` to the current state and its ancestors.
+    ///
+    /// Returns `false` if no state handled the event.
+    pub fn dispatch(&mut self, event: &M::Event) -> bool {
+        let mut handler = Some(self.state);
+        while let Some(state) = handler {
+            match self.machine.handle(state, event) {
+                Handled::Transition(target) => {
+                    self.transition(target);
+                    return true;
+                }
+                Handled::Stay => return true,
+                Handled::Unhandled => handler = M::parent(state),
+            }
+        }
+        false
+    }
+
+    /// Dispatches all events from the `events` stream until it ends.
+    pub async fn run<S: Stream<Item = M::Event> + Unpin>(&mut self, mut events: S) {
+        while let Some(event) = events.next().await {
+            self.dispatch(&event);
+        }
+    }
+
+    fn transition(&mut self, target: M::State) {
+        let source = self.state;
+        if let Some(port) = self.trace {
+            log::write_fmt(port, format_args!("fsm: {:?} -> {:?}\n", source, target));
+        }
+        let lca = if source == target {
+            // A self-transition exits and re-enters the state.
+            M::parent(source)
+        } else {
+            common_ancestor::<M>(source, target)
+        };
+        let mut state = Some(source);
+        while let Some(exited) = state.filter(|&state| Some(state) != lca) {
+            self.machine.on_exit(exited);
+            state = M::parent(exited);
+        }
+        enter(&mut self.machine, lca, target);
+        self.state = target;
+    }
+}
+
+fn common_ancestor<M: Machine>(a: M::State, b: M::State) -> Option<M::State> {
+    let mut ancestor = Some(a);
+    while let Some(candidate) = ancestor {
+        let mut state = Some(b);
+        while let Some(other) = state {
+            if other == candidate {
+                return Some(candidate);
+            }
+            state = M::parent(other);
+        }
+        ancestor = M::parent(candidate);
+    }
+    None
+}
+
+fn enter<M: Machine>(machine: &mut M, from: Option<M::State>, target: M::State) {
+    if Some(target) == from {
+        return;
+    }
+    if let Some(parent) = M::parent(target) {
+        enter(machine, from, parent);
+    }
+    machine.on_entry(target);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    enum State {
+        A,
+        A1,
+        A2,
+        B,
+    }
+
+    #[derive(Default)]
+    struct Hooks(Vec<(bool, State)>);
+
+    impl Machine for Hooks {
+        type Event = State;
+        type State = State;
+
+        fn parent(state: State) -> Option<State> {
+            match state {
+                State::A1 | State::A2 => Some(State::A),
+                State::A | State::B => None,
+            }
+        }
+
+        fn on_entry(&mut self, state: State) {
+            self.0.push((true, state));
+        }
+
+        fn on_exit(&mut self, state: State) {
+            self.0.push((false, state));
+        }
+
+        fn handle(&mut self, _state: State, event: &State) -> Handled<State> {
+            Handled::Transition(*event)
+        }
+    }
+
+    #[test]
+    fn hooks() {
+        let mut fsm = Fsm::new(Hooks::default(), State::A1);
+        assert_eq!(fsm.machine().0, [(true, State::A), (true, State::A1)]);
+        fsm.machine_mut().0.clear();
+        fsm.dispatch(&State::A2);
+        assert_eq!(fsm.machine().0, [(false, State::A1), (true, State::A2)]);
+        fsm.machine_mut().0.clear();
+        fsm.dispatch(&State::A2);
+        assert_eq!(fsm.machine().0, [(false, State::A2), (true, State::A2)]);
+        fsm.machine_mut().0.clear();
+        fsm.dispatch(&State::B);
+        assert_eq!(fsm.machine().0, [(false, State::A2), (false, State::A), (true, State::B)]);
+        assert_eq!(fsm.state(), State::B);
+    }
+}
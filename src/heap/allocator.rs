@@ -1,9 +1,19 @@
-use super::pool::{Fits, Pool, Statistics};
+use super::{
+    cache,
+    oom::{self, OomAction},
+    pool::{Fits, Pool, PoolCounters, ReallocStatistics, Statistics},
+    snapshot::Snapshot,
+    tag, trace,
+};
+use crate::{
+    check::{self, Fault},
+    mem,
+};
 use core::{
     alloc::{AllocError, Layout},
-    ptr,
     ptr::NonNull,
     slice::SliceIndex,
+    sync::atomic::AtomicU8,
 };
 
 /// Allocator for a generic memory pools layout.
@@ -16,6 +26,15 @@ pub trait Allocator<const N: usize>: Sized {
     /// Logger port for heap tracing. Disabled if `None`.
     const TRACE_PORT: Option<u8>;
 
+    /// Whether the heap is in non-coherent memory used for DMA. If `true`, the
+    /// [`CacheMaintenance`](super::CacheMaintenance) hooks are called on
+    /// allocation and deallocation.
+    const DMA: bool = false;
+
+    /// Whether each pool is placed at its own origin address outside of the
+    /// heap region. See [the module level documentation](super#placement).
+    const PLACED: [bool; N] = [false; N];
+
     /// Returns a reference to a pool or subslice, without doing bounds
     /// checking.
     ///
@@ -26,15 +45,98 @@ pub trait Allocator<const N: usize>: Sized {
     where
         I: SliceIndex<[Pool]>;
 
+    /// Returns a reference to the allocation tag of a block, without doing
+    /// bounds checking. Returns `None` if the heap doesn't record tags.
+    ///
+    /// See [`with_tag`](super::with_tag).
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an out-of-bounds index is Undefined Behavior.
+    #[inline]
+    unsafe fn get_tag_unchecked(&self, _pool_idx: usize, _block_idx: usize) -> Option<&AtomicU8> {
+        None
+    }
+
+    /// Returns a reference to the statistics counters of a pool, without doing
+    /// bounds checking. Returns `None` if the heap doesn't collect statistics.
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an out-of-bounds index is Undefined Behavior.
+    #[inline]
+    unsafe fn get_counters_unchecked(&self, _pool_idx: usize) -> Option<&PoolCounters> {
+        None
+    }
+
     /// Returns allocation statistics in form of
     /// [(`block_size`, capacity, remain); `pool_size`]
-    fn get_statistics(&self) -> [Statistics; N] {
+    ///
+    /// Returns `None` if the heap doesn't collect statistics.
+    fn get_statistics(&self) -> Option<[Statistics; N]> {
         let mut statistics = [Statistics::default(); N];
         for i in 0..N {
             let pool = unsafe { self.get_pool_unchecked(i) };
-            statistics[i] = pool.statistics();
+            statistics[i] = unsafe { self.get_counters_unchecked(i) }?.statistics(pool);
+        }
+        Some(statistics)
+    }
+
+    /// Returns reallocation statistics for each pool, which the blocks are
+    /// moved out of.
+    ///
+    /// Frequent moves within the same pool or to the next pool indicate a
+    /// collection growing in small steps, which could be pre-sized instead.
+    ///
+    /// Returns `None` if the heap doesn't collect statistics.
+    fn realloc_statistics(&self) -> Option<[ReallocStatistics; N]> {
+        let mut statistics = [ReallocStatistics::default(); N];
+        for i in 0..N {
+            let pool = unsafe { self.get_pool_unchecked(i) };
+            statistics[i] = unsafe { self.get_counters_unchecked(i) }?.realloc_statistics(pool);
+        }
+        Some(statistics)
+    }
+
+    /// Checks that the pools occupy exactly the memory region from `start` to
+    /// `end`. The pools in [`PLACED`](Allocator::PLACED) are not checked
+    /// against the region.
+    ///
+    /// Should be called at boot with the heap boundaries defined by the linker
+    /// script, to catch a drift between `Drone.toml` and the memory layout.
+    ///
+    /// # Panics
+    ///
+    /// If the pools are not sorted by block size, are not contiguous, or don't
+    /// match the region.
+    fn check_region(&self, start: usize, end: usize) {
+        let mut pointer = start;
+        let mut block_size = 0;
+        for i in 0..N {
+            let pool = unsafe { self.get_pool_unchecked(i) };
+            assert!(pool.block_size() > block_size, "heap pools are not sorted by block size");
+            block_size = pool.block_size();
+            if Self::PLACED[i] {
+                continue;
+            }
+            assert!(
+                pool.origin() == pointer,
+                "heap pool #{} starts at {:#x}, expected {:#x}",
+                i,
+                pool.origin(),
+                pointer
+            );
+            pointer = pool.edge();
         }
-        statistics
+        assert!(pointer == end, "heap ends at {:#x}, but the region ends at {:#x}", pointer, end);
+    }
+
+    /// Takes a snapshot of the heap statistics.
+    ///
+    /// Two snapshots can be compared with [`diff`](super::diff) to find leaks.
+    /// Returns `None` if the heap doesn't collect statistics.
+    fn snapshot(&self) -> Option<Snapshot<N>> {
+        self.get_statistics().map(|statistics| Snapshot { statistics })
     }
 }
 
@@ -59,15 +161,38 @@ pub fn allocate<A: Allocator<N>, const N: usize>(
     heap: &A,
     layout: Layout,
 ) -> Result<NonNull<[u8]>, AllocError> {
+    if cfg!(debug_assertions) && !mem::is_initialized() {
+        panic!("heap used before `mem::bss_init` and `mem::data_init`");
+    }
     if let Some(trace_port) = A::TRACE_PORT {
         trace::allocate(trace_port, layout);
     }
     if layout.size() == 0 {
         return Ok(NonNull::slice_from_raw_parts(layout.dangling(), 0));
     }
+    if check::inject!(Fault::Alloc) {
+        return Err(AllocError);
+    }
+    allocate_pools(heap, layout).or_else(|AllocError| match oom::drone_oom_handler(layout) {
+        OomAction::Retry => allocate_pools(heap, layout),
+        OomAction::Fail => Err(AllocError),
+    })
+}
+
+fn allocate_pools<A: Allocator<N>, const N: usize>(
+    heap: &A,
+    layout: Layout,
+) -> Result<NonNull<[u8]>, AllocError> {
     for pool_idx in binary_search(heap, &layout)..N {
         let pool = unsafe { heap.get_pool_unchecked(pool_idx) };
         if let Some(ptr) = pool.allocate() {
+            if let Some(counters) = unsafe { heap.get_counters_unchecked(pool_idx) } {
+                counters.allocate();
+            }
+            tag::allocate(heap, pool_idx, pool, ptr);
+            if A::DMA {
+                cache::drone_heap_cache_invalidate(ptr.as_ptr(), pool.block_size());
+            }
             return Ok(NonNull::slice_from_raw_parts(ptr, pool.block_size()));
         }
     }
@@ -81,6 +206,9 @@ pub fn allocate_zeroed<A: Allocator<N>, const N: usize>(
 ) -> Result<NonNull<[u8]>, AllocError> {
     let ptr = allocate(heap, layout)?;
     unsafe { ptr.as_non_null_ptr().as_ptr().write_bytes(0, ptr.len()) }
+    if A::DMA {
+        cache::drone_heap_cache_clean(ptr.as_mut_ptr(), ptr.len());
+    }
     Ok(ptr)
 }
 
@@ -96,9 +224,20 @@ pub unsafe fn deallocate<A: Allocator<N>, const N: usize>(
     if layout.size() == 0 {
         return;
     }
+    let pool_idx = binary_search(heap, ptr);
+    if cfg!(debug_assertions) && pool_idx == N {
+        panic!("invalid free of {:p}", ptr);
+    }
     unsafe {
-        let pool = heap.get_pool_unchecked(binary_search(heap, ptr));
+        let pool = heap.get_pool_unchecked(pool_idx);
+        if A::DMA {
+            cache::drone_heap_cache_invalidate(ptr.as_ptr(), pool.block_size());
+        }
+        tag::deallocate(heap, pool_idx, pool, ptr);
         pool.deallocate(ptr);
+        if let Some(counters) = heap.get_counters_unchecked(pool_idx) {
+            counters.deallocate();
+        }
     }
 }
 
@@ -114,7 +253,9 @@ pub unsafe fn grow<A: Allocator<N>, const N: usize>(
     }
     unsafe {
         let new_ptr = allocate(heap, new_layout)?;
-        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_mut_ptr(), old_layout.size());
+        // Pool blocks are always word-aligned.
+        mem::copy_words(new_ptr.as_mut_ptr(), ptr.as_ptr(), old_layout.size());
+        record_move(heap, ptr, new_ptr, true, old_layout.size());
         deallocate(heap, ptr, old_layout);
         Ok(new_ptr)
     }
@@ -132,7 +273,8 @@ pub unsafe fn grow_zeroed<A: Allocator<N>, const N: usize>(
     }
     unsafe {
         let new_ptr = allocate_zeroed(heap, new_layout)?;
-        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_mut_ptr(), old_layout.size());
+        mem::copy_words(new_ptr.as_mut_ptr(), ptr.as_ptr(), old_layout.size());
+        record_move(heap, ptr, new_ptr, true, old_layout.size());
         deallocate(heap, ptr, old_layout);
         Ok(new_ptr)
     }
@@ -150,77 +292,28 @@ pub unsafe fn shrink<A: Allocator<N>, const N: usize>(
     }
     unsafe {
         let new_ptr = allocate(heap, new_layout)?;
-        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_mut_ptr(), new_layout.size());
+        mem::copy_words(new_ptr.as_mut_ptr(), ptr.as_ptr(), new_layout.size());
+        record_move(heap, ptr, new_ptr, false, new_layout.size());
         deallocate(heap, ptr, old_layout);
         Ok(new_ptr)
     }
 }
 
-mod trace {
-    use crate::{heap::HEAPTRACE_KEY, log::Port};
-    use core::alloc::Layout;
-
-    #[inline(always)]
-    pub(super) fn allocate(trace_port: u8, layout: Layout) {
-        #[inline(never)]
-        fn trace(trace_port: u8, layout: Layout) {
-            Port::new(trace_port)
-                .write::<u32>((0xA1 << 24 | layout.size() as u32 >> 24) ^ HEAPTRACE_KEY)
-                .write::<u32>((0xA2 << 24 | layout.size() as u32 & 0xFF) ^ HEAPTRACE_KEY);
-        }
-        if Port::new(trace_port).is_enabled() {
-            trace(trace_port, layout);
-        }
-    }
-
-    #[inline(always)]
-    pub(super) fn deallocate(trace_port: u8, layout: Layout) {
-        #[inline(never)]
-        fn trace(trace_port: u8, layout: Layout) {
-            Port::new(trace_port)
-                .write::<u32>((0xD1 << 24 | layout.size() as u32 >> 24) ^ HEAPTRACE_KEY)
-                .write::<u32>((0xD2 << 24 | layout.size() as u32 & 0xFF) ^ HEAPTRACE_KEY);
-        }
-        if Port::new(trace_port).is_enabled() {
-            trace(trace_port, layout);
-        }
+fn record_move<A: Allocator<N>, const N: usize>(
+    heap: &A,
+    ptr: NonNull<u8>,
+    new_ptr: NonNull<[u8]>,
+    grow: bool,
+    copied: usize,
+) {
+    if copied == 0 {
+        return;
     }
-
-    #[inline(always)]
-    pub(super) fn grow(trace_port: u8, old_layout: Layout, new_layout: Layout) {
-        #[inline(never)]
-        fn trace(trace_port: u8, old_layout: Layout, new_layout: Layout) {
-            Port::new(trace_port)
-                .write::<u32>((0xB1 << 24 | old_layout.size() as u32 >> 24) ^ HEAPTRACE_KEY)
-                .write::<u32>(
-                    (0xB2 << 24
-                        | (old_layout.size() as u32 & 0xFF) << 16
-                        | new_layout.size() as u32 >> 16)
-                        ^ HEAPTRACE_KEY,
-                )
-                .write::<u32>((0xB3 << 24 | new_layout.size() as u32 & 0xFFFF) ^ HEAPTRACE_KEY);
-        }
-        if Port::new(trace_port).is_enabled() {
-            trace(trace_port, old_layout, new_layout);
-        }
-    }
-
-    #[inline(always)]
-    pub(super) fn shrink(trace_port: u8, old_layout: Layout, new_layout: Layout) {
-        #[inline(never)]
-        fn trace(trace_port: u8, old_layout: Layout, new_layout: Layout) {
-            Port::new(trace_port)
-                .write::<u32>((0xC1 << 24 | old_layout.size() as u32 >> 24) ^ HEAPTRACE_KEY)
-                .write::<u32>(
-                    (0xC2 << 24
-                        | (old_layout.size() as u32 & 0xFF) << 16
-                        | new_layout.size() as u32 >> 16)
-                        ^ HEAPTRACE_KEY,
-                )
-                .write::<u32>((0xC3 << 24 | new_layout.size() as u32 & 0xFFFF) ^ HEAPTRACE_KEY);
-        }
-        if Port::new(trace_port).is_enabled() {
-            trace(trace_port, old_layout, new_layout);
+    let pool_idx = binary_search(heap, ptr);
+    if pool_idx < N {
+        if let Some(counters) = unsafe { heap.get_counters_unchecked(pool_idx) } {
+            let pool = unsafe { heap.get_pool_unchecked(pool_idx) };
+            counters.record_move(pool, grow, new_ptr.as_non_null_ptr(), copied);
         }
     }
 }
@@ -228,9 +321,11 @@ mod trace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::heap::{diff, Delta};
 
     struct TestHeap {
         pools: [Pool; 10],
+        counters: Vec<PoolCounters>,
     }
 
     const POOL_COUNT: usize = 10;
@@ -244,6 +339,10 @@ mod tests {
         {
             unsafe { self.pools.get_unchecked(index) }
         }
+
+        unsafe fn get_counters_unchecked(&self, pool_idx: usize) -> Option<&PoolCounters> {
+            self.counters.get(pool_idx)
+        }
     }
 
     #[test]
@@ -277,6 +376,7 @@ mod tests {
                 Pool::new(16020, 72, 100),
                 Pool::new(23220, 91, 100),
             ],
+            counters: Vec::new(),
         };
         assert_eq!(search_layout(&heap, 1), Some(2));
         assert_eq!(search_layout(&heap, 2), Some(2));
@@ -321,6 +421,7 @@ mod tests {
                 Pool::new(o + 1600, 72, 10),
                 Pool::new(o + 2320, 91, 10),
             ],
+            counters: Vec::new(),
         };
         let layout = Layout::from_size_align(32, 1).unwrap();
         unsafe {
@@ -342,4 +443,161 @@ mod tests {
             assert_eq!(*(&m[736] as *const _ as *const usize), o + 698);
         }
     }
+
+    fn single_pool_heap(o: usize) -> TestHeap {
+        let mut heap = single_pool_heap_without_statistics(o);
+        heap.counters = (0..POOL_COUNT)
+            .map(|i| PoolCounters::new(unsafe { heap.get_pool_unchecked(i) }.capacity()))
+            .collect();
+        heap
+    }
+
+    fn single_pool_heap_without_statistics(o: usize) -> TestHeap {
+        TestHeap {
+            pools: [
+                Pool::new(o, 8, 4),
+                Pool::new(o + 32, 16, 0),
+                Pool::new(o + 32, 24, 0),
+                Pool::new(o + 32, 32, 0),
+                Pool::new(o + 32, 40, 0),
+                Pool::new(o + 32, 48, 0),
+                Pool::new(o + 32, 56, 0),
+                Pool::new(o + 32, 64, 0),
+                Pool::new(o + 32, 72, 0),
+                Pool::new(o + 32, 80, 0),
+            ],
+            counters: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn snapshots() {
+        let mut m = [0usize; 8];
+        let o = &mut m as *mut _ as usize;
+        let heap = single_pool_heap(o);
+        let layout = Layout::from_size_align(8, 1).unwrap();
+        let a = heap.snapshot().unwrap();
+        let ptr = allocate(&heap, layout).unwrap().as_non_null_ptr();
+        let b = heap.snapshot().unwrap();
+        assert_eq!(diff(&a, &b)[0], Delta { block_size: 8, allocated: 1 });
+        assert!(!a.is_balanced(&b));
+        unsafe { deallocate(&heap, ptr, layout) };
+        assert!(a.is_balanced(&heap.snapshot().unwrap()));
+    }
+
+    #[test]
+    fn reallocs() {
+        let mut m = [0usize; 8];
+        let o = &mut m as *mut _ as usize;
+        let heap = single_pool_heap(o);
+        let small = Layout::from_size_align(2, 1).unwrap();
+        let medium = Layout::from_size_align(4, 1).unwrap();
+        let large = Layout::from_size_align(6, 1).unwrap();
+        unsafe {
+            let ptr = allocate(&heap, medium).unwrap().as_non_null_ptr();
+            let ptr = grow(&heap, ptr, medium, large).unwrap().as_non_null_ptr();
+            let ptr = shrink(&heap, ptr, large, small).unwrap().as_non_null_ptr();
+            deallocate(&heap, ptr, small);
+        }
+        let statistics = heap.realloc_statistics().unwrap();
+        assert_eq!(statistics[0], ReallocStatistics {
+            block_size: 8,
+            grows: 1,
+            shrinks: 1,
+            same_pool: 2,
+            copied: 6,
+        });
+        assert_eq!(statistics[1], ReallocStatistics {
+            block_size: 16,
+            ..ReallocStatistics::default()
+        });
+    }
+
+    #[test]
+    fn without_statistics() {
+        let mut m = [0usize; 8];
+        let o = &mut m as *mut _ as usize;
+        let heap = single_pool_heap_without_statistics(o);
+        let small = Layout::from_size_align(2, 1).unwrap();
+        let large = Layout::from_size_align(6, 1).unwrap();
+        unsafe {
+            let ptr = allocate(&heap, small).unwrap().as_non_null_ptr();
+            grow(&heap, ptr, small, large).unwrap();
+        }
+        assert!(heap.get_statistics().is_none());
+        assert!(heap.realloc_statistics().is_none());
+        assert!(heap.snapshot().is_none());
+    }
+
+    #[test]
+    fn region() {
+        let heap = single_pool_heap(0x2000_0000);
+        heap.check_region(0x2000_0000, 0x2000_0020);
+    }
+
+    #[test]
+    #[should_panic(expected = "heap ends at 0x20000020, but the region ends at 0x20000040")]
+    fn region_mismatch() {
+        let heap = single_pool_heap(0x2000_0000);
+        heap.check_region(0x2000_0000, 0x2000_0040);
+    }
+
+    struct PlacedHeap {
+        pools: [Pool; 2],
+    }
+
+    impl Allocator<2> for PlacedHeap {
+        const PLACED: [bool; 2] = [true, false];
+        const TRACE_PORT: Option<u8> = None;
+
+        unsafe fn get_pool_unchecked<I>(&self, index: I) -> &I::Output
+        where
+            I: SliceIndex<[Pool]>,
+        {
+            unsafe { self.pools.get_unchecked(index) }
+        }
+    }
+
+    #[test]
+    fn region_placed() {
+        let mut m = [0usize; 16];
+        let o = &mut m as *mut _ as usize;
+        let heap = PlacedHeap { pools: [Pool::new(o, 8, 2), Pool::new(o + 64, 16, 2)] };
+        heap.check_region(o + 64, o + 96);
+        let layout = Layout::from_size_align(12, 1).unwrap();
+        unsafe {
+            let ptr = allocate(&heap, layout).unwrap().as_non_null_ptr();
+            assert_eq!(ptr.as_ptr() as usize, o + 64);
+            deallocate(&heap, ptr, layout);
+            assert_eq!(allocate(&heap, layout).unwrap().as_non_null_ptr(), ptr);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "double free")]
+    fn double_free() {
+        let mut m = [0usize; 8];
+        let o = &mut m as *mut _ as usize;
+        let heap = single_pool_heap(o);
+        let layout = Layout::from_size_align(8, 1).unwrap();
+        unsafe {
+            let ptr = allocate(&heap, layout).unwrap().as_non_null_ptr();
+            allocate(&heap, layout).unwrap();
+            deallocate(&heap, ptr, layout);
+            deallocate(&heap, ptr, layout);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid free")]
+    fn invalid_free() {
+        let mut m = [0usize; 8];
+        let o = &mut m as *mut _ as usize;
+        let heap = single_pool_heap(o);
+        let layout = Layout::from_size_align(8, 1).unwrap();
+        unsafe {
+            let ptr = allocate(&heap, layout).unwrap().as_non_null_ptr();
+            deallocate(&heap, NonNull::new_unchecked(ptr.as_ptr().add(1)), layout);
+        }
+    }
 }
@@ -0,0 +1,70 @@
+/// Data cache maintenance for DMA heaps.
+///
+/// Heaps declared with `dma => true;` in the [`heap`](crate::heap) macro call
+/// these hooks, so that blocks handed out from non-coherent memory are safe to
+/// pass to a DMA controller:
+///
+/// * A freshly allocated block is invalidated, so that stale cache lines of a
+///   previous owner are never written back over DMA-written data.
+/// * A block zeroed by the allocator is cleaned, so that the zeros reach the
+///   memory.
+/// * A deallocated block is invalidated, so that dirty lines of dead data are
+///   discarded.
+///
+/// Pools of a DMA heap should have block sizes and origins aligned to the cache
+/// line size, otherwise maintenance of one block affects its neighbours.
+///
+/// The hooks are registered with the
+/// [`set_cache_maintenance!`](crate::set_cache_maintenance) macro:
+///
+/// ```
+/// use drone_core::{heap::CacheMaintenance, set_cache_maintenance};
+///
+/// pub struct Scb;
+///
+/// impl CacheMaintenance for Scb {
+///     fn clean(_ptr: *const u8, _len: usize) {
+///         // Clean D-cache by address here.
+///     }
+///
+///     fn invalidate(_ptr: *mut u8, _len: usize) {
+///         // Invalidate D-cache by address here.
+///     }
+/// }
+///
+/// set_cache_maintenance!(Scb);
+/// # fn main() {}
+/// ```
+pub trait CacheMaintenance {
+    /// Writes back dirty cache lines covering `len` bytes at `ptr`.
+    fn clean(ptr: *const u8, len: usize);
+
+    /// Discards cache lines covering `len` bytes at `ptr`.
+    fn invalidate(ptr: *mut u8, len: usize);
+}
+
+/// Registers `$hooks` type as the data cache maintenance for DMA heaps.
+///
+/// The type must implement [`CacheMaintenance`](crate::heap::CacheMaintenance).
+#[macro_export]
+macro_rules! set_cache_maintenance {
+    ($hooks:ty) => {
+        #[no_mangle]
+        fn drone_heap_cache_clean(ptr: *const u8, len: usize) {
+            <$hooks as $crate::heap::CacheMaintenance>::clean(ptr, len)
+        }
+
+        #[no_mangle]
+        fn drone_heap_cache_invalidate(ptr: *mut u8, len: usize) {
+            <$hooks as $crate::heap::CacheMaintenance>::invalidate(ptr, len)
+        }
+    };
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+pub(crate) fn drone_heap_cache_clean(_ptr: *const u8, _len: usize) {}
+
+#[linkage = "weak"]
+#[no_mangle]
+pub(crate) fn drone_heap_cache_invalidate(_ptr: *mut u8, _len: usize) {}
@@ -25,7 +25,9 @@
 //! ]
 //! ```
 //!
-//! The `size` field should match the resulting size of the pools.
+//! The `size` field should match the resulting size of the pools. The
+//! `heap!` macro rejects configurations where it doesn't, as well as block
+//! sizes that aren't multiples of 4 and duplicate block sizes.
 //!
 //! Then in the application code:
 //!
@@ -59,6 +61,11 @@
 //!     global => true;
 //!     // Uncomment the following line to enable heap tracing feature:
 //!     // trace_port => 31;
+//!     // Uncomment the following line to enable allocation tags:
+//!     // tags => true;
+//!     // Uncomment the following line to collect statistics in debug builds
+//!     // only:
+//!     // statistics => debug;
 //! }
 //!
 //! // Create a static instance of the heap type and declare it as the global
@@ -68,6 +75,86 @@
 //! pub static HEAP: Heap = Heap::new();
 //! ```
 //!
+//! To catch a drift between `Drone.toml` and the linker script, check the heap
+//! against the linker symbols at boot:
+//!
+//! ```ignore
+//! use drone_core::heap::Allocator;
+//!
+//! extern "C" {
+//!     static HEAP_START: u8;
+//!     static HEAP_END: u8;
+//! }
+//!
+//! unsafe {
+//!     HEAP.check_region(
+//!         &HEAP_START as *const u8 as usize,
+//!         &HEAP_END as *const u8 as usize,
+//!     );
+//! }
+//! ```
+//!
+//! # DMA
+//!
+//! On cores with a data cache, like Cortex-M7, a heap placed in non-coherent
+//! memory can maintain the cache by itself. Declare it with `dma => true;` and
+//! register the hooks with [`set_cache_maintenance!`](crate::set_cache_maintenance).
+//! See [`CacheMaintenance`] for the details.
+//!
+//! # Placement
+//!
+//! Pools can be placed into distinct memory regions, e.g. the pools of small
+//! blocks into a fast tightly-coupled memory, and the rest into SRAM. Map the
+//! block sizes of such pools to their origin addresses with the `placement`
+//! key:
+//!
+//! ```ignore
+//! heap! {
+//!     config => main;
+//!     metadata => pub Heap;
+//!     global => true;
+//!     // Place the pool of 4-byte blocks at the beginning of DTCM.
+//!     placement => {
+//!         4 => 0x2000_0000;
+//!     };
+//! }
+//! ```
+//!
+//! The placed pools don't count toward the `size` field of `Drone.toml`, and
+//! are skipped by [`Allocator::check_region`]. The remaining pools stay
+//! contiguous from the heap origin. The pools must still be located in the
+//! ascending order of their block sizes, and the linker script must reserve
+//! the memory of each placed pool.
+//!
+//! # Allocation tags
+//!
+//! To attribute memory consumption to subsystems, declare the heap with
+//! `tags => true;`. The heap then records a small integer tag for every
+//! allocated block, taken from the current tag set by [`with_tag`]:
+//!
+//! ```
+//! use drone_core::heap;
+//!
+//! const TAG_NET: u8 = 1;
+//!
+//! heap::with_tag(TAG_NET, || {
+//!     // Allocations made here are accounted to `TAG_NET`.
+//! });
+//! let net = heap::tag_statistics(TAG_NET);
+//! ```
+//!
+//! The tags take one byte of RAM per block.
+//!
+//! # Statistics
+//!
+//! By default the heap counts the free blocks and the reallocations of each
+//! pool, which feeds [`Allocator::get_statistics`],
+//! [`Allocator::realloc_statistics`], and [`Allocator::snapshot`]. The
+//! counters cost five words of RAM per pool and a few atomic operations on
+//! every allocation. Size-constrained builds can drop them with
+//! `statistics => false;`, or keep them only in debug builds with
+//! `statistics => debug;`. Without the counters these methods return `None`.
+//!
 //! # Tuning
 //!
 //! Using empiric values for the memory pools layout may lead to undesired
@@ -79,14 +166,24 @@
 //! The actual steps are platform-specific. Refer to the platform crate
 //! documentation for instructions.
 
+pub mod trace;
+
 mod allocator;
+mod cache;
+mod oom;
 mod pool;
+mod snapshot;
+mod tag;
 
 pub use self::{
     allocator::{
         allocate, allocate_zeroed, binary_search, deallocate, grow, grow_zeroed, shrink, Allocator,
     },
-    pool::Pool,
+    cache::CacheMaintenance,
+    oom::{OomAction, OomHandler},
+    pool::{Pool, PoolCounters, ReallocStatistics},
+    snapshot::{diff, Delta, Snapshot},
+    tag::{current_tag, tag_statistics, with_tag, TagStatistics, MAX_TAGS, UNTAGGED},
 };
 
 /// XOR pattern for heap trace output.
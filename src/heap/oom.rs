@@ -0,0 +1,66 @@
+use core::alloc::Layout;
+
+/// An action to take after an out-of-memory condition has been handled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OomAction {
+    /// Some memory has been freed, the allocator should try once again.
+    Retry,
+    /// The allocation should fail.
+    Fail,
+}
+
+/// An out-of-memory handler.
+///
+/// The handler is called by the allocator when no pool can satisfy an
+/// allocation request. It may attempt a recovery, e.g. by dropping caches, and
+/// return [`OomAction::Retry`], in which case the allocator will retry the
+/// allocation once. If the allocation fails nevertheless, the standard
+/// out-of-memory path is taken, which aborts for infallible allocations.
+///
+/// The handler is registered with the [`set_oom_handler!`](crate::set_oom_handler)
+/// macro:
+///
+/// ```
+/// use core::alloc::Layout;
+/// use drone_core::{
+///     heap::{OomAction, OomHandler},
+///     set_oom_handler,
+/// };
+///
+/// pub struct DropCaches;
+///
+/// impl OomHandler for DropCaches {
+///     fn handle(_layout: Layout) -> OomAction {
+///         // Free some memory here.
+///         OomAction::Retry
+///     }
+/// }
+///
+/// set_oom_handler!(DropCaches);
+/// # fn main() {}
+/// ```
+pub trait OomHandler {
+    /// Handles a failure to allocate a block for `layout`.
+    ///
+    /// The handler must not allocate from the same heap.
+    fn handle(layout: Layout) -> OomAction;
+}
+
+/// Registers `$handler` type as the out-of-memory handler.
+///
+/// The type must implement [`OomHandler`](crate::heap::OomHandler).
+#[macro_export]
+macro_rules! set_oom_handler {
+    ($handler:ty) => {
+        #[no_mangle]
+        fn drone_oom_handler(layout: ::core::alloc::Layout) -> $crate::heap::OomAction {
+            <$handler as $crate::heap::OomHandler>::handle(layout)
+        }
+    };
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+pub(crate) fn drone_oom_handler(_layout: Layout) -> OomAction {
+    OomAction::Fail
+}
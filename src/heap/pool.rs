@@ -1,3 +1,4 @@
+use crate::sync::Backoff;
 use core::{
     alloc::Layout,
     ptr::{self, NonNull},
@@ -11,6 +12,29 @@ pub struct Statistics {
     pub remain: usize,
 }
 
+/// Reallocation statistics of a pool.
+///
+/// See [`Allocator::realloc_statistics`](super::Allocator::realloc_statistics).
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ReallocStatistics {
+    /// The block size of the pool.
+    pub block_size: usize,
+    /// The number of blocks moved out of the pool to grow.
+    pub grows: usize,
+    /// The number of blocks moved out of the pool to shrink.
+    pub shrinks: usize,
+    /// The number of moves, which landed in the same pool. Such moves copy the
+    /// data without changing the block size.
+    pub same_pool: usize,
+    /// The total number of bytes copied by the moves.
+    pub copied: usize,
+}
+
+/// The maximum number of free list nodes scanned for a double free in debug
+/// builds.
+#[cfg(debug_assertions)]
+const DOUBLE_FREE_SCAN: usize = 64;
+
 /// The set of free memory blocks.
 ///
 /// It operates by connecting unallocated regions of memory together in a linked
@@ -19,8 +43,6 @@ pub struct Statistics {
 pub struct Pool {
     /// Total blocks
     capacity: usize,
-    /// Remain blocks
-    remain: AtomicUsize,
     /// Block size. Doesn't change in the run-time.
     block_size: usize,
     /// Address of the byte past the last element. Doesn't change in the
@@ -32,6 +54,24 @@ pub struct Pool {
     uninit: AtomicPtr<u8>,
 }
 
+/// Statistics counters of a pool.
+///
+/// The counters are kept apart from [`Pool`], so a heap can be built without
+/// them. See
+/// [`Allocator::get_counters_unchecked`](super::Allocator::get_counters_unchecked).
+pub struct PoolCounters {
+    /// Remain blocks
+    remain: AtomicUsize,
+    /// Number of blocks moved out to grow.
+    grows: AtomicUsize,
+    /// Number of blocks moved out to shrink.
+    shrinks: AtomicUsize,
+    /// Number of moves within this pool.
+    same_pool: AtomicUsize,
+    /// Number of bytes copied by the moves.
+    copied: AtomicUsize,
+}
+
 unsafe impl Sync for Pool {}
 
 impl Pool {
@@ -39,7 +79,6 @@ impl Pool {
     pub const fn new(address: usize, block_size: usize, capacity: usize) -> Self {
         Self {
             capacity,
-            remain: AtomicUsize::new(capacity),
             block_size,
             edge: (address + block_size * capacity) as *mut u8,
             free: AtomicPtr::new(ptr::null_mut()),
@@ -59,13 +98,16 @@ impl Pool {
         self.block_size
     }
 
-    /// Returns pool allocation statistics.
-    pub fn statistics(&self) -> Statistics {
-        Statistics {
-            block_size: self.block_size,
-            capacity: self.capacity,
-            remain: self.remain.load(Ordering::Relaxed),
-        }
+    /// Returns the address of the first block.
+    #[inline]
+    pub fn origin(&self) -> usize {
+        self.edge() - self.block_size * self.capacity
+    }
+
+    /// Returns the address past the last block.
+    #[inline]
+    pub fn edge(&self) -> usize {
+        self.edge as usize
     }
 
     /// Allocates one block of memory.
@@ -83,13 +125,24 @@ impl Pool {
     ///
     /// This operation is lock-free and has *O(1)* time complexity.
     ///
+    /// In debug builds `ptr` is validated to be a start of a block, and a
+    /// bounded number of the most recently freed blocks is scanned for `ptr`.
+    ///
     /// # Safety
     ///
     /// * `ptr` must point to a block previously allocated by
     ///   [`alloc`](Pool::alloc).
     /// * `ptr` must not be used after deallocation.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, if `ptr` is not a start of an allocated block of this
+    /// pool, or if it is already freed.
     #[allow(clippy::cast_ptr_alignment)]
     pub unsafe fn deallocate(&self, ptr: NonNull<u8>) {
+        #[cfg(debug_assertions)]
+        self.check_free(ptr);
+        let mut backoff = Backoff::new();
         loop {
             let curr = self.free.load(Ordering::Acquire);
             unsafe { ptr::write(ptr.as_ptr().cast::<*mut u8>(), curr) };
@@ -99,14 +152,15 @@ impl Pool {
                 .compare_exchange_weak(curr, next, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
-                self.remain.fetch_add(1, Ordering::Relaxed);
                 break;
             }
+            backoff.spin();
         }
     }
 
     #[allow(clippy::cast_ptr_alignment)]
     unsafe fn alloc_free(&self) -> Option<NonNull<u8>> {
+        let mut backoff = Backoff::new();
         loop {
             let curr = self.free.load(Ordering::Acquire);
             if curr.is_null() {
@@ -118,13 +172,46 @@ impl Pool {
                 .compare_exchange_weak(curr, next, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
-                self.remain.fetch_sub(1, Ordering::Relaxed);
                 break Some(unsafe { NonNull::new_unchecked(curr) });
             }
+            backoff.spin();
         }
     }
 
+    #[cfg(debug_assertions)]
+    #[allow(clippy::cast_ptr_alignment)]
+    fn check_free(&self, ptr: NonNull<u8>) {
+        let origin = self.origin();
+        let addr = ptr.as_ptr() as usize;
+        let uninit = self.uninit.load(Ordering::Relaxed) as usize;
+        if addr < origin || addr >= uninit || (addr - origin) % self.block_size != 0 {
+            panic!("invalid free of {:p}", ptr);
+        }
+        // The free list links are read while other contexts can allocate the
+        // blocks and overwrite them, so the scan needs a critical section. A
+        // panic in the deallocation path is fatal, hence the scan is skipped if
+        // the platform doesn't implement critical sections.
+        if !crate::thr::critical_available() {
+            return;
+        }
+        let is_block =
+            |node: usize| node >= origin && node < uninit && (node - origin) % self.block_size == 0;
+        crate::thr::critical(|_| {
+            let mut node = self.free.load(Ordering::Acquire);
+            for _ in 0..DOUBLE_FREE_SCAN {
+                if !is_block(node as usize) {
+                    break;
+                }
+                if node == ptr.as_ptr() {
+                    panic!("double free of {:p}", ptr);
+                }
+                node = unsafe { ptr::read(node as *const *mut u8) };
+            }
+        });
+    }
+
     unsafe fn alloc_uninit(&self) -> Option<NonNull<u8>> {
+        let mut backoff = Backoff::new();
         loop {
             let curr = self.uninit.load(Ordering::Relaxed);
             if curr == self.edge {
@@ -136,10 +223,69 @@ impl Pool {
                 .compare_exchange_weak(curr, next, Ordering::Relaxed, Ordering::Relaxed)
                 .is_ok()
             {
-                self.remain.fetch_sub(1, Ordering::Relaxed);
                 break Some(unsafe { NonNull::new_unchecked(curr) });
             }
+            backoff.spin();
+        }
+    }
+}
+
+impl PoolCounters {
+    /// Creates new counters for a pool of `capacity` blocks.
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            remain: AtomicUsize::new(capacity),
+            grows: AtomicUsize::new(0),
+            shrinks: AtomicUsize::new(0),
+            same_pool: AtomicUsize::new(0),
+            copied: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns allocation statistics of `pool`.
+    pub fn statistics(&self, pool: &Pool) -> Statistics {
+        Statistics {
+            block_size: pool.block_size(),
+            capacity: pool.capacity(),
+            remain: self.remain.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns reallocation statistics of `pool`.
+    pub fn realloc_statistics(&self, pool: &Pool) -> ReallocStatistics {
+        ReallocStatistics {
+            block_size: pool.block_size(),
+            grows: self.grows.load(Ordering::Relaxed),
+            shrinks: self.shrinks.load(Ordering::Relaxed),
+            same_pool: self.same_pool.load(Ordering::Relaxed),
+            copied: self.copied.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Records an allocation of a block.
+    #[inline]
+    pub(super) fn allocate(&self) {
+        self.remain.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Records a deallocation of a block.
+    #[inline]
+    pub(super) fn deallocate(&self) {
+        self.remain.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a move of a block out of `pool` to `new_ptr` with `copied`
+    /// bytes.
+    pub(super) fn record_move(&self, pool: &Pool, grow: bool, new_ptr: NonNull<u8>, copied: usize) {
+        if grow {
+            self.grows.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.shrinks.fetch_add(1, Ordering::Relaxed);
+        }
+        if (pool.origin()..pool.edge()).contains(&(new_ptr.as_ptr() as usize)) {
+            self.same_pool.fetch_add(1, Ordering::Relaxed);
         }
+        self.copied.fetch_add(copied, Ordering::Relaxed);
     }
 }
 
@@ -0,0 +1,47 @@
+use super::pool::Statistics;
+
+/// An opaque snapshot of heap statistics taken by
+/// [`Allocator::snapshot`](super::Allocator::snapshot).
+#[derive(Clone, Copy)]
+pub struct Snapshot<const N: usize> {
+    pub(super) statistics: [Statistics; N],
+}
+
+/// A net change of allocations in a pool between two snapshots.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Delta {
+    /// The block size of the pool.
+    pub block_size: usize,
+    /// The number of blocks allocated minus the number of blocks freed.
+    pub allocated: isize,
+}
+
+/// Returns per-pool net allocation deltas from `a` to `b`.
+///
+/// ```no_run
+/// # #![feature(allocator_api)]
+/// # use drone_core::heap::{self, Allocator};
+/// # fn check<A: Allocator<3>>(heap: &A) {
+/// let before = heap.snapshot().unwrap();
+/// // Do some work here.
+/// let after = heap.snapshot().unwrap();
+/// assert!(heap::diff(&before, &after).iter().all(|delta| delta.allocated == 0));
+/// # }
+/// # fn main() {}
+/// ```
+pub fn diff<const N: usize>(a: &Snapshot<N>, b: &Snapshot<N>) -> [Delta; N] {
+    let mut deltas = [Delta::default(); N];
+    for (delta, (a, b)) in deltas.iter_mut().zip(a.statistics.iter().zip(&b.statistics)) {
+        delta.block_size = a.block_size;
+        delta.allocated = a.remain as isize - b.remain as isize;
+    }
+    deltas
+}
+
+impl<const N: usize> Snapshot<N> {
+    /// Returns `true` if no pool has a net allocation change from `self` to
+    /// `other`.
+    pub fn is_balanced(&self, other: &Self) -> bool {
+        diff(self, other).iter().all(|delta| delta.allocated == 0)
+    }
+}
@@ -0,0 +1,186 @@
+use super::{allocator::Allocator, pool::Pool};
+use core::{
+    ptr::NonNull,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+};
+
+/// The maximum number of distinct allocation tags.
+pub const MAX_TAGS: usize = 16;
+
+/// The tag of allocations made outside of [`with_tag`].
+pub const UNTAGGED: u8 = 0;
+
+static CURRENT: AtomicU8 = AtomicU8::new(UNTAGGED);
+
+static COUNTERS: [Counters; MAX_TAGS] = [Counters::ZERO; MAX_TAGS];
+
+struct Counters {
+    blocks: AtomicUsize,
+    bytes: AtomicUsize,
+    peak_bytes: AtomicUsize,
+    allocations: AtomicUsize,
+}
+
+/// Allocation statistics of a tag.
+///
+/// See [`tag_statistics`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct TagStatistics {
+    /// The number of blocks currently allocated with the tag.
+    pub blocks: usize,
+    /// The total size of the blocks currently allocated with the tag.
+    pub bytes: usize,
+    /// The maximum value of `bytes` observed so far.
+    pub peak_bytes: usize,
+    /// The total number of allocations made with the tag.
+    pub allocations: usize,
+}
+
+struct Restore(u8);
+
+impl Counters {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ZERO: Self = Self {
+        blocks: AtomicUsize::new(0),
+        bytes: AtomicUsize::new(0),
+        peak_bytes: AtomicUsize::new(0),
+        allocations: AtomicUsize::new(0),
+    };
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.store(self.0, Ordering::Relaxed);
+    }
+}
+
+/// Calls `f` with the current allocation tag set to `tag`.
+///
+/// Every block allocated by `f` from a heap declared with `tags => true;` is
+/// recorded with `tag`, and accounted in [`tag_statistics`] until it is
+/// deallocated. Calls can be nested, the previous tag is restored on return.
+///
+/// The current tag is global, so allocations made by threads preempting `f`
+/// are attributed to `tag` as well.
+///
+/// # Panics
+///
+/// If `tag` is not less than [`MAX_TAGS`].
+pub fn with_tag<R>(tag: u8, f: impl FnOnce() -> R) -> R {
+    assert!(usize::from(tag) < MAX_TAGS, "allocation tag {} is out of range", tag);
+    let _restore = Restore(CURRENT.swap(tag, Ordering::Relaxed));
+    f()
+}
+
+/// Returns the current allocation tag.
+#[inline]
+pub fn current_tag() -> u8 {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Returns allocation statistics of `tag`, aggregated over all heaps declared
+/// with `tags => true;`.
+///
+/// # Panics
+///
+/// If `tag` is not less than [`MAX_TAGS`].
+pub fn tag_statistics(tag: u8) -> TagStatistics {
+    let counters = &COUNTERS[usize::from(tag)];
+    TagStatistics {
+        blocks: counters.blocks.load(Ordering::Relaxed),
+        bytes: counters.bytes.load(Ordering::Relaxed),
+        peak_bytes: counters.peak_bytes.load(Ordering::Relaxed),
+        allocations: counters.allocations.load(Ordering::Relaxed),
+    }
+}
+
+pub(super) fn allocate<A: Allocator<N>, const N: usize>(
+    heap: &A,
+    pool_idx: usize,
+    pool: &Pool,
+    ptr: NonNull<u8>,
+) {
+    if let Some(slot) = unsafe { heap.get_tag_unchecked(pool_idx, block_idx(pool, ptr)) } {
+        let tag = current_tag();
+        slot.store(tag, Ordering::Relaxed);
+        let counters = &COUNTERS[usize::from(tag)];
+        counters.blocks.fetch_add(1, Ordering::Relaxed);
+        counters.allocations.fetch_add(1, Ordering::Relaxed);
+        let bytes = counters.bytes.fetch_add(pool.block_size(), Ordering::Relaxed);
+        counters.peak_bytes.fetch_max(bytes + pool.block_size(), Ordering::Relaxed);
+    }
+}
+
+pub(super) fn deallocate<A: Allocator<N>, const N: usize>(
+    heap: &A,
+    pool_idx: usize,
+    pool: &Pool,
+    ptr: NonNull<u8>,
+) {
+    if let Some(slot) = unsafe { heap.get_tag_unchecked(pool_idx, block_idx(pool, ptr)) } {
+        let counters = &COUNTERS[usize::from(slot.load(Ordering::Relaxed))];
+        counters.blocks.fetch_sub(1, Ordering::Relaxed);
+        counters.bytes.fetch_sub(pool.block_size(), Ordering::Relaxed);
+    }
+}
+
+fn block_idx(pool: &Pool, ptr: NonNull<u8>) -> usize {
+    (ptr.as_ptr() as usize - pool.origin()) / pool.block_size()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heap;
+    use core::{alloc::Layout, slice::SliceIndex};
+
+    struct TaggedHeap {
+        pools: [Pool; 2],
+        tags: [AtomicU8; 6],
+    }
+
+    impl Allocator<2> for TaggedHeap {
+        const TRACE_PORT: Option<u8> = None;
+
+        unsafe fn get_pool_unchecked<I>(&self, index: I) -> &I::Output
+        where
+            I: SliceIndex<[Pool]>,
+        {
+            unsafe { self.pools.get_unchecked(index) }
+        }
+
+        unsafe fn get_tag_unchecked(&self, pool_idx: usize, block_idx: usize) -> Option<&AtomicU8> {
+            Some(unsafe { self.tags.get_unchecked([0, 4][pool_idx] + block_idx) })
+        }
+    }
+
+    #[test]
+    fn accounting() {
+        const TAG: u8 = 7;
+        let mut m = [0usize; 12];
+        let o = &mut m as *mut _ as usize;
+        let tagged = TaggedHeap {
+            pools: [Pool::new(o, 8, 4), Pool::new(o + 32, 32, 2)],
+            tags: Default::default(),
+        };
+        let small = Layout::from_size_align(4, 1).unwrap();
+        let large = Layout::from_size_align(20, 1).unwrap();
+        let (a, b) = with_tag(TAG, || {
+            let a = heap::allocate(&tagged, small).unwrap().as_non_null_ptr();
+            let b = heap::allocate(&tagged, large).unwrap().as_non_null_ptr();
+            (a, b)
+        });
+        assert_eq!(current_tag(), UNTAGGED);
+        assert_eq!(tagged.tags[0].load(Ordering::Relaxed), TAG);
+        assert_eq!(tagged.tags[4].load(Ordering::Relaxed), TAG);
+        unsafe { heap::deallocate(&tagged, a, small) };
+        assert_eq!(tag_statistics(TAG), TagStatistics {
+            blocks: 1,
+            bytes: 32,
+            peak_bytes: 40,
+            allocations: 2,
+        });
+        unsafe { heap::deallocate(&tagged, b, large) };
+        assert_eq!(tag_statistics(TAG).bytes, 0);
+    }
+}
@@ -0,0 +1,216 @@
+//! Heap trace records.
+//!
+//! If a heap is defined with `trace_port` option, it writes a record to the
+//! log port for each allocator operation. A record consists of big-endian
+//! `u32` words XOR-ed with [`HEAPTRACE_KEY`](super::HEAPTRACE_KEY). The most
+//! significant byte of each word is a tag, which identifies the operation and
+//! the position of the word inside the record.
+//!
+//! With `std` feature, [`decode`] parses the byte stream back into [`Event`]s,
+//! so that the host tools share the same encoding with the target.
+
+use super::HEAPTRACE_KEY;
+use crate::log::Port;
+use core::alloc::Layout;
+#[cfg(feature = "std")]
+use core::fmt;
+
+/// A heap trace record.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// A block for `size` bytes was allocated.
+    Allocate {
+        /// The requested size.
+        size: u32,
+    },
+    /// A block for `size` bytes was deallocated.
+    Deallocate {
+        /// The size of the deallocated layout.
+        size: u32,
+    },
+    /// An allocation was grown from `old_size` to `new_size` bytes.
+    Grow {
+        /// The size of the old layout.
+        old_size: u32,
+        /// The size of the new layout.
+        new_size: u32,
+    },
+    /// An allocation was shrunk from `old_size` to `new_size` bytes.
+    Shrink {
+        /// The size of the old layout.
+        old_size: u32,
+        /// The size of the new layout.
+        new_size: u32,
+    },
+}
+
+/// The error type returned from [`decode`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    /// The stream ends in the middle of a record.
+    Truncated,
+    /// A word with an unexpected tag at the given byte offset.
+    UnexpectedTag {
+        /// The byte offset of the word.
+        offset: usize,
+        /// The tag of the word.
+        tag: u8,
+    },
+}
+
+impl Event {
+    /// Encodes the record and passes each word to `f`.
+    pub fn encode(self, mut f: impl FnMut(u32)) {
+        match self {
+            Self::Allocate { size } => short(0xA1, size, &mut f),
+            Self::Deallocate { size } => short(0xD1, size, &mut f),
+            Self::Grow { old_size, new_size } => long(0xB1, old_size, new_size, &mut f),
+            Self::Shrink { old_size, new_size } => long(0xC1, old_size, new_size, &mut f),
+        }
+    }
+}
+
+fn short(tag: u32, size: u32, f: &mut impl FnMut(u32)) {
+    f((tag << 24 | size >> 8) ^ HEAPTRACE_KEY);
+    f(((tag + 1) << 24 | size & 0xFF) ^ HEAPTRACE_KEY);
+}
+
+fn long(tag: u32, old_size: u32, new_size: u32, f: &mut impl FnMut(u32)) {
+    f((tag << 24 | old_size >> 8) ^ HEAPTRACE_KEY);
+    f(((tag + 1) << 24 | (old_size & 0xFF) << 16 | new_size >> 16) ^ HEAPTRACE_KEY);
+    f(((tag + 2) << 24 | new_size & 0xFFFF) ^ HEAPTRACE_KEY);
+}
+
+/// Decodes the heap trace byte stream into a sequence of events.
+///
+/// # Errors
+///
+/// If the stream is corrupted or truncated.
+#[cfg(feature = "std")]
+pub fn decode(bytes: &[u8]) -> Result<Vec<Event>, DecodeError> {
+    let mut words = Words { bytes, offset: 0 };
+    let mut events = Vec::new();
+    while let Some((tag, high)) = words.next()? {
+        let event = match tag {
+            0xA1 => Event::Allocate { size: high << 8 | words.expect(0xA2)? },
+            0xD1 => Event::Deallocate { size: high << 8 | words.expect(0xD2)? },
+            0xB1 | 0xC1 => {
+                let middle = words.expect(tag + 1)?;
+                let old_size = high << 8 | middle >> 16;
+                let new_size = (middle & 0xFFFF) << 16 | words.expect(tag + 2)?;
+                if tag == 0xB1 {
+                    Event::Grow { old_size, new_size }
+                } else {
+                    Event::Shrink { old_size, new_size }
+                }
+            }
+            _ => return Err(DecodeError::UnexpectedTag { offset: words.offset - 4, tag }),
+        };
+        events.push(event);
+    }
+    Ok(events)
+}
+
+#[cfg(feature = "std")]
+struct Words<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+#[cfg(feature = "std")]
+impl Words<'_> {
+    fn next(&mut self) -> Result<Option<(u8, u32)>, DecodeError> {
+        if self.offset == self.bytes.len() {
+            return Ok(None);
+        }
+        let chunk = self.bytes.get(self.offset..self.offset + 4).ok_or(DecodeError::Truncated)?;
+        let word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) ^ HEAPTRACE_KEY;
+        self.offset += 4;
+        Ok(Some(((word >> 24) as u8, word & 0xFF_FFFF)))
+    }
+
+    fn expect(&mut self, tag: u8) -> Result<u32, DecodeError> {
+        let offset = self.offset;
+        match self.next()? {
+            Some((word_tag, payload)) if word_tag == tag => Ok(payload),
+            Some((tag, _)) => Err(DecodeError::UnexpectedTag { offset, tag }),
+            None => Err(DecodeError::Truncated),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "Heap trace is truncated."),
+            Self::UnexpectedTag { offset, tag } => {
+                write!(f, "Unexpected heap trace tag {:#04x} at offset {}.", tag, offset)
+            }
+        }
+    }
+}
+
+#[inline(always)]
+pub(super) fn allocate(trace_port: u8, layout: Layout) {
+    write(trace_port, Event::Allocate { size: layout.size() as u32 });
+}
+
+#[inline(always)]
+pub(super) fn deallocate(trace_port: u8, layout: Layout) {
+    write(trace_port, Event::Deallocate { size: layout.size() as u32 });
+}
+
+#[inline(always)]
+pub(super) fn grow(trace_port: u8, old_layout: Layout, new_layout: Layout) {
+    write(trace_port, Event::Grow {
+        old_size: old_layout.size() as u32,
+        new_size: new_layout.size() as u32,
+    });
+}
+
+#[inline(always)]
+pub(super) fn shrink(trace_port: u8, old_layout: Layout, new_layout: Layout) {
+    write(trace_port, Event::Shrink {
+        old_size: old_layout.size() as u32,
+        new_size: new_layout.size() as u32,
+    });
+}
+
+#[inline(always)]
+fn write(trace_port: u8, event: Event) {
+    #[inline(never)]
+    fn trace(trace_port: u8, event: Event) {
+        event.encode(|word| {
+            Port::new(trace_port).write::<u32>(word);
+        });
+    }
+    if Port::new(trace_port).is_enabled() {
+        trace(trace_port, event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let events = [
+            Event::Allocate { size: 0x0012_3456 },
+            Event::Grow { old_size: 0x0012_3456, new_size: 0x0100_0001 },
+            Event::Shrink { old_size: 0x0100_0001, new_size: 24 },
+            Event::Deallocate { size: 24 },
+        ];
+        let mut bytes = Vec::new();
+        for event in &events {
+            event.encode(|word| bytes.extend_from_slice(&word.to_be_bytes()));
+        }
+        assert_eq!(bytes.len(), 40);
+        assert_eq!(decode(&bytes), Ok(events.to_vec()));
+        assert_eq!(decode(&bytes[..38]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&bytes[..36]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&bytes[4..]), Err(DecodeError::UnexpectedTag { offset: 0, tag: 0xA2 }));
+    }
+}
@@ -207,10 +207,27 @@
 //! }
 //! ```
 
+mod plugin;
+mod registry;
+
+pub use self::{
+    plugin::{plugin_registry, PluginRegistry},
+    registry::{Registry, RegistryFull},
+};
+
+#[doc(hidden)]
+pub use self::plugin::{assert_plugin, section};
+
+use alloc::sync::Arc;
 use core::{
+    future::Future,
     marker::PhantomData,
     ops::{Add, Deref, DerefMut, Sub},
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
 };
+use futures::task::AtomicWaker;
 use typenum::{Diff, Sum, Unsigned, U0, U1, U2, U3, U4, U5, U6, U7, U8};
 
 /// The inventory wrapper for `T`. Parameter `C` encodes the number of emitted
@@ -239,6 +256,61 @@ pub struct GuardToken<T: Item>(PhantomData<T>);
 /// guarantees that `T` is in its active state.
 pub struct Token<T: Item>(PhantomData<T>);
 
+/// The inventory wrapper for `T` with run-time counting of emitted tokens.
+///
+/// Unlike [`Inventory`], which encodes the number of emitted tokens in its
+/// type, this wrapper counts [`CountedToken`]s at run-time. This allows tokens
+/// to be freely passed to in-flight operations, while the owner can
+/// [`evict`](Counted::evict) the item asynchronously: the returned future
+/// resolves once every outstanding token is dropped, and only then calls
+/// [`Item::teardown`].
+///
+/// ```
+/// # async {
+/// use drone_core::inventory::{self, Counted};
+///
+/// pub struct Periph;
+///
+/// impl inventory::Item for Periph {
+///     fn teardown(&mut self, _token: &mut inventory::GuardToken<Periph>) {
+///         // Disable the peripheral here.
+///     }
+/// }
+///
+/// // The peripheral is already enabled.
+/// let periph = Counted::new(Periph);
+/// let token = periph.token();
+/// // Pass `token` to an in-flight operation. The operation drops it on
+/// // completion.
+/// drop(token);
+/// // Disable the peripheral once every operation is complete.
+/// let periph: Periph = periph.evict().await;
+/// # };
+/// ```
+pub struct Counted<T: Item> {
+    item: T,
+    shared: Arc<CountedShared>,
+}
+
+/// An owned token for resource `T` emitted by [`Counted`]. Having an instance
+/// guarantees that `T` is in its active state.
+pub struct CountedToken<T: Item> {
+    shared: Arc<CountedShared>,
+    token: Token<T>,
+}
+
+/// A future returned by [`Counted::evict`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Evict<T: Item> {
+    item: Option<T>,
+    shared: Arc<CountedShared>,
+}
+
+struct CountedShared {
+    count: AtomicUsize,
+    waker: AtomicWaker,
+}
+
 /// An inventory item interface.
 pub trait Item: Sized {
     /// Sets the inactive state. Called by [`Guard`] on `drop`.
@@ -409,3 +481,102 @@ impl<T: Item> Drop for Guard<'_, T> {
         self.borrow.teardown(&mut self.guard_token);
     }
 }
+
+impl<T: Item> Counted<T> {
+    /// Creates a new [`Counted`] with zero tokens emitted.
+    ///
+    /// The item should be already in its active state.
+    #[inline]
+    pub fn new(item: T) -> Self {
+        Self {
+            item,
+            shared: Arc::new(CountedShared {
+                count: AtomicUsize::new(0),
+                waker: AtomicWaker::new(),
+            }),
+        }
+    }
+
+    /// Emits a new token, incrementing the counter.
+    #[inline]
+    pub fn token(&self) -> CountedToken<T> {
+        self.shared.count.fetch_add(1, Ordering::Relaxed);
+        CountedToken { shared: Arc::clone(&self.shared), token: Token(PhantomData) }
+    }
+
+    /// Returns the number of outstanding tokens.
+    ///
+    /// The returned value may be immediately stale.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.shared.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns a future, which resolves when all outstanding tokens are
+    /// dropped. Then it calls [`Item::teardown`] and returns the item in its
+    /// inactive state.
+    #[inline]
+    pub fn evict(self) -> Evict<T> {
+        Evict { item: Some(self.item), shared: self.shared }
+    }
+}
+
+impl<T: Item> Deref for Counted<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.item
+    }
+}
+
+impl<T: Item> DerefMut for Counted<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.item
+    }
+}
+
+impl<T: Item> CountedToken<T> {
+    /// Returns a reference to [`Token`]`<T>`. While the reference exists, the
+    /// item is always in its active state.
+    #[inline]
+    pub fn inventory_token(&self) -> &Token<T> {
+        &self.token
+    }
+}
+
+impl<T: Item> Clone for CountedToken<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.shared.count.fetch_add(1, Ordering::Relaxed);
+        Self { shared: Arc::clone(&self.shared), token: Token(PhantomData) }
+    }
+}
+
+impl<T: Item> Drop for CountedToken<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.shared.count.fetch_sub(1, Ordering::Release) == 1 {
+            self.shared.waker.wake();
+        }
+    }
+}
+
+impl<T: Item> Unpin for Evict<T> {}
+
+impl<T: Item> Future for Evict<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let this = self.get_mut();
+        this.shared.waker.register(cx.waker());
+        if this.shared.count.load(Ordering::Acquire) == 0 {
+            let mut item = this.item.take().expect("`Evict` polled after completion");
+            item.teardown(&mut GuardToken(PhantomData));
+            Poll::Ready(item)
+        } else {
+            Poll::Pending
+        }
+    }
+}
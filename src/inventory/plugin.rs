@@ -0,0 +1,78 @@
+use core::{cell::UnsafeCell, mem::size_of, slice};
+
+/// A compile-time registry of items placed in a dedicated linker section.
+///
+/// Unlike [`Registry`](super::Registry), which is populated at run-time,
+/// plugins are collected by the linker. Optional components (e.g. shell
+/// commands, power hooks, or test cases) declare their descriptors with
+/// [`#[register]`](crate::register) in any crate, and the application doesn't
+/// need to maintain a central list.
+///
+/// Registries are declared with
+/// [`inventory::plugin_registry!`](crate::inventory::plugin_registry). A
+/// registry named `PowerHooks` places its items in the
+/// `.drone_plugins.PowerHooks` section. The linker script must keep the section
+/// and surround it with `DRONE_PLUGINS_POWER_HOOKS_START` and
+/// `DRONE_PLUGINS_POWER_HOOKS_END` symbols. Therefore registry names must be
+/// unique within the program.
+///
+/// ```
+/// use drone_core::inventory::{self, PluginRegistry};
+///
+/// pub struct PowerHook {
+///     pub name: &'static str,
+///     pub suspend: fn(),
+/// }
+///
+/// inventory::plugin_registry! {
+///     /// Hooks to run before the system enters a sleep mode.
+///     pub struct PowerHooks: PowerHook;
+/// }
+///
+/// fn uart_suspend() {}
+///
+/// #[drone_core::register(PowerHooks)]
+/// static UART: PowerHook = PowerHook { name: "uart", suspend: uart_suspend };
+///
+/// pub fn suspend_all() {
+///     for hook in PowerHooks::plugins() {
+///         (hook.suspend)();
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub trait PluginRegistry: 'static {
+    /// The type of registered items.
+    type Plugin: Sync + 'static;
+
+    /// Returns all items registered with [`#[register]`](crate::register), in
+    /// the linker order.
+    fn plugins() -> &'static [Self::Plugin];
+}
+
+/// Declares a compile-time registry of items.
+///
+/// See [`PluginRegistry`] for details.
+#[doc(inline)]
+pub use drone_core_macros::plugin_registry;
+
+/// Checks at compile-time that `T` is the plugin type of the registry `R`.
+#[doc(hidden)]
+#[inline]
+pub fn assert_plugin<R: PluginRegistry<Plugin = T>, T>() {}
+
+/// Returns a slice of `T` placed by the linker between `start` and `end`.
+///
+/// # Safety
+///
+/// `start` and `end` must surround a linker section containing only `T`
+/// values.
+#[doc(hidden)]
+#[inline]
+pub unsafe fn section<T>(
+    start: &'static UnsafeCell<usize>,
+    end: &'static UnsafeCell<usize>,
+) -> &'static [T] {
+    let count = (end.get() as usize - start.get() as usize) / size_of::<T>();
+    unsafe { slice::from_raw_parts(start.get().cast::<T>(), count) }
+}
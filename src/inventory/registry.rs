@@ -0,0 +1,192 @@
+use crate::sync::Backoff;
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NOT_READY: AtomicBool = AtomicBool::new(false);
+
+/// A run-time registry of named items with static storage.
+///
+/// Drivers register their entries, usually trait objects, at initialization,
+/// and cross-cutting services (e.g. power management) enumerate them later
+/// without hard-coding the participants. The registry has a fixed capacity of
+/// `N` entries and never removes them.
+///
+/// ```
+/// use drone_core::inventory::Registry;
+///
+/// pub trait PowerManaged: Sync {
+///     fn suspend(&self);
+/// }
+///
+/// struct Uart;
+///
+/// impl PowerManaged for Uart {
+///     fn suspend(&self) {}
+/// }
+///
+/// static UART: Uart = Uart;
+/// static POWER_MANAGED: Registry<dyn PowerManaged, 8> = Registry::new();
+///
+/// POWER_MANAGED.register("uart", &UART).unwrap();
+/// for (_id, item) in POWER_MANAGED.iter() {
+///     item.suspend();
+/// }
+/// assert!(POWER_MANAGED.get("uart").is_some());
+/// ```
+pub struct Registry<T: ?Sized + 'static, const N: usize> {
+    reserved: AtomicUsize,
+    ready: [AtomicBool; N],
+    entries: UnsafeCell<[Option<Entry<T>>; N]>,
+}
+
+/// The error type returned from [`Registry::register`] when the registry is
+/// full.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RegistryFull;
+
+struct Entry<T: ?Sized + 'static> {
+    id: &'static str,
+    item: &'static T,
+}
+
+unsafe impl<T: ?Sized + Sync, const N: usize> Sync for Registry<T, N> {}
+
+impl<T: ?Sized, const N: usize> Registry<T, N> {
+    /// Creates an empty registry.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            reserved: AtomicUsize::new(0),
+            ready: [NOT_READY; N],
+            entries: UnsafeCell::new([None; N]),
+        }
+    }
+
+    /// Registers `item` under the `id`.
+    ///
+    /// The registration is lock-free, so it can be called from any context,
+    /// including an interrupt preempting another registration.
+    ///
+    /// Returns an error if the registry is full.
+    pub fn register(&self, id: &'static str, item: &'static T) -> Result<(), RegistryFull> {
+        let mut backoff = Backoff::new();
+        let mut index = self.reserved.load(Ordering::Relaxed);
+        loop {
+            if index >= N {
+                return Err(RegistryFull);
+            }
+            match self.reserved.compare_exchange_weak(
+                index,
+                index + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(next_index) => index = next_index,
+            }
+            backoff.spin();
+        }
+        // The slot at `index` is exclusively reserved and not yet ready.
+        unsafe { self.entry_ptr(index).write(Some(Entry { id, item })) };
+        self.ready[index].store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns the number of registered items.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if no items are registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over registered items with their ids, in order of
+    /// registration. The items, which are being registered concurrently, are
+    /// skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static T)> + '_ {
+        let reserved = self.reserved.load(Ordering::Relaxed);
+        (0..reserved).filter_map(move |index| {
+            if self.ready[index].load(Ordering::Acquire) {
+                // Ready entries are never modified.
+                unsafe { *self.entry_ptr(index) }.map(|entry| (entry.id, entry.item))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the first item registered under the `id`.
+    pub fn get(&self, id: &str) -> Option<&'static T> {
+        self.iter().find(|&(entry_id, _)| entry_id == id).map(|(_, item)| item)
+    }
+
+    // Doesn't create a reference to the whole array, which would alias the
+    // slots being written concurrently.
+    fn entry_ptr(&self, index: usize) -> *mut Option<Entry<T>> {
+        unsafe { self.entries.get().cast::<Option<Entry<T>>>().add(index) }
+    }
+}
+
+impl<T: ?Sized> Clone for Entry<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for Entry<T> {}
+
+impl<T: ?Sized, const N: usize> fmt::Debug for Registry<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(|(id, _)| id)).finish()
+    }
+}
+
+impl fmt::Display for RegistryFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Registry is full.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register() {
+        static A: usize = 1;
+        static B: usize = 2;
+        let registry = Registry::<usize, 2>::new();
+        assert!(registry.is_empty());
+        assert_eq!(registry.register("a", &A), Ok(()));
+        assert_eq!(registry.register("b", &B), Ok(()));
+        assert_eq!(registry.register("c", &B), Err(RegistryFull));
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.get("b"), Some(&2));
+        assert_eq!(registry.get("c"), None);
+        assert_eq!(registry.iter().map(|(id, _)| id).collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn unready() {
+        static A: usize = 1;
+        static B: usize = 2;
+        let registry = Registry::<usize, 3>::new();
+        // Simulate a registration preempted between reserving and publishing.
+        registry.reserved.store(1, Ordering::Relaxed);
+        assert_eq!(registry.register("b", &B), Ok(()));
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.get("b"), Some(&2));
+        unsafe { registry.entry_ptr(0).write(Some(Entry { id: "a", item: &A })) };
+        registry.ready[0].store(true, Ordering::Release);
+        assert_eq!(registry.iter().map(|(id, _)| id).collect::<Vec<_>>(), ["a", "b"]);
+    }
+}
@@ -0,0 +1,128 @@
+use super::{DecodeError, Decoder, Encoder};
+use core::mem;
+
+/// The maximum number of data bytes in a COBS block.
+const BLOCK_SIZE: usize = 254;
+
+/// Consistent Overhead Byte Stuffing codec.
+///
+/// Each frame is encoded without zero bytes and terminated by a zero byte. The
+/// encoding adds one byte of overhead per 254 bytes of the frame, plus the
+/// delimiter. Consecutive delimiters are skipped by the decoder.
+pub struct Cobs {
+    max_len: usize,
+    buffer: Vec<u8>,
+    discard: bool,
+}
+
+impl Cobs {
+    /// Creates a new COBS codec, which decodes frames up to `max_len` bytes.
+    #[inline]
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, buffer: Vec::new(), discard: false }
+    }
+
+    fn encoded_max_len(&self) -> usize {
+        self.max_len + self.max_len / BLOCK_SIZE + 1
+    }
+}
+
+impl Encoder for Cobs {
+    fn encode(&mut self, frame: &[u8], out: &mut Vec<u8>) {
+        let mut code_idx = out.len();
+        out.push(0);
+        for &byte in frame {
+            if byte != 0 {
+                out.push(byte);
+            }
+            let code = out.len() - code_idx;
+            if byte == 0 || code == BLOCK_SIZE + 1 {
+                out[code_idx] = code as u8;
+                code_idx = out.len();
+                out.push(0);
+            }
+        }
+        out[code_idx] = (out.len() - code_idx) as u8;
+        out.push(0);
+    }
+}
+
+impl Decoder for Cobs {
+    fn decode(&mut self, byte: u8) -> Result<Option<Vec<u8>>, DecodeError> {
+        if byte == 0 {
+            if mem::take(&mut self.discard) || self.buffer.is_empty() {
+                self.buffer.clear();
+                return Ok(None);
+            }
+            let frame = unstuff(&self.buffer);
+            self.buffer.clear();
+            return frame.map(Some);
+        }
+        if self.discard {
+            return Ok(None);
+        }
+        if self.buffer.len() >= self.encoded_max_len() {
+            self.buffer.clear();
+            self.discard = true;
+            return Err(DecodeError::Oversized);
+        }
+        self.buffer.push(byte);
+        Ok(None)
+    }
+}
+
+fn unstuff(encoded: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut frame = Vec::with_capacity(encoded.len());
+    let mut idx = 0;
+    while idx < encoded.len() {
+        let code = usize::from(encoded[idx]);
+        let end = idx + code;
+        if end > encoded.len() {
+            return Err(DecodeError::Malformed);
+        }
+        frame.extend_from_slice(&encoded[idx + 1..end]);
+        idx = end;
+        if code <= BLOCK_SIZE && idx < encoded.len() {
+            frame.push(0);
+        }
+    }
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(cobs: &mut Cobs, bytes: &[u8]) -> Vec<Result<Vec<u8>, DecodeError>> {
+        bytes.iter().filter_map(|&byte| cobs.decode(byte).transpose()).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let long = (1..=255).collect::<Vec<u8>>();
+        let frames: [&[u8]; 5] = [&[0], &[0x11, 0x22, 0x00, 0x33], &[0, 0], &long, &long[..254]];
+        let mut cobs = Cobs::new(300);
+        for frame in &frames {
+            let mut encoded = Vec::new();
+            cobs.encode(frame, &mut encoded);
+            assert_eq!(encoded.iter().position(|&byte| byte == 0), Some(encoded.len() - 1));
+            assert_eq!(decode_all(&mut cobs, &encoded), [Ok(frame.to_vec())]);
+        }
+        let mut encoded = Vec::new();
+        cobs.encode(&[0x11, 0x22, 0x00, 0x33], &mut encoded);
+        assert_eq!(encoded, [0x03, 0x11, 0x22, 0x02, 0x33, 0x00]);
+    }
+
+    #[test]
+    fn resync() {
+        let mut cobs = Cobs::new(4);
+        assert_eq!(decode_all(&mut cobs, &[0x05, 0x11, 0x00, 0x02, 0x22, 0x00]), [
+            Err(DecodeError::Malformed),
+            Ok(vec![0x22])
+        ]);
+        assert_eq!(decode_all(&mut cobs, &[0x09, 1, 2, 3, 4, 5, 6, 7, 8, 0x00, 0x01, 0x00]), [
+            Err(DecodeError::Oversized),
+            Ok(vec![])
+        ]);
+    }
+}
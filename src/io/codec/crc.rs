@@ -0,0 +1,88 @@
+use super::{DecodeError, Decoder, Encoder};
+use crate::crc::{self, CRC16_IBM_3740};
+
+const CHECKSUM_LEN: usize = 2;
+
+/// A codec wrapper, which appends a CRC-16 checksum to each frame.
+///
+/// The checksum is CRC-16/IBM-3740 (also known as CRC-16/CCITT-FALSE) of the
+/// frame by default, stored big-endian after the frame. Decoded frames with
+/// mismatched checksums are reported as [`DecodeError::Checksum`].
+pub struct Crc16<C> {
+    inner: C,
+    crc: crc::Crc16,
+    buffer: Vec<u8>,
+}
+
+impl<C> Crc16<C> {
+    /// Wraps the `inner` codec.
+    #[inline]
+    pub fn new(inner: C) -> Self {
+        Self::with_crc(inner, crc::Crc16::new(CRC16_IBM_3740))
+    }
+
+    /// Wraps the `inner` codec, computing checksums with the given `crc`.
+    #[inline]
+    pub fn with_crc(inner: C, crc: crc::Crc16) -> Self {
+        Self { inner, crc, buffer: Vec::new() }
+    }
+
+    /// Returns the inner codec.
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: Encoder> Encoder for Crc16<C> {
+    fn encode(&mut self, frame: &[u8], out: &mut Vec<u8>) {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(frame);
+        self.buffer.extend_from_slice(&self.crc.checksum(frame).to_be_bytes());
+        self.inner.encode(&self.buffer, out);
+    }
+}
+
+impl<C: Decoder> Decoder for Crc16<C> {
+    fn decode(&mut self, byte: u8) -> Result<Option<Vec<u8>>, DecodeError> {
+        let mut frame = match self.inner.decode(byte)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        if frame.len() < CHECKSUM_LEN {
+            return Err(DecodeError::Malformed);
+        }
+        let len = frame.len() - CHECKSUM_LEN;
+        let checksum = u16::from_be_bytes([frame[len], frame[len + 1]]);
+        if self.crc.checksum(&frame[..len]) != checksum {
+            return Err(DecodeError::Checksum);
+        }
+        frame.truncate(len);
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::codec::Slip;
+
+    #[test]
+    fn checksum() {
+        let mut codec = Crc16::new(Slip::new(8));
+        let mut encoded = Vec::new();
+        codec.encode(b"123456789", &mut encoded);
+        assert_eq!(encoded[10..12], [0x29, 0xB1]);
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut codec = Crc16::new(Slip::new(8));
+        let mut encoded = Vec::new();
+        codec.encode(b"abc", &mut encoded);
+        encoded[2] ^= 0x01;
+        codec.encode(b"d", &mut encoded);
+        let frames = encoded.iter().filter_map(|&byte| codec.decode(byte).transpose());
+        assert_eq!(frames.collect::<Vec<_>>(), [Err(DecodeError::Checksum), Ok(b"d".to_vec())]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Framing codecs for byte streams.
+//!
+//! A serial link transfers a stream of bytes, while protocols exchange frames.
+//! This module provides encoder/decoder state machines for the common framing
+//! schemes:
+//!
+//! * [`Cobs`] - Consistent Overhead Byte Stuffing, frames are delimited by zero
+//!   bytes.
+//! * [`Slip`] - Serial Line Internet Protocol (RFC 1055), frames are delimited
+//!   by `0xC0` bytes.
+//! * [`LengthPrefixed`] - frames are prefixed by their length.
+//!
+//! Any of them can be wrapped into [`Crc16`] to detect corrupted frames.
+//!
+//! The decoders resynchronize after malformed or oversized frames: a decode
+//! error is reported once, and the decoder skips to the start of the next
+//! frame.
+//!
+//! The state machines don't perform I/O by themselves. They can be fed by hand,
+//! or adapted to a byte [`io::Read`] with [`frames`], and to a byte
+//! [`io::Write`] with [`frame_sink`].
+//!
+//! ```
+//! use drone_core::io::codec::{Cobs, Crc16, Decoder, Encoder};
+//!
+//! let mut codec = Crc16::new(Cobs::new(64));
+//! let mut encoded = Vec::new();
+//! codec.encode(&[0x11, 0x00, 0x22], &mut encoded);
+//! assert!(!encoded[..encoded.len() - 1].contains(&0));
+//!
+//! let mut frames = encoded.iter().filter_map(|&byte| codec.decode(byte).unwrap());
+//! assert_eq!(frames.next(), Some(vec![0x11, 0x00, 0x22]));
+//! ```
+
+mod cobs;
+mod crc;
+mod prefix;
+mod slip;
+
+pub use self::{cobs::Cobs, crc::Crc16, prefix::LengthPrefixed, slip::Slip};
+
+use crate::io;
+use core::fmt;
+use futures::{
+    sink::{self, Sink},
+    stream::{self, Stream},
+};
+
+/// The size of the buffer for reading from [`io::Read`] in [`frames`].
+const CHUNK_SIZE: usize = 16;
+
+/// A frame encoder.
+pub trait Encoder {
+    /// Appends the encoded `frame` to `out`.
+    fn encode(&mut self, frame: &[u8], out: &mut Vec<u8>);
+}
+
+/// A frame decoder.
+pub trait Decoder {
+    /// Feeds the next `byte` of the stream.
+    ///
+    /// Returns `Ok(Some(frame))` when the `byte` completes a frame.
+    ///
+    /// # Errors
+    ///
+    /// If the `byte` makes the current frame malformed or oversized. The
+    /// decoder skips the rest of the frame.
+    fn decode(&mut self, byte: u8) -> Result<Option<Vec<u8>>, DecodeError>;
+}
+
+/// A frame decoding error.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    /// The frame exceeds the maximum length.
+    Oversized,
+    /// The frame encoding is invalid.
+    Malformed,
+    /// The frame checksum doesn't match.
+    Checksum,
+}
+
+/// An error of [`frames`] and [`frame_sink`] adapters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CodecError<E> {
+    /// The underlying I/O error.
+    Io(E),
+    /// A frame can't be decoded.
+    Decode(DecodeError),
+    /// The writer accepted zero bytes.
+    WriteZero,
+}
+
+struct FrameReader<R, D> {
+    reader: R,
+    decoder: D,
+    chunk: [u8; CHUNK_SIZE],
+    position: usize,
+    length: usize,
+}
+
+/// Adapts a byte `reader` into a stream of frames decoded with `decoder`.
+///
+/// A decode error is yielded without terminating the stream. An I/O error is
+/// yielded as the last item. The stream ends when the `reader` returns zero
+/// bytes.
+pub fn frames<R, D, E>(reader: R, decoder: D) -> impl Stream<Item = Result<Vec<u8>, CodecError<E>>>
+where
+    R: for<'sess> io::Read<'sess, u8, &'sess mut [u8], Error = E>,
+    D: Decoder,
+{
+    let state = FrameReader { reader, decoder, chunk: [0; CHUNK_SIZE], position: 0, length: 0 };
+    stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        loop {
+            while state.position < state.length {
+                let byte = state.chunk[state.position];
+                state.position += 1;
+                match state.decoder.decode(byte) {
+                    Ok(None) => {}
+                    Ok(Some(frame)) => return Some((Ok(frame), Some(state))),
+                    Err(err) => return Some((Err(CodecError::Decode(err)), Some(state))),
+                }
+            }
+            match state.reader.read(&mut state.chunk[..]).await {
+                Ok(0) => return None,
+                Ok(length) => {
+                    state.position = 0;
+                    state.length = length;
+                }
+                Err(err) => return Some((Err(CodecError::Io(err)), None)),
+            }
+        }
+    })
+}
+
+/// Adapts a byte `writer` into a sink of frames encoded with `encoder`.
+pub fn frame_sink<W, C, T, E>(writer: W, encoder: C) -> impl Sink<T, Error = CodecError<E>>
+where
+    W: for<'sess> io::Write<'sess, u8, &'sess [u8], Error = E>,
+    C: Encoder,
+    T: AsRef<[u8]>,
+{
+    let state = (writer, encoder, Vec::new());
+    sink::unfold(state, |(mut writer, mut encoder, mut buffer), frame: T| async move {
+        buffer.clear();
+        encoder.encode(frame.as_ref(), &mut buffer);
+        let mut bytes = &buffer[..];
+        while !bytes.is_empty() {
+            match writer.write(bytes).await {
+                Ok(0) => return Err(CodecError::WriteZero),
+                Ok(count) => bytes = &bytes[count..],
+                Err(err) => return Err(CodecError::Io(err)),
+            }
+        }
+        Ok((writer, encoder, buffer))
+    })
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Oversized => write!(f, "Frame is too long."),
+            Self::Malformed => write!(f, "Frame is malformed."),
+            Self::Checksum => write!(f, "Frame checksum mismatch."),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for CodecError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::Decode(err) => err.fmt(f),
+            Self::WriteZero => write!(f, "Writer accepted zero bytes."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use futures::{pin_mut, task::noop_waker_ref};
+
+    struct Chunks(Vec<&'static [u8]>);
+
+    struct Bytes<'a>(&'a mut Vec<u8>);
+
+    impl<'sess> io::Read<'sess, u8, &'sess mut [u8]> for Chunks {
+        type Error = ();
+
+        fn read(
+            &'sess mut self,
+            buffer: &'sess mut [u8],
+        ) -> Pin<Box<dyn Future<Output = Result<usize, ()>> + Send + 'sess>> {
+            Box::pin(async move {
+                if self.0.is_empty() {
+                    return Ok(0);
+                }
+                let chunk = self.0.remove(0);
+                buffer[..chunk.len()].copy_from_slice(chunk);
+                Ok(chunk.len())
+            })
+        }
+    }
+
+    impl<'sess> io::Write<'sess, u8, &'sess [u8]> for Bytes<'_> {
+        type Error = ();
+
+        fn write(
+            &'sess mut self,
+            words: &'sess [u8],
+        ) -> Pin<Box<dyn Future<Output = Result<usize, ()>> + Send + 'sess>> {
+            Box::pin(async move {
+                let count = words.len().min(3);
+                self.0.extend_from_slice(&words[..count]);
+                Ok(count)
+            })
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let reader = Chunks(vec![b"\xC0ab", b"\xC0\xC0c\xDB\x01", b"\xC0d"]);
+        let frames = frames(reader, Slip::new(8));
+        pin_mut!(frames);
+        assert_eq!(frames.as_mut().poll_next(&mut cx), Poll::Ready(Some(Ok(b"ab".to_vec()))));
+        assert_eq!(
+            frames.as_mut().poll_next(&mut cx),
+            Poll::Ready(Some(Err(CodecError::Decode(DecodeError::Malformed))))
+        );
+        assert_eq!(frames.as_mut().poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn sink() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut output = Vec::new();
+        {
+            let sink = frame_sink(Bytes(&mut output), LengthPrefixed::new(8));
+            pin_mut!(sink);
+            assert_eq!(sink.as_mut().poll_ready(&mut cx), Poll::Ready(Ok(())));
+            sink.as_mut().start_send(b"hello").unwrap();
+            assert_eq!(sink.as_mut().poll_flush(&mut cx), Poll::Ready(Ok(())));
+        }
+        assert_eq!(output, b"\x00\x05hello");
+    }
+}
@@ -0,0 +1,91 @@
+use super::{DecodeError, Decoder, Encoder};
+use core::{convert::TryFrom, mem};
+
+/// Length-prefixed frame codec.
+///
+/// Each frame is preceded by its length as a big-endian `u16`. As the format
+/// has no delimiters, the decoder resynchronizes after an oversized length by
+/// shifting the header by one byte.
+pub struct LengthPrefixed {
+    max_len: usize,
+    header: [u8; 2],
+    header_len: usize,
+    frame_len: usize,
+    buffer: Vec<u8>,
+}
+
+impl LengthPrefixed {
+    /// Creates a new length-prefixed codec, which decodes frames up to
+    /// `max_len` bytes.
+    #[inline]
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, header: [0; 2], header_len: 0, frame_len: 0, buffer: Vec::new() }
+    }
+}
+
+impl Encoder for LengthPrefixed {
+    /// # Panics
+    ///
+    /// If the `frame` is longer than `u16::MAX` bytes.
+    fn encode(&mut self, frame: &[u8], out: &mut Vec<u8>) {
+        let len = u16::try_from(frame.len()).expect("frame is too long");
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(frame);
+    }
+}
+
+impl Decoder for LengthPrefixed {
+    fn decode(&mut self, byte: u8) -> Result<Option<Vec<u8>>, DecodeError> {
+        if self.header_len < self.header.len() {
+            self.header[self.header_len] = byte;
+            self.header_len += 1;
+            if self.header_len < self.header.len() {
+                return Ok(None);
+            }
+            self.frame_len = usize::from(u16::from_be_bytes(self.header));
+            if self.frame_len > self.max_len {
+                self.header[0] = self.header[1];
+                self.header_len = 1;
+                return Err(DecodeError::Oversized);
+            }
+        } else {
+            self.buffer.push(byte);
+        }
+        if self.buffer.len() < self.frame_len {
+            return Ok(None);
+        }
+        self.header_len = 0;
+        Ok(Some(mem::take(&mut self.buffer)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(
+        prefixed: &mut LengthPrefixed,
+        bytes: &[u8],
+    ) -> Vec<Result<Vec<u8>, DecodeError>> {
+        bytes.iter().filter_map(|&byte| prefixed.decode(byte).transpose()).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut prefixed = LengthPrefixed::new(4);
+        let mut encoded = Vec::new();
+        prefixed.encode(&[0x11, 0x22, 0x33], &mut encoded);
+        prefixed.encode(&[], &mut encoded);
+        assert_eq!(encoded, [0x00, 0x03, 0x11, 0x22, 0x33, 0x00, 0x00]);
+        assert_eq!(decode_all(&mut prefixed, &encoded), [Ok(vec![0x11, 0x22, 0x33]), Ok(vec![])]);
+    }
+
+    #[test]
+    fn resync() {
+        let mut prefixed = LengthPrefixed::new(4);
+        assert_eq!(decode_all(&mut prefixed, &[0xFF, 0x00, 0x01, 0x11]), [
+            Err(DecodeError::Oversized),
+            Ok(vec![0x11])
+        ]);
+    }
+}
@@ -0,0 +1,113 @@
+use super::{DecodeError, Decoder, Encoder};
+use core::mem;
+
+const END: u8 = 0xC0;
+const ESC: u8 = 0xDB;
+const ESC_END: u8 = 0xDC;
+const ESC_ESC: u8 = 0xDD;
+
+/// Serial Line Internet Protocol (RFC 1055) codec.
+///
+/// Each frame is surrounded by `END` bytes, and the `END` and `ESC` bytes
+/// inside the frame are escaped. Empty frames are skipped by the decoder.
+pub struct Slip {
+    max_len: usize,
+    buffer: Vec<u8>,
+    escape: bool,
+    discard: bool,
+}
+
+impl Slip {
+    /// Creates a new SLIP codec, which decodes frames up to `max_len` bytes.
+    #[inline]
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, buffer: Vec::new(), escape: false, discard: false }
+    }
+
+    fn push(&mut self, byte: u8) -> Result<Option<Vec<u8>>, DecodeError> {
+        if self.buffer.len() >= self.max_len {
+            self.buffer.clear();
+            self.discard = true;
+            return Err(DecodeError::Oversized);
+        }
+        self.buffer.push(byte);
+        Ok(None)
+    }
+}
+
+impl Encoder for Slip {
+    fn encode(&mut self, frame: &[u8], out: &mut Vec<u8>) {
+        out.push(END);
+        for &byte in frame {
+            match byte {
+                END => out.extend_from_slice(&[ESC, ESC_END]),
+                ESC => out.extend_from_slice(&[ESC, ESC_ESC]),
+                _ => out.push(byte),
+            }
+        }
+        out.push(END);
+    }
+}
+
+impl Decoder for Slip {
+    fn decode(&mut self, byte: u8) -> Result<Option<Vec<u8>>, DecodeError> {
+        if byte == END {
+            self.escape = false;
+            if mem::take(&mut self.discard) || self.buffer.is_empty() {
+                self.buffer.clear();
+                return Ok(None);
+            }
+            return Ok(Some(mem::take(&mut self.buffer)));
+        }
+        if self.discard {
+            return Ok(None);
+        }
+        if mem::take(&mut self.escape) {
+            match byte {
+                ESC_END => self.push(END),
+                ESC_ESC => self.push(ESC),
+                _ => {
+                    self.buffer.clear();
+                    self.discard = true;
+                    Err(DecodeError::Malformed)
+                }
+            }
+        } else if byte == ESC {
+            self.escape = true;
+            Ok(None)
+        } else {
+            self.push(byte)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(slip: &mut Slip, bytes: &[u8]) -> Vec<Result<Vec<u8>, DecodeError>> {
+        bytes.iter().filter_map(|&byte| slip.decode(byte).transpose()).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut slip = Slip::new(8);
+        let mut encoded = Vec::new();
+        slip.encode(&[0x01, END, ESC, 0x02], &mut encoded);
+        assert_eq!(encoded, [END, 0x01, ESC, ESC_END, ESC, ESC_ESC, 0x02, END]);
+        assert_eq!(decode_all(&mut slip, &encoded), [Ok(vec![0x01, END, ESC, 0x02])]);
+    }
+
+    #[test]
+    fn resync() {
+        let mut slip = Slip::new(2);
+        assert_eq!(decode_all(&mut slip, &[0x01, ESC, 0x02, 0x03, END, 0x04, END]), [
+            Err(DecodeError::Malformed),
+            Ok(vec![0x04])
+        ]);
+        assert_eq!(decode_all(&mut slip, &[0x01, 0x02, 0x03, 0x04, END, END, 0x05, END]), [
+            Err(DecodeError::Oversized),
+            Ok(vec![0x05])
+        ]);
+    }
+}
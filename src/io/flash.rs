@@ -0,0 +1,274 @@
+use core::{fmt, future::Future, pin::Pin};
+
+/// The `Flash` trait provides access to a non-volatile memory with erase
+/// semantics, such as NOR flash or EEPROM.
+///
+/// The memory is divided into [`Flash::SECTOR_COUNT`] sectors of
+/// [`Flash::SECTOR_SIZE`] bytes. An erased sector reads as all `0xFF` bytes.
+/// Programming can only clear bits, so an area must be erased before it can be
+/// written again.
+pub trait Flash {
+    /// The error type returned by the flash operations.
+    type Error;
+
+    /// The size of an erase unit in bytes.
+    const SECTOR_SIZE: u32;
+
+    /// The number of sectors.
+    const SECTOR_COUNT: u32;
+
+    /// The program granularity in bytes. Write addresses and lengths must be
+    /// multiples of this value.
+    const WRITE_SIZE: u32;
+
+    /// Reads `buffer.len()` bytes starting from `addr` asynchronously.
+    fn read<'a>(
+        &'a mut self,
+        addr: u32,
+        buffer: &'a mut [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>>;
+
+    /// Programs `data` starting from `addr` asynchronously.
+    fn write<'a>(
+        &'a mut self,
+        addr: u32,
+        data: &'a [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>>;
+
+    /// Erases the sector number `sector` asynchronously.
+    fn erase(
+        &mut self,
+        sector: u32,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + '_>>;
+}
+
+/// An [`io::Write`](super::Write) adapter for a region of a [`Flash`].
+///
+/// The buffer appends the written bytes sequentially, collecting them into
+/// [`Flash::WRITE_SIZE`] units, so the writer isn't concerned with the program
+/// granularity. Sectors are erased lazily when the write position enters them,
+/// and a sector which is already blank is not erased again, which saves erase
+/// cycles when resuming a partially used region.
+///
+/// Up to [`Flash::WRITE_SIZE`] - 1 trailing bytes are held in RAM until they
+/// form a complete unit. Use [`WriteBuffer::flush`] to pad and program them.
+pub struct WriteBuffer<F: Flash> {
+    flash: F,
+    addr: u32,
+    end: u32,
+    pending: Vec<u8>,
+}
+
+/// The error type for [`WriteBuffer`] operations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WriteBufferError<E> {
+    /// The underlying flash returned an error.
+    Flash(E),
+    /// The end of the region is reached.
+    OutOfSpace,
+}
+
+impl<F: Flash> WriteBuffer<F> {
+    /// Creates a new write buffer over the sectors `start..end` of the `flash`.
+    ///
+    /// # Panics
+    ///
+    /// If the sector range is empty or exceeds [`Flash::SECTOR_COUNT`].
+    pub fn new(flash: F, start: u32, end: u32) -> Self {
+        assert!(start < end && end <= F::SECTOR_COUNT, "invalid flash sector range");
+        let pending = Vec::with_capacity(F::WRITE_SIZE as usize);
+        Self { flash, addr: start * F::SECTOR_SIZE, end: end * F::SECTOR_SIZE, pending }
+    }
+
+    /// Returns the flash address of the next written byte.
+    #[inline]
+    pub fn position(&self) -> u32 {
+        self.addr + self.pending.len() as u32
+    }
+
+    /// Returns the number of bytes left until the end of the region.
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.end - self.position()
+    }
+
+    /// Pads the pending bytes with `0xFF` to a complete unit and programs it.
+    ///
+    /// The padding bytes are skipped, so that the next write starts at the next
+    /// unit.
+    pub async fn flush(&mut self) -> Result<(), WriteBufferError<F::Error>> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.pending.resize(F::WRITE_SIZE as usize, 0xFF);
+        self.program_pending().await
+    }
+
+    /// Returns the underlying flash, discarding the pending bytes.
+    #[inline]
+    pub fn into_flash(self) -> F {
+        self.flash
+    }
+
+    async fn write_bytes(&mut self, data: &[u8]) -> Result<usize, WriteBufferError<F::Error>> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining() == 0 {
+            return Err(WriteBufferError::OutOfSpace);
+        }
+        let data = &data[..data.len().min(self.remaining() as usize)];
+        let write_size = F::WRITE_SIZE as usize;
+        let mut rest = data;
+        if !self.pending.is_empty() {
+            let len = (write_size - self.pending.len()).min(rest.len());
+            self.pending.extend_from_slice(&rest[..len]);
+            rest = &rest[len..];
+            if self.pending.len() < write_size {
+                return Ok(data.len());
+            }
+            self.program_pending().await?;
+        }
+        let aligned = rest.len() - rest.len() % write_size;
+        self.program(&rest[..aligned]).await?;
+        self.pending.extend_from_slice(&rest[aligned..]);
+        Ok(data.len())
+    }
+
+    async fn program_pending(&mut self) -> Result<(), WriteBufferError<F::Error>> {
+        let pending = core::mem::take(&mut self.pending);
+        let result = self.program(&pending).await;
+        self.pending = pending;
+        self.pending.clear();
+        result
+    }
+
+    async fn program(&mut self, mut data: &[u8]) -> Result<(), WriteBufferError<F::Error>> {
+        while !data.is_empty() {
+            let offset = self.addr % F::SECTOR_SIZE;
+            if offset == 0 {
+                self.prepare_sector(self.addr / F::SECTOR_SIZE).await?;
+            }
+            let len = data.len().min((F::SECTOR_SIZE - offset) as usize);
+            self.flash.write(self.addr, &data[..len]).await.map_err(WriteBufferError::Flash)?;
+            self.addr += len as u32;
+            data = &data[len..];
+        }
+        Ok(())
+    }
+
+    async fn prepare_sector(&mut self, sector: u32) -> Result<(), WriteBufferError<F::Error>> {
+        let mut chunk = [0; 32];
+        let mut addr = sector * F::SECTOR_SIZE;
+        let end = addr + F::SECTOR_SIZE;
+        while addr < end {
+            let len = chunk.len().min((end - addr) as usize);
+            self.flash.read(addr, &mut chunk[..len]).await.map_err(WriteBufferError::Flash)?;
+            if chunk[..len].iter().any(|&byte| byte != 0xFF) {
+                return self.flash.erase(sector).await.map_err(WriteBufferError::Flash);
+            }
+            addr += len as u32;
+        }
+        Ok(())
+    }
+}
+
+impl<'sess, F> super::Write<'sess, u8, &'sess [u8]> for WriteBuffer<F>
+where
+    F: Flash + Send + 'sess,
+{
+    type Error = WriteBufferError<F::Error>;
+
+    fn write(
+        &'sess mut self,
+        words: &'sess [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>> {
+        Box::pin(self.write_bytes(words))
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for WriteBufferError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flash(err) => write!(f, "flash error: {}", err),
+            Self::OutOfSpace => f.write_str("flash region is full"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Write;
+    use core::task::{Context, Poll};
+    use futures::{future, pin_mut, task::noop_waker_ref};
+
+    struct RamFlash {
+        memory: Vec<u8>,
+        erases: u32,
+    }
+
+    impl Flash for RamFlash {
+        type Error = !;
+
+        const SECTOR_COUNT: u32 = 3;
+        const SECTOR_SIZE: u32 = 16;
+        const WRITE_SIZE: u32 = 4;
+
+        fn read<'a>(
+            &'a mut self,
+            addr: u32,
+            buffer: &'a mut [u8],
+        ) -> Pin<Box<dyn Future<Output = Result<(), !>> + Send + 'a>> {
+            let addr = addr as usize;
+            buffer.copy_from_slice(&self.memory[addr..addr + buffer.len()]);
+            Box::pin(future::ready(Ok(())))
+        }
+
+        fn write<'a>(
+            &'a mut self,
+            addr: u32,
+            data: &'a [u8],
+        ) -> Pin<Box<dyn Future<Output = Result<(), !>> + Send + 'a>> {
+            assert_eq!(addr % Self::WRITE_SIZE, 0);
+            assert_eq!(data.len() as u32 % Self::WRITE_SIZE, 0);
+            for (i, &byte) in data.iter().enumerate() {
+                assert_eq!(self.memory[addr as usize + i], 0xFF);
+                self.memory[addr as usize + i] = byte;
+            }
+            Box::pin(future::ready(Ok(())))
+        }
+
+        fn erase(&mut self, sector: u32) -> Pin<Box<dyn Future<Output = Result<(), !>> + Send>> {
+            let start = (sector * Self::SECTOR_SIZE) as usize;
+            self.memory[start..start + Self::SECTOR_SIZE as usize].fill(0xFF);
+            self.erases += 1;
+            Box::pin(future::ready(Ok(())))
+        }
+    }
+
+    fn ready<F: Future>(future: F) -> F::Output {
+        pin_mut!(future);
+        match future.poll(&mut Context::from_waker(noop_waker_ref())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is not ready"),
+        }
+    }
+
+    #[test]
+    fn write_buffer() {
+        let mut memory = vec![0xFF; 48];
+        memory[32] = 0;
+        let mut buffer = WriteBuffer::new(RamFlash { memory, erases: 0 }, 1, 3);
+        assert_eq!(ready(buffer.write(b"abc")), Ok(3));
+        assert_eq!(buffer.position(), 19);
+        assert_eq!(ready(buffer.write(b"defghijklmnopq")), Ok(14));
+        assert_eq!(ready(buffer.flush()), Ok(()));
+        assert_eq!(buffer.position(), 36);
+        assert_eq!(ready(buffer.write(&[0; 16])), Ok(12));
+        assert_eq!(ready(buffer.write(b"x")), Err(WriteBufferError::OutOfSpace));
+        let flash = buffer.into_flash();
+        assert_eq!(flash.erases, 1);
+        assert_eq!(&flash.memory[16..36], b"abcdefghijklmnopq\xFF\xFF\xFF");
+    }
+}
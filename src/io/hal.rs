@@ -0,0 +1,310 @@
+use super::{InputPin, OutputPin, Read, Write};
+use core::{
+    convert::Infallible,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use embedded_hal_02::{
+    blocking::{i2c, serial as blocking_serial, spi},
+    digital::v2 as digital,
+    serial,
+};
+use futures::future;
+
+/// An adapter, which exposes a Drone abstraction through `embedded-hal`
+/// traits.
+///
+/// Implements:
+///
+/// * [`digital::InputPin`] for [`InputPin`]
+/// * [`digital::OutputPin`] for [`OutputPin`]
+pub struct IntoHal<T>(pub T);
+
+/// An adapter, which exposes an `embedded-hal` SPI bus through [`Read`] and
+/// [`Write`] traits.
+///
+/// Reading transfers the contents of the buffer and replaces them with the
+/// received words. The wrapped bus is blocking, so the returned futures are
+/// always ready.
+pub struct FromHalSpi<T>(T);
+
+/// An adapter, which exposes an `embedded-hal` I2C bus through [`Read`] and
+/// [`Write`] traits, addressing a single device on the bus.
+///
+/// The wrapped bus is blocking, so the returned futures are always ready.
+pub struct FromHalI2c<T> {
+    bus: T,
+    address: u8,
+}
+
+/// An adapter, which exposes an `embedded-hal` serial port through [`Read`]
+/// and [`Write`] traits.
+///
+/// Reading polls the non-blocking `serial::Read` trait, and resolves when at
+/// least one word is read. When no word is available, the returned future
+/// re-schedules the task and yields. Writing uses the blocking
+/// `serial::Write` trait, so the returned futures are always ready.
+pub struct FromHalSerial<T>(T);
+
+/// An adapter, which exposes an `embedded-hal` implementation through Drone
+/// traits.
+///
+/// Errors returned by the wrapped implementation are treated as fatal, because
+/// Drone pin traits are infallible.
+pub struct FromHal<T>(T);
+
+impl<T: InputPin> digital::InputPin for IntoHal<T> {
+    type Error = Infallible;
+
+    #[inline]
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(self.0.is_high())
+    }
+
+    #[inline]
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(self.0.is_low())
+    }
+}
+
+impl<T: OutputPin> digital::OutputPin for IntoHal<T> {
+    type Error = Infallible;
+
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.set_low();
+        Ok(())
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.set_high();
+        Ok(())
+    }
+}
+
+impl<T> FromHal<T> {
+    /// Wraps an `embedded-hal` implementation.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self(inner)
+    }
+
+    /// Returns the wrapped implementation.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: digital::InputPin> InputPin for FromHal<T>
+where
+    T::Error: fmt::Debug,
+{
+    #[inline]
+    fn is_high(&self) -> bool {
+        self.0.is_high().unwrap_or_else(|err| pin_error(&err))
+    }
+
+    #[inline]
+    fn is_low(&self) -> bool {
+        self.0.is_low().unwrap_or_else(|err| pin_error(&err))
+    }
+}
+
+impl<T: digital::OutputPin> OutputPin for FromHal<T>
+where
+    T::Error: fmt::Debug,
+{
+    #[inline]
+    fn set_high(&mut self) {
+        self.0.set_high().unwrap_or_else(|err| pin_error(&err));
+    }
+
+    #[inline]
+    fn set_low(&mut self) {
+        self.0.set_low().unwrap_or_else(|err| pin_error(&err));
+    }
+}
+
+#[cold]
+fn pin_error(err: &impl fmt::Debug) -> ! {
+    panic!("pin error: {:?}", err);
+}
+
+impl<T> FromHalSpi<T> {
+    /// Wraps an `embedded-hal` SPI bus.
+    #[inline]
+    pub fn new(bus: T) -> Self {
+        Self(bus)
+    }
+
+    /// Returns the wrapped bus.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<'sess, W, B, T> Read<'sess, W, B> for FromHalSpi<T>
+where
+    W: Copy + 'static,
+    B: AsMut<[W]> + 'sess,
+    T: spi::Transfer<W>,
+    T::Error: Send,
+{
+    type Error = T::Error;
+
+    fn read(
+        &'sess mut self,
+        mut buffer: B,
+    ) -> Pin<Box<dyn Future<Output = Result<usize, T::Error>> + Send + 'sess>> {
+        Box::pin(future::ready(self.0.transfer(buffer.as_mut()).map(<[W]>::len)))
+    }
+}
+
+impl<'sess, W, B, T> Write<'sess, W, B> for FromHalSpi<T>
+where
+    W: Copy + 'static,
+    B: AsRef<[W]> + 'sess,
+    T: spi::Write<W>,
+    T::Error: Send,
+{
+    type Error = T::Error;
+
+    fn write(
+        &'sess mut self,
+        words: B,
+    ) -> Pin<Box<dyn Future<Output = Result<usize, T::Error>> + Send + 'sess>> {
+        let words = words.as_ref();
+        Box::pin(future::ready(self.0.write(words).map(|()| words.len())))
+    }
+}
+
+impl<T> FromHalI2c<T> {
+    /// Wraps an `embedded-hal` I2C bus, addressing the device with the 7-bit
+    /// `address`.
+    #[inline]
+    pub fn new(bus: T, address: u8) -> Self {
+        Self { bus, address }
+    }
+
+    /// Returns the 7-bit address of the device.
+    #[inline]
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns the wrapped bus.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.bus
+    }
+}
+
+impl<'sess, B, T> Read<'sess, u8, B> for FromHalI2c<T>
+where
+    B: AsMut<[u8]> + 'sess,
+    T: i2c::Read,
+    T::Error: Send,
+{
+    type Error = T::Error;
+
+    fn read(
+        &'sess mut self,
+        mut buffer: B,
+    ) -> Pin<Box<dyn Future<Output = Result<usize, T::Error>> + Send + 'sess>> {
+        let buffer = buffer.as_mut();
+        Box::pin(future::ready(self.bus.read(self.address, buffer).map(|()| buffer.len())))
+    }
+}
+
+impl<'sess, B, T> Write<'sess, u8, B> for FromHalI2c<T>
+where
+    B: AsRef<[u8]> + 'sess,
+    T: i2c::Write,
+    T::Error: Send,
+{
+    type Error = T::Error;
+
+    fn write(
+        &'sess mut self,
+        words: B,
+    ) -> Pin<Box<dyn Future<Output = Result<usize, T::Error>> + Send + 'sess>> {
+        let words = words.as_ref();
+        Box::pin(future::ready(self.bus.write(self.address, words).map(|()| words.len())))
+    }
+}
+
+impl<T> FromHalSerial<T> {
+    /// Wraps an `embedded-hal` serial port.
+    #[inline]
+    pub fn new(port: T) -> Self {
+        Self(port)
+    }
+
+    /// Returns the wrapped port.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<'sess, B, T> Read<'sess, u8, B> for FromHalSerial<T>
+where
+    B: AsMut<[u8]> + Send + 'sess,
+    T: serial::Read<u8> + Send,
+    T::Error: Send,
+{
+    type Error = T::Error;
+
+    fn read(
+        &'sess mut self,
+        mut buffer: B,
+    ) -> Pin<Box<dyn Future<Output = Result<usize, T::Error>> + Send + 'sess>> {
+        let port = &mut self.0;
+        Box::pin(future::poll_fn(move |cx| poll_serial_read(port, buffer.as_mut(), cx)))
+    }
+}
+
+impl<'sess, B, T> Write<'sess, u8, B> for FromHalSerial<T>
+where
+    B: AsRef<[u8]> + 'sess,
+    T: blocking_serial::Write<u8>,
+    T::Error: Send,
+{
+    type Error = T::Error;
+
+    fn write(
+        &'sess mut self,
+        words: B,
+    ) -> Pin<Box<dyn Future<Output = Result<usize, T::Error>> + Send + 'sess>> {
+        let words = words.as_ref();
+        Box::pin(future::ready(self.0.bwrite_all(words).map(|()| words.len())))
+    }
+}
+
+fn poll_serial_read<T: serial::Read<u8>>(
+    port: &mut T,
+    buffer: &mut [u8],
+    cx: &mut Context<'_>,
+) -> Poll<Result<usize, T::Error>> {
+    let mut count = 0;
+    while count < buffer.len() {
+        match port.read() {
+            Ok(word) => {
+                buffer[count] = word;
+                count += 1;
+            }
+            Err(nb::Error::WouldBlock) => break,
+            Err(nb::Error::Other(err)) => return Poll::Ready(Err(err)),
+        }
+    }
+    if count == 0 && !buffer.is_empty() {
+        cx.waker().wake_by_ref();
+        return Poll::Pending;
+    }
+    Poll::Ready(Ok(count))
+}
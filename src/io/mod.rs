@@ -3,14 +3,35 @@
 //! The module contains a number of common things you'll need when doing input
 //! and output. The most core part of this module is the [`Read`] and [`Write`]
 //! traits, which provide the most general interface for reading and writing
-//! input and output.
+//! input and output. The [`Flash`] trait abstracts non-volatile memories with
+//! erase semantics, and [`WriteBuffer`] adapts it to [`Write`]. The
+//! [`InputPin`], [`OutputPin`], and [`PinEvent`] traits abstract digital pins
+//! for portable drivers. The [`codec`] module splits byte streams into frames.
+//! [`read_into_reservation`] reads bytes directly into a
+//! [`ring`](crate::sync::spsc::ring) channel.
+//!
+//! With `embedded-hal` feature enabled, the [`IntoHal`] and [`FromHal`]
+//! adapters connect Drone pins with `embedded-hal` 0.2 digital traits, so
+//! existing HAL drivers can be used on top of Drone. The [`FromHalSpi`],
+//! [`FromHalI2c`], and [`FromHalSerial`] adapters expose `embedded-hal` SPI,
+//! I2C, and serial implementations through [`Read`] and [`Write`].
+
+pub mod codec;
 
+mod flash;
+#[cfg(feature = "embedded-hal")]
+mod hal;
+mod pin;
 mod read;
 mod seek;
 mod write;
 
+#[cfg(feature = "embedded-hal")]
+pub use self::hal::{FromHal, FromHalI2c, FromHalSerial, FromHalSpi, IntoHal};
 pub use self::{
-    read::Read,
+    flash::{Flash, WriteBuffer, WriteBufferError},
+    pin::{Edge, InputPin, OutputPin, PinEvent},
+    read::{read_into_reservation, Read, ReadIntoError, Reservation},
     seek::{Seek, SeekFrom},
     write::Write,
 };
@@ -0,0 +1,71 @@
+use core::{future::Future, pin::Pin};
+use futures::future;
+
+/// A digital input pin.
+pub trait InputPin {
+    /// Returns `true` if the pin is driven high.
+    fn is_high(&self) -> bool;
+
+    /// Returns `true` if the pin is driven low.
+    #[inline]
+    fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// A digital output pin.
+pub trait OutputPin {
+    /// Drives the pin high.
+    fn set_high(&mut self);
+
+    /// Drives the pin low.
+    fn set_low(&mut self);
+
+    /// Drives the pin high if `high` is `true`, and low otherwise.
+    #[inline]
+    fn set_level(&mut self, high: bool) {
+        if high { self.set_high() } else { self.set_low() }
+    }
+}
+
+/// A signal edge of a [`PinEvent`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    /// Low-to-high transition.
+    Rising,
+    /// High-to-low transition.
+    Falling,
+    /// Any transition.
+    Both,
+}
+
+/// An input pin, which can wait for signal edges asynchronously.
+///
+/// Platform crates usually implement [`PinEvent::wait_edge`] by adding a fiber
+/// to the external interrupt thread with
+/// [`ThrFiberFuture::add_future`](crate::fib::ThrFiberFuture::add_future).
+///
+/// ```
+/// use drone_core::io::{Edge, OutputPin, PinEvent};
+///
+/// // A portable driver, which waits for a button press and lights a LED.
+/// async fn button<B: PinEvent, L: OutputPin>(button: &mut B, led: &mut L) {
+///     button.wait_edge(Edge::Falling).await;
+///     led.set_high();
+/// }
+/// ```
+pub trait PinEvent: InputPin {
+    /// Returns a future, which resolves on the next `edge` of the pin signal.
+    fn wait_edge(&mut self, edge: Edge) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+
+    /// Returns a future, which resolves when the pin is driven high if `high`
+    /// is `true`, and low otherwise. Resolves immediately if the pin is
+    /// already at the level.
+    fn wait_level(&mut self, high: bool) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        if self.is_high() == high {
+            Box::pin(future::ready(()))
+        } else {
+            self.wait_edge(if high { Edge::Rising } else { Edge::Falling })
+        }
+    }
+}
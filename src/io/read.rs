@@ -1,4 +1,5 @@
-use core::{future::Future, pin::Pin};
+use crate::sync::spsc::ring::{SendErrorKind, Sender};
+use core::{future::Future, marker::PhantomData, mem::MaybeUninit, pin::Pin, ptr::NonNull, slice};
 
 /// The `Read` trait allows for reading bytes from a source asynchronously.
 pub trait Read<'sess, W, B: AsMut<[W]> + 'sess> {
@@ -12,3 +13,148 @@ pub trait Read<'sess, W, B: AsMut<[W]> + 'sess> {
         buffer: B,
     ) -> Pin<Box<dyn Future<Output = Result<usize, Self::Error>> + Send + 'sess>>;
 }
+
+/// A window of a ring channel lent to [`Read::read`] by
+/// [`read_into_reservation`].
+///
+/// The window can't outlive the borrow of the [`Sender`], so the bytes can't be
+/// written after they are sent.
+pub struct Reservation<'sess> {
+    ptr: NonNull<u8>,
+    len: usize,
+    _marker: PhantomData<&'sess mut [u8]>,
+}
+
+/// The error type for [`read_into_reservation`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReadIntoError<E> {
+    /// The reader returned an error.
+    Read(E),
+    /// The channel is full, or the receiving end is dropped.
+    Send(SendErrorKind),
+    /// The reader returned more bytes than the reservation holds.
+    Overrun(usize),
+}
+
+unsafe impl Send for Reservation<'_> {}
+
+impl AsMut<[u8]> for Reservation<'_> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+/// Reads bytes from `reader` directly into the free space of the ring channel
+/// `tx`, and sends them. Returns the number of bytes sent.
+///
+/// This removes the intermediate buffer of a read-then-send loop, like in a
+/// UART receiver feeding a parser. The bytes are read into the window returned
+/// by [`Sender::reserve`], so a single read can return fewer bytes than the
+/// channel can hold, when the free space wraps around the end of the ring
+/// buffer.
+///
+/// # Errors
+///
+/// If the channel is full, the reader is not called, and
+/// [`SendErrorKind::Overflow`] is returned. If the receiving end is dropped
+/// while reading, the read bytes are discarded. If the reader returns a count
+/// larger than the reservation, nothing is sent, and
+/// [`ReadIntoError::Overrun`] is returned.
+pub async fn read_into_reservation<'sess, R, E>(
+    reader: &'sess mut R,
+    tx: &'sess mut Sender<u8, E>,
+) -> Result<usize, ReadIntoError<R::Error>>
+where
+    R: Read<'sess, u8, Reservation<'sess>>,
+{
+    let window = tx.reserve();
+    if window.is_empty() {
+        return Err(ReadIntoError::Send(SendErrorKind::Overflow));
+    }
+    // The reader gets a plain byte slice, so the window must be initialized.
+    for slot in window.iter_mut() {
+        *slot = MaybeUninit::new(0);
+    }
+    let len = window.len();
+    let reservation = Reservation { ptr: NonNull::from(window).cast(), len, _marker: PhantomData };
+    let count = reader.read(reservation).await.map_err(ReadIntoError::Read)?;
+    if count > len {
+        return Err(ReadIntoError::Overrun(count));
+    }
+    unsafe { tx.commit(count) }.map_err(ReadIntoError::Send)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::task::{Context, Poll};
+    use futures::{future, pin_mut, task::noop_waker_ref};
+
+    struct SliceReader(&'static [u8]);
+
+    impl<'sess, B: AsMut<[u8]> + Send + 'sess> Read<'sess, u8, B> for SliceReader {
+        type Error = ();
+
+        fn read(
+            &'sess mut self,
+            mut buffer: B,
+        ) -> Pin<Box<dyn Future<Output = Result<usize, ()>> + Send + 'sess>> {
+            let buffer = buffer.as_mut();
+            let count = buffer.len().min(self.0.len());
+            buffer[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+            Box::pin(future::ready(Ok(count)))
+        }
+    }
+
+    struct LyingReader;
+
+    impl<'sess, B: AsMut<[u8]> + Send + 'sess> Read<'sess, u8, B> for LyingReader {
+        type Error = ();
+
+        fn read(
+            &'sess mut self,
+            mut buffer: B,
+        ) -> Pin<Box<dyn Future<Output = Result<usize, ()>> + Send + 'sess>> {
+            Box::pin(future::ready(Ok(buffer.as_mut().len() + 1)))
+        }
+    }
+
+    fn poll<T>(fut: impl Future<Output = T>) -> T {
+        pin_mut!(fut);
+        match fut.poll(&mut Context::from_waker(noop_waker_ref())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("pending"),
+        }
+    }
+
+    #[test]
+    fn reservation() {
+        let (mut tx, mut rx) = crate::sync::spsc::ring::channel::<u8, ()>(4);
+        let mut reader = SliceReader(b"hello");
+        assert_eq!(poll(read_into_reservation(&mut reader, &mut tx)), Ok(4));
+        assert_eq!(
+            poll(read_into_reservation(&mut reader, &mut tx)),
+            Err(ReadIntoError::Send(SendErrorKind::Overflow))
+        );
+        assert_eq!(rx.try_next(), Ok(Some(b'h')));
+        assert_eq!(rx.try_next(), Ok(Some(b'e')));
+        assert_eq!(poll(read_into_reservation(&mut reader, &mut tx)), Ok(1));
+        assert_eq!(rx.try_next(), Ok(Some(b'l')));
+        assert_eq!(rx.try_next(), Ok(Some(b'l')));
+        assert_eq!(rx.try_next(), Ok(Some(b'o')));
+        assert_eq!(rx.try_next(), Ok(None));
+    }
+
+    #[test]
+    fn overrun() {
+        let (mut tx, mut rx) = crate::sync::spsc::ring::channel::<u8, ()>(4);
+        assert_eq!(
+            poll(read_into_reservation(&mut LyingReader, &mut tx)),
+            Err(ReadIntoError::Overrun(5))
+        );
+        assert_eq!(rx.try_next(), Ok(None));
+    }
+}
@@ -1,23 +1,26 @@
-use crate::{eprintln, log};
+use crate::{
+    crash::{self, Reason},
+    eprintln, panic,
+};
 use core::{alloc::Layout, panic::PanicInfo};
 
+#[cfg(feature = "panic-backtrace")]
 extern "C" {
-    fn drone_self_reset() -> !;
+    fn drone_frame_pointer() -> usize;
 }
 
 #[panic_handler]
 fn begin_panic(pi: &PanicInfo<'_>) -> ! {
-    eprintln!("{}", pi);
-    abort()
+    #[cfg(feature = "panic-backtrace")]
+    unsafe {
+        crash::capture_backtrace(drone_frame_pointer());
+    }
+    panic::drone_panic_handler(pi)
 }
 
 #[lang = "oom"]
 fn oom(layout: Layout) -> ! {
     eprintln!("Couldn't allocate memory of size {}. Aborting!", layout.size());
-    abort()
-}
-
-fn abort() -> ! {
-    log::flush();
-    unsafe { drone_self_reset() }
+    crash::store(Reason::Oom { size: layout.size() as u32 });
+    panic::abort()
 }
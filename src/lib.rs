@@ -24,13 +24,16 @@
 #![feature(alloc_layout_extra)]
 #![feature(alloc_prelude)]
 #![feature(allocator_api)]
+#![feature(associated_type_defaults)]
 #![feature(const_fn_trait_bound)]
+#![feature(const_panic)]
 #![feature(const_raw_ptr_deref)]
 #![feature(core_intrinsics)]
 #![feature(exhaustive_patterns)]
 #![feature(generator_trait)]
 #![feature(generators)]
 #![feature(lang_items)]
+#![feature(linkage)]
 #![feature(marker_trait_attr)]
 #![feature(maybe_uninit_extra)]
 #![feature(negative_impls)]
@@ -63,22 +66,45 @@
 
 extern crate alloc;
 
+pub mod bench;
 pub mod bitfield;
+pub mod boot;
+pub mod bus;
+pub mod check;
+pub mod clock;
+pub mod collections;
+pub mod crash;
+pub mod crc;
+pub mod dma;
+pub mod exec;
 pub mod ffi;
 pub mod fib;
+pub mod fsm;
 pub mod heap;
 pub mod inventory;
 pub mod io;
 pub mod log;
 pub mod mem;
+pub mod nvstore;
+pub mod panic;
 pub mod periph;
+pub mod power;
 pub mod prelude;
 pub mod proc_loop;
 pub mod reg;
+pub mod sched;
+pub mod shell;
+#[cfg(feature = "sim")]
+pub mod sim;
 pub mod sync;
+pub mod testing;
 pub mod thr;
+pub mod time;
 pub mod token;
+pub mod trace;
+pub mod watchdog;
 
+mod fnv;
 #[cfg(not(feature = "std"))]
 mod lang_items;
 
@@ -100,6 +126,12 @@ pub use drone_core_macros::periph;
 #[doc(inline)]
 pub use drone_core_macros::reg;
 
+/// Registers an item in a compile-time plugin registry.
+///
+/// See [`inventory::PluginRegistry`] for details.
+#[doc(inline)]
+pub use drone_core_macros::register;
+
 #[doc(hidden)]
 pub use drone_core_macros::config_override;
 
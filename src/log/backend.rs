@@ -0,0 +1,234 @@
+use super::{drone_log_is_enabled, drone_log_write_bytes, PORTS_COUNT};
+use core::{
+    cell::UnsafeCell,
+    ptr,
+    sync::atomic::{AtomicU16, AtomicU8, Ordering},
+};
+
+/// Maximum number of distinct backends, which can be passed to
+/// [`set_backends`].
+pub const MAX_BACKENDS: usize = 15;
+
+/// Maximum length of the fallback list of a port.
+pub const MAX_FALLBACKS: usize = 4;
+
+const SLOT_BITS: u32 = 4;
+const SLOT_MASK: u16 = (1 << SLOT_BITS) - 1;
+
+const SLOT_EMPTY: u8 = 0;
+const SLOT_CLAIMED: u8 = 1;
+const SLOT_READY: u8 = 2;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NOT_ROUTED: AtomicU16 = AtomicU16::new(0);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: Slot = Slot { state: AtomicU8::new(SLOT_EMPTY), backend: UnsafeCell::new(None) };
+
+// Fallback lists of the ports. Each list is a sequence of 4-bit slots, the
+// first backend in the least significant slot. A slot holds an index into
+// `REGISTRY` plus one, zero terminates the list.
+static ROUTES: [AtomicU16; PORTS_COUNT as usize] = [NOT_ROUTED; PORTS_COUNT as usize];
+
+// Registered backends. A slot is written once, and published by switching its
+// state to `SLOT_READY`, so the readers never see a torn pointer.
+static REGISTRY: [Slot; MAX_BACKENDS] = [EMPTY_SLOT; MAX_BACKENDS];
+
+struct Slot {
+    state: AtomicU8,
+    backend: UnsafeCell<Option<&'static dyn Backend>>,
+}
+
+unsafe impl Sync for Slot {}
+
+/// A log output backend, e.g. ITM, UART, or a RAM ring buffer read over RTT.
+///
+/// See [the module level documentation](super#backends) for details.
+pub trait Backend: Sync {
+    /// Returns `true` if the backend can deliver output of the `port` at the
+    /// moment, e.g. the debug probe is attached.
+    fn is_available(&self, port: u8) -> bool;
+
+    /// Writes `bytes` to the `port`. The bytes of a single call must not be
+    /// interleaved with concurrent writes.
+    fn write_bytes(&self, port: u8, bytes: &[u8]);
+
+    /// Blocks until all pending output is transmitted. Called from
+    /// [`log::flush`](super::flush).
+    fn flush(&self) {}
+}
+
+/// The backend implemented by the platform crate, which is the only backend of
+/// the ports without a fallback list.
+///
+/// The platform output is flushed by [`log::flush`](super::flush)
+/// unconditionally.
+pub struct Platform;
+
+impl Backend for Platform {
+    #[inline]
+    fn is_available(&self, port: u8) -> bool {
+        #[cfg(feature = "std")]
+        return false;
+        unsafe { drone_log_is_enabled(port) }
+    }
+
+    #[inline]
+    fn write_bytes(&self, port: u8, bytes: &[u8]) {
+        #[cfg(feature = "std")]
+        return;
+        unsafe { drone_log_write_bytes(port, bytes) };
+    }
+}
+
+/// Sets the ordered fallback list of `backends` for each port in the bit mask
+/// `ports`.
+///
+/// Each write to the port goes to the first backend in the list, which is
+/// [available](Backend::is_available) at the moment, and is dropped if there
+/// is none. An empty list returns the ports to the [`Platform`] backend.
+///
+/// The function is lock-free, so it can be called from any context, and
+/// doesn't require a critical section implementation. Registered backends are
+/// never released, even if no port uses them anymore.
+///
+/// # Panics
+///
+/// * If `backends` is longer than [`MAX_FALLBACKS`].
+/// * If the total number of distinct backends exceeds [`MAX_BACKENDS`].
+pub fn set_backends(ports: u32, backends: &[&'static dyn Backend]) {
+    assert!(backends.len() <= MAX_FALLBACKS, "too many fallback backends");
+    let route = backends
+        .iter()
+        .rev()
+        .fold(0, |route, &backend| route << SLOT_BITS | register(backend) as u16 + 1);
+    for (port, slot) in ROUTES.iter().enumerate() {
+        if ports & 1 << port != 0 {
+            slot.store(route, Ordering::Release);
+        }
+    }
+}
+
+pub(super) fn is_enabled(port: u8) -> bool {
+    match ROUTES[usize::from(port)].load(Ordering::Acquire) {
+        0 => Platform.is_available(port),
+        route => select(route, port).is_some(),
+    }
+}
+
+pub(super) fn write_bytes(port: u8, bytes: &[u8]) {
+    if !write_routed(port, bytes) {
+        Platform.write_bytes(port, bytes);
+    }
+}
+
+/// Writes `bytes` to the `port` if it has a fallback list, otherwise returns
+/// `false`.
+pub(super) fn write_routed(port: u8, bytes: &[u8]) -> bool {
+    match ROUTES[usize::from(port)].load(Ordering::Acquire) {
+        0 => false,
+        route => {
+            if let Some(backend) = select(route, port) {
+                backend.write_bytes(port, bytes);
+            }
+            true
+        }
+    }
+}
+
+pub(super) fn flush() {
+    for backend in REGISTRY.iter().filter_map(Slot::get) {
+        backend.flush();
+    }
+}
+
+// Returns the registry index of `backend`, registering it in the first empty
+// slot if needed. Concurrent registrations of the same backend may take two
+// slots, which is harmless.
+fn register(backend: &'static dyn Backend) -> usize {
+    for (index, slot) in REGISTRY.iter().enumerate() {
+        match slot.state.compare_exchange(
+            SLOT_EMPTY,
+            SLOT_CLAIMED,
+            Ordering::Acquire,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => {
+                unsafe { *slot.backend.get() = Some(backend) };
+                slot.state.store(SLOT_READY, Ordering::Release);
+                return index;
+            }
+            Err(SLOT_READY) if slot.get().map_or(false, |slot| same(slot, backend)) => {
+                return index;
+            }
+            Err(_) => {}
+        }
+    }
+    panic!("too many log backends");
+}
+
+fn select(mut route: u16, port: u8) -> Option<&'static dyn Backend> {
+    while route != 0 {
+        let backend = REGISTRY[usize::from(route & SLOT_MASK) - 1].get()?;
+        if backend.is_available(port) {
+            return Some(backend);
+        }
+        route >>= SLOT_BITS;
+    }
+    None
+}
+
+impl Slot {
+    fn get(&self) -> Option<&'static dyn Backend> {
+        if self.state.load(Ordering::Acquire) == SLOT_READY {
+            unsafe { *self.backend.get() }
+        } else {
+            None
+        }
+    }
+}
+
+fn same(a: &dyn Backend, b: &dyn Backend) -> bool {
+    ptr::eq(a as *const dyn Backend as *const u8, b as *const dyn Backend as *const u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicBool, AtomicUsize};
+
+    struct Probe {
+        attached: AtomicBool,
+        written: AtomicUsize,
+    }
+
+    impl Backend for Probe {
+        fn is_available(&self, _port: u8) -> bool {
+            self.attached.load(Ordering::Relaxed)
+        }
+
+        fn write_bytes(&self, _port: u8, bytes: &[u8]) {
+            self.written.fetch_add(bytes.len(), Ordering::Relaxed);
+        }
+    }
+
+    static PROBE: Probe = Probe { attached: AtomicBool::new(false), written: AtomicUsize::new(0) };
+    static RING: Probe = Probe { attached: AtomicBool::new(true), written: AtomicUsize::new(0) };
+
+    #[test]
+    fn fallback() {
+        assert!(!is_enabled(11));
+        set_backends(1 << 11 | 1 << 12, &[&PROBE, &RING]);
+        assert!(is_enabled(11));
+        write_bytes(11, b"ring");
+        assert_eq!(PROBE.written.load(Ordering::Relaxed), 0);
+        assert_eq!(RING.written.load(Ordering::Relaxed), 4);
+        PROBE.attached.store(true, Ordering::Relaxed);
+        write_bytes(12, b"probe");
+        assert_eq!(PROBE.written.load(Ordering::Relaxed), 5);
+        assert_eq!(RING.written.load(Ordering::Relaxed), 4);
+        set_backends(1 << 11 | 1 << 12, &[]);
+        assert!(!is_enabled(11));
+        assert!(!write_routed(12, b"platform"));
+    }
+}
@@ -0,0 +1,172 @@
+use super::{backend, Port, STDERR_PORT};
+use crate::thr::{critical, critical_available};
+use core::{fmt, fmt::Write, ptr};
+
+/// The size of the standard error fallback buffer in bytes.
+pub const ERROR_BUFFER_SIZE: usize = 256;
+
+const MAGIC: u32 = 0xE7F0_B0F5;
+
+#[cfg_attr(not(feature = "std"), link_section = ".noinit")]
+static mut BUFFER: ErrorBuffer = ErrorBuffer::ZERO;
+
+struct ErrorBuffer {
+    magic: u32,
+    head: usize,
+    len: usize,
+    bytes: [u8; ERROR_BUFFER_SIZE],
+}
+
+struct ErrorWriter;
+
+struct PortWriter;
+
+impl ErrorBuffer {
+    const ZERO: Self = Self { magic: 0, head: 0, len: 0, bytes: [0; ERROR_BUFFER_SIZE] };
+
+    fn validate(&mut self) {
+        if self.magic != MAGIC || self.head >= ERROR_BUFFER_SIZE || self.len > ERROR_BUFFER_SIZE {
+            self.magic = MAGIC;
+            self.head = 0;
+            self.len = 0;
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.bytes[(self.head + self.len) % ERROR_BUFFER_SIZE] = byte;
+            if self.len < ERROR_BUFFER_SIZE {
+                self.len += 1;
+            } else {
+                self.head = (self.head + 1) % ERROR_BUFFER_SIZE;
+            }
+        }
+    }
+
+    fn pop(&mut self, buffer: &mut [u8]) -> usize {
+        let count = buffer.len().min(self.len);
+        for byte in &mut buffer[..count] {
+            *byte = self.bytes[self.head];
+            self.head = (self.head + 1) % ERROR_BUFFER_SIZE;
+        }
+        self.len -= count;
+        count
+    }
+}
+
+impl Write for ErrorWriter {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        write_bytes(string.as_bytes());
+        Ok(())
+    }
+}
+
+impl Write for PortWriter {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        if Port::is_static_enabled(STDERR_PORT) {
+            backend::write_bytes(STDERR_PORT, string.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Writes `string` to the standard error port with guaranteed delivery.
+///
+/// The string is written with interrupts masked, and the function blocks until
+/// it is transmitted. If no debug probe is listening to the port, the string is
+/// appended to a fallback RAM buffer of [`ERROR_BUFFER_SIZE`] bytes instead,
+/// which keeps the most recent output. The buffer is placed into the `.noinit`
+/// linker section, so it survives a device reset, and is transmitted before the
+/// next write once a probe is connected. See also [`take_error_buffer`].
+///
+/// Masking interrupts requires a critical section implementation registered
+/// with [`set_critical!`](crate::set_critical). Without it the string is
+/// written to the port directly, and is dropped if no probe is listening.
+///
+/// This is the output path of [`eprint!`](crate::eprint) and panic messages,
+/// so it never panics by itself.
+#[inline(never)]
+pub fn write_error_str(string: &str) {
+    guaranteed(|writer| {
+        let _ = writer.write_str(string);
+    });
+}
+
+/// Writes `args` to the standard error port with guaranteed delivery.
+///
+/// See [`write_error_str`] for details.
+#[inline(never)]
+pub fn write_error_fmt(args: fmt::Arguments<'_>) {
+    guaranteed(|writer| {
+        let _ = writer.write_fmt(args);
+    });
+}
+
+/// Moves the contents of the standard error fallback buffer into `buffer`,
+/// oldest bytes first. Returns the number of bytes moved.
+///
+/// Can be used after a reset to retrieve the last error output, which was
+/// written while no debug probe was connected. Always returns zero if the
+/// platform doesn't implement critical sections.
+pub fn take_error_buffer(buffer: &mut [u8]) -> usize {
+    if !critical_available() {
+        return 0;
+    }
+    critical(|_| with_buffer(|error_buffer| error_buffer.pop(buffer)))
+}
+
+fn guaranteed(f: impl FnOnce(&mut dyn Write)) {
+    if critical_available() {
+        // Critical sections nest, so a nested error output, e.g. a panic while
+        // formatting, enters here again without breaking the exclusion.
+        critical(|_| f(&mut ErrorWriter));
+    } else {
+        f(&mut PortWriter);
+    }
+    // Flushing with interrupts masked could deadlock interrupt-driven
+    // backends.
+    if Port::new(STDERR_PORT).is_enabled() {
+        super::flush();
+    }
+}
+
+fn write_bytes(bytes: &[u8]) {
+    if !Port::is_static_enabled(STDERR_PORT) {
+        return;
+    }
+    if Port::new(STDERR_PORT).is_enabled() {
+        let mut chunk = [0; 32];
+        loop {
+            let count = with_buffer(|error_buffer| error_buffer.pop(&mut chunk));
+            if count == 0 {
+                break;
+            }
+            backend::write_bytes(STDERR_PORT, &chunk[..count]);
+        }
+        backend::write_bytes(STDERR_PORT, bytes);
+    } else {
+        with_buffer(|error_buffer| error_buffer.push(bytes));
+    }
+}
+
+fn with_buffer<R>(f: impl FnOnce(&mut ErrorBuffer) -> R) -> R {
+    let error_buffer = unsafe { &mut *ptr::addr_of_mut!(BUFFER) };
+    error_buffer.validate();
+    f(error_buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback() {
+        write_error_str("lost ");
+        write_error_fmt(format_args!("{}", "x".repeat(ERROR_BUFFER_SIZE)));
+        write_error_str("final words\n");
+        let mut buffer = [0; ERROR_BUFFER_SIZE + 1];
+        assert_eq!(take_error_buffer(&mut buffer), ERROR_BUFFER_SIZE);
+        assert!(buffer[..ERROR_BUFFER_SIZE].ends_with(b"xxxfinal words\n"));
+        assert_eq!(take_error_buffer(&mut buffer), 0);
+    }
+}
@@ -0,0 +1,143 @@
+/// Comma-separated list of the module paths to compile out.
+const OFF: &str = match option_env!("DRONE_LOG_OFF") {
+    Some(off) => off,
+    None => "",
+};
+
+/// Bit mask of the ports to compile in.
+pub(super) const STATIC_MASK: u32 = match option_env!("DRONE_LOG_PORTS") {
+    Some(ports) => ports_mask(ports),
+    None => u32::MAX,
+};
+
+/// Bit mask of the ports with sequenced frames.
+pub(super) const SEQ_MASK: u32 = match option_env!("DRONE_LOG_SEQ_PORTS") {
+    Some(ports) => ports_mask(ports),
+    None => 0,
+};
+
+/// Returns `true` if the log macros invoked with `target` are compiled in.
+///
+/// All targets are disabled with `log-off` feature. Otherwise a target is
+/// disabled if it equals to or is a submodule of one of the module paths
+/// listed in `DRONE_LOG_OFF` environment variable at the build time.
+///
+/// # Examples
+///
+/// ```
+/// use drone_core::log;
+///
+/// const VERBOSE: bool = log::target_enabled(module_path!());
+///
+/// if VERBOSE {
+///     // Do expensive formatting.
+/// }
+/// ```
+#[inline]
+pub const fn target_enabled(target: &str) -> bool {
+    !cfg!(feature = "log-off") && !filtered(OFF, target)
+}
+
+const fn filtered(off: &str, target: &str) -> bool {
+    let off = off.as_bytes();
+    let mut start = 0;
+    while start < off.len() {
+        let mut end = start;
+        while end < off.len() && off[end] != b',' {
+            end += 1;
+        }
+        if is_prefix(off, start, end, target.as_bytes()) {
+            return true;
+        }
+        start = end + 1;
+    }
+    false
+}
+
+const fn is_prefix(off: &[u8], mut start: usize, mut end: usize, target: &[u8]) -> bool {
+    while start < end && off[start] == b' ' {
+        start += 1;
+    }
+    while start < end && off[end - 1] == b' ' {
+        end -= 1;
+    }
+    let len = end - start;
+    if len == 0 || len > target.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < len {
+        if off[start + i] != target[i] {
+            return false;
+        }
+        i += 1;
+    }
+    len == target.len() || target[len] == b':'
+}
+
+const fn ports_mask(ports: &str) -> u32 {
+    let ports = ports.as_bytes();
+    let mut mask = 0;
+    let mut i = 0;
+    while i < ports.len() {
+        if ports[i] == b',' || ports[i] == b' ' {
+            i += 1;
+            continue;
+        }
+        let (first, next) = parse_port(ports, i);
+        let (last, next) = if next < ports.len() && ports[next] == b'-' {
+            parse_port(ports, next + 1)
+        } else {
+            (first, next)
+        };
+        let mut port = first;
+        while port <= last {
+            mask |= 1 << port;
+            port += 1;
+        }
+        i = next;
+    }
+    mask
+}
+
+const fn parse_port(ports: &[u8], mut i: usize) -> (u32, usize) {
+    while i < ports.len() && ports[i] == b' ' {
+        i += 1;
+    }
+    let mut port = 0;
+    while i < ports.len() && ports[i] != b',' && ports[i] != b'-' && ports[i] != b' ' {
+        port = port * 10 + (ports[i] - b'0') as u32;
+        i += 1;
+    }
+    while i < ports.len() && ports[i] == b' ' {
+        i += 1;
+    }
+    (port, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_paths() {
+        let off = "app::net, drone_stm32_drv::dma";
+        assert!(filtered(off, "app::net"));
+        assert!(filtered(off, "app::net::tcp"));
+        assert!(filtered(off, "drone_stm32_drv::dma"));
+        assert!(!filtered(off, "app"));
+        assert!(!filtered(off, "app::network"));
+        assert!(!filtered(off, "drone_stm32_drv::uart"));
+        assert!(!filtered("", "app"));
+        assert!(!filtered(",", "app"));
+    }
+
+    #[test]
+    fn port_ranges() {
+        assert_eq!(ports_mask(""), 0);
+        assert_eq!(ports_mask("0"), 0b1);
+        assert_eq!(ports_mask("0,1, 29 - 31"), 0xE000_0003);
+        assert_eq!(ports_mask("3-3,,10"), 0b100_0000_1000);
+        assert_eq!(ports_mask("0-31"), u32::MAX);
+    }
+}
@@ -0,0 +1,117 @@
+use super::Port;
+use alloc::string::String;
+use core::{
+    cmp, fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A source of buffer availability notifications for log ports.
+///
+/// The log output implementation is provided by downstream crates, and only
+/// they know when the probe has drained the port buffer. This trait lets the
+/// application wire such a signal (e.g. a DMA transfer-complete interrupt) into
+/// the asynchronous port writes, see [`Port::write_bytes_async`].
+pub trait Flushed {
+    /// Attempts to get the number of bytes that can be written to `port`
+    /// without blocking.
+    ///
+    /// If no space is available, the method must return `Poll::Pending` and
+    /// schedule the current task to be woken when more buffer space becomes
+    /// available. `Poll::Ready(0)` is treated the same way, except that the
+    /// caller wakes the task immediately, so it should be avoided.
+    fn poll_available(&mut self, port: u8, cx: &mut Context<'_>) -> Poll<usize>;
+}
+
+/// A future returned by [`Port::write_bytes_async`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WriteBytesFuture<'a, F: Flushed> {
+    port: Port,
+    bytes: &'a [u8],
+    flushed: &'a mut F,
+}
+
+/// A future returned by [`Port::write_fmt_async`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WriteFmtFuture<'a, F: Flushed> {
+    port: Port,
+    string: String,
+    cursor: usize,
+    flushed: &'a mut F,
+}
+
+impl Port {
+    /// Writes a sequence of bytes to the port asynchronously.
+    ///
+    /// Unlike [`Port::write_bytes`], which blocks while the port buffer is
+    /// full, the returned future writes `bytes` in chunks which fit into the
+    /// available buffer space as reported by `flushed`, and suspends otherwise.
+    #[inline]
+    pub fn write_bytes_async<'a, F: Flushed>(
+        self,
+        bytes: &'a [u8],
+        flushed: &'a mut F,
+    ) -> WriteBytesFuture<'a, F> {
+        WriteBytesFuture { port: self, bytes, flushed }
+    }
+
+    /// Writes formatted `args` to the port asynchronously.
+    ///
+    /// The arguments are formatted eagerly into a heap-allocated string, which
+    /// is then written the same way as in [`Port::write_bytes_async`].
+    #[inline]
+    pub fn write_fmt_async<F: Flushed>(
+        self,
+        args: fmt::Arguments<'_>,
+        flushed: &mut F,
+    ) -> WriteFmtFuture<'_, F> {
+        WriteFmtFuture { port: self, string: alloc::fmt::format(args), cursor: 0, flushed }
+    }
+}
+
+impl<F: Flushed> Future for WriteBytesFuture<'_, F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let Self { port, bytes, flushed } = self.get_mut();
+        let mut cursor = 0;
+        let poll = poll_write(*port, bytes, &mut cursor, *flushed, cx);
+        *bytes = &bytes[cursor..];
+        poll
+    }
+}
+
+impl<F: Flushed> Future for WriteFmtFuture<'_, F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let Self { port, string, cursor, flushed } = self.get_mut();
+        poll_write(*port, string.as_bytes(), cursor, *flushed, cx)
+    }
+}
+
+fn poll_write<F: Flushed>(
+    port: Port,
+    bytes: &[u8],
+    cursor: &mut usize,
+    flushed: &mut F,
+    cx: &mut Context<'_>,
+) -> Poll<()> {
+    let Port(port_number) = port;
+    while *cursor < bytes.len() {
+        match flushed.poll_available(port_number, cx) {
+            Poll::Ready(0) => {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(available) => {
+                let end = cmp::min(*cursor + available, bytes.len());
+                port.write_bytes(&bytes[*cursor..end]);
+                *cursor = end;
+            }
+            Poll::Pending => return Poll::Pending,
+        }
+    }
+    Poll::Ready(())
+}
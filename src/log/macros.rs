@@ -10,6 +10,10 @@
 /// Use `print!` only for the primary output of your program. Use [`eprint!`]
 /// instead to print error and progress messages.
 ///
+/// The output can be compiled out for the `target`, which defaults to the
+/// module path of the call site. See [the module-level
+/// documentation](crate::log#filtering) for details.
+///
 /// # Examples
 ///
 /// ```
@@ -31,16 +35,26 @@
 /// ```
 #[macro_export]
 macro_rules! print {
-    ($str:expr) => {
-        if $crate::log::stdout().is_enabled() {
+    (target: $target:expr, $str:expr) => {
+        if $crate::__log_enabled!($target, $crate::log::STDOUT_PORT)
+            && $crate::log::stdout().is_enabled()
+        {
             $crate::log::write_str($crate::log::STDOUT_PORT, $str);
         }
     };
-    ($($arg:tt)*) => {
-        if $crate::log::stdout().is_enabled() {
+    (target: $target:expr, $($arg:tt)*) => {
+        if $crate::__log_enabled!($target, $crate::log::STDOUT_PORT)
+            && $crate::log::stdout().is_enabled()
+        {
             $crate::log::write_fmt($crate::log::STDOUT_PORT, format_args!($($arg)*));
         }
     };
+    ($str:expr) => {
+        $crate::print!(target: module_path!(), $str)
+    };
+    ($($arg:tt)*) => {
+        $crate::print!(target: module_path!(), $($arg)*)
+    };
 }
 
 /// Prints to the log port #0, with a newline, if a debug probe is connected.
@@ -62,6 +76,15 @@ macro_rules! print {
 /// ```
 #[macro_export]
 macro_rules! println {
+    (target: $target:expr) => {
+        $crate::print!(target: $target, "\n");
+    };
+    (target: $target:expr, $fmt:expr) => {
+        $crate::print!(target: $target, concat!($fmt, "\n"));
+    };
+    (target: $target:expr, $fmt:expr, $($arg:tt)*) => {
+        $crate::print!(target: $target, concat!($fmt, "\n"), $($arg)*);
+    };
     () => {
         $crate::print!("\n");
     };
@@ -73,10 +96,12 @@ macro_rules! println {
     };
 }
 
-/// Prints to the log port #1, if a debug probe is connected.
+/// Prints to the log port #1 with guaranteed delivery.
 ///
 /// Equivalent to the [`print!`] macro, except that output goes to the port #1
-/// instead of #0. See [`print!`] for example usage.
+/// instead of #0, and is kept in a fallback RAM buffer if no debug probe is
+/// connected. See [`print!`] for example usage, and
+/// [`log::write_error_str`](crate::log::write_error_str) for the delivery mode.
 ///
 /// Use `eprint!` only for error and progress messages. Use `print!` instead for
 /// the primary output of your program.
@@ -88,22 +113,30 @@ macro_rules! println {
 /// ```
 #[macro_export]
 macro_rules! eprint {
-    ($str:expr) => {
-        if $crate::log::stderr().is_enabled() {
-            $crate::log::write_str($crate::log::STDERR_PORT, $str);
+    (target: $target:expr, $str:expr) => {
+        if $crate::__log_enabled!($target, $crate::log::STDERR_PORT) {
+            $crate::log::write_error_str($str);
         }
     };
-    ($($arg:tt)*) => {
-        if $crate::log::stderr().is_enabled() {
-            $crate::log::write_fmt($crate::log::STDERR_PORT, format_args!($($arg)*));
+    (target: $target:expr, $($arg:tt)*) => {
+        if $crate::__log_enabled!($target, $crate::log::STDERR_PORT) {
+            $crate::log::write_error_fmt(format_args!($($arg)*));
         }
     };
+    ($str:expr) => {
+        $crate::eprint!(target: module_path!(), $str)
+    };
+    ($($arg:tt)*) => {
+        $crate::eprint!(target: module_path!(), $($arg)*)
+    };
 }
 
-/// Prints to the log port #1, with a newline, if a debug probe is connected.
+/// Prints to the log port #1, with a newline, with guaranteed delivery.
 ///
 /// Equivalent to the [`println!`] macro, except that output goes to the port #1
-/// instead of #0. See [`println!`] for example usage.
+/// instead of #0, and is kept in a fallback RAM buffer if no debug probe is
+/// connected. See [`println!`] for example usage, and
+/// [`log::write_error_str`](crate::log::write_error_str) for the delivery mode.
 ///
 /// Use `eprintln!` only for error and progress messages. Use `println!` instead
 /// for the primary output of your program.
@@ -115,6 +148,15 @@ macro_rules! eprint {
 /// ```
 #[macro_export]
 macro_rules! eprintln {
+    (target: $target:expr) => {
+        $crate::eprint!(target: $target, "\n");
+    };
+    (target: $target:expr, $fmt:expr) => {
+        $crate::eprint!(target: $target, concat!($fmt, "\n"));
+    };
+    (target: $target:expr, $fmt:expr, $($arg:tt)*) => {
+        $crate::eprint!(target: $target, concat!($fmt, "\n"), $($arg)*);
+    };
     () => {
         $crate::eprint!("\n");
     };
@@ -165,3 +207,13 @@ macro_rules! dbg {
         ($($crate::dbg!($val)),+,)
     };
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_enabled {
+    ($target:expr, $port:expr) => {{
+        const ENABLED: bool =
+            $crate::log::target_enabled($target) && $crate::log::Port::is_static_enabled($port);
+        ENABLED
+    }};
+}
@@ -7,13 +7,88 @@
 //! Reserved ports:
 //!
 //! * `0` - standard output
-//! * `1` - standard error
+//! * `1` - standard error, with guaranteed delivery
+//! * `27` - on-target test reports
+//! * `28` - benchmark reports
+//! * `29` - trace records
+//! * `30` - runtime check failures
 //! * `31` - heap trace
+//!
+//! # Filtering
+//!
+//! The log macros take an optional `target: "..."` argument, which defaults to
+//! the module path of the call site:
+//!
+//! ```
+//! use drone_core::eprintln;
+//!
+//! eprintln!(target: "app::net", "retransmit");
+//! ```
+//!
+//! Whole subsystems can be compiled out by listing their module paths in
+//! `DRONE_LOG_OFF` environment variable at the build time, e.g.
+//! `DRONE_LOG_OFF=app::net,drone_stm32_drv::dma`. A target is compiled out if
+//! it equals to or is a submodule of one of the listed paths. With `log-off`
+//! feature all log macros are compiled out. See [`target_enabled`].
+//!
+//! Ports can be compiled out as well by listing the ports to keep in
+//! `DRONE_LOG_PORTS` environment variable at the build time, e.g.
+//! `DRONE_LOG_PORTS=0,29-31`. A malformed list fails the build. Any access to
+//! a port outside of the list compiles to nothing. See [`Port::STATIC_MASK`].
+//!
+//! # Sequence numbers
+//!
+//! When the debug probe can't keep up, output is dropped. Ports listed in
+//! `DRONE_LOG_SEQ_PORTS` environment variable at the build time embed a
+//! sequence number in each frame, so the dropped frames can be detected and
+//! counted on the host with [`GapDetector`]. See [`Port::SEQ_MASK`].
+//!
+//! # Error output
+//!
+//! Unlike other ports, the standard error port, which is used by
+//! [`eprint!`](crate::eprint) and panic messages, doesn't drop output when no
+//! debug probe is listening. The output is written with interrupts masked and
+//! flushed before returning, and falls back to a RAM buffer which survives a
+//! reset. This requires a critical section implementation registered with
+//! [`set_critical!`](crate::set_critical). See [`write_error_str`].
+//!
+//! # Backends
+//!
+//! By default the output is implemented by the platform crate, e.g. over ITM.
+//! Each port can be given an ordered list of [`Backend`]s at run-time with
+//! [`set_backends`]. A write goes to the first backend in the list, which is
+//! available at the moment, so a device in the field can log to a RAM ring
+//! buffer when no debug probe is attached:
+//!
+//! ```
+//! use drone_core::log::{self, Backend, Platform};
+//!
+//! struct RamRing;
+//!
+//! impl Backend for RamRing {
+//!     fn is_available(&self, _port: u8) -> bool {
+//!         true
+//!     }
+//!
+//!     fn write_bytes(&self, _port: u8, _bytes: &[u8]) {
+//!         // Append `bytes` to the RAM ring buffer.
+//!     }
+//! }
+//!
+//! static RAM_RING: RamRing = RamRing;
+//!
+//! log::set_backends(1 << log::STDOUT_PORT | 1 << log::STDERR_PORT, &[&Platform, &RAM_RING]);
+//! ```
 
 #![cfg_attr(feature = "std", allow(unreachable_code, unused_variables))]
 
+mod backend;
+mod error;
+mod filter;
+mod flushed;
 mod macros;
 mod port;
+mod seq;
 
 /// Returns log output baud rate defined in `Drone.toml`.
 ///
@@ -43,7 +118,14 @@ mod port;
 #[doc(inline)]
 pub use drone_core_macros::log_baud_rate as baud_rate;
 
-pub use self::port::Port;
+pub use self::{
+    backend::{set_backends, Backend, Platform, MAX_BACKENDS, MAX_FALLBACKS},
+    error::{take_error_buffer, write_error_fmt, write_error_str, ERROR_BUFFER_SIZE},
+    filter::target_enabled,
+    flushed::{Flushed, WriteBytesFuture, WriteFmtFuture},
+    port::Port,
+    seq::{GapDetector, GapStatistics, SEQ_FRAME_SIZE, SEQ_HEADER_SIZE},
+};
 
 use core::{fmt, fmt::Write};
 
@@ -119,9 +201,11 @@ pub fn write_fmt(port: u8, args: fmt::Arguments<'_>) {
 
 /// Blocks until all pending packets are transmitted.
 ///
-/// This function is a no-op if no debug probe is connected and listening.
+/// This function is a no-op if no debug probe is connected and listening. The
+/// backends passed to [`set_backends`] are flushed as well.
 #[inline]
 pub fn flush() {
+    backend::flush();
     #[cfg(feature = "std")]
     return;
     unsafe { drone_log_flush() };
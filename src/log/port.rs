@@ -1,18 +1,52 @@
 use super::{
-    drone_log_is_enabled, drone_log_write_bytes, drone_log_write_u16, drone_log_write_u32,
-    drone_log_write_u8, PORTS_COUNT,
+    backend, drone_log_write_u16, drone_log_write_u32, drone_log_write_u8, filter, seq, PORTS_COUNT,
 };
 use core::{fmt, fmt::Write};
 
 /// Logger port handle.
 #[derive(Clone, Copy)]
-pub struct Port(u8);
+pub struct Port(pub(super) u8);
 
 pub trait PortWrite: Copy {
     fn port_write(port: u8, value: Self);
+
+    fn port_write_sequenced(port: u8, value: Self);
 }
 
 impl Port {
+    /// Bit mask of the ports with sequenced frames.
+    ///
+    /// Empty by default. The mask can be set by listing port numbers and ranges
+    /// in `DRONE_LOG_SEQ_PORTS` environment variable at the build time, e.g.
+    /// `DRONE_LOG_SEQ_PORTS=0,2-5`. Every write to a sequenced port is split
+    /// into frames of at most [`SEQ_FRAME_SIZE`](super::SEQ_FRAME_SIZE) bytes.
+    /// Each frame starts with a byte holding the payload length, so that the
+    /// frames can be split out of a byte stream, followed by a big-endian `u16`
+    /// sequence number, which is incremented per frame and per port, so that
+    /// the host tooling can detect frames dropped when the port buffer is
+    /// full. See [`GapDetector`](super::GapDetector).
+    pub const SEQ_MASK: u32 = filter::SEQ_MASK;
+    /// Bit mask of the ports, which are compiled in.
+    ///
+    /// Includes all ports by default. The mask can be narrowed by listing port
+    /// numbers and ranges in `DRONE_LOG_PORTS` environment variable at the
+    /// build time, e.g. `DRONE_LOG_PORTS=0,29-31`. Any access to a port outside
+    /// of the mask compiles to nothing when the port number is known at
+    /// compile time.
+    pub const STATIC_MASK: u32 = filter::STATIC_MASK;
+
+    /// Returns `true` if `port` is included in [`Port::STATIC_MASK`].
+    #[inline]
+    pub const fn is_static_enabled(port: u8) -> bool {
+        port < PORTS_COUNT && Self::STATIC_MASK & 1 << port != 0
+    }
+
+    /// Returns `true` if `port` is included in [`Port::SEQ_MASK`].
+    #[inline]
+    pub const fn is_sequenced(port: u8) -> bool {
+        port < PORTS_COUNT && Self::SEQ_MASK & 1 << port != 0
+    }
+
     /// Creates a new port handle.
     ///
     /// # Panics
@@ -25,13 +59,16 @@ impl Port {
     }
 
     /// Returns `true` if the debug probe is connected and listening to the
-    /// `port` stream.
+    /// `port` stream. If the port has a fallback list of backends, returns
+    /// `true` if any of them is available. See [`set_backends`].
+    ///
+    /// [`set_backends`]: super::set_backends
+    ///
+    /// Always returns `false` if the port is not in [`Port::STATIC_MASK`].
     #[inline]
     pub fn is_enabled(self) -> bool {
-        #[cfg(feature = "std")]
-        return false;
         let Self(port) = self;
-        unsafe { drone_log_is_enabled(port) }
+        Self::is_static_enabled(port) && backend::is_enabled(port)
     }
 
     /// Writes a sequence of bytes to the port.
@@ -41,10 +78,14 @@ impl Port {
     /// atomic byte sequences.
     #[inline]
     pub fn write_bytes(self, bytes: &[u8]) -> Self {
-        #[cfg(feature = "std")]
-        return self;
         let Self(port) = self;
-        unsafe { drone_log_write_bytes(port, bytes) };
+        if Self::is_static_enabled(port) {
+            if Self::is_sequenced(port) {
+                seq::write_bytes(port, bytes);
+            } else {
+                backend::write_bytes(port, bytes);
+            }
+        }
         self
     }
 
@@ -52,11 +93,18 @@ impl Port {
     /// `u16`, `u32`.
     ///
     /// Bytes are written in big-endian order. It's guaranteed that all bytes of
-    /// `value` will not be split.
+    /// `value` will not be split. On a sequenced port `value` is written in a
+    /// single frame.
     #[inline]
     pub fn write<T: PortWrite>(self, value: T) -> Self {
         let Self(port) = self;
-        T::port_write(port, value);
+        if Self::is_static_enabled(port) {
+            if Self::is_sequenced(port) {
+                T::port_write_sequenced(port, value);
+            } else {
+                T::port_write(port, value);
+            }
+        }
         self
     }
 }
@@ -71,24 +119,45 @@ impl Write for Port {
 
 impl PortWrite for u8 {
     fn port_write(port: u8, value: Self) {
+        if backend::write_routed(port, &value.to_be_bytes()) {
+            return;
+        }
         #[cfg(feature = "std")]
         return;
         unsafe { drone_log_write_u8(port, value) };
     }
+
+    fn port_write_sequenced(port: u8, value: Self) {
+        seq::write_bytes(port, &value.to_be_bytes());
+    }
 }
 
 impl PortWrite for u16 {
     fn port_write(port: u8, value: Self) {
+        if backend::write_routed(port, &value.to_be_bytes()) {
+            return;
+        }
         #[cfg(feature = "std")]
         return;
         unsafe { drone_log_write_u16(port, value) };
     }
+
+    fn port_write_sequenced(port: u8, value: Self) {
+        seq::write_bytes(port, &value.to_be_bytes());
+    }
 }
 
 impl PortWrite for u32 {
     fn port_write(port: u8, value: Self) {
+        if backend::write_routed(port, &value.to_be_bytes()) {
+            return;
+        }
         #[cfg(feature = "std")]
         return;
         unsafe { drone_log_write_u32(port, value) };
     }
+
+    fn port_write_sequenced(port: u8, value: Self) {
+        seq::write_bytes(port, &value.to_be_bytes());
+    }
 }
@@ -0,0 +1,171 @@
+use super::{backend, PORTS_COUNT};
+use core::sync::atomic::{AtomicU16, Ordering};
+
+/// The size of the header of a sequenced frame in bytes: the payload length
+/// byte followed by the big-endian `u16` sequence number.
+pub const SEQ_HEADER_SIZE: usize = 3;
+
+/// The maximum size of a sequenced frame in bytes, including the header.
+pub const SEQ_FRAME_SIZE: usize = 32;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU16 = AtomicU16::new(0);
+
+static SEQUENCES: [AtomicU16; PORTS_COUNT as usize] = [ZERO; PORTS_COUNT as usize];
+
+/// Detects gaps in the sequence numbers of frames read from a sequenced port.
+///
+/// This is a helper for the host tooling. Split the byte stream of the port
+/// into frames by the length byte of the header, and feed the detector with the
+/// sequence number of each frame, in the order of arrival, to find out how
+/// many frames were dropped on the way. See
+/// [`Port::SEQ_MASK`](super::Port::SEQ_MASK).
+///
+/// A writer preempted between taking a sequence number and writing its frame
+/// lets the preempting writer's frame go first. Such a frame arriving late is
+/// counted as reordered rather than lost.
+///
+/// ```
+/// use drone_core::log::{GapDetector, GapStatistics};
+///
+/// let mut detector = GapDetector::new();
+/// assert_eq!(detector.observe(7), 0);
+/// assert_eq!(detector.observe(8), 0);
+/// assert_eq!(detector.observe(11), 2);
+/// assert_eq!(detector.statistics(), GapStatistics { frames: 3, lost: 2, gaps: 1, reordered: 0 });
+/// ```
+#[derive(Clone, Copy, Default, Debug)]
+pub struct GapDetector {
+    next: Option<u16>,
+    statistics: GapStatistics,
+}
+
+/// Statistics collected by [`GapDetector`].
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct GapStatistics {
+    /// The number of received frames.
+    pub frames: u32,
+    /// The number of dropped frames.
+    pub lost: u32,
+    /// The number of gaps, i.e. runs of consecutive dropped frames.
+    pub gaps: u32,
+    /// The number of frames arrived after a frame with a greater sequence
+    /// number.
+    pub reordered: u32,
+}
+
+impl GapDetector {
+    /// Creates a new detector, which accepts any sequence number of the first
+    /// frame.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { next: None, statistics: GapStatistics { frames: 0, lost: 0, gaps: 0, reordered: 0 } }
+    }
+
+    /// Records a frame with the sequence number `seq`. Returns the number of
+    /// frames dropped right before it.
+    ///
+    /// A sequence number behind the expected one is a frame arriving late,
+    /// which was already counted as lost, so it is moved from the lost frames
+    /// to the reordered ones. Consequently a gap of more than `i16::MAX`
+    /// frames is not detected.
+    ///
+    /// A device reset restarts the sequence from zero, which looks like a gap
+    /// to the detector. Call [`GapDetector::reset`] when the reset is known.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub fn observe(&mut self, seq: u16) -> u16 {
+        self.statistics.frames += 1;
+        let delta = self.next.map_or(0, |next| seq.wrapping_sub(next) as i16);
+        if delta < 0 {
+            self.statistics.reordered += 1;
+            self.statistics.lost = self.statistics.lost.saturating_sub(1);
+            return 0;
+        }
+        let lost = delta as u16;
+        self.next = Some(seq.wrapping_add(1));
+        if lost > 0 {
+            self.statistics.gaps += 1;
+            self.statistics.lost += u32::from(lost);
+        }
+        lost
+    }
+
+    /// Forgets the expected sequence number, keeping the statistics.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.next = None;
+    }
+
+    /// Returns the collected statistics.
+    #[inline]
+    pub fn statistics(&self) -> GapStatistics {
+        self.statistics
+    }
+}
+
+pub(super) fn write_bytes(port: u8, bytes: &[u8]) {
+    frames(port, bytes, |frame| backend::write_bytes(port, frame));
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn frames(port: u8, bytes: &[u8], mut emit: impl FnMut(&[u8])) {
+    let mut frame = [0; SEQ_FRAME_SIZE];
+    for chunk in bytes.chunks(SEQ_FRAME_SIZE - SEQ_HEADER_SIZE) {
+        let seq = SEQUENCES[usize::from(port)].fetch_add(1, Ordering::Relaxed);
+        let length = SEQ_HEADER_SIZE + chunk.len();
+        frame[0] = chunk.len() as u8;
+        frame[1..SEQ_HEADER_SIZE].copy_from_slice(&seq.to_be_bytes());
+        frame[SEQ_HEADER_SIZE..length].copy_from_slice(chunk);
+        emit(&frame[..length]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks() {
+        let bytes = (0..40).collect::<Vec<u8>>();
+        let mut frames = Vec::new();
+        super::frames(9, &bytes, |frame| frames.push(frame.to_vec()));
+        super::frames(9, &[0xFF], |frame| frames.push(frame.to_vec()));
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0][..4], [29, 0, 0, 0]);
+        assert_eq!(frames[0].len(), SEQ_FRAME_SIZE);
+        assert_eq!(frames[1][..4], [11, 0, 1, 29]);
+        assert_eq!(frames[1].len(), 14);
+        assert_eq!(frames[2], [1, 0, 2, 0xFF]);
+    }
+
+    #[test]
+    fn gaps() {
+        let mut detector = GapDetector::new();
+        assert_eq!(detector.observe(u16::MAX - 1), 0);
+        assert_eq!(detector.observe(1), 2);
+        assert_eq!(detector.observe(2), 0);
+        detector.reset();
+        assert_eq!(detector.observe(0), 0);
+        assert_eq!(detector.statistics(), GapStatistics {
+            frames: 4,
+            lost: 2,
+            gaps: 1,
+            reordered: 0
+        });
+    }
+
+    #[test]
+    fn reordered() {
+        let mut detector = GapDetector::new();
+        assert_eq!(detector.observe(u16::MAX), 0);
+        assert_eq!(detector.observe(1), 1);
+        assert_eq!(detector.observe(0), 0);
+        assert_eq!(detector.observe(2), 0);
+        assert_eq!(detector.statistics(), GapStatistics {
+            frames: 4,
+            lost: 0,
+            gaps: 1,
+            reordered: 1
+        });
+    }
+}
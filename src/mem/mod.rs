@@ -0,0 +1,91 @@
+//! Basic functions for dealing with memory.
+
+pub mod stack;
+
+mod region;
+mod section;
+mod volatile;
+mod words;
+
+pub use self::{
+    region::{find_region, region, regions, Region, RegionAttrs, RegionEntry},
+    section::{init_sections, section, Init, SectionInit},
+    volatile::{copy_from_peripheral, copy_to_peripheral, volatile_copy, Step},
+    words::{copy_words, set_words},
+};
+
+use core::{
+    cell::UnsafeCell,
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+const BSS_READY: u32 = 0x0B55_B007;
+const DATA_READY: u32 = 0xDA7A_B007;
+const READY: u32 = 0x5EC7_B007;
+
+extern "C" {
+    static BSS_START: UnsafeCell<usize>;
+    static BSS_END: UnsafeCell<usize>;
+    static DATA_LOAD: UnsafeCell<usize>;
+    static DATA_START: UnsafeCell<usize>;
+    static DATA_END: UnsafeCell<usize>;
+}
+
+// Placed outside of BSS and DATA, so that the segment initialization doesn't
+// clobber it. Holds garbage after a power-on reset, which is unlikely to match
+// any of the state values.
+#[cfg_attr(not(feature = "std"), link_section = ".noinit")]
+static INIT_STATE: AtomicU32 = AtomicU32::new(0);
+
+/// Initializes the BSS mutable memory segment.
+///
+/// This function **must** be called as early as possible.
+///
+/// See also [`data_init`] and [`init_sections`].
+///
+/// # Safety
+///
+/// This function reverts the state of initially zeroed mutable statics.
+pub unsafe fn bss_init() {
+    unsafe {
+        let length = BSS_END.get() as usize - BSS_START.get() as usize;
+        ptr::write_bytes(BSS_START.get(), 0, length >> 2);
+    }
+    mark_ready(BSS_READY, DATA_READY);
+}
+
+/// Initializes the DATA mutable memory segment.
+///
+/// This function **must** be called as early as possible.
+///
+/// See also [`bss_init`] and [`init_sections`].
+///
+/// # Safety
+///
+/// This function reverts the state of initially non-zeroed mutable statics.
+pub unsafe fn data_init() {
+    unsafe {
+        let length = DATA_END.get() as usize - DATA_START.get() as usize;
+        ptr::copy_nonoverlapping(DATA_LOAD.get(), DATA_START.get(), length >> 2);
+    }
+    mark_ready(DATA_READY, BSS_READY);
+}
+
+/// Returns `true` if both [`bss_init`] and [`data_init`] have been called.
+///
+/// Mutable statics, including the heap, are unusable until then. In debug
+/// builds the heap allocator panics when used too early, instead of silently
+/// corrupting the memory.
+///
+/// The state is kept in RAM, so after a warm reset it may be left over from
+/// the previous run until the first of the functions is called. Always returns
+/// `true` with `std` feature.
+pub fn is_initialized() -> bool {
+    cfg!(feature = "std") || INIT_STATE.load(Ordering::Relaxed) == READY
+}
+
+fn mark_ready(segment: u32, other: u32) {
+    let state = INIT_STATE.load(Ordering::Relaxed);
+    INIT_STATE.store(if state == other { READY } else { segment }, Ordering::Relaxed);
+}
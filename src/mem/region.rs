@@ -0,0 +1,221 @@
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::size_of,
+    ops::BitOr,
+    slice,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+extern "C" {
+    static DRONE_REGIONS_START: UnsafeCell<usize>;
+    static DRONE_REGIONS_END: UnsafeCell<usize>;
+}
+
+/// A memory region reserved for static carve-outs.
+///
+/// Regions are declared with [`mem::region!`](crate::mem::region) macro and
+/// can be enumerated at run-time with [`regions`]. Drivers obtain buffers from
+/// a region with [`Region::take_slice`] instead of hard-coding addresses.
+pub struct Region {
+    name: &'static str,
+    start: usize,
+    size: usize,
+    attrs: RegionAttrs,
+    taken: AtomicUsize,
+}
+
+/// Attributes of a memory [`Region`].
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct RegionAttrs(u32);
+
+impl RegionAttrs {
+    /// The memory is cacheable.
+    pub const CACHEABLE: Self = Self(1 << 1);
+    /// The memory is accessible by DMA controllers.
+    pub const DMA: Self = Self(1 << 0);
+    /// The memory is executable.
+    pub const EXECUTABLE: Self = Self(1 << 2);
+    /// No attributes.
+    pub const NONE: Self = Self(0);
+
+    /// Returns `true` if all attributes in `other` are set.
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOr for RegionAttrs {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl fmt::Debug for RegionAttrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegionAttrs")
+            .field("dma", &self.contains(Self::DMA))
+            .field("cacheable", &self.contains(Self::CACHEABLE))
+            .field("executable", &self.contains(Self::EXECUTABLE))
+            .finish()
+    }
+}
+
+/// A registry entry for [`mem::region!`](crate::mem::region).
+#[doc(hidden)]
+#[repr(transparent)]
+pub struct RegionEntry(pub &'static Region);
+
+/// Declares memory regions and registers them in the run-time registry.
+///
+/// The linker script must keep the `.drone_regions` section and surround it
+/// with `DRONE_REGIONS_START` and `DRONE_REGIONS_END` symbols.
+///
+/// ```
+/// use drone_core::mem::{self, RegionAttrs};
+///
+/// mem::region! {
+///     /// The second SRAM bank, accessible by DMA.
+///     pub SRAM2 => "sram2", 0x2001_C000, 0x4000, RegionAttrs::DMA;
+///
+///     /// Core-coupled memory.
+///     pub CCM => "ccm", 0x1000_0000, 0x1_0000, RegionAttrs::NONE;
+/// }
+/// # fn main() {}
+/// ```
+#[doc(inline)]
+pub use crate::__mem_region as region;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __mem_region {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis $ident:ident => $name:literal, $start:expr, $size:expr, $attrs:expr;
+    )*) => {
+        $(
+            $(#[$attr])*
+            $vis static $ident: $crate::mem::Region =
+                $crate::mem::Region::new($name, $start, $size, $attrs);
+
+            const _: () = {
+                #[used]
+                #[link_section = ".drone_regions"]
+                static REGION_ENTRY: $crate::mem::RegionEntry = $crate::mem::RegionEntry(&$ident);
+            };
+        )*
+    };
+}
+
+impl Region {
+    /// Creates a new region descriptor.
+    ///
+    /// Prefer [`mem::region!`](crate::mem::region) macro, which also registers
+    /// the region.
+    #[inline]
+    pub const fn new(name: &'static str, start: usize, size: usize, attrs: RegionAttrs) -> Self {
+        Self { name, start, size, attrs, taken: AtomicUsize::new(0) }
+    }
+
+    /// Returns the name of the region.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the starting address of the region.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the size of the region in bytes.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the region attributes.
+    #[inline]
+    pub fn attrs(&self) -> RegionAttrs {
+        self.attrs
+    }
+
+    /// Returns the number of bytes not yet taken from the region.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.size - self.taken.load(Ordering::Relaxed)
+    }
+
+    /// Carves out `len` bytes aligned to `align` from the region.
+    ///
+    /// Returns `None` if the region has not enough free space. Taken memory is
+    /// never returned to the region. The returned slice contents are
+    /// unspecified.
+    ///
+    /// This operation is lock-free.
+    ///
+    /// # Panics
+    ///
+    /// If `align` is not a power of two.
+    pub fn take_slice(&self, len: usize, align: usize) -> Option<&'static mut [u8]> {
+        assert!(align.is_power_of_two());
+        let mut taken = self.taken.load(Ordering::Relaxed);
+        loop {
+            let address = (self.start + taken + align - 1) & !(align - 1);
+            let end = address.checked_add(len)?;
+            if end > self.start + self.size {
+                return None;
+            }
+            match self.taken.compare_exchange_weak(
+                taken,
+                end - self.start,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Some(unsafe { slice::from_raw_parts_mut(address as *mut u8, len) })
+                }
+                Err(next_taken) => taken = next_taken,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Region")
+            .field("name", &self.name)
+            .field("start", &format_args!("{:#010X}", self.start))
+            .field("size", &self.size)
+            .field("attrs", &self.attrs)
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+/// Returns an iterator over all regions declared with
+/// [`mem::region!`](crate::mem::region).
+pub fn regions() -> impl Iterator<Item = &'static Region> {
+    let entries = unsafe {
+        let count = (DRONE_REGIONS_END.get() as usize - DRONE_REGIONS_START.get() as usize)
+            / size_of::<RegionEntry>();
+        slice::from_raw_parts(DRONE_REGIONS_START.get().cast::<RegionEntry>(), count)
+    };
+    entries.iter().map(|entry| entry.0)
+}
+
+/// Returns the first registered region with the `name`.
+pub fn find_region(name: &str) -> Option<&'static Region> {
+    regions().find(|region| region.name == name)
+}
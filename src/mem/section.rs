@@ -0,0 +1,128 @@
+use core::{cell::UnsafeCell, mem::size_of, ptr};
+
+extern "C" {
+    static DRONE_SECTIONS_START: UnsafeCell<usize>;
+    static DRONE_SECTIONS_END: UnsafeCell<usize>;
+}
+
+/// Initialization policy for a static declared with
+/// [`mem::section!`](crate::mem::section).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Init {
+    /// The static is zeroed by [`init_sections`].
+    Zeroed,
+    /// The static is left untouched, preserving its contents across resets.
+    Preserved,
+    /// Every byte of the static is set to the given value by
+    /// [`init_sections`].
+    Pattern(u8),
+}
+
+/// A descriptor of a static declared with
+/// [`mem::section!`](crate::mem::section).
+///
+/// Descriptors are collected by the linker into the `.drone_sections` section.
+#[repr(C)]
+pub struct SectionInit {
+    ptr: *mut u8,
+    size: usize,
+    init: Init,
+}
+
+unsafe impl Sync for SectionInit {}
+
+/// Declares statics placed into custom linker sections with an explicit
+/// initialization policy.
+///
+/// The declared statics have [`MaybeUninit`](core::mem::MaybeUninit) type,
+/// because the contents of custom sections are not initialized by the
+/// standard BSS/DATA initialization. The contents are initialized according to
+/// the [`Init`](crate::mem::Init) policy by
+/// [`mem::init_sections`](crate::mem::init_sections).
+///
+/// The linker script must keep the `.drone_sections` section and surround it
+/// with `DRONE_SECTIONS_START` and `DRONE_SECTIONS_END` symbols.
+///
+/// ```
+/// use drone_core::mem::{self, Init};
+///
+/// mem::section! {
+///     /// Log buffer which survives resets.
+///     pub static mut LOG_BUF: [u8; 256] => ".noinit", Init::Preserved;
+///
+///     /// Zeroed buffer in the DMA-capable memory.
+///     pub static mut DMA_BUF: [u32; 64] => ".dma", Init::Zeroed;
+/// }
+/// # fn main() {}
+/// ```
+#[doc(inline)]
+pub use crate::__mem_section as section;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __mem_section {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static mut $ident:ident: $ty:ty => $section:literal, $init:expr;
+    )*) => {
+        $(
+            $(#[$attr])*
+            #[link_section = $section]
+            $vis static mut $ident: ::core::mem::MaybeUninit<$ty> =
+                ::core::mem::MaybeUninit::uninit();
+
+            const _: () = {
+                #[used]
+                #[link_section = ".drone_sections"]
+                static SECTION_INIT: $crate::mem::SectionInit = unsafe {
+                    $crate::mem::SectionInit::new(
+                        ::core::ptr::addr_of_mut!($ident).cast(),
+                        ::core::mem::size_of::<$ty>(),
+                        $init,
+                    )
+                };
+            };
+        )*
+    };
+}
+
+impl SectionInit {
+    /// Creates a new descriptor.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes of `size` bytes.
+    #[doc(hidden)]
+    pub const unsafe fn new(ptr: *mut u8, size: usize, init: Init) -> Self {
+        Self { ptr, size, init }
+    }
+
+    unsafe fn run(&self) {
+        match self.init {
+            Init::Zeroed => unsafe { ptr::write_bytes(self.ptr, 0, self.size) },
+            Init::Preserved => {}
+            Init::Pattern(value) => unsafe { ptr::write_bytes(self.ptr, value, self.size) },
+        }
+    }
+}
+
+/// Initializes statics declared with [`mem::section!`](crate::mem::section)
+/// according to their [`Init`] policies.
+///
+/// This function should be called right after [`bss_init`](super::bss_init)
+/// and [`data_init`](super::data_init).
+///
+/// # Safety
+///
+/// This function reverts the state of the statics declared with
+/// [`mem::section!`](crate::mem::section).
+pub unsafe fn init_sections() {
+    unsafe {
+        let count = (DRONE_SECTIONS_END.get() as usize - DRONE_SECTIONS_START.get() as usize)
+            / size_of::<SectionInit>();
+        let sections = DRONE_SECTIONS_START.get().cast::<SectionInit>();
+        for i in 0..count {
+            (*sections.add(i)).run();
+        }
+    }
+}
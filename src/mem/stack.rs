@@ -0,0 +1,91 @@
+//! Stack usage measurement and overflow detection.
+//!
+//! The stack region is painted with a known pattern at boot by [`paint`]. Later
+//! [`usage`] finds the high-water mark by scanning for the first overwritten
+//! word, and [`guard_intact`] checks a few words at the very bottom of the
+//! stack, which are overwritten only on an overflow. The [`check`] function is
+//! cheap enough to be called from a periodic fiber.
+
+use core::{cell::UnsafeCell, mem::size_of, ops::Range, ptr};
+
+/// The pattern the unused stack is painted with.
+pub const PAINT_PATTERN: u32 = 0xC0DE_57AC;
+
+/// Number of words at the bottom of the stack checked by [`guard_intact`].
+pub const GUARD_WORDS: usize = 8;
+
+/// Number of bytes below the current stack pointer left unpainted by
+/// [`paint`], to not clobber the frame of the function itself.
+const PAINT_MARGIN: usize = 64;
+
+extern "C" {
+    static STACK_START: UnsafeCell<usize>;
+    static STACK_END: UnsafeCell<usize>;
+}
+
+/// Paints the unused part of the stack with [`PAINT_PATTERN`].
+///
+/// This function should be called as early as possible, right after
+/// [`bss_init`](super::bss_init) and [`data_init`](super::data_init).
+///
+/// # Safety
+///
+/// The memory between the stack bottom and the current stack pointer must not
+/// be in use.
+#[inline(never)]
+pub unsafe fn paint() {
+    let marker = 0_u8;
+    let end = ptr::addr_of!(marker) as usize - PAINT_MARGIN & !(size_of::<u32>() - 1);
+    let mut cursor = bottom();
+    while (cursor as usize) < end {
+        unsafe {
+            cursor.write_volatile(PAINT_PATTERN);
+            cursor = cursor.add(1);
+        }
+    }
+}
+
+/// Returns the total size of the stack in bytes.
+pub fn size() -> usize {
+    top() as usize - bottom() as usize
+}
+
+/// Returns the maximum number of bytes of the stack used since [`paint`] was
+/// called.
+pub fn usage() -> usize {
+    let top = top();
+    let mut cursor = bottom();
+    while cursor < top && unsafe { cursor.read_volatile() } == PAINT_PATTERN {
+        cursor = unsafe { cursor.add(1) };
+    }
+    top as usize - cursor as usize
+}
+
+/// Returns `true` if the guard words at the bottom of the stack are still
+/// intact, i.e. the stack has never overflowed.
+pub fn guard_intact() -> bool {
+    let bottom = bottom();
+    (0..GUARD_WORDS).all(|i| unsafe { bottom.add(i).read_volatile() } == PAINT_PATTERN)
+}
+
+/// Checks the guard words at the bottom of the stack.
+///
+/// # Panics
+///
+/// If the guard words were overwritten.
+pub fn check() {
+    assert!(guard_intact(), "stack overflow detected");
+}
+
+/// Returns the address range of the stack.
+pub(crate) fn range() -> Range<usize> {
+    bottom() as usize..top() as usize
+}
+
+fn bottom() -> *mut u32 {
+    unsafe { STACK_START.get().cast() }
+}
+
+fn top() -> *mut u32 {
+    unsafe { STACK_END.get().cast() }
+}
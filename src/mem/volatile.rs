@@ -0,0 +1,111 @@
+use core::{ptr, sync::atomic};
+
+/// Address progression of a peripheral memory during copying.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Step {
+    /// The peripheral address doesn't change, e.g. a FIFO data register.
+    Fixed,
+    /// The peripheral address is advanced by the given number of elements
+    /// after each access. `Stride(1)` is a plain continuous memory, while
+    /// e.g. `Stride(2)` addresses 16-bit words in a memory mapped with 32-bit
+    /// spacing.
+    Stride(usize),
+}
+
+/// Copies `count` elements from `src` to `dst` with volatile accesses.
+///
+/// Unlike [`ptr::copy_nonoverlapping`], each element is accessed exactly once,
+/// with an access of size `size_of::<T>()`, in ascending address order.
+///
+/// # Safety
+///
+/// * `src` must be valid for reads of `count` elements.
+/// * `dst` must be valid for writes of `count` elements.
+/// * Both pointers must be properly aligned.
+#[inline]
+pub unsafe fn volatile_copy<T: Copy>(dst: *mut T, src: *const T, count: usize) {
+    for i in 0..count {
+        unsafe { ptr::write_volatile(dst.add(i), ptr::read_volatile(src.add(i))) };
+    }
+}
+
+/// Writes the elements of `src` to the peripheral memory at `dst`.
+///
+/// Each element is written with a single volatile access of size
+/// `size_of::<T>()`, in order. The peripheral address progresses according to
+/// `step`. The function issues a compiler fence after the last write, so the
+/// subsequent peripheral register writes (e.g. starting a transfer) are not
+/// reordered before the data.
+///
+/// # Safety
+///
+/// `dst` must be a valid, properly aligned peripheral address for
+/// `src.len()` accesses with the given `step`.
+#[inline]
+pub unsafe fn copy_to_peripheral<T: Copy>(dst: *mut T, src: &[T], step: Step) {
+    let mut cursor = dst;
+    for &value in src {
+        unsafe {
+            ptr::write_volatile(cursor, value);
+            cursor = advance(cursor, step);
+        }
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// Reads the peripheral memory at `src` into the elements of `dst`.
+///
+/// Each element is read with a single volatile access of size
+/// `size_of::<T>()`, in order. The peripheral address progresses according to
+/// `step`. The function issues a compiler fence before the first read, so the
+/// preceding peripheral register reads (e.g. checking a transfer status) are
+/// not reordered after the data.
+///
+/// # Safety
+///
+/// `src` must be a valid, properly aligned peripheral address for
+/// `dst.len()` accesses with the given `step`.
+#[inline]
+pub unsafe fn copy_from_peripheral<T: Copy>(dst: &mut [T], src: *const T, step: Step) {
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+    let mut cursor = src as *mut T;
+    for value in dst {
+        unsafe {
+            *value = ptr::read_volatile(cursor);
+            cursor = advance(cursor, step);
+        }
+    }
+}
+
+#[inline]
+unsafe fn advance<T>(ptr: *mut T, step: Step) -> *mut T {
+    match step {
+        Step::Fixed => ptr,
+        Step::Stride(stride) => unsafe { ptr.add(stride) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strided() {
+        let mut pma = [0_u16; 8];
+        unsafe { copy_to_peripheral(pma.as_mut_ptr(), &[1, 2, 3, 4], Step::Stride(2)) };
+        assert_eq!(pma, [1, 0, 2, 0, 3, 0, 4, 0]);
+        let mut data = [0_u16; 4];
+        unsafe { copy_from_peripheral(&mut data, pma.as_ptr(), Step::Stride(2)) };
+        assert_eq!(data, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn fixed() {
+        let mut fifo = 0_u32;
+        unsafe { copy_to_peripheral(&mut fifo, &[1, 2, 3], Step::Fixed) };
+        assert_eq!(fifo, 3);
+        let mut data = [0_u32; 3];
+        unsafe { copy_from_peripheral(&mut data, &fifo, Step::Fixed) };
+        assert_eq!(data, [3, 3, 3]);
+    }
+}
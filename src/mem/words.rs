@@ -0,0 +1,149 @@
+use core::mem::{size_of, MaybeUninit};
+
+trait Word: Copy {
+    fn splat(byte: u8) -> Self;
+}
+
+/// Copies `count` bytes from `src` to `dst` with word accesses.
+///
+/// The bulk of the data is moved with 32-bit accesses, or with 64-bit accesses
+/// on 64-bit targets when both pointers are suitably aligned, and the
+/// remaining tail is moved bytewise. Copies up to four words are unrolled, so
+/// this function is faster than [`ptr::copy_nonoverlapping`](core::ptr) for
+/// the tiny sizes, where the latter usually ends up in a generic `memcpy`.
+///
+/// This function is for the plain RAM only. Use
+/// [`volatile_copy`](super::volatile_copy) for the peripheral memory.
+///
+/// # Safety
+///
+/// * `src` must be valid for reads of `count` bytes. The bytes may be
+///   uninitialized.
+/// * `dst` must be valid for writes of `count` bytes.
+/// * Both pointers must be 4-byte aligned.
+/// * The regions must not overlap.
+#[inline]
+pub unsafe fn copy_words(dst: *mut u8, src: *const u8, count: usize) {
+    debug_assert!((dst as usize | src as usize) % size_of::<u32>() == 0);
+    #[cfg(target_pointer_width = "64")]
+    if (dst as usize | src as usize) % size_of::<u64>() == 0 {
+        return unsafe { copy::<u64>(dst, src, count) };
+    }
+    unsafe { copy::<u32>(dst, src, count) }
+}
+
+/// Sets `count` bytes at `dst` to `value` with word accesses.
+///
+/// This is a counterpart of [`copy_words`] for [`ptr::write_bytes`](core::ptr).
+///
+/// # Safety
+///
+/// * `dst` must be valid for writes of `count` bytes.
+/// * `dst` must be 4-byte aligned.
+#[inline]
+pub unsafe fn set_words(dst: *mut u8, value: u8, count: usize) {
+    debug_assert!(dst as usize % size_of::<u32>() == 0);
+    #[cfg(target_pointer_width = "64")]
+    if dst as usize % size_of::<u64>() == 0 {
+        return unsafe { set::<u64>(dst, value, count) };
+    }
+    unsafe { set::<u32>(dst, value, count) }
+}
+
+// The source bytes may be uninitialized, e.g. the unused part of a reallocated
+// block, so they are moved as `MaybeUninit` values, never as integers.
+#[inline]
+unsafe fn copy<W: Word>(dst: *mut u8, src: *const u8, count: usize) {
+    let words = count / size_of::<W>();
+    let (dst_words, src_words) = (dst.cast::<MaybeUninit<W>>(), src.cast::<MaybeUninit<W>>());
+    let word = |i: usize| unsafe { dst_words.add(i).write(src_words.add(i).read()) };
+    unroll(words, word);
+    let (dst, src) = (dst.cast::<MaybeUninit<u8>>(), src.cast::<MaybeUninit<u8>>());
+    for i in words * size_of::<W>()..count {
+        unsafe { dst.add(i).write(src.add(i).read()) };
+    }
+}
+
+#[inline]
+unsafe fn set<W: Word>(dst: *mut u8, value: u8, count: usize) {
+    let words = count / size_of::<W>();
+    let pattern = W::splat(value);
+    let word = |i: usize| unsafe { dst.cast::<W>().add(i).write(pattern) };
+    unroll(words, word);
+    for i in words * size_of::<W>()..count {
+        unsafe { dst.add(i).write(value) };
+    }
+}
+
+#[inline]
+fn unroll(count: usize, mut f: impl FnMut(usize)) {
+    match count {
+        0 => {}
+        1 => f(0),
+        2 => {
+            f(0);
+            f(1);
+        }
+        3 => {
+            f(0);
+            f(1);
+            f(2);
+        }
+        4 => {
+            f(0);
+            f(1);
+            f(2);
+            f(3);
+        }
+        _ => (0..count).for_each(f),
+    }
+}
+
+impl Word for u32 {
+    #[inline]
+    fn splat(byte: u8) -> Self {
+        Self::from_ne_bytes([byte; 4])
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+impl Word for u64 {
+    #[inline]
+    fn splat(byte: u8) -> Self {
+        Self::from_ne_bytes([byte; 8])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::slice;
+
+    #[test]
+    fn copy_sizes() {
+        let src = [0x0102_0304_0506_0708_u64, 0x1112_1314_1516_1718, 0x2122_2324_2526_2728, 0];
+        for &offset in &[0, 4] {
+            for count in 0..=20 {
+                let mut dst = [0_u64; 4];
+                let src_ptr = unsafe { src.as_ptr().cast::<u8>().add(offset) };
+                let dst_ptr = unsafe { dst.as_mut_ptr().cast::<u8>().add(offset) };
+                unsafe { copy_words(dst_ptr, src_ptr, count) };
+                let src_bytes = unsafe { slice::from_raw_parts(src.as_ptr().cast::<u8>(), 32) };
+                let dst_bytes = unsafe { slice::from_raw_parts(dst.as_ptr().cast::<u8>(), 32) };
+                assert_eq!(dst_bytes[offset..offset + count], src_bytes[offset..offset + count]);
+                assert!(dst_bytes[offset + count..].iter().all(|&byte| byte == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn set_sizes() {
+        for count in 0..=20 {
+            let mut dst = [0_u32; 8];
+            unsafe { set_words(dst.as_mut_ptr().cast(), 0xA5, count) };
+            let dst_bytes = unsafe { slice::from_raw_parts(dst.as_ptr().cast::<u8>(), 32) };
+            assert!(dst_bytes[..count].iter().all(|&byte| byte == 0xA5));
+            assert!(dst_bytes[count..].iter().all(|&byte| byte == 0));
+        }
+    }
+}
@@ -0,0 +1,582 @@
+//! Non-volatile key-value storage.
+//!
+//! [`NvStore`] keeps small values, such as settings and calibration data, in a
+//! journal on top of a [`Flash`] memory. Each update appends a new record to the
+//! active sector, and when the sector is full, the latest records are copied to
+//! the next sector in a round-robin fashion, which spreads erase cycles over all
+//! sectors.
+//!
+//! Updates are safe against power loss. Every record and sector header is
+//! protected with a CRC-32, and a sector becomes active only after all live
+//! records have been copied into it. A torn record is ignored on the next
+//! mount, and the previous value of the key is used instead. The flash is
+//! assumed to program bytes in address order.
+//!
+//! Values are stored as raw bytes, or as types implementing [`Value`]. The
+//! trait is implemented for integers, `bool`, byte arrays, and
+//! [`Bitfield`](crate::bitfield::Bitfield) types which implement `Default`.
+//!
+//! ```
+//! use drone_core::{
+//!     io::Flash,
+//!     nvstore::{Error, NvStore},
+//! };
+//!
+//! const BRIGHTNESS: u16 = 1;
+//!
+//! async fn adjust<F: Flash>(flash: F) -> Result<(), Error<F::Error>> {
+//!     let mut store = NvStore::mount(flash).await?;
+//!     let brightness = store.get::<u8>(BRIGHTNESS).await?.unwrap_or(50);
+//!     store.set(BRIGHTNESS, &brightness.saturating_add(10)).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    bitfield::Bitfield,
+    crc::{Crc32, CRC32_ISO_HDLC},
+    io::Flash,
+};
+use core::{cmp, convert::TryInto, fmt};
+
+const MAGIC: u32 = 0x5453_564E;
+const SECTOR_HEADER_SIZE: u32 = 12;
+const RECORD_HEADER_SIZE: u32 = 8;
+const ERASED_KEY: u16 = 0xFFFF;
+const MAX_LEN: usize = 0xFFFE;
+const CHUNK_SIZE: u32 = 32;
+const CRC: Crc32 = Crc32::new(CRC32_ISO_HDLC);
+
+/// A journaling key-value store on top of a [`Flash`].
+///
+/// The flash must have at least two sectors. Keys are 16-bit integers except
+/// `0xFFFF`. Writing an empty value removes the key.
+pub struct NvStore<F: Flash> {
+    flash: F,
+    sector: u32,
+    seq: u32,
+    end: u32,
+    torn: bool,
+}
+
+/// A value which can be stored in an [`NvStore`].
+pub trait Value: Sized {
+    /// The size of the encoded value in bytes.
+    const SIZE: usize;
+
+    /// Encodes the value into `buffer` of [`Value::SIZE`] bytes.
+    fn encode(&self, buffer: &mut [u8]);
+
+    /// Decodes a value from `buffer` of [`Value::SIZE`] bytes. Returns `None`
+    /// if the bytes don't represent a valid value.
+    fn decode(buffer: &[u8]) -> Option<Self>;
+}
+
+/// The error type for [`NvStore`] operations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error<E> {
+    /// The underlying flash returned an error.
+    Flash(E),
+    /// The key is reserved.
+    InvalidKey,
+    /// The value doesn't fit into a sector.
+    TooLarge,
+    /// The live values don't leave enough space for the new record.
+    Full,
+}
+
+enum Entry {
+    End,
+    Torn,
+    Record { key: u16, len: u32 },
+}
+
+impl<F: Flash> NvStore<F> {
+    /// Mounts the store on the `flash`. If no valid store is found, the flash
+    /// is formatted.
+    ///
+    /// # Panics
+    ///
+    /// If the flash has less than two sectors.
+    pub async fn mount(flash: F) -> Result<Self, Error<F::Error>> {
+        assert!(F::SECTOR_COUNT >= 2, "nvstore requires at least two sectors");
+        let mut store = Self { flash, sector: 0, seq: 0, end: 0, torn: false };
+        let mut active = None;
+        for sector in 0..F::SECTOR_COUNT {
+            let mut header = [0; SECTOR_HEADER_SIZE as usize];
+            store.read_raw(sector * F::SECTOR_SIZE, &mut header).await?;
+            let seq = u32::from_le_bytes(header[4..8].try_into().unwrap());
+            let crc = u32::from_le_bytes(header[8..12].try_into().unwrap());
+            if u32::from_le_bytes(header[0..4].try_into().unwrap()) == MAGIC
+                && crc == CRC.checksum(&header[..8])
+                && active.map_or(true, |(_, active_seq)| is_newer(seq, active_seq))
+            {
+                active = Some((sector, seq));
+            }
+        }
+        match active {
+            Some((sector, seq)) => {
+                store.sector = sector;
+                store.seq = seq;
+                store.end = Self::first_record();
+                loop {
+                    match store.entry(store.sector, store.end, true).await? {
+                        Entry::End => break,
+                        Entry::Torn => {
+                            store.torn = true;
+                            break;
+                        }
+                        Entry::Record { len, .. } => store.end += Self::record_size(len),
+                    }
+                }
+            }
+            None => store.format().await?,
+        }
+        Ok(store)
+    }
+
+    /// Erases all values.
+    pub async fn format(&mut self) -> Result<(), Error<F::Error>> {
+        // Every sector with a valid header could be picked up by the next
+        // mount. The active sector is erased last, so an interrupted format
+        // doesn't bring back older values.
+        let active = self.sector;
+        for sector in (0..F::SECTOR_COUNT).filter(|&sector| sector != active) {
+            self.flash.erase(sector).await.map_err(Error::Flash)?;
+        }
+        self.flash.erase(active).await.map_err(Error::Flash)?;
+        self.activate(0, 1).await?;
+        self.end = Self::first_record();
+        self.torn = false;
+        Ok(())
+    }
+
+    /// Reads the value of the `key` into `buffer`, and returns the full length
+    /// of the value, or `None` if the key doesn't exist. If the value is longer
+    /// than `buffer`, it is truncated.
+    pub async fn read(
+        &mut self,
+        key: u16,
+        buffer: &mut [u8],
+    ) -> Result<Option<usize>, Error<F::Error>> {
+        let mut found = None;
+        let mut offset = Self::first_record();
+        while offset < self.end {
+            if let Entry::Record { key: record_key, len } =
+                self.entry(self.sector, offset, false).await?
+            {
+                if record_key == key {
+                    found = Some((offset, len));
+                }
+                offset += Self::record_size(len);
+            } else {
+                break;
+            }
+        }
+        match found {
+            Some((offset, len)) if len > 0 => {
+                let count = cmp::min(len as usize, buffer.len());
+                let addr = self.sector * F::SECTOR_SIZE + offset + RECORD_HEADER_SIZE;
+                self.read_raw(addr, &mut buffer[..count]).await?;
+                Ok(Some(len as usize))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Writes the `data` as the value of the `key`. If `data` is empty, the key
+    /// is removed.
+    #[allow(clippy::cast_possible_truncation)]
+    pub async fn write(&mut self, key: u16, data: &[u8]) -> Result<(), Error<F::Error>> {
+        if key == ERASED_KEY {
+            return Err(Error::InvalidKey);
+        }
+        if data.len() > MAX_LEN {
+            return Err(Error::TooLarge);
+        }
+        let size = Self::record_size(data.len() as u32);
+        if size > F::SECTOR_SIZE - Self::first_record() {
+            return Err(Error::TooLarge);
+        }
+        if self.torn || self.end + size > F::SECTOR_SIZE {
+            self.compact().await?;
+            if self.end + size > F::SECTOR_SIZE {
+                return Err(Error::Full);
+            }
+        }
+        let mut record = alloc::vec![0xFF; size as usize];
+        record[0..2].copy_from_slice(&key.to_le_bytes());
+        record[2..4].copy_from_slice(&(data.len() as u16).to_le_bytes());
+        let header_size = RECORD_HEADER_SIZE as usize;
+        record[header_size..header_size + data.len()].copy_from_slice(data);
+        let crc = CRC.finalize(CRC.update(CRC.update(CRC.init(), &record[0..4]), data));
+        record[4..8].copy_from_slice(&crc.to_le_bytes());
+        // If the write fails midway, the next write must start from a fresh
+        // sector.
+        self.torn = true;
+        self.flash
+            .write(self.sector * F::SECTOR_SIZE + self.end, &record)
+            .await
+            .map_err(Error::Flash)?;
+        self.torn = false;
+        self.end += size;
+        Ok(())
+    }
+
+    /// Removes the `key`.
+    #[inline]
+    pub async fn remove(&mut self, key: u16) -> Result<(), Error<F::Error>> {
+        self.write(key, &[]).await
+    }
+
+    /// Reads a typed value of the `key`. Returns `None` if the key doesn't
+    /// exist, or the stored value has a different size or can't be decoded.
+    pub async fn get<T: Value>(&mut self, key: u16) -> Result<Option<T>, Error<F::Error>> {
+        let mut buffer = alloc::vec![0; T::SIZE];
+        match self.read(key, &mut buffer).await? {
+            Some(len) if len == T::SIZE => Ok(T::decode(&buffer)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Writes a typed `value` of the `key`.
+    pub async fn set<T: Value>(&mut self, key: u16, value: &T) -> Result<(), Error<F::Error>> {
+        let mut buffer = alloc::vec![0; T::SIZE];
+        value.encode(&mut buffer);
+        self.write(key, &buffer).await
+    }
+
+    /// Returns a mutable reference to the underlying flash.
+    #[inline]
+    pub fn flash_mut(&mut self) -> &mut F {
+        &mut self.flash
+    }
+
+    /// Releases the underlying flash.
+    #[inline]
+    pub fn into_flash(self) -> F {
+        self.flash
+    }
+
+    async fn compact(&mut self) -> Result<(), Error<F::Error>> {
+        let next = (self.sector + 1) % F::SECTOR_COUNT;
+        self.flash.erase(next).await.map_err(Error::Flash)?;
+        let mut src = Self::first_record();
+        let mut dst = Self::first_record();
+        while src < self.end {
+            if let Entry::Record { key, len } = self.entry(self.sector, src, false).await? {
+                let size = Self::record_size(len);
+                if len > 0 && !self.superseded(key, src + size).await? {
+                    let mut record = alloc::vec![0; size as usize];
+                    self.read_raw(self.sector * F::SECTOR_SIZE + src, &mut record).await?;
+                    self.flash
+                        .write(next * F::SECTOR_SIZE + dst, &record)
+                        .await
+                        .map_err(Error::Flash)?;
+                    dst += size;
+                }
+                src += size;
+            } else {
+                break;
+            }
+        }
+        self.activate(next, self.seq.wrapping_add(1)).await?;
+        self.end = dst;
+        self.torn = false;
+        Ok(())
+    }
+
+    async fn superseded(&mut self, key: u16, mut offset: u32) -> Result<bool, Error<F::Error>> {
+        while offset < self.end {
+            if let Entry::Record { key: record_key, len } =
+                self.entry(self.sector, offset, false).await?
+            {
+                if record_key == key {
+                    return Ok(true);
+                }
+                offset += Self::record_size(len);
+            } else {
+                break;
+            }
+        }
+        Ok(false)
+    }
+
+    async fn activate(&mut self, sector: u32, seq: u32) -> Result<(), Error<F::Error>> {
+        let mut header = alloc::vec![0xFF; Self::first_record() as usize];
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[4..8].copy_from_slice(&seq.to_le_bytes());
+        let crc = CRC.checksum(&header[0..8]);
+        header[8..12].copy_from_slice(&crc.to_le_bytes());
+        self.flash.write(sector * F::SECTOR_SIZE, &header).await.map_err(Error::Flash)?;
+        self.sector = sector;
+        self.seq = seq;
+        Ok(())
+    }
+
+    async fn entry(
+        &mut self,
+        sector: u32,
+        offset: u32,
+        verify: bool,
+    ) -> Result<Entry, Error<F::Error>> {
+        if offset + RECORD_HEADER_SIZE > F::SECTOR_SIZE {
+            return Ok(Entry::End);
+        }
+        let addr = sector * F::SECTOR_SIZE + offset;
+        let mut header = [0; RECORD_HEADER_SIZE as usize];
+        self.read_raw(addr, &mut header).await?;
+        if header.iter().all(|&byte| byte == 0xFF) {
+            return Ok(Entry::End);
+        }
+        let key = u16::from_le_bytes(header[0..2].try_into().unwrap());
+        let len = u32::from(u16::from_le_bytes(header[2..4].try_into().unwrap()));
+        if key == ERASED_KEY
+            || len as usize > MAX_LEN
+            || offset + Self::record_size(len) > F::SECTOR_SIZE
+        {
+            return Ok(Entry::Torn);
+        }
+        if verify {
+            let mut crc = CRC.update(CRC.init(), &header[0..4]);
+            let mut chunk = [0; CHUNK_SIZE as usize];
+            let mut cursor = 0;
+            while cursor < len {
+                let count = cmp::min(len - cursor, CHUNK_SIZE);
+                let chunk = &mut chunk[..count as usize];
+                self.read_raw(addr + RECORD_HEADER_SIZE + cursor, chunk).await?;
+                crc = CRC.update(crc, chunk);
+                cursor += count;
+            }
+            if CRC.finalize(crc) != u32::from_le_bytes(header[4..8].try_into().unwrap()) {
+                return Ok(Entry::Torn);
+            }
+        }
+        Ok(Entry::Record { key, len })
+    }
+
+    async fn read_raw(&mut self, addr: u32, buffer: &mut [u8]) -> Result<(), Error<F::Error>> {
+        self.flash.read(addr, buffer).await.map_err(Error::Flash)
+    }
+
+    fn align(size: u32) -> u32 {
+        let align = cmp::max(F::WRITE_SIZE, 4);
+        (size + align - 1) / align * align
+    }
+
+    fn first_record() -> u32 {
+        Self::align(SECTOR_HEADER_SIZE)
+    }
+
+    fn record_size(len: u32) -> u32 {
+        Self::align(RECORD_HEADER_SIZE + len)
+    }
+}
+
+/// Compares the sector sequence numbers with serial number arithmetic, so the
+/// order is preserved when the sequence number wraps around.
+#[allow(clippy::cast_possible_wrap)]
+fn is_newer(seq: u32, other: u32) -> bool {
+    (seq.wrapping_sub(other) as i32) > 0
+}
+
+macro_rules! int_value {
+    ($($ty:ty),*) => {
+        $(
+            impl Value for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                #[inline]
+                fn encode(&self, buffer: &mut [u8]) {
+                    buffer.copy_from_slice(&self.to_le_bytes());
+                }
+
+                #[inline]
+                fn decode(buffer: &[u8]) -> Option<Self> {
+                    buffer.try_into().ok().map(Self::from_le_bytes)
+                }
+            }
+        )*
+    };
+}
+
+int_value!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Value for bool {
+    const SIZE: usize = 1;
+
+    #[inline]
+    fn encode(&self, buffer: &mut [u8]) {
+        buffer[0] = u8::from(*self);
+    }
+
+    #[inline]
+    fn decode(buffer: &[u8]) -> Option<Self> {
+        match buffer {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: usize> Value for [u8; N] {
+    const SIZE: usize = N;
+
+    #[inline]
+    fn encode(&self, buffer: &mut [u8]) {
+        buffer.copy_from_slice(self);
+    }
+
+    #[inline]
+    fn decode(buffer: &[u8]) -> Option<Self> {
+        buffer.try_into().ok()
+    }
+}
+
+impl<T: Bitfield + Default> Value for T
+where
+    T::Bits: Value,
+{
+    const SIZE: usize = T::Bits::SIZE;
+
+    #[inline]
+    fn encode(&self, buffer: &mut [u8]) {
+        self.bits().encode(buffer);
+    }
+
+    #[inline]
+    fn decode(buffer: &[u8]) -> Option<Self> {
+        let mut value = T::default();
+        *value.bits_mut() = T::Bits::decode(buffer)?;
+        Some(value)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flash(err) => write!(f, "flash error: {}", err),
+            Self::InvalidKey => f.write_str("reserved key"),
+            Self::TooLarge => f.write_str("value is too large"),
+            Self::Full => f.write_str("storage is full"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use futures::{future, pin_mut, task::noop_waker_ref};
+
+    struct RamFlash(Vec<u8>);
+
+    impl Flash for RamFlash {
+        type Error = !;
+
+        const SECTOR_COUNT: u32 = 2;
+        const SECTOR_SIZE: u32 = 128;
+        const WRITE_SIZE: u32 = 4;
+
+        fn read<'a>(
+            &'a mut self,
+            addr: u32,
+            buffer: &'a mut [u8],
+        ) -> Pin<Box<dyn Future<Output = Result<(), !>> + Send + 'a>> {
+            let addr = addr as usize;
+            buffer.copy_from_slice(&self.0[addr..addr + buffer.len()]);
+            Box::pin(future::ready(Ok(())))
+        }
+
+        fn write<'a>(
+            &'a mut self,
+            addr: u32,
+            data: &'a [u8],
+        ) -> Pin<Box<dyn Future<Output = Result<(), !>> + Send + 'a>> {
+            assert_eq!(addr % Self::WRITE_SIZE, 0);
+            assert_eq!(data.len() as u32 % Self::WRITE_SIZE, 0);
+            for (i, &byte) in data.iter().enumerate() {
+                self.0[addr as usize + i] &= byte;
+            }
+            Box::pin(future::ready(Ok(())))
+        }
+
+        fn erase(&mut self, sector: u32) -> Pin<Box<dyn Future<Output = Result<(), !>> + Send>> {
+            let start = (sector * Self::SECTOR_SIZE) as usize;
+            self.0[start..start + Self::SECTOR_SIZE as usize].fill(0xFF);
+            Box::pin(future::ready(Ok(())))
+        }
+    }
+
+    fn ready<F: Future>(future: F) -> F::Output {
+        pin_mut!(future);
+        match future.poll(&mut Context::from_waker(noop_waker_ref())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is not ready"),
+        }
+    }
+
+    #[test]
+    fn journal() {
+        let mut store = ready(NvStore::mount(RamFlash(vec![0; 256]))).unwrap();
+        ready(store.set(1, &0xDEAD_BEEF_u32)).unwrap();
+        ready(store.set(2, &[1_u8, 2, 3, 4])).unwrap();
+        for i in 0..20_u32 {
+            ready(store.set(3, &i)).unwrap();
+        }
+        assert_eq!(ready(store.get::<u32>(1)), Ok(Some(0xDEAD_BEEF)));
+        assert_eq!(ready(store.get::<[u8; 4]>(2)), Ok(Some([1, 2, 3, 4])));
+        assert_eq!(ready(store.get::<u32>(3)), Ok(Some(19)));
+        ready(store.remove(2)).unwrap();
+        let mut store = ready(NvStore::mount(store.into_flash())).unwrap();
+        assert_eq!(ready(store.get::<u32>(1)), Ok(Some(0xDEAD_BEEF)));
+        assert_eq!(ready(store.get::<[u8; 4]>(2)), Ok(None));
+        assert_eq!(ready(store.get::<u32>(3)), Ok(Some(19)));
+    }
+
+    #[test]
+    fn torn_record() {
+        let mut store = ready(NvStore::mount(RamFlash(vec![0; 256]))).unwrap();
+        ready(store.set(1, &1_u32)).unwrap();
+        ready(store.set(1, &2_u32)).unwrap();
+        let end = store.end as usize;
+        let mut flash = store.into_flash();
+        flash.0[end - 4] = 0;
+        let mut store = ready(NvStore::mount(flash)).unwrap();
+        assert_eq!(ready(store.get::<u32>(1)), Ok(Some(1)));
+        ready(store.set(1, &3_u32)).unwrap();
+        assert_eq!(store.sector, 1);
+        assert_eq!(ready(store.get::<u32>(1)), Ok(Some(3)));
+    }
+
+    #[test]
+    fn format() {
+        let mut store = ready(NvStore::mount(RamFlash(vec![0; 256]))).unwrap();
+        for i in 0..10_u32 {
+            ready(store.set(1, &i)).unwrap();
+        }
+        assert_eq!(store.sector, 1);
+        ready(store.format()).unwrap();
+        let mut store = ready(NvStore::mount(store.into_flash())).unwrap();
+        assert_eq!(ready(store.get::<u32>(1)), Ok(None));
+    }
+
+    #[test]
+    fn seq_wrap() {
+        let mut store = ready(NvStore::mount(RamFlash(vec![0; 256]))).unwrap();
+        ready(store.flash.erase(0)).unwrap();
+        ready(store.activate(0, u32::MAX)).unwrap();
+        for i in 0..10_u32 {
+            ready(store.set(1, &i)).unwrap();
+        }
+        assert_eq!((store.sector, store.seq), (1, 0));
+        let mut store = ready(NvStore::mount(store.into_flash())).unwrap();
+        assert_eq!(store.sector, 1);
+        assert_eq!(ready(store.get::<u32>(1)), Ok(Some(9)));
+    }
+}
@@ -0,0 +1,99 @@
+//! Pluggable panic strategies.
+//!
+//! By default a panic in a Drone application prints the panic message to the
+//! standard error log port and resets the device. An application can choose a
+//! different strategy by implementing the [`PanicHandler`] trait and
+//! registering it with the [`set_panic_handler!`](crate::set_panic_handler)
+//! macro. The registration replaces the default weak handler at link time, so
+//! at most one handler may be registered per application.
+//!
+//! ```
+//! use core::panic::PanicInfo;
+//! use drone_core::{panic::PanicHandler, set_panic_handler};
+//!
+//! /// Drives the outputs into a safe state before halting.
+//! pub struct SafeState;
+//!
+//! impl PanicHandler for SafeState {
+//!     fn handle(info: &PanicInfo<'_>) -> ! {
+//!         // Switch off the power stage here.
+//!         drone_core::panic::Halt::handle(info)
+//!     }
+//! }
+//!
+//! set_panic_handler!(SafeState);
+//! # fn main() {}
+//! ```
+
+#![cfg_attr(feature = "std", allow(unreachable_code, unused_variables))]
+
+use crate::{
+    crash::{self, Reason},
+    eprintln, log,
+};
+use core::panic::PanicInfo;
+
+extern "C" {
+    fn drone_self_reset() -> !;
+}
+
+/// A panic strategy.
+pub trait PanicHandler {
+    /// Handles the panic described by `info`. Never returns.
+    fn handle(info: &PanicInfo<'_>) -> !;
+}
+
+/// The default strategy: prints the panic message to the standard error log
+/// port, stores a [crash record](crate::crash), and resets the device.
+pub struct LogAndReset;
+
+/// Prints the panic message to the standard error log port and halts in an
+/// infinite loop, leaving the device state intact for a debugger.
+pub struct Halt;
+
+impl PanicHandler for LogAndReset {
+    fn handle(info: &PanicInfo<'_>) -> ! {
+        eprintln!("{}", info);
+        crash::store(Reason::Panic { location: info.location().map_or(0, crash::location_hash) });
+        abort()
+    }
+}
+
+impl PanicHandler for Halt {
+    fn handle(info: &PanicInfo<'_>) -> ! {
+        eprintln!("{}", info);
+        log::flush();
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Registers `$handler` type as the application panic strategy.
+///
+/// The type must implement [`PanicHandler`](crate::panic::PanicHandler). See
+/// [the module-level documentation](crate::panic) for details.
+#[macro_export]
+macro_rules! set_panic_handler {
+    ($handler:ty) => {
+        #[no_mangle]
+        fn drone_panic_handler(info: &::core::panic::PanicInfo<'_>) -> ! {
+            <$handler as $crate::panic::PanicHandler>::handle(info)
+        }
+    };
+}
+
+/// Flushes the log buffers and resets the device.
+pub fn abort() -> ! {
+    #[cfg(feature = "std")]
+    std::process::abort();
+    log::flush();
+    unsafe { drone_self_reset() }
+}
+
+#[cfg(not(feature = "std"))]
+#[linkage = "weak"]
+#[no_mangle]
+pub(crate) fn drone_panic_handler(info: &PanicInfo<'_>) -> ! {
+    LogAndReset::handle(info)
+}
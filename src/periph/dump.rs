@@ -0,0 +1,66 @@
+use crate::{
+    bitfield::Bitfield,
+    log::Port,
+    reg::{tag::Srt, RReg},
+    token::Token,
+};
+use core::fmt::Write;
+
+/// A register, which can be written to a log port by a generated peripheral
+/// `dump` method, or captured by a generated peripheral `freeze_view` method.
+pub trait RegDump {
+    /// Writes the name, the address, and the current value of the register to
+    /// `port`.
+    fn dump(&self, name: &str, port: Port);
+
+    /// Captures the current value of the register without a token. Returns
+    /// `None` if the register is not present.
+    ///
+    /// # Safety
+    ///
+    /// The read bypasses the token ownership. It must happen only when the
+    /// owner of the register token can't run, like in a panic or fault
+    /// handler.
+    unsafe fn view(name: &'static str) -> Option<RegView>
+    where
+        Self: Sized;
+}
+
+/// A snapshot of a register value captured by a generated peripheral
+/// `freeze_view` method.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RegView {
+    /// The register name.
+    pub name: &'static str,
+    /// The register address in memory.
+    pub address: usize,
+    /// The register value, or `None` if reading the register has side effects.
+    pub bits: Option<u64>,
+}
+
+impl RegDump for () {
+    #[inline]
+    fn dump(&self, _name: &str, _port: Port) {}
+
+    #[inline]
+    unsafe fn view(_name: &'static str) -> Option<RegView> {
+        None
+    }
+}
+
+impl<R: RReg<Srt>> RegDump for R
+where
+    <R::Val as Bitfield>::Bits: Into<u64>,
+{
+    fn dump(&self, name: &str, mut port: Port) {
+        let _ = match self.read_debug() {
+            Some(bits) => writeln!(port, "{} @ {:#010x} = {:#x?}", name, R::ADDRESS, bits),
+            None => writeln!(port, "{} @ {:#010x} = <read side effects>", name, R::ADDRESS),
+        };
+    }
+
+    unsafe fn view(name: &'static str) -> Option<RegView> {
+        let reg = unsafe { R::take() };
+        Some(RegView { name, address: R::ADDRESS, bits: reg.read_debug().map(Into::into) })
+    }
+}
@@ -0,0 +1,164 @@
+use core::{
+    any::Any,
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+const EMPTY: u8 = 0;
+const BUSY: u8 = 1;
+const FULL: u8 = 2;
+
+/// A type-erased peripheral.
+///
+/// Zero-sized peripheral tokens don't allocate when wrapped into a handle.
+pub struct PeriphHandle(Box<dyn Any + Send>);
+
+/// A storage for a single [`PeriphHandle`], which can be placed in a static.
+///
+/// The slot enforces single ownership at runtime: a peripheral can be reclaimed
+/// from the slot only once, until it is handed off again.
+pub struct PeriphSlot {
+    state: AtomicU8,
+    handle: UnsafeCell<Option<PeriphHandle>>,
+}
+
+unsafe impl Sync for PeriphSlot {}
+
+impl PeriphHandle {
+    /// Erases the type of `periph`.
+    #[inline]
+    pub fn new<T: Any + Send>(periph: T) -> Self {
+        Self(Box::new(periph))
+    }
+
+    /// Returns `true` if the handle holds a peripheral of type `T`.
+    #[inline]
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.is::<T>()
+    }
+
+    /// Attempts to reclaim the peripheral of type `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the handle back if it holds a peripheral of a different type.
+    #[inline]
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        self.0.downcast::<T>().map(|periph| *periph).map_err(Self)
+    }
+}
+
+impl fmt::Debug for PeriphHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeriphHandle").finish()
+    }
+}
+
+impl PeriphSlot {
+    /// Creates a new empty slot.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { state: AtomicU8::new(EMPTY), handle: UnsafeCell::new(None) }
+    }
+
+    /// Returns `true` if the slot holds no peripheral.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.state.load(Ordering::Acquire) == EMPTY
+    }
+
+    /// Hands off the peripheral `handle` to the slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns the handle back if the slot is occupied or is being accessed
+    /// concurrently.
+    pub fn put(&self, handle: PeriphHandle) -> Result<(), PeriphHandle> {
+        if self.state.compare_exchange(EMPTY, BUSY, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return Err(handle);
+        }
+        unsafe { *self.handle.get() = Some(handle) };
+        self.state.store(FULL, Ordering::Release);
+        Ok(())
+    }
+
+    /// Hands off the peripheral `periph` to the slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns the peripheral back if the slot is occupied or is being
+    /// accessed concurrently.
+    pub fn put_periph<T: Any + Send>(&self, periph: T) -> Result<(), T> {
+        self.put(PeriphHandle::new(periph))
+            .map_err(|handle| handle.downcast().unwrap_or_else(|_| unreachable!()))
+    }
+
+    /// Takes the handle out of the slot.
+    pub fn take(&self) -> Option<PeriphHandle> {
+        self.take_if(|_| true)
+    }
+
+    /// Reclaims the peripheral of type `T` from the slot.
+    ///
+    /// Returns `None` if the slot is empty, holds a peripheral of a different
+    /// type, or is being accessed concurrently. In the second case the
+    /// peripheral is left in the slot.
+    pub fn reclaim<T: Any + Send>(&self) -> Option<T> {
+        self.take_if(PeriphHandle::is::<T>).and_then(|handle| handle.downcast().ok())
+    }
+
+    fn take_if(&self, f: impl FnOnce(&PeriphHandle) -> bool) -> Option<PeriphHandle> {
+        if self.state.compare_exchange(FULL, BUSY, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return None;
+        }
+        let handle = unsafe { &mut *self.handle.get() };
+        if handle.as_ref().map_or(false, f) {
+            let handle = handle.take();
+            self.state.store(EMPTY, Ordering::Release);
+            handle
+        } else {
+            self.state.store(FULL, Ordering::Release);
+            None
+        }
+    }
+}
+
+impl Default for PeriphSlot {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PeriphSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeriphSlot").field("empty", &self.is_empty()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Console;
+
+    struct Update(u32);
+
+    #[test]
+    fn handoff() {
+        static SLOT: PeriphSlot = PeriphSlot::new();
+        assert!(SLOT.is_empty());
+        assert!(SLOT.put_periph(Console).is_ok());
+        assert!(SLOT.put_periph(Update(1)).is_err());
+        assert!(SLOT.reclaim::<Update>().is_none());
+        assert!(SLOT.reclaim::<Console>().is_some());
+        assert!(SLOT.reclaim::<Console>().is_none());
+        assert!(SLOT.put_periph(Update(2)).is_ok());
+        let handle = SLOT.take().unwrap();
+        assert!(SLOT.is_empty());
+        assert!(handle.is::<Update>());
+        let handle = handle.downcast::<Console>().err().unwrap();
+        assert_eq!(handle.downcast::<Update>().ok().unwrap().0, 2);
+    }
+}
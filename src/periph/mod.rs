@@ -187,6 +187,10 @@
 //!     }
 //! }
 //!
+//! // A capability marker trait. Generic drivers can bound on it to provide
+//! // richer behavior where the capability is available.
+//! pub trait SupportsDma: UartMap {}
+//!
 //! // Here we define the concrete UART4 peripheral.
 //! periph::map! {
 //!     // Extracts UART4 register tokens.
@@ -197,6 +201,9 @@
 //!     impl UartMap for Uart4 {
 //!         // If `UartMap` defined some items, they should be implemented here.
 //!     }
+//!     // Optionally, the concrete peripheral can implement capability traits.
+//!     // Items can be provided in braces the same way as above.
+//!     impl SupportsDma for Uart4;
 //!
 //!     // Path prefix to reach registers.
 //!     crate;
@@ -242,7 +249,80 @@
 //!     T: UartMap + RccApbenrUartrst + UartCr1Eobie + UartRtorRto,
 //! {
 //! }
+//!
+//! // Here is a generic function over peripherals with a capability.
+//! fn dma_fields<T: SupportsDma>(uart: UartPeriph<T>) {}
+//!
+//! // Every optional item also has a `PRESENT` constant, so a function generic
+//! // over all variants can still check what the concrete peripheral has.
+//! fn opt_flags<T: UartMap>(uart: &UartPeriph<T>) -> (bool, bool) {
+//!     (T::UART_RTOR_PRESENT, T::UART_CR1_EOBIE_PRESENT)
+//! }
 //! ```
+//!
+//! # Dump
+//!
+//! With `periph-dump` feature enabled, the generic peripheral struct gets a
+//! `dump` method, which writes the current values of all readable registers
+//! owned by the peripheral to a [log port](crate::log::Port). It is useful to
+//! capture the whole peripheral state during fault analysis:
+//!
+//! ```ignore
+//! uart.dump(drone_core::log::stderr());
+//! ```
+//!
+//! Registers marked with `Shared` are not owned by the peripheral, so they are
+//! skipped. Registers declared with `read_side_effects => true;` are listed
+//! without reading them.
+//!
+//! The same feature also adds a `freeze_view` associated function, which
+//! captures the values into an array of [`RegView`] in one pass, without
+//! formatting. It doesn't need the peripheral tokens, and relies on
+//! [`RReg::read_debug`](crate::reg::RReg::read_debug). It is `unsafe`, because
+//! it bypasses the token ownership, so it should be called only from a panic or
+//! fault handler:
+//!
+//! ```ignore
+//! let view = unsafe { UartPeriph::<Uart1>::freeze_view() };
+//! ```
+//!
+//! # Handoff
+//!
+//! A peripheral can be converted into a type-erased [`PeriphHandle`] and stored
+//! in a static [`PeriphSlot`]. A device manager can then hand the peripheral
+//! off between drivers at runtime, while the slot ensures that only one driver
+//! owns it at a time:
+//!
+//! ```
+//! use drone_core::periph::PeriphSlot;
+//!
+//! # struct UartPeriph;
+//! struct Console(UartPeriph);
+//! struct Update(UartPeriph);
+//!
+//! static UART: PeriphSlot = PeriphSlot::new();
+//!
+//! fn enter_update() {
+//!     // Take the peripheral from the console driver.
+//!     let Console(uart) = UART.reclaim::<Console>().expect("UART is busy");
+//!     UART.put_periph(Update(uart)).ok().unwrap();
+//! }
+//!
+//! # fn main() {
+//! UART.put_periph(Console(UartPeriph)).ok().unwrap();
+//! enter_update();
+//! assert!(UART.reclaim::<Console>().is_none());
+//! assert!(UART.reclaim::<Update>().is_some());
+//! # }
+//! ```
+
+#[cfg(feature = "periph-dump")]
+mod dump;
+mod handle;
+
+#[cfg(feature = "periph-dump")]
+pub use self::dump::{RegDump, RegView};
+pub use self::handle::{PeriphHandle, PeriphSlot};
 
 /// Implements the generic peripheral.
 ///
@@ -0,0 +1,211 @@
+//! Power management.
+//!
+//! Drivers which need to prepare for low-power states implement the
+//! [`PowerAware`] trait and register themselves with [`register`]. Code which
+//! temporarily can't tolerate deep sleep (e.g. an ongoing DMA transfer from a
+//! peripheral clocked off in the *Stop* mode) holds a [`PowerLock`]. The
+//! governor picks the deepest [`SleepLevel`] allowed by all active locks, but
+//! not deeper than the level set with [`set_deepest`], which is
+//! [`SleepLevel::Sleep`] by default.
+//!
+//! The platform crate provides the actual low-power entry by implementing the
+//! [`SleepEnter`] trait and registering it with the
+//! [`set_sleep_enter!`](crate::set_sleep_enter) macro. The application then
+//! registers [`idle`] as the last idle hook:
+//!
+//! ```
+//! use drone_core::{power, thr};
+//!
+//! thr::idle_hook!(u16::MAX, power::idle);
+//!
+//! fn main() {
+//!     // Allow the governor to stop the clocks.
+//!     power::set_deepest(power::SleepLevel::Stop);
+//! }
+//! ```
+//!
+//! [`idle`] runs within a [`thr::critical`] section, so it requires a critical
+//! section implementation registered with
+//! [`set_critical!`](crate::set_critical).
+
+use crate::{
+    inventory::{Registry, RegistryFull},
+    thr,
+};
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// The maximum number of [`PowerAware`] participants.
+pub const MAX_PARTICIPANTS: usize = 32;
+
+static PARTICIPANTS: Registry<dyn PowerAware, MAX_PARTICIPANTS> = Registry::new();
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicUsize = AtomicUsize::new(0);
+static LOCKS: [AtomicUsize; SleepLevel::COUNT] = [ZERO; SleepLevel::COUNT];
+
+static DEEPEST: AtomicU8 = AtomicU8::new(SleepLevel::Sleep as u8);
+
+/// A processor sleep level, from the shallowest to the deepest.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(u8)]
+pub enum SleepLevel {
+    /// No sleep, the processor keeps running.
+    Run = 0,
+    /// The processor clock is stopped, peripherals keep running.
+    Sleep = 1,
+    /// Most clocks are stopped, the memory contents are retained.
+    Stop = 2,
+    /// The deepest level, from which the device wakes up with a reset.
+    Standby = 3,
+}
+
+/// A driver participating in low-power state transitions.
+pub trait PowerAware: Sync {
+    /// Prepares the driver for entering the sleep `level`.
+    fn suspend(&self, level: SleepLevel);
+
+    /// Restores the driver after waking up from the sleep `level`.
+    fn resume(&self, level: SleepLevel);
+}
+
+/// A platform implementation of low-power states.
+pub trait SleepEnter {
+    /// Enters the sleep `level` and returns after a wake-up.
+    ///
+    /// This method is called with interrupts masked by [`thr::critical`]. A
+    /// pending interrupt must still wake the processor up, like `WFI` does
+    /// with `PRIMASK` set on Cortex-M. The interrupt is handled after the
+    /// critical section ends.
+    fn enter(level: SleepLevel);
+}
+
+/// A constraint, which prevents the governor from entering sleep levels deeper
+/// than the given one while held.
+#[must_use = "the constraint is released immediately if unused"]
+pub struct PowerLock {
+    level: SleepLevel,
+}
+
+/// Registers `$enter` type as the platform implementation of low-power states.
+///
+/// The type must implement [`SleepEnter`](crate::power::SleepEnter).
+#[macro_export]
+macro_rules! set_sleep_enter {
+    ($enter:ty) => {
+        #[no_mangle]
+        fn drone_power_enter(level: $crate::power::SleepLevel) {
+            <$enter as $crate::power::SleepEnter>::enter(level)
+        }
+    };
+}
+
+impl SleepLevel {
+    const COUNT: usize = 4;
+    const LEVELS: [Self; Self::COUNT] = [Self::Run, Self::Sleep, Self::Stop, Self::Standby];
+}
+
+impl PowerLock {
+    /// Acquires a constraint, which allows sleep levels not deeper than
+    /// `level`.
+    pub fn acquire(level: SleepLevel) -> Self {
+        LOCKS[level as usize].fetch_add(1, Ordering::Acquire);
+        Self { level }
+    }
+
+    /// Returns the deepest sleep level allowed by this constraint.
+    #[inline]
+    pub fn level(&self) -> SleepLevel {
+        self.level
+    }
+}
+
+impl Drop for PowerLock {
+    fn drop(&mut self) {
+        LOCKS[self.level as usize].fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Registers a [`PowerAware`] participant under the `id`.
+///
+/// Returns an error if [`MAX_PARTICIPANTS`] participants are already
+/// registered.
+#[inline]
+pub fn register(
+    id: &'static str,
+    participant: &'static dyn PowerAware,
+) -> Result<(), RegistryFull> {
+    PARTICIPANTS.register(id, participant)
+}
+
+/// Sets the deepest sleep level the governor may enter when no [`PowerLock`]
+/// forbids it.
+///
+/// The default is [`SleepLevel::Sleep`], the shallowest one, so deeper levels
+/// are entered only when the application explicitly allows them.
+pub fn set_deepest(level: SleepLevel) {
+    DEEPEST.store(level as u8, Ordering::Relaxed);
+}
+
+/// Returns the deepest sleep level allowed by all active [`PowerLock`]s and
+/// [`set_deepest`].
+pub fn allowed() -> SleepLevel {
+    let deepest = SleepLevel::LEVELS[usize::from(DEEPEST.load(Ordering::Relaxed))];
+    SleepLevel::LEVELS
+        .iter()
+        .copied()
+        .take_while(|&level| level < deepest)
+        .find(|&level| LOCKS[level as usize].load(Ordering::Acquire) > 0)
+        .unwrap_or(deepest)
+}
+
+/// Enters the deepest allowed sleep level.
+///
+/// All registered participants are suspended before entering the sleep level,
+/// and resumed after the wake-up, in order of registration. Returns
+/// immediately if sleeping is not allowed.
+///
+/// The allowed level is checked, and the sleep level is entered, with
+/// interrupts masked. Therefore an interrupt, which acquires a [`PowerLock`]
+/// or makes a thread pending right before the sleep, isn't missed: it keeps
+/// pending and wakes the processor up immediately.
+pub fn idle() {
+    thr::critical(|_| {
+        let level = allowed();
+        if level == SleepLevel::Run {
+            return;
+        }
+        for (_, participant) in PARTICIPANTS.iter() {
+            participant.suspend(level);
+        }
+        drone_power_enter(level);
+        for (_, participant) in PARTICIPANTS.iter() {
+            participant.resume(level);
+        }
+    });
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_power_enter(_level: SleepLevel) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn governor() {
+        assert_eq!(allowed(), SleepLevel::Sleep);
+        let stop = PowerLock::acquire(SleepLevel::Stop);
+        assert_eq!(allowed(), SleepLevel::Sleep);
+        set_deepest(SleepLevel::Standby);
+        assert_eq!(allowed(), SleepLevel::Stop);
+        let run = PowerLock::acquire(SleepLevel::Run);
+        assert_eq!(allowed(), SleepLevel::Run);
+        drop(run);
+        assert_eq!(allowed(), SleepLevel::Stop);
+        drop(stop);
+        assert_eq!(allowed(), SleepLevel::Standby);
+        set_deepest(SleepLevel::Sleep);
+        assert_eq!(allowed(), SleepLevel::Sleep);
+    }
+}
@@ -6,11 +6,16 @@
 
 #![allow(clippy::wildcard_imports)]
 
-use crate::{fib, fib::Fiber};
+use crate::{fib, fib::Fiber, trace};
 use core::{future::Future, mem::ManuallyDrop, pin::Pin};
+use futures::stream::{self, Stream};
 
 type SessFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
+type SessStream<'a, T> = Pin<Box<dyn Stream<Item = T> + Send + 'a>>;
+
+type SessStep<P> = CmdStep<<P as ProcLoop>::Progress, <P as ProcLoop>::CmdRes>;
+
 /// The trait for declaring a synchronous command loop.
 ///
 /// This trait uses only associated items, thus it doesn't require the type to
@@ -18,7 +23,7 @@ type SessFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 pub trait ProcLoop: Send + 'static {
     /// Token type that allows suspending the task while waiting for a request
     /// result.
-    type Context: Context<Self::Req, Self::ReqRes>;
+    type Context: Context<Self::Req, Self::ReqRes, Self::Progress>;
 
     /// `enum` of all possible commands.
     type Cmd: Send + 'static;
@@ -32,6 +37,10 @@ pub trait ProcLoop: Send + 'static {
     /// `union` of all possible request results.
     type ReqRes: Send + 'static;
 
+    /// Intermediate progress value of a command. Defaults to `!`, for commands
+    /// which don't report progress.
+    type Progress: Send + 'static = !;
+
     /// Size of the process stack in bytes.
     const STACK_SIZE: usize;
 
@@ -51,6 +60,19 @@ pub trait ProcLoop: Send + 'static {
     /// Runs on the process destruction.
     #[inline]
     fn on_drop() {}
+
+    /// Returns a value identifying the request `req` in trace records, usually
+    /// its discriminant.
+    ///
+    /// If `Some` is returned, [`Sess`] writes a [`trace`] event named
+    /// `proc_loop::yield` when the process yields with `req`, and an event
+    /// named `proc_loop::resume` when the process is resumed with the request
+    /// result. This shows how long-running commands interleave with
+    /// interrupts on the host timeline. Returns `None` by default.
+    #[inline]
+    fn trace_req(_req: &Self::Req) -> Option<u32> {
+        None
+    }
 }
 
 /// A session type for the synchronous command loop [`ProcLoop`].
@@ -64,7 +86,11 @@ pub trait Sess: Send {
     /// Fiber that runs the command loop.
     type Fiber: Fiber<
             Input = In<<Self::ProcLoop as ProcLoop>::Cmd, <Self::ProcLoop as ProcLoop>::ReqRes>,
-            Yield = Out<<Self::ProcLoop as ProcLoop>::Req, <Self::ProcLoop as ProcLoop>::CmdRes>,
+            Yield = Out<
+                <Self::ProcLoop as ProcLoop>::Req,
+                <Self::ProcLoop as ProcLoop>::CmdRes,
+                <Self::ProcLoop as ProcLoop>::Progress,
+            >,
             Return = !,
         > + Send;
 
@@ -81,6 +107,9 @@ pub trait Sess: Send {
     ) -> SessFuture<'_, Result<<Self::ProcLoop as ProcLoop>::ReqRes, Self::Error>>;
 
     /// Returns a future that will return a result for the command `cmd`.
+    ///
+    /// Progress values reported by the command are discarded. See
+    /// [`cmd_progress`](Sess::cmd_progress) for observing them.
     fn cmd(
         &mut self,
         cmd: <Self::ProcLoop as ProcLoop>::Cmd,
@@ -90,16 +119,55 @@ pub trait Sess: Send {
             loop {
                 let fib::Yielded(output) = self.fib().resume(input);
                 input = match output {
-                    Out::Req(req) => In::from_req_res(self.run_req(req).await?),
+                    Out::Req(req) => {
+                        let trace_id = trace_yield::<Self::ProcLoop>(&req);
+                        let req_res = self.run_req(req).await?;
+                        trace_resume(trace_id);
+                        In::from_req_res(req_res)
+                    }
+                    Out::Progress(_) => In::ack(),
                     Out::CmdRes(res) => break Ok(res),
                 }
             }
         })
     }
+
+    /// Returns a stream of progress values reported by the command `cmd`,
+    /// terminated by the command result.
+    ///
+    /// The stream ends after yielding [`CmdStep::Done`] or an error.
+    fn cmd_progress(
+        &mut self,
+        cmd: <Self::ProcLoop as ProcLoop>::Cmd,
+    ) -> SessStream<'_, Result<SessStep<Self::ProcLoop>, Self::Error>> {
+        let state = Some((self, In::from_cmd(cmd)));
+        Box::pin(stream::unfold(state, |state| async move {
+            let (sess, mut input) = state?;
+            loop {
+                let fib::Yielded(output) = sess.fib().resume(input);
+                input = match output {
+                    Out::Req(req) => {
+                        let trace_id = trace_yield::<Self::ProcLoop>(&req);
+                        match sess.run_req(req).await {
+                            Ok(req_res) => {
+                                trace_resume(trace_id);
+                                In::from_req_res(req_res)
+                            }
+                            Err(err) => break Some((Err(err), None)),
+                        }
+                    }
+                    Out::Progress(progress) => {
+                        break Some((Ok(CmdStep::Progress(progress)), Some((sess, In::ack()))));
+                    }
+                    Out::CmdRes(res) => break Some((Ok(CmdStep::Done(res)), None)),
+                }
+            }
+        }))
+    }
 }
 
 /// A token that allows suspending synchronous code.
-pub trait Context<Req, ReqRes>: Copy + 'static {
+pub trait Context<Req, ReqRes, Progress = !>: Copy + 'static {
     /// Creates a new token.
     ///
     /// # Safety
@@ -112,6 +180,17 @@ pub trait Context<Req, ReqRes>: Copy + 'static {
     /// This method suspends execution of the current task allowing to escape
     /// from synchronous code.
     fn req(self, req: Req) -> ReqRes;
+
+    /// Reports an intermediate `progress` value of the current command.
+    ///
+    /// This method suspends execution of the current task until the value is
+    /// taken by the command stream. The default implementation discards the
+    /// value without suspending, for platforms which don't support progress
+    /// reporting.
+    #[inline]
+    fn progress(self, progress: Progress) {
+        drop(progress);
+    }
 }
 
 /// [`Sess::Fiber`] input.
@@ -122,18 +201,30 @@ pub union In<Cmd, ReqRes> {
     cmd: ManuallyDrop<Cmd>,
     /// Result for the last request.
     req_res: ManuallyDrop<ReqRes>,
+    /// Acknowledgement of the last progress value.
+    ack: (),
 }
 
 /// [`Sess::Fiber`] output.
 ///
 /// See also [`In`].
-pub enum Out<Req, CmdRes> {
+pub enum Out<Req, CmdRes, Progress = !> {
     /// Request that the command loop is waiting for.
     Req(Req),
+    /// Intermediate progress value of the current command.
+    Progress(Progress),
     /// Result for the last command.
     CmdRes(CmdRes),
 }
 
+/// An item of the stream returned by [`Sess::cmd_progress`].
+pub enum CmdStep<Progress, CmdRes> {
+    /// Intermediate progress value of the command.
+    Progress(Progress),
+    /// Result of the command.
+    Done(CmdRes),
+}
+
 impl<Cmd, ReqRes> In<Cmd, ReqRes> {
     /// Creates a new command input.
     pub fn from_cmd(cmd: Cmd) -> Self {
@@ -145,6 +236,11 @@ impl<Cmd, ReqRes> In<Cmd, ReqRes> {
         Self { req_res: ManuallyDrop::new(req_res) }
     }
 
+    /// Creates a new progress acknowledgement input.
+    pub fn ack() -> Self {
+        Self { ack: () }
+    }
+
     /// Interprets the input as a command.
     ///
     /// # Safety
@@ -163,3 +259,17 @@ impl<Cmd, ReqRes> In<Cmd, ReqRes> {
         ManuallyDrop::into_inner(unsafe { self.req_res })
     }
 }
+
+fn trace_yield<P: ProcLoop>(req: &P::Req) -> Option<u32> {
+    let trace_id = P::trace_req(req);
+    if let Some(trace_id) = trace_id {
+        trace::event!("proc_loop::yield", trace_id);
+    }
+    trace_id
+}
+
+fn trace_resume(trace_id: Option<u32>) {
+    if let Some(trace_id) = trace_id {
+        trace::event!("proc_loop::resume", trace_id);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{
+    bitfield::Bitfield,
+    reg::{
+        field::{RRRegField, RegField, WWRegField},
+        mem_ptr, mem_store,
+        tag::RegTag,
+        RReg, Reg, WReg,
+    },
+    thr::CriticalSection,
+};
+use core::{fmt, marker::PhantomData, mem::size_of, ptr::read_volatile};
+
+/// A type-erased read-only handle to a register field.
+///
+/// The handle captures the register address, and the field offset and width
+/// at runtime, so that fields of different registers can be stored in a
+/// single table. It borrows the field token for its lifetime.
+#[derive(Clone, Copy)]
+pub struct DynField<'a> {
+    layout: Layout,
+    _token: PhantomData<&'a ()>,
+}
+
+/// A type-erased read-write handle to a register field.
+///
+/// The handle mutably borrows the field token for its lifetime. Writing a field
+/// is a read-modify-write operation on the whole register, so it must be done
+/// inside a [`thr::critical`](crate::thr::critical) section, in order not to
+/// race with writes to the other fields of the same register. Critical
+/// sections require an implementation registered by the platform crate with
+/// [`set_critical!`](crate::set_critical).
+pub struct DynFieldMut<'a> {
+    layout: Layout,
+    _token: PhantomData<&'a mut ()>,
+}
+
+#[derive(Clone, Copy)]
+struct Layout {
+    address: usize,
+    size: u8,
+    offset: u8,
+    width: u8,
+}
+
+impl<'a> DynField<'a> {
+    /// Creates a new handle for the readable `field`.
+    ///
+    /// # Panics
+    ///
+    /// If the register is wider than 32 bits.
+    #[inline]
+    pub fn new<T, F>(field: &'a F) -> Self
+    where
+        T: RegTag,
+        F: RRRegField<T>,
+        F::Reg: RReg<T>,
+    {
+        let _ = field;
+        Self { layout: Layout::of::<T, F>(), _token: PhantomData }
+    }
+
+    /// Returns the register address.
+    #[inline]
+    pub fn address(&self) -> usize {
+        self.layout.address
+    }
+
+    /// Returns the offset of the field inside the register.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.layout.offset.into()
+    }
+
+    /// Returns the bit-width of the field.
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.layout.width.into()
+    }
+
+    /// Reads the register memory and extracts the field bits.
+    #[inline]
+    pub fn read(&self) -> u32 {
+        self.layout.read()
+    }
+}
+
+impl<'a> DynFieldMut<'a> {
+    /// Creates a new handle for the readable and writable `field`.
+    ///
+    /// ```no_run
+    /// # use drone_core::{reg::{prelude::*, DynFieldMut}, thr, token::Token};
+    /// # drone_core::reg! {
+    /// #     GPIOA MODER => {
+    /// #         address => 0x4002_0000; size => 0x20; reset => 0; traits => { RReg WReg };
+    /// #         fields => {
+    /// #             MODER0 => { offset => 0; width => 2; traits => { RRRegField WWRegField } };
+    /// #             MODER1 => { offset => 2; width => 2; traits => { RRRegField WWRegField } };
+    /// #         };
+    /// #     };
+    /// # }
+    /// # fn main() {
+    /// #   let mut gpioa_moder = unsafe { gpioa_moder::Reg::<Urt>::take() };
+    /// // Switch PA0 and PA1 to the alternate function mode.
+    /// let mut pins =
+    ///     [DynFieldMut::new(&mut gpioa_moder.moder0), DynFieldMut::new(&mut gpioa_moder.moder1)];
+    /// thr::critical(|cs| {
+    ///     for pin in &mut pins {
+    ///         pin.write(cs, 0b10);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the register is wider than 32 bits.
+    #[inline]
+    pub fn new<T, F>(field: &'a mut F) -> Self
+    where
+        T: RegTag,
+        F: RRRegField<T> + WWRegField<T>,
+        F::Reg: RReg<T> + WReg<T>,
+    {
+        let _ = field;
+        Self { layout: Layout::of::<T, F>(), _token: PhantomData }
+    }
+
+    /// Returns a read-only handle for the same field.
+    #[inline]
+    pub fn as_dyn_field(&self) -> DynField<'_> {
+        DynField { layout: self.layout, _token: PhantomData }
+    }
+
+    /// Returns the register address.
+    #[inline]
+    pub fn address(&self) -> usize {
+        self.layout.address
+    }
+
+    /// Returns the offset of the field inside the register.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.layout.offset.into()
+    }
+
+    /// Returns the bit-width of the field.
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.layout.width.into()
+    }
+
+    /// Reads the register memory and extracts the field bits.
+    #[inline]
+    pub fn read(&self) -> u32 {
+        self.layout.read()
+    }
+
+    /// Reads the register memory, replaces the field bits by `bits`, then
+    /// writes the result back.
+    ///
+    /// The critical section `cs` excludes concurrent writes to the other
+    /// fields of the register. Several writes can share a single critical
+    /// section. The bits of `bits` outside of the field width are ignored.
+    #[inline]
+    pub fn write(&mut self, cs: CriticalSection<'_>, bits: u32) {
+        let _ = cs;
+        let mask = self.layout.mask();
+        let offset = self.layout.offset;
+        let val = self.layout.load() & !(mask << offset) | (bits & mask) << offset;
+        self.layout.store(val);
+    }
+}
+
+impl Layout {
+    fn of<T, F>() -> Self
+    where
+        T: RegTag,
+        F: RegField<T>,
+    {
+        let size = size_of::<<<F::Reg as Reg<T>>::Val as Bitfield>::Bits>();
+        assert!(size <= size_of::<u32>(), "registers wider than 32 bits are not supported");
+        Self {
+            address: F::Reg::ADDRESS,
+            size: size as u8,
+            offset: F::OFFSET as u8,
+            width: F::WIDTH as u8,
+        }
+    }
+
+    fn mask(self) -> u32 {
+        u32::MAX >> (32 - u32::from(self.width))
+    }
+
+    fn read(self) -> u32 {
+        self.load() >> self.offset & self.mask()
+    }
+
+    fn load(self) -> u32 {
+        unsafe {
+            match self.size {
+                1 => read_volatile(mem_ptr::<u8>(self.address)).into(),
+                2 => read_volatile(mem_ptr::<u16>(self.address)).into(),
+                _ => read_volatile(mem_ptr::<u32>(self.address)),
+            }
+        }
+    }
+
+    fn store(self, val: u32) {
+        unsafe {
+            match self.size {
+                1 => mem_store(self.address, val as u8),
+                2 => mem_store(self.address, val as u16),
+                _ => mem_store(self.address, val),
+            }
+        }
+    }
+
+    fn fmt(self, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(name)
+            .field("address", &format_args!("{:#010x}", self.address))
+            .field("offset", &self.offset)
+            .field("width", &self.width)
+            .finish()
+    }
+}
+
+impl fmt::Debug for DynField<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.layout.fmt("DynField", f)
+    }
+}
+
+impl fmt::Debug for DynFieldMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.layout.fmt("DynFieldMut", f)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{
+    fib::{self, FiberStreamPulse},
+    reg::{field::RRRegFieldBit, tag::RegTag, RReg},
+    thr::prelude::*,
+};
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures::Stream;
+
+/// A stream of flag events returned by [`RegFieldEvents::events`].
+///
+/// Dropping or closing this stream will remove the fiber on a next thread
+/// invocation without resuming it.
+#[must_use = "streams do nothing unless you `.await` or poll them"]
+pub struct FieldEvents {
+    stream: FiberStreamPulse,
+    pending: usize,
+}
+
+/// Extends readable single-bit field tokens with event streams.
+pub trait RegFieldEvents<T: RegTag>: RRRegFieldBit<T> + Send + 'static
+where
+    Self::Reg: RReg<T>,
+{
+    /// Returns a stream, which yields an item each time the thread `thr` finds
+    /// the flag set.
+    ///
+    /// A fiber is added to the thread, which checks the flag on every thread
+    /// invocation. When the flag is set, `clear` is called to acknowledge it.
+    /// Events, which the consumer hasn't caught up with, are not lost.
+    ///
+    /// ```
+    /// use drone_core::{
+    ///     reg::{field::WoWoRegFieldBit, prelude::*, FieldEvents, RegFieldEvents},
+    ///     thr::ThrToken,
+    /// };
+    ///
+    /// /// Returns a stream of transfer-complete events of a DMA channel. The
+    /// /// flag is cleared by writing one to `ctcif`.
+    /// fn transfers<F, C, H>(tcif: F, ctcif: C, thr: H) -> FieldEvents
+    /// where
+    ///     F: RegFieldEvents<Crt>,
+    ///     F::Reg: RReg<Crt>,
+    ///     C: WoWoRegFieldBit<Crt> + Send + 'static,
+    ///     C::Reg: WoReg<Crt>,
+    ///     H: ThrToken,
+    /// {
+    ///     tcif.events(thr, move |_| ctcif.set_bit())
+    /// }
+    /// # fn main() {}
+    /// ```
+    fn events<H, C>(self, thr: H, mut clear: C) -> FieldEvents
+    where
+        H: ThrToken,
+        C: FnMut(&Self) + Send + 'static,
+    {
+        let stream = thr.add_saturating_pulse_stream(fib::new_fn(move || {
+            if self.read_bit() {
+                clear(&self);
+                fib::Yielded(Some(1))
+            } else {
+                fib::Yielded(None)
+            }
+        }));
+        FieldEvents { stream, pending: 0 }
+    }
+}
+
+impl<T, R> RegFieldEvents<T> for R
+where
+    T: RegTag,
+    R: RRRegFieldBit<T> + Send + 'static,
+    R::Reg: RReg<T>,
+{
+}
+
+impl FieldEvents {
+    /// Gracefully close this stream.
+    ///
+    /// The fiber will be removed on a next thread invocation without resuming.
+    #[inline]
+    pub fn close(&mut self) {
+        self.stream.close();
+    }
+}
+
+impl Stream for FieldEvents {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.pending > 0 {
+            this.pending -= 1;
+            return Poll::Ready(Some(()));
+        }
+        let stream = unsafe { Pin::new_unchecked(&mut this.stream) };
+        stream.poll_next(cx).map(|pulses| {
+            pulses.map(|pulses| {
+                this.pending = pulses.get() - 1;
+            })
+        })
+    }
+}
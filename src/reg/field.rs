@@ -5,12 +5,13 @@
 use crate::{
     bitfield::{Bitfield, Bits},
     reg::{
+        mem_ptr, mem_store,
         tag::{Crt, RegAtomic, RegTag, Srt, Urt},
         RReg, Reg, WReg, WoReg,
     },
     token::Token,
 };
-use core::ptr::{read_volatile, write_volatile};
+use core::ptr::read_volatile;
 
 /// The base trait for a field token of a memory-mapped register.
 pub trait RegField<T: RegTag>: Token + Sync {
@@ -85,11 +86,8 @@ where
     /// Reads the value from the register memory to the opaque value type.
     #[inline]
     fn load_val(&self) -> <Self::Reg as Reg<T>>::Val {
-        unsafe {
-            Self::Reg::val_from(read_volatile(
-                Self::Reg::ADDRESS as *const <<Self::Reg as Reg<T>>::Val as Bitfield>::Bits,
-            ))
-        }
+        let ptr = mem_ptr::<<<Self::Reg as Reg<T>>::Val as Bitfield>::Bits>(Self::Reg::ADDRESS);
+        unsafe { Self::Reg::val_from(read_volatile(ptr)) }
     }
 }
 
@@ -246,12 +244,7 @@ where
 
     #[inline]
     fn store_val(&self, val: <Self::Reg as Reg<T>>::Val) {
-        unsafe {
-            write_volatile(
-                Self::Reg::ADDRESS as *mut <<Self::Reg as Reg<T>>::Val as Bitfield>::Bits,
-                val.bits(),
-            );
-        }
+        unsafe { mem_store(Self::Reg::ADDRESS, val.bits()) };
     }
 
     #[inline]
@@ -87,6 +87,7 @@
 //! | [`store`](field::WoWoRegField::store)               |             | write      | write-only    |
 //! | [`read`](field::RRRegFieldBit::read)                | one-bit     | read       | read          |
 //! | [`read_bit`](field::RRRegFieldBit::read_bit)        | one-bit     | read       | read          |
+//! | [`events`](RegFieldEvents::events)                  | one-bit     | read       | read          |
 //! | [`set`](field::WWRegFieldBit::set)                  | one-bit     | write      | write         |
 //! | [`clear`](field::WWRegFieldBit::clear)              | one-bit     | write      | write         |
 //! | [`toggle`](field::WWRegFieldBit::toggle)            | one-bit     | write      | write         |
@@ -183,6 +184,78 @@
 //! | Srt       | -     | **+** | **+** |
 //! | Crt       | -     | -     | **+** |
 //!
+//! # Off-chip Registers
+//!
+//! Registers of external devices, like PMICs or sensors attached over SPI or
+//! I2C, can be declared with the same [`reg!`](crate::reg!) macro, using the
+//! device register address as the `address`. Such tokens are accessed through
+//! a [`RegTransport`] or an [`AsyncRegTransport`] with [`RemoteReg`] methods,
+//! while the values are manipulated with the same typed field API. The memory
+//! access methods like [`RReg::load`] must not be used on these tokens.
+//!
+//! # Debug Views
+//!
+//! A panic or fault handler can't satisfy the normal token ownership rules, but
+//! still needs to capture the hardware state. Every readable register module
+//! generated by [`reg!`](crate::reg!) has a token-less `read_debug` function,
+//! which performs a volatile read of the register. The function is `unsafe`,
+//! because it bypasses the token ownership, so it must be called only when the
+//! owner of the token can't run:
+//!
+//! ```
+//! # use drone_core::reg::prelude::*;
+//! # drone_core::reg! {
+//! #     pub SCB CFSR => {
+//! #         address => 0xE000_ED28; size => 0x20; reset => 0; traits => { RReg RoReg };
+//! #     };
+//! # }
+//! fn panic_report() {
+//!     if let Some(cfsr) = unsafe { scb_cfsr::read_debug() } {
+//!         // Write `cfsr` to the crash log.
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Registers, for which reading has side effects, like data FIFOs or flags
+//! cleared on read, should be declared with `read_side_effects => true;`. For
+//! such registers `read_debug` returns `None` without touching the hardware.
+//! See also [`RReg::read_debug`].
+//!
+//! # Shared Registers
+//!
+//! Some registers hold fields owned by unrelated drivers, like clock enable
+//! bits of different peripherals. A driver holding the whole register token
+//! could accidentally overwrite the fields of another driver with a
+//! read-modify-write. Such registers can be declared with `shared => true;`:
+//!
+//! ```
+//! # #![feature(proc_macro_hygiene)]
+//! # use drone_core::{reg::prelude::*, token::Token};
+//! # use drone_core::reg;
+//! reg! {
+//!     pub RCC APB2ENR => {
+//!         address => 0x4002_1018; size => 0x20; reset => 0; traits => { RReg WReg };
+//!         shared => true;
+//!         fields => {
+//!             IOPAEN => { offset => 2; width => 1; traits => { RRRegField WWRegField } };
+//!             USART1EN => { offset => 14; width => 1; traits => { RRRegField WWRegField } };
+//!         };
+//!     };
+//! }
+//! # reg::tokens!(macro reg_tokens; crate; crate; pub mod RCC { APB2ENR; });
+//! # reg_tokens!(index => Regs);
+//! # fn main() {
+//! let reg = unsafe { Regs::take() };
+//! let rcc_apb2enr::Fields { iopaen, usart1en } = reg.rcc_apb2enr;
+//! # }
+//! ```
+//!
+//! The register tokens index then holds only the field tokens of the register,
+//! grouped in a `Fields` struct, and there is no safe way to obtain the
+//! register token. The fields are meant to be manipulated with atomic field
+//! operations provided by platform crates.
+//!
 //! # Mappings
 //!
 //! We define concrete register mappings in platform crates. Usually the user
@@ -212,6 +285,9 @@
 //!         //     RReg WReg  - read-write register
 //!         //     WReg WoReg - write-only register
 //!         traits => { RReg WReg };
+//!         // Optional compile-time checks against the reference manual. Here
+//!         // `mask` is the union of all field masks.
+//!         assert => { mask => 0x0000_0001 };
 //!
 //!         // Register fields.
 //!         fields => {
@@ -224,6 +300,9 @@
 //!                 //     RRRegField WWRegField  - read-write field
 //!                 //     WWRegField WoWRegField - read-write field
 //!                 traits => { RRRegField WWRegField };
+//!                 // Optional compile-time checks of the field `mask`, `offset`,
+//!                 // and `width`. A mismatch fails the build.
+//!                 assert => { mask => 0x0000_0001 };
 //!             };
 //!         };
 //!     };
@@ -299,6 +378,16 @@ pub mod marker;
 pub mod prelude;
 pub mod tag;
 
+mod dyn_field;
+mod events;
+mod remote;
+
+pub use self::{
+    dyn_field::{DynField, DynFieldMut},
+    events::{FieldEvents, RegFieldEvents},
+    remote::{AsyncRegTransport, RegTransport, RemoteReg},
+};
+
 /// A macro to define a macro to define a set of register tokens.
 ///
 /// See [the module level documentation](self) for details.
@@ -342,6 +431,10 @@ pub trait Reg<T: RegTag>: Token + Sync {
     /// The register default value.
     const RESET: <Self::Val as Bitfield>::Bits;
 
+    /// Whether reading the register has side effects, like popping a FIFO or
+    /// clearing flags. Such registers are skipped by debug views.
+    const READ_SIDE_EFFECTS: bool = false;
+
     /// Creates a new instance of [`Reg::Val`] from raw `bits`.
     ///
     /// # Safety
@@ -458,12 +551,26 @@ pub trait RReg<T: RegTag>: Reg<T> {
         unsafe { read_volatile(self.as_ptr()) }
     }
 
+    /// Reads the raw value from the register memory for a debug view.
+    ///
+    /// The read is volatile and never inlined, so it is kept intact even in a
+    /// panic handler. Returns `None` without reading if the register has
+    /// [`READ_SIDE_EFFECTS`](Reg::READ_SIDE_EFFECTS).
+    #[inline(never)]
+    fn read_debug(&self) -> Option<<Self::Val as Bitfield>::Bits> {
+        if Self::READ_SIDE_EFFECTS {
+            None
+        } else {
+            Some(self.load_bits())
+        }
+    }
+
     /// Returns a raw pointer to the register memory.
     ///
     /// See also [`as_mut_ptr`](WReg::as_mut_ptr).
     #[inline]
     fn as_ptr(&self) -> *const <Self::Val as Bitfield>::Bits {
-        Self::ADDRESS as *const <Self::Val as Bitfield>::Bits
+        mem_ptr(Self::ADDRESS)
     }
 }
 
@@ -474,7 +581,7 @@ pub trait WReg<T: RegTag>: Reg<T> {
     /// See also [`as_ptr`](RReg::as_ptr).
     #[inline]
     fn as_mut_ptr(&self) -> *mut <Self::Val as Bitfield>::Bits {
-        Self::ADDRESS as *mut <Self::Val as Bitfield>::Bits
+        mem_ptr(Self::ADDRESS)
     }
 }
 
@@ -608,7 +715,7 @@ where
         ) -> &'b mut <Self as RegRef<'a, Urt>>::Hold,
     {
         unsafe {
-            write_volatile(self.as_mut_ptr(), f(&mut self.default()).val().bits());
+            mem_store(Self::ADDRESS, f(&mut self.default()).val().bits());
         }
     }
 
@@ -629,12 +736,12 @@ where
 
     #[inline]
     fn store_bits(&mut self, bits: <Self::Val as Bitfield>::Bits) {
-        unsafe { write_volatile(self.as_mut_ptr(), bits) };
+        unsafe { mem_store(Self::ADDRESS, bits) };
     }
 
     #[inline]
     fn reset(&'a mut self) {
-        unsafe { write_volatile(self.as_mut_ptr(), self.default_val().bits()) };
+        unsafe { mem_store(Self::ADDRESS, self.default_val().bits()) };
     }
 }
 
@@ -672,7 +779,7 @@ where
 
     #[inline]
     fn store_bits(&self, bits: <Self::Val as Bitfield>::Bits) {
-        unsafe { write_volatile(self.as_mut_ptr(), bits) };
+        unsafe { mem_store(Self::ADDRESS, bits) };
     }
 
     #[inline]
@@ -693,7 +800,7 @@ where
         ) -> &'b mut <Self as RegRef<'a, Urt>>::Hold,
     {
         unsafe {
-            write_volatile(self.as_mut_ptr(), f(&mut self.load()).val().bits());
+            mem_store(Self::ADDRESS, f(&mut self.load()).val().bits());
         }
     }
 
@@ -708,7 +815,52 @@ where
     }
 }
 
+/// Returns a pointer to the register memory at `address`. With `sim` feature
+/// the memory is backed by [`sim::mem`](crate::sim::mem).
+#[inline]
+fn mem_ptr<B>(address: usize) -> *mut B {
+    #[cfg(feature = "sim")]
+    return crate::sim::mem::translate(address, core::mem::size_of::<B>()).cast();
+    #[cfg(not(feature = "sim"))]
+    {
+        address as *mut B
+    }
+}
+
+/// Writes `bits` to the register memory at `address`. With `sim` feature the
+/// write triggers the behaviors registered in
+/// [`sim::script`](crate::sim::script).
+#[inline]
+unsafe fn mem_store<B: Copy>(address: usize, bits: B) {
+    unsafe { write_volatile(mem_ptr(address), bits) };
+    #[cfg(feature = "sim")]
+    crate::sim::script::stored(address, core::mem::size_of::<B>());
+}
+
 mod compile_tests {
+    //! ```compile_fail
+    //! use drone_core::reg::prelude::*;
+    //! drone_core::reg! {
+    //!     pub FOO BAR => {
+    //!         address => 0xDEAD_BEEF; size => 0x20; reset => 0xBEEF_CACE; traits => { RReg WReg };
+    //!         fields => { BAZ => { offset => 4; width => 2; assert => { mask => 0x0000_0060 } } }
+    //!     };
+    //! }
+    //! fn main() {}
+    //! ```
+    //!
+    //! ```compile_fail
+    //! use drone_core::reg::prelude::*;
+    //! drone_core::reg! {
+    //!     pub FOO BAR => {
+    //!         address => 0xDEAD_BEEF; size => 0x20; reset => 0xBEEF_CACE; traits => { RReg WReg };
+    //!         assert => { mask => 0x0000_0003 };
+    //!         fields => { BAZ => { offset => 0; width => 1 }; QUX => { offset => 2; width => 1 } }
+    //!     };
+    //! }
+    //! fn main() {}
+    //! ```
+    //!
     //! ```compile_fail
     //! use drone_core::reg::prelude::*;
     //! drone_core::reg! {
@@ -939,6 +1091,33 @@ mod compile_tests {
     //!
     //! ```compile_fail
     //! #![feature(proc_macro_hygiene)]
+    //! use drone_core::{reg::prelude::*, token::Token};
+    //! drone_core::reg! {
+    //!     pub FOO BAR => {
+    //!         address => 0xDEAD_BEEF; size => 0x20; reset => 0xBEEF_CACE;
+    //!         traits => { RReg WReg }; shared => true;
+    //!         fields => { BAZ => { offset => 0; width => 1; traits => { RRRegField } } };
+    //!     };
+    //! }
+    //! drone_core::reg::tokens!(macro reg_tokens; crate; crate; pub mod FOO { BAR; });
+    //! reg_tokens!(index => Regs);
+    //! fn main() {
+    //!     let reg = unsafe { Regs::take() };
+    //!     reg.foo_bar.load();
+    //! }
+    //! ```
+    //!
+    //! ```compile_fail
+    //! drone_core::reg! {
+    //!     pub FOO BAR => {
+    //!         address => 0xDEAD_BEEF; size => 0x20; reset => 0xBEEF_CACE; shared => true;
+    //!     };
+    //! }
+    //! fn main() {}
+    //! ```
+    //!
+    //! ```compile_fail
+    //! #![feature(proc_macro_hygiene)]
     //! drone_core::reg::assert_taken!("foo_bar");
     //! drone_core::reg::assert_taken!(concat!("foo", "_bar"));
     //! ```
@@ -22,5 +22,6 @@ pub use crate::reg::{
         WWRegFieldBit as _, WWRegFieldBits as _, WoWoRegField as _, WoWoRegFieldBit as _,
         WoWoRegFieldBits as _,
     },
-    RegRef as _, RwRegUnsync as _, WRegAtomic as _, WRegUnsync as _,
+    RegFieldEvents as _, RegRef as _, RemoteReg as _, RwRegUnsync as _, WRegAtomic as _,
+    WRegUnsync as _,
 };
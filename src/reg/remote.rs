@@ -0,0 +1,122 @@
+use crate::{
+    bitfield::{Bitfield, Bits},
+    reg::{tag::RegTag, Reg, RegHold, RegRef},
+};
+use core::{future::Future, pin::Pin};
+
+/// A transport to registers of an off-chip device, e.g. over SPI or I2C.
+///
+/// `B` is the raw register type, usually `u8` or `u16`.
+pub trait RegTransport<B: Bits> {
+    /// The error type returned by the transport.
+    type Error;
+
+    /// Reads the register at `address`.
+    fn read(&mut self, address: usize) -> Result<B, Self::Error>;
+
+    /// Writes `bits` to the register at `address`.
+    fn write(&mut self, address: usize, bits: B) -> Result<(), Self::Error>;
+}
+
+/// An asynchronous transport to registers of an off-chip device.
+///
+/// See also [`RegTransport`].
+pub trait AsyncRegTransport<'sess, B: Bits> {
+    /// The error type returned by the transport.
+    type Error;
+
+    /// Reads the register at `address` asynchronously.
+    fn read(
+        &'sess mut self,
+        address: usize,
+    ) -> Pin<Box<dyn Future<Output = Result<B, Self::Error>> + Send + 'sess>>;
+
+    /// Writes `bits` to the register at `address` asynchronously.
+    fn write(
+        &'sess mut self,
+        address: usize,
+        bits: B,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'sess>>;
+}
+
+/// Extends register tokens with access through a [`RegTransport`] or an
+/// [`AsyncRegTransport`] instead of the memory.
+///
+/// The [`Reg::ADDRESS`] is passed to the transport as is.
+pub trait RemoteReg<T: RegTag>: Reg<T> {
+    /// Reads the register through `transport` to the exposed value type.
+    #[inline]
+    fn load_via<'a, X>(
+        &'a self,
+        transport: &mut X,
+    ) -> Result<<Self as RegRef<'a, T>>::Hold, X::Error>
+    where
+        Self: RegRef<'a, T>,
+        X: RegTransport<<Self::Val as Bitfield>::Bits>,
+    {
+        let bits = transport.read(Self::ADDRESS)?;
+        Ok(self.hold(unsafe { Self::val_from(bits) }))
+    }
+
+    /// Passes the reset value to the closure `f`, then writes the result of the
+    /// closure to the register through `transport`.
+    #[inline]
+    fn store_via<'a, X, F>(&'a self, transport: &mut X, f: F) -> Result<(), X::Error>
+    where
+        Self: RegRef<'a, T>,
+        X: RegTransport<<Self::Val as Bitfield>::Bits>,
+        F: for<'b> FnOnce(
+            &'b mut <Self as RegRef<'a, T>>::Hold,
+        ) -> &'b mut <Self as RegRef<'a, T>>::Hold,
+    {
+        transport.write(Self::ADDRESS, f(&mut self.default()).val().bits())
+    }
+
+    /// Reads the register through `transport`, passes it to the closure `f`,
+    /// then writes the result of the closure back.
+    #[inline]
+    fn modify_via<'a, X, F>(&'a self, transport: &mut X, f: F) -> Result<(), X::Error>
+    where
+        Self: RegRef<'a, T>,
+        X: RegTransport<<Self::Val as Bitfield>::Bits>,
+        F: for<'b> FnOnce(
+            &'b mut <Self as RegRef<'a, T>>::Hold,
+        ) -> &'b mut <Self as RegRef<'a, T>>::Hold,
+    {
+        let mut hold = self.load_via(transport)?;
+        transport.write(Self::ADDRESS, f(&mut hold).val().bits())
+    }
+
+    /// Reads the register through `transport` asynchronously to the opaque
+    /// value type.
+    ///
+    /// The value can be exposed with [`hold`](RegRef::hold).
+    #[inline]
+    fn load_val_async<'sess, X>(
+        &self,
+        transport: &'sess mut X,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Val, X::Error>> + Send + 'sess>>
+    where
+        X: AsyncRegTransport<'sess, <Self::Val as Bitfield>::Bits>,
+        Self: 'sess,
+    {
+        let read = transport.read(Self::ADDRESS);
+        Box::pin(async move { read.await.map(|bits| unsafe { Self::val_from(bits) }) })
+    }
+
+    /// Writes an opaque value `val` to the register through `transport`
+    /// asynchronously.
+    #[inline]
+    fn store_val_async<'sess, X>(
+        &self,
+        transport: &'sess mut X,
+        val: Self::Val,
+    ) -> Pin<Box<dyn Future<Output = Result<(), X::Error>> + Send + 'sess>>
+    where
+        X: AsyncRegTransport<'sess, <Self::Val as Bitfield>::Bits>,
+    {
+        transport.write(Self::ADDRESS, val.bits())
+    }
+}
+
+impl<T: RegTag, R: Reg<T>> RemoteReg<T> for R {}
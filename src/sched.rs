@@ -0,0 +1,157 @@
+//! Scheduling of periodic tasks.
+//!
+//! [`periodic`] returns a stream, which yields at absolute deadlines `t0 +
+//! phase + n * period`, where `t0` is the creation time. Unlike a loop of
+//! relative sleeps, the deadlines don't drift with the time spent in the loop
+//! body. If the consumer falls behind by one or more whole periods, the missed
+//! deadlines are skipped, and counted as overruns.
+//!
+//! ```
+//! use drone_core::{
+//!     sched,
+//!     time::{Alarm, Duration, Timer},
+//! };
+//! use futures::prelude::*;
+//!
+//! async fn control_loop<T: Timer>(alarm: &Alarm<T>) {
+//!     let mut ticks = sched::periodic(alarm, Duration::from_millis(1), Duration::ZERO);
+//!     while let Some(_deadline) = ticks.next().await {
+//!         // Read the sensors and update the actuators here.
+//!     }
+//! }
+//! ```
+
+use crate::time::{Alarm, Duration, Instant, Sleep, Timer};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures::stream::{FusedStream, Stream};
+
+/// A stream returned by [`periodic`].
+#[must_use = "streams do nothing unless polled"]
+pub struct Periodic<'a, T: Timer> {
+    alarm: &'a Alarm<T>,
+    period: Duration<T::Tick>,
+    next: Instant<T::Tick>,
+    sleep: Sleep<'a, T>,
+    overruns: u32,
+}
+
+/// Returns a stream of periodic deadlines driven by the `alarm`.
+///
+/// The first deadline is at `phase` from now, and the following deadlines are
+/// every `period` after it. The stream yields the deadline instants and never
+/// ends.
+///
+/// # Panics
+///
+/// If `period` is zero.
+pub fn periodic<T: Timer>(
+    alarm: &Alarm<T>,
+    period: Duration<T::Tick>,
+    phase: Duration<T::Tick>,
+) -> Periodic<'_, T> {
+    assert!(period.ticks() > 0, "period must be non-zero");
+    let next = alarm.now() + phase;
+    Periodic { alarm, period, next, sleep: alarm.sleep_until(next), overruns: 0 }
+}
+
+impl<T: Timer> Unpin for Periodic<'_, T> {}
+
+impl<T: Timer> Periodic<'_, T> {
+    /// Returns the period.
+    #[inline]
+    pub fn period(&self) -> Duration<T::Tick> {
+        self.period
+    }
+
+    /// Returns the next deadline.
+    #[inline]
+    pub fn next_deadline(&self) -> Instant<T::Tick> {
+        self.next
+    }
+
+    /// Returns the number of deadlines skipped because the consumer was late.
+    #[inline]
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
+
+impl<T: Timer> Stream for Periodic<'_, T> {
+    type Item = Instant<T::Tick>;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if Pin::new(&mut this.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let deadline = this.next;
+        let period = this.period.ticks();
+        let missed = this.alarm.now().saturating_duration_since(deadline).ticks() / period;
+        this.overruns = this.overruns.saturating_add(missed as u32);
+        this.next = deadline + Duration::from_ticks(period * (missed + 1));
+        this.sleep = this.alarm.sleep_until(this.next);
+        Poll::Ready(Some(deadline))
+    }
+}
+
+impl<T: Timer> FusedStream for Periodic<'_, T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Tick;
+    use core::cell::Cell;
+    use futures::{pin_mut, task::noop_waker_ref};
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Millis;
+
+    impl Tick for Millis {
+        const FREQ: u64 = 1_000;
+    }
+
+    struct FakeTimer(Cell<u64>);
+
+    unsafe impl Sync for FakeTimer {}
+
+    impl Timer for FakeTimer {
+        type Tick = Millis;
+
+        fn now(&self) -> Instant<Millis> {
+            Instant::from_ticks(self.0.get())
+        }
+
+        fn schedule(&self, _at: Instant<Millis>) {}
+
+        fn cancel(&self) {}
+    }
+
+    #[test]
+    fn drift_free() {
+        let alarm = Alarm::new(FakeTimer(Cell::new(100)));
+        let ticks = periodic(&alarm, Duration::from_millis(10), Duration::from_millis(5));
+        pin_mut!(ticks);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut poll_at = |now| {
+            alarm.timer().0.set(now);
+            ticks.as_mut().poll_next(&mut cx).map(|deadline| deadline.unwrap().ticks())
+        };
+        assert_eq!(poll_at(104), Poll::Pending);
+        assert_eq!(poll_at(107), Poll::Ready(105));
+        assert_eq!(poll_at(114), Poll::Pending);
+        assert_eq!(poll_at(118), Poll::Ready(115));
+        assert_eq!(poll_at(147), Poll::Ready(125));
+        assert_eq!(ticks.overruns(), 2);
+        assert_eq!(ticks.next_deadline().ticks(), 155);
+    }
+}
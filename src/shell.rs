@@ -0,0 +1,249 @@
+//! An extensible command shell.
+//!
+//! Commands are registered from any crate with the
+//! [`shell::command!`](crate::shell::command) macro. A [`Shell`] accumulates
+//! input bytes into a line buffer, parses the line into a command name and
+//! arguments, and runs the command writing its output to a log [`Port`]. The
+//! input can come from any source: a log down-channel, a UART, or any
+//! [`io::Read`](crate::io::Read) implementation with [`Shell::run`].
+//!
+//! The linker script must keep the `.drone_shell_commands` section and surround
+//! it with `DRONE_SHELL_COMMANDS_START` and `DRONE_SHELL_COMMANDS_END` symbols.
+//!
+//! ```
+//! use core::fmt::Write;
+//! use drone_core::{
+//!     log::Port,
+//!     shell::{self, Args, Error},
+//! };
+//!
+//! fn peek(args: &mut Args<'_>, out: &mut Port) -> Result<(), Error> {
+//!     let address: usize = args.parse()?;
+//!     let value = unsafe { core::ptr::read_volatile(address as *const u32) };
+//!     writeln!(out, "{:#010X}", value).ok();
+//!     Ok(())
+//! }
+//!
+//! shell::command!("peek", "peek <address> - reads a word from memory", peek);
+//! # fn main() {}
+//! ```
+
+use crate::{io, log::Port};
+use core::{cell::UnsafeCell, fmt, fmt::Write, mem::size_of, slice, str, str::FromStr};
+
+extern "C" {
+    static DRONE_SHELL_COMMANDS_START: UnsafeCell<usize>;
+    static DRONE_SHELL_COMMANDS_END: UnsafeCell<usize>;
+}
+
+/// A shell command registered with [`shell::command!`](crate::shell::command).
+pub struct Command {
+    /// The command name.
+    pub name: &'static str,
+    /// A one-line usage description.
+    pub help: &'static str,
+    /// The command handler.
+    pub run: fn(&mut Args<'_>, &mut Port) -> Result<(), Error>,
+}
+
+/// Arguments of a command.
+pub struct Args<'a> {
+    words: str::SplitWhitespace<'a>,
+}
+
+/// A command error.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// A required argument is missing.
+    MissingArgument,
+    /// An argument can't be parsed.
+    InvalidArgument,
+    /// The command failed with the given message.
+    Failed(&'static str),
+}
+
+/// A line-oriented command interpreter with an input buffer of `N` bytes.
+pub struct Shell<const N: usize> {
+    port: Port,
+    buffer: [u8; N],
+    length: usize,
+    overflow: bool,
+}
+
+/// Registers a shell command.
+///
+/// See [the module-level documentation](self) for details.
+#[doc(inline)]
+pub use crate::__shell_command as command;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __shell_command {
+    ($name:expr, $help:expr, $run:path $(,)?) => {
+        const _: () = {
+            #[used]
+            #[link_section = ".drone_shell_commands"]
+            static COMMAND: $crate::shell::Command =
+                $crate::shell::Command { name: $name, help: $help, run: $run };
+        };
+    };
+}
+
+/// Returns an iterator over all registered commands.
+pub fn commands() -> impl Iterator<Item = &'static Command> {
+    let commands = unsafe {
+        let count = (DRONE_SHELL_COMMANDS_END.get() as usize
+            - DRONE_SHELL_COMMANDS_START.get() as usize)
+            / size_of::<Command>();
+        slice::from_raw_parts(DRONE_SHELL_COMMANDS_START.get().cast::<Command>(), count)
+    };
+    commands.iter()
+}
+
+impl<'a> Args<'a> {
+    /// Creates arguments by splitting `line` by whitespace.
+    #[inline]
+    pub fn new(line: &'a str) -> Self {
+        Self { words: line.split_whitespace() }
+    }
+
+    /// Returns the next argument, if any.
+    #[inline]
+    pub fn next_opt(&mut self) -> Option<&'a str> {
+        self.words.next()
+    }
+
+    /// Returns the next argument.
+    #[inline]
+    pub fn next_str(&mut self) -> Result<&'a str, Error> {
+        self.next_opt().ok_or(Error::MissingArgument)
+    }
+
+    /// Parses the next argument.
+    #[inline]
+    pub fn parse<T: FromStr>(&mut self) -> Result<T, Error> {
+        self.next_str()?.parse().map_err(|_| Error::InvalidArgument)
+    }
+
+    /// Parses the next argument as an unsigned integer. The argument can be
+    /// decimal, or hexadecimal with `0x` prefix, or binary with `0b` prefix.
+    pub fn parse_int(&mut self) -> Result<u64, Error> {
+        let arg = self.next_str()?;
+        let result = if let Some(hex) = arg.strip_prefix("0x") {
+            u64::from_str_radix(hex, 16)
+        } else if let Some(bin) = arg.strip_prefix("0b") {
+            u64::from_str_radix(bin, 2)
+        } else {
+            arg.parse()
+        };
+        result.map_err(|_| Error::InvalidArgument)
+    }
+}
+
+impl<const N: usize> Shell<N> {
+    /// Creates a new shell writing its output to the `port`.
+    #[inline]
+    pub const fn new(port: Port) -> Self {
+        Self { port, buffer: [0; N], length: 0, overflow: false }
+    }
+
+    /// Feeds an input `byte` to the shell.
+    ///
+    /// A line feed or carriage return executes the accumulated line. Backspace
+    /// and delete characters remove the last byte.
+    pub fn feed(&mut self, byte: u8) {
+        match byte {
+            b'\r' | b'\n' => {
+                let length = self.length;
+                self.length = 0;
+                if self.overflow {
+                    self.overflow = false;
+                    writeln!(self.port, "error: line too long").ok();
+                } else if length > 0 {
+                    match str::from_utf8(&self.buffer[..length]) {
+                        Ok(line) => execute(self.port, line),
+                        Err(_) => drop(writeln!(self.port, "error: invalid UTF-8")),
+                    }
+                }
+            }
+            0x08 | 0x7F => self.length = self.length.saturating_sub(1),
+            _ if self.length < N => {
+                self.buffer[self.length] = byte;
+                self.length += 1;
+            }
+            _ => self.overflow = true,
+        }
+    }
+
+    /// Feeds all `bytes` to the shell.
+    #[inline]
+    pub fn feed_bytes(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|&byte| self.feed(byte));
+    }
+
+    /// Reads input from the `reader` and executes commands until the reader
+    /// returns an error or end of input.
+    pub async fn run<R>(&mut self, reader: &mut R) -> Result<(), R::Error>
+    where
+        R: for<'sess> io::Read<'sess, u8, &'sess mut [u8]>,
+    {
+        let mut chunk = [0; 16];
+        loop {
+            let count = reader.read(&mut chunk[..]).await?;
+            if count == 0 {
+                return Ok(());
+            }
+            self.feed_bytes(&chunk[..count]);
+        }
+    }
+}
+
+/// Executes a command `line` writing the output to the `port`.
+///
+/// The built-in `help` command lists all registered commands.
+pub fn execute(mut port: Port, line: &str) {
+    let mut args = Args::new(line);
+    let name = match args.next_opt() {
+        Some(name) => name,
+        None => return,
+    };
+    if name == "help" {
+        for command in commands() {
+            writeln!(port, "{:<12}{}", command.name, command.help).ok();
+        }
+        return;
+    }
+    match commands().find(|command| command.name == name) {
+        Some(command) => {
+            if let Err(err) = (command.run)(&mut args, &mut port) {
+                writeln!(port, "error: {}", err).ok();
+            }
+        }
+        None => drop(writeln!(port, "error: unknown command `{}`", name)),
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingArgument => f.write_str("missing argument"),
+            Self::InvalidArgument => f.write_str("invalid argument"),
+            Self::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn args() {
+        let mut args = Args::new("  0x10 0b101  42 foo ");
+        assert_eq!(args.parse_int(), Ok(16));
+        assert_eq!(args.parse_int(), Ok(5));
+        assert_eq!(args.parse::<u8>(), Ok(42));
+        assert_eq!(args.parse::<u8>(), Err(Error::InvalidArgument));
+        assert_eq!(args.next_str(), Err(Error::MissingArgument));
+    }
+}
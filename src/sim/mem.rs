@@ -0,0 +1,98 @@
+//! Emulated address space of the MCU.
+//!
+//! The memory is allocated lazily in pages of [`PAGE_SIZE`] bytes, which are
+//! zero-initialized. Register tokens access this memory with `sim` feature.
+//! [`reset_reg`] loads the reset value of a register.
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use drone_core::sim::mem;
+//!
+//! // Pretend that the peripheral has finished the conversion.
+//! mem::write::<u32>(0x4001_2400, 1 << 1);
+//! assert_eq!(mem::read::<u32>(0x4001_2400), 0b10);
+//! # }
+//! ```
+
+use crate::{
+    bitfield::Bitfield,
+    reg::{tag::RegTag, Reg},
+    thr::critical,
+};
+use core::{cell::UnsafeCell, mem::size_of, ptr};
+
+/// The size of an emulated memory page.
+pub const PAGE_SIZE: usize = 0x1000;
+
+struct Pages(UnsafeCell<Vec<(usize, *mut u8)>>);
+
+unsafe impl Sync for Pages {}
+
+static PAGES: Pages = Pages(UnsafeCell::new(Vec::new()));
+
+/// Returns a host pointer to `size` bytes of the emulated memory at `address`.
+///
+/// # Panics
+///
+/// If `size` is not a power of two up to 8, or `address` is not aligned to
+/// `size`.
+pub fn translate(address: usize, size: usize) -> *mut u8 {
+    assert!(size.is_power_of_two() && size <= 8, "unsupported access size");
+    assert!(address % size == 0, "misaligned access");
+    let page = address / PAGE_SIZE;
+    let base = critical(|_| {
+        let pages = unsafe { &mut *PAGES.0.get() };
+        match pages.binary_search_by_key(&page, |&(page, _)| page) {
+            Ok(index) => pages[index].1,
+            Err(index) => {
+                let memory = Box::leak(Box::new([0_u64; PAGE_SIZE / 8]));
+                let base = memory.as_mut_ptr().cast::<u8>();
+                pages.insert(index, (page, base));
+                base
+            }
+        }
+    });
+    unsafe { base.add(address % PAGE_SIZE) }
+}
+
+/// Reads a value of type `T` from the emulated memory at `address`.
+#[inline]
+pub fn read<T: Copy>(address: usize) -> T {
+    unsafe { ptr::read_volatile(translate(address, size_of::<T>()).cast()) }
+}
+
+/// Writes a value of type `T` to the emulated memory at `address`.
+#[inline]
+pub fn write<T: Copy>(address: usize, value: T) {
+    unsafe { ptr::write_volatile(translate(address, size_of::<T>()).cast(), value) };
+}
+
+/// Writes the reset value of the register `R` to the emulated memory.
+#[inline]
+pub fn reset_reg<T: RegTag, R: Reg<T>>() {
+    write::<<R::Val as Bitfield>::Bits>(R::ADDRESS, R::RESET);
+}
+
+/// Fills all allocated pages with zeros.
+pub fn clear() {
+    critical(|_| {
+        let pages = unsafe { &*PAGES.0.get() };
+        for &(_, base) in pages {
+            unsafe { ptr::write_bytes(base, 0, PAGE_SIZE) };
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages() {
+        write::<u32>(0x2000_0FFC, 0xDEAD_BEEF);
+        write::<u16>(0x2000_1000, 0xCAFE);
+        assert_eq!(read::<u16>(0x2000_0FFE), 0xDEAD);
+        assert_eq!(read::<u8>(0x2000_1001), 0xCA);
+        assert_eq!(read::<u32>(0x4000_0000), 0);
+    }
+}
@@ -0,0 +1,58 @@
+//! Simulation of a Drone application on the host.
+//!
+//! With `sim` feature, the application logic can run and be debugged on a PC
+//! with the same code that runs on the MCU:
+//!
+//! * Register tokens access [`mem`], a host memory emulating the address space
+//!   of the MCU, instead of the real addresses. A test harness can read and
+//!   write the emulated registers with [`mem::read`] and [`mem::write`] to
+//!   play the role of the peripherals. Hardware handshakes can be scripted
+//!   with [`script`], which runs actions on register writes.
+//! * Each thread of a pool is backed by a host thread, which is started with
+//!   [`start`]. Waking up a thread with [`pend`] signals its host thread, which
+//!   resumes the fiber chain.
+//!
+//! The platform threads are pended by the platform's [`ThrExec`] implementation
+//! for the thread tokens, which is replaced in simulation as follows:
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use core::task::Waker;
+//! use drone_core::{
+//!     sim,
+//!     thr::{self, ThrExec, ThrToken},
+//! };
+//!
+//! thr::pool! {
+//!     thread => pub Thr {};
+//!     local => pub ThrLocal {};
+//!     index => pub Thrs;
+//!     threads => { pub sys_tick; };
+//! }
+//!
+//! impl ThrExec for SysTick {
+//!     fn wakeup(self) {
+//!         sim::pend::<Thr>(Self::THR_IDX);
+//!     }
+//!
+//!     fn waker(self) -> Waker {
+//!         unsafe { thr::static_waker::<sim::SimWake<Thr>>(Self::THR_IDX) }
+//!     }
+//! }
+//!
+//! sim::start::<Thr>();
+//! # }
+//! ```
+//!
+//! # Limitations
+//!
+//! The simulation doesn't model priorities and preemption. Threads run one at a
+//! time, each resuming its fiber chain to completion, which is the same
+//! behavior as threads with equal priorities on the MCU.
+
+pub mod mem;
+pub mod script;
+
+mod thr;
+
+pub use self::thr::{exclusive, pend, start, SimWake};
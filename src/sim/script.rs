@@ -0,0 +1,274 @@
+//! Scripted behaviors of the emulated peripherals.
+//!
+//! A behavior is an action, which is triggered by a register token writing a
+//! matching value to the emulated register. The action can run immediately or
+//! after a number of simulated ticks, which are advanced by the test harness
+//! with [`tick`]. This way host tests can simulate hardware handshakes and
+//! drive driver state machines to completion.
+//!
+//! Only writes made through register tokens trigger behaviors. Writes made by
+//! the harness itself with [`mem::write`] don't.
+//!
+//! A behavior stays registered while the [`Behavior`] handle returned by
+//! [`on_store`] is alive, so tests running in parallel don't interfere as long
+//! as they script different registers.
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use drone_core::sim::{mem, script};
+//!
+//! const CR: usize = 0x4000_0000;
+//! const SR: usize = 0x4000_0004;
+//! const CR_EN: u64 = 1 << 0;
+//! const SR_RDY: u32 = 1 << 3;
+//!
+//! // When CR.EN is written 1, set SR.RDY after 2 ticks.
+//! let ready = script::on_store(CR, CR_EN, CR_EN, 2, || {
+//!     mem::write::<u32>(SR, mem::read::<u32>(SR) | SR_RDY);
+//! });
+//! // The behavior is removed here.
+//! drop(ready);
+//! # }
+//! ```
+
+use super::mem;
+use crate::thr::critical;
+use core::cell::UnsafeCell;
+
+/// A handle of a behavior registered with [`on_store`].
+///
+/// The behavior and its scheduled actions are removed when the handle is
+/// dropped.
+#[must_use = "the behavior is removed immediately if unused"]
+pub struct Behavior {
+    id: usize,
+}
+
+struct Entry {
+    id: usize,
+    address: usize,
+    mask: u64,
+    value: u64,
+    delay: u32,
+    action: Option<Box<dyn FnMut() + Send>>,
+    retriggers: u32,
+}
+
+struct Script {
+    next_id: usize,
+    entries: Vec<Entry>,
+    scheduled: Vec<(u32, usize)>,
+}
+
+struct Global(UnsafeCell<Script>);
+
+unsafe impl Sync for Global {}
+
+static SCRIPT: Global =
+    Global(UnsafeCell::new(Script { next_id: 0, entries: Vec::new(), scheduled: Vec::new() }));
+
+/// Registers a behavior for the register at `address`.
+///
+/// When a register token stores a value `bits` to the register such that
+/// `bits & mask == value`, the `action` is scheduled to run after `delay`
+/// ticks. If `delay` is zero, the `action` runs immediately on the thread that
+/// made the write.
+///
+/// If the `action` itself triggers its own behavior with zero `delay`, the
+/// nested run is queued, and the `action` runs again right after it returns.
+///
+/// The behavior is removed when the returned handle is dropped.
+pub fn on_store<F>(address: usize, mask: u64, value: u64, delay: u32, action: F) -> Behavior
+where
+    F: FnMut() + Send + 'static,
+{
+    critical(|_| {
+        let script = unsafe { &mut *SCRIPT.0.get() };
+        let id = script.next_id;
+        script.next_id += 1;
+        script.entries.push(Entry {
+            id,
+            address,
+            mask,
+            value,
+            delay,
+            action: Some(Box::new(action)),
+            retriggers: 0,
+        });
+        Behavior { id }
+    })
+}
+
+/// Advances the simulated time by `ticks`, running the actions which become
+/// due in the order they were scheduled.
+pub fn tick(ticks: u32) {
+    for _ in 0..ticks {
+        let due = critical(|_| {
+            let script = unsafe { &mut *SCRIPT.0.get() };
+            let mut due = Vec::new();
+            let mut i = 0;
+            while i < script.scheduled.len() {
+                let (delay, id) = &mut script.scheduled[i];
+                *delay -= 1;
+                if *delay == 0 {
+                    due.push(*id);
+                    script.scheduled.remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+            due
+        });
+        for id in due {
+            run(id);
+        }
+    }
+}
+
+/// Returns the number of actions scheduled but not yet run.
+pub fn pending() -> usize {
+    critical(|_| unsafe { &*SCRIPT.0.get() }.scheduled.len())
+}
+
+impl Behavior {
+    /// Returns the number of actions of this behavior scheduled but not yet
+    /// run.
+    pub fn pending(&self) -> usize {
+        critical(|_| {
+            let script = unsafe { &*SCRIPT.0.get() };
+            script.scheduled.iter().filter(|&&(_, id)| id == self.id).count()
+        })
+    }
+}
+
+impl Drop for Behavior {
+    fn drop(&mut self) {
+        let entry = critical(|_| {
+            let script = unsafe { &mut *SCRIPT.0.get() };
+            script.scheduled.retain(|&(_, id)| id != self.id);
+            let index = script.entries.iter().position(|entry| entry.id == self.id)?;
+            Some(script.entries.swap_remove(index))
+        });
+        // The action is dropped outside of the critical section.
+        drop(entry);
+    }
+}
+
+pub(crate) fn stored(address: usize, size: usize) {
+    let bits = match size {
+        1 => mem::read::<u8>(address).into(),
+        2 => mem::read::<u16>(address).into(),
+        4 => mem::read::<u32>(address).into(),
+        _ => mem::read::<u64>(address),
+    };
+    let due = critical(|_| {
+        let script = unsafe { &mut *SCRIPT.0.get() };
+        let mut due = Vec::new();
+        for entry in &script.entries {
+            if entry.address == address && bits & entry.mask == entry.value {
+                if entry.delay == 0 {
+                    due.push(entry.id);
+                } else {
+                    script.scheduled.push((entry.delay, entry.id));
+                }
+            }
+        }
+        due
+    });
+    for id in due {
+        run(id);
+    }
+}
+
+// Runs the action outside of the critical section, so it can access the
+// emulated memory and trigger other behaviors. If the action is already
+// running up the stack, the run is queued for it instead.
+fn run(id: usize) {
+    loop {
+        let action = critical(|_| {
+            let script = unsafe { &mut *SCRIPT.0.get() };
+            let entry = script.entries.iter_mut().find(|entry| entry.id == id)?;
+            let action = entry.action.take();
+            if action.is_none() {
+                entry.retriggers += 1;
+            }
+            action
+        });
+        let mut action = match action {
+            Some(action) => action,
+            None => return,
+        };
+        action();
+        let (action, again) = critical(|_| {
+            let script = unsafe { &mut *SCRIPT.0.get() };
+            match script.entries.iter_mut().find(|entry| entry.id == id) {
+                Some(entry) => {
+                    entry.action = Some(action);
+                    let again = entry.retriggers > 0;
+                    entry.retriggers = entry.retriggers.saturating_sub(1);
+                    (None, again)
+                }
+                // The behavior was removed by the action.
+                None => (Some(action), false),
+            }
+        });
+        drop(action);
+        if !again {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    const CR: usize = 0x4000_1000;
+    const SR: usize = 0x4000_1004;
+    const DR: usize = 0x4000_1008;
+
+    #[test]
+    fn handshake() {
+        let behavior = on_store(CR, 1, 1, 2, || {
+            mem::write::<u32>(SR, mem::read::<u32>(SR) | 1 << 3);
+        });
+        mem::write::<u32>(CR, 0);
+        stored(CR, 4);
+        assert_eq!(behavior.pending(), 0);
+        mem::write::<u32>(CR, 1);
+        stored(CR, 4);
+        assert_eq!(behavior.pending(), 1);
+        tick(1);
+        assert_eq!(mem::read::<u32>(SR), 0);
+        tick(1);
+        assert_eq!(mem::read::<u32>(SR), 1 << 3);
+        assert_eq!(behavior.pending(), 0);
+        mem::write::<u32>(SR, 0);
+        stored(CR, 4);
+        assert_eq!(behavior.pending(), 1);
+        drop(behavior);
+        stored(CR, 4);
+        tick(2);
+        assert_eq!(mem::read::<u32>(SR), 0);
+    }
+
+    #[test]
+    fn retrigger() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let behavior = on_store(DR, 0, 0, 0, {
+            let runs = Arc::clone(&runs);
+            move || {
+                if runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                    stored(DR, 4);
+                }
+            }
+        });
+        stored(DR, 4);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        drop(behavior);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::thr::{critical, StaticWake, Thread};
+use alloc::sync::Arc;
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::{
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+/// A [`StaticWake`] implementation, which pends threads of the pool `T` with
+/// [`pend`].
+pub struct SimWake<T: Thread>(PhantomData<T>);
+
+struct Signal {
+    pending: Mutex<bool>,
+    condvar: Condvar,
+}
+
+struct Pools(UnsafeCell<Vec<(usize, Vec<Arc<Signal>>)>>);
+
+struct Cpu;
+
+unsafe impl Sync for Pools {}
+
+static POOLS: Pools = Pools(UnsafeCell::new(Vec::new()));
+
+static CPU: AtomicBool = AtomicBool::new(false);
+
+/// Starts a host thread for each thread of the pool `T`.
+///
+/// # Panics
+///
+/// If the pool `T` is already started.
+pub fn start<T: Thread>() {
+    let signals = (0..T::COUNT)
+        .map(|thr_idx| {
+            let signal = Arc::new(Signal { pending: Mutex::new(false), condvar: Condvar::new() });
+            let host_signal = Arc::clone(&signal);
+            thread::Builder::new()
+                .name(format!("drone-thr-{}", thr_idx))
+                .spawn(move || run::<T>(thr_idx, &host_signal))
+                .expect("failed to spawn a host thread");
+            signal
+        })
+        .collect();
+    critical(|_| {
+        let pools = unsafe { &mut *POOLS.0.get() };
+        assert!(
+            pools.iter().all(|&(key, _)| key != pool_key::<T>()),
+            "thread pool is already started"
+        );
+        pools.push((pool_key::<T>(), signals));
+    });
+}
+
+/// Pends the thread number `thr_idx` of the pool `T`.
+///
+/// # Panics
+///
+/// * If the pool `T` is not started with [`start`].
+/// * If `thr_idx` is greater than or equals to [`Thread::COUNT`].
+pub fn pend<T: Thread>(thr_idx: u16) {
+    let signal = critical(|_| {
+        let pools = unsafe { &*POOLS.0.get() };
+        pools
+            .iter()
+            .find(|&&(key, _)| key == pool_key::<T>())
+            .map(|(_, signals)| Arc::clone(&signals[usize::from(thr_idx)]))
+    });
+    let signal = signal.expect("thread pool is not started");
+    *signal.pending.lock().unwrap() = true;
+    signal.condvar.notify_one();
+}
+
+/// Runs `f` while no simulated thread is running.
+///
+/// The host thread, which plays the role of the reset handler, should wrap
+/// the code touching the shared state with this function.
+pub fn exclusive<R>(f: impl FnOnce() -> R) -> R {
+    let _cpu = Cpu::acquire();
+    f()
+}
+
+impl<T: Thread> StaticWake for SimWake<T> {
+    #[inline]
+    unsafe fn wake(thr_idx: u16) {
+        pend::<T>(thr_idx);
+    }
+}
+
+impl Cpu {
+    fn acquire() -> Self {
+        while CPU.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err()
+        {
+            thread::yield_now();
+        }
+        Self
+    }
+}
+
+impl Drop for Cpu {
+    fn drop(&mut self) {
+        CPU.store(false, Ordering::Release);
+    }
+}
+
+fn run<T: Thread>(thr_idx: u16, signal: &Signal) -> ! {
+    loop {
+        let mut pending = signal.pending.lock().unwrap();
+        while !*pending {
+            pending = signal.condvar.wait(pending).unwrap();
+        }
+        *pending = false;
+        drop(pending);
+        let _cpu = Cpu::acquire();
+        unsafe { T::call(thr_idx, T::resume) };
+    }
+}
+
+fn pool_key<T: Thread>() -> usize {
+    T::pool() as usize
+}
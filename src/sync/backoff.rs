@@ -0,0 +1,178 @@
+//! Exponential backoff for spin loops.
+
+use core::hint;
+
+const SPIN_LIMIT: u32 = 6;
+const WAIT_LIMIT: u32 = 10;
+
+/// Performs exponential backoff in spin loops.
+///
+/// [`spin`](Backoff::spin) is meant for retrying a failed compare-and-swap
+/// operation, when the other party is making progress. [`snooze`](Backoff::snooze)
+/// is meant for waiting until the other party releases a resource; after a few
+/// rounds of spinning it calls the platform wait hook, which can put the core
+/// to sleep until an event, e.g. with `WFE` instruction.
+///
+/// The platform hooks are registered with the
+/// [`set_backoff_wait!`](crate::set_backoff_wait) macro:
+///
+/// ```
+/// use drone_core::{set_backoff_wait, sync::BackoffWait};
+///
+/// pub struct Wfe;
+///
+/// impl BackoffWait for Wfe {
+///     fn wait() {
+///         // Execute `WFE` instruction here.
+///     }
+///
+///     fn notify() {
+///         // Execute `SEV` instruction here.
+///     }
+/// }
+///
+/// set_backoff_wait!(Wfe);
+/// # fn main() {}
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+/// use drone_core::sync::Backoff;
+///
+/// fn fetch_mul(counter: &AtomicUsize, x: usize) -> usize {
+///     let mut backoff = Backoff::new();
+///     let mut curr = counter.load(Ordering::Relaxed);
+///     loop {
+///         match counter.compare_exchange_weak(curr, curr * x, Ordering::AcqRel, Ordering::Relaxed)
+///         {
+///             Ok(prev) => break prev,
+///             Err(next) => curr = next,
+///         }
+///         backoff.spin();
+///     }
+/// }
+/// # assert_eq!(fetch_mul(&AtomicUsize::new(3), 2), 3);
+/// ```
+#[derive(Default, Debug)]
+pub struct Backoff {
+    step: u32,
+}
+
+/// Platform hooks for [`Backoff::snooze`].
+pub trait BackoffWait {
+    /// Waits for an event or an interrupt.
+    fn wait();
+
+    /// Signals an event to cores waiting in [`wait`](BackoffWait::wait).
+    fn notify();
+}
+
+impl Backoff {
+    /// Creates a new backoff state.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { step: 0 }
+    }
+
+    /// Resets the backoff state.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Backs off in a lock-free loop.
+    ///
+    /// Spins for an exponentially growing number of iterations, bounded by a
+    /// small limit.
+    #[inline]
+    pub fn spin(&mut self) {
+        for _ in 0..1 << self.step.min(SPIN_LIMIT) {
+            hint::spin_loop();
+        }
+        if self.step <= SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+
+    /// Backs off in a blocking loop.
+    ///
+    /// Spins for a few rounds, then calls the platform wait hook.
+    #[inline]
+    pub fn snooze(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                hint::spin_loop();
+            }
+        } else {
+            drone_backoff_wait();
+        }
+        if self.step <= WAIT_LIMIT {
+            self.step += 1;
+        }
+    }
+
+    /// Returns `true` if the backoff has moved to the platform wait hook.
+    #[inline]
+    pub fn is_completed(&self) -> bool {
+        self.step > SPIN_LIMIT
+    }
+
+    /// Wakes up cores waiting in [`snooze`](Backoff::snooze).
+    ///
+    /// Should be called after releasing a resource, which other cores may wait
+    /// for.
+    #[inline]
+    pub fn notify() {
+        drone_backoff_notify();
+    }
+}
+
+/// Registers `$hooks` type as the platform wait hooks for [`Backoff`].
+///
+/// The type must implement [`BackoffWait`].
+///
+/// [`Backoff`]: crate::sync::Backoff
+/// [`BackoffWait`]: crate::sync::BackoffWait
+#[macro_export]
+macro_rules! set_backoff_wait {
+    ($hooks:ty) => {
+        #[no_mangle]
+        fn drone_backoff_wait() {
+            <$hooks as $crate::sync::BackoffWait>::wait()
+        }
+
+        #[no_mangle]
+        fn drone_backoff_notify() {
+            <$hooks as $crate::sync::BackoffWait>::notify()
+        }
+    };
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_backoff_wait() {
+    hint::spin_loop();
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_backoff_notify() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes() {
+        let mut backoff = Backoff::new();
+        for _ in 0..=SPIN_LIMIT {
+            assert!(!backoff.is_completed());
+            backoff.snooze();
+        }
+        assert!(backoff.is_completed());
+        backoff.reset();
+        assert!(!backoff.is_completed());
+    }
+}
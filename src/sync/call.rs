@@ -0,0 +1,402 @@
+//! A channel for calling a service running on another thread and awaiting its
+//! response.
+//!
+//! See [`channel`] constructor for more.
+
+use alloc::sync::Arc;
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicU8, Ordering},
+    task::{Context, Poll},
+};
+use futures::task::AtomicWaker;
+
+const IDLE: u8 = 0;
+const REQUEST: u8 = 1;
+const SERVING: u8 = 2;
+const RESPONSE: u8 = 3;
+const REJECTED: u8 = 4;
+const PHASE_MASK: u8 = 0b111;
+const ABANDONED: u8 = 1 << 3;
+const CALLER_CLOSED: u8 = 1 << 4;
+const SERVER_CLOSED: u8 = 1 << 5;
+
+/// Creates a new call channel, returning the caller/server halves.
+///
+/// The [`Caller`] half sends a request and awaits a response, which is
+/// produced by a fiber owning the [`Server`] half, possibly on a thread of
+/// different priority. Only one call can be in flight at a time. The request
+/// and response slots are allocated once here and reused by all subsequent
+/// calls.
+///
+/// ```
+/// # use futures::{pin_mut, task::noop_waker_ref};
+/// # use std::{future::Future, task::{Context, Poll}};
+/// use drone_core::sync::call;
+///
+/// let (mut caller, mut server) = call::channel::<u32, u32>();
+/// # let mut cx = Context::from_waker(noop_waker_ref());
+///
+/// // On the requester side.
+/// let call = caller.call(2);
+/// pin_mut!(call);
+/// assert!(call.as_mut().poll(&mut cx).is_pending());
+///
+/// // On the server side.
+/// let recv = server.recv();
+/// pin_mut!(recv);
+/// if let Poll::Ready(Ok((request, responder))) = recv.poll(&mut cx) {
+///     responder.respond(request * 10);
+/// }
+///
+/// // Back on the requester side.
+/// assert_eq!(call.poll(&mut cx), Poll::Ready(Ok(20)));
+/// ```
+#[inline]
+pub fn channel<Req, Resp>() -> (Caller<Req, Resp>, Server<Req, Resp>) {
+    let inner = Arc::new(Inner::new());
+    let caller = Caller { inner: Arc::clone(&inner) };
+    let server = Server { inner };
+    (caller, server)
+}
+
+/// The requesting half of a call channel.
+///
+/// This half can be created with the [`channel`] function.
+pub struct Caller<Req, Resp> {
+    inner: Arc<Inner<Req, Resp>>,
+}
+
+/// The serving half of a call channel.
+///
+/// This half can be created with the [`channel`] function.
+pub struct Server<Req, Resp> {
+    inner: Arc<Inner<Req, Resp>>,
+}
+
+/// A future returned by [`Caller::call`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Call<'a, Req, Resp> {
+    caller: &'a mut Caller<Req, Resp>,
+    request: Option<Req>,
+    done: bool,
+}
+
+/// A future returned by [`Server::recv`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Recv<'a, Req, Resp> {
+    server: Option<&'a mut Server<Req, Resp>>,
+}
+
+/// A handle for responding to a received request.
+///
+/// If the responder is dropped without calling [`Responder::respond`], the
+/// corresponding call resolves with [`Canceled`].
+#[must_use = "the call is canceled if the responder is dropped"]
+pub struct Responder<'a, Req, Resp> {
+    server: &'a mut Server<Req, Resp>,
+}
+
+/// Error returned when the other half of a call channel is dropped, or the
+/// request is rejected.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Canceled;
+
+struct Inner<Req, Resp> {
+    state: AtomicU8,
+    request: UnsafeCell<Option<Req>>,
+    response: UnsafeCell<Option<Resp>>,
+    caller_waker: AtomicWaker,
+    server_waker: AtomicWaker,
+}
+
+unsafe impl<Req: Send, Resp: Send> Send for Inner<Req, Resp> {}
+unsafe impl<Req: Send, Resp: Send> Sync for Inner<Req, Resp> {}
+
+impl<Req, Resp> Caller<Req, Resp> {
+    /// Sends the `request` to the server and returns a future, which resolves
+    /// with the response.
+    ///
+    /// If the previous call was abandoned while the server was processing it,
+    /// the request is sent only after the server finishes with the previous
+    /// one.
+    #[inline]
+    pub fn call(&mut self, request: Req) -> Call<'_, Req, Resp> {
+        Call { caller: self, request: Some(request), done: false }
+    }
+
+    /// Returns `true` if the server half is dropped.
+    #[inline]
+    pub fn is_canceled(&self) -> bool {
+        self.inner.state.load(Ordering::Relaxed) & SERVER_CLOSED != 0
+    }
+}
+
+impl<Req, Resp> Drop for Caller<Req, Resp> {
+    fn drop(&mut self) {
+        self.inner.state.fetch_or(CALLER_CLOSED, Ordering::AcqRel);
+        self.inner.server_waker.wake();
+    }
+}
+
+impl<Req, Resp> Server<Req, Resp> {
+    /// Returns a future, which resolves with the next request and a
+    /// [`Responder`] for it.
+    ///
+    /// The future resolves with [`Canceled`] if the caller half is dropped.
+    #[inline]
+    pub fn recv(&mut self) -> Recv<'_, Req, Resp> {
+        Recv { server: Some(self) }
+    }
+
+    /// Returns `true` if the caller half is dropped.
+    #[inline]
+    pub fn is_canceled(&self) -> bool {
+        self.inner.state.load(Ordering::Relaxed) & CALLER_CLOSED != 0
+    }
+}
+
+impl<Req, Resp> Drop for Server<Req, Resp> {
+    fn drop(&mut self) {
+        self.inner.state.fetch_or(SERVER_CLOSED, Ordering::AcqRel);
+        self.inner.caller_waker.wake();
+    }
+}
+
+impl<Req, Resp> Unpin for Call<'_, Req, Resp> {}
+
+impl<Req, Resp> Future for Call<'_, Req, Resp> {
+    type Output = Result<Resp, Canceled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let call = self.get_mut();
+        let inner = &*call.caller.inner;
+        inner.caller_waker.register(cx.waker());
+        if call.request.is_some() {
+            let state = inner.state.load(Ordering::Acquire);
+            if state & SERVER_CLOSED != 0 {
+                call.done = true;
+                return Poll::Ready(Err(Canceled));
+            }
+            if state & PHASE_MASK != IDLE {
+                return Poll::Pending;
+            }
+            unsafe { *inner.request.get() = call.request.take() };
+            inner.set_phase(REQUEST);
+            inner.server_waker.wake();
+        }
+        let state = inner.state.load(Ordering::Acquire);
+        match state & PHASE_MASK {
+            RESPONSE => {
+                let response = unsafe { (*inner.response.get()).take() };
+                inner.set_phase(IDLE);
+                call.done = true;
+                Poll::Ready(Ok(response.unwrap()))
+            }
+            REJECTED => {
+                inner.set_phase(IDLE);
+                call.done = true;
+                Poll::Ready(Err(Canceled))
+            }
+            _ if state & SERVER_CLOSED != 0 => {
+                call.done = true;
+                Poll::Ready(Err(Canceled))
+            }
+            _ => Poll::Pending,
+        }
+    }
+}
+
+impl<Req, Resp> Drop for Call<'_, Req, Resp> {
+    fn drop(&mut self) {
+        if self.request.is_some() || self.done {
+            return;
+        }
+        let inner = &*self.caller.inner;
+        let state = inner.state.fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+            match state & PHASE_MASK {
+                SERVING => Some(state | ABANDONED),
+                REQUEST | RESPONSE | REJECTED => Some(state & !PHASE_MASK),
+                _ => None,
+            }
+        });
+        match state.map(|state| state & PHASE_MASK) {
+            Ok(REQUEST) => drop(unsafe { (*inner.request.get()).take() }),
+            Ok(RESPONSE) => drop(unsafe { (*inner.response.get()).take() }),
+            _ => {}
+        }
+    }
+}
+
+impl<'a, Req, Resp> Future for Recv<'a, Req, Resp> {
+    type Output = Result<(Req, Responder<'a, Req, Resp>), Canceled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = &*self.server.as_ref().expect("`Recv` polled after completion").inner;
+        inner.server_waker.register(cx.waker());
+        let state = inner.state.fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+            if state & PHASE_MASK == REQUEST { Some(state & !PHASE_MASK | SERVING) } else { None }
+        });
+        match state {
+            Ok(_) => {
+                let request = unsafe { (*inner.request.get()).take() };
+                let server = self.server.take().unwrap();
+                Poll::Ready(Ok((request.unwrap(), Responder { server })))
+            }
+            Err(state) if state & CALLER_CLOSED != 0 => Poll::Ready(Err(Canceled)),
+            Err(_) => Poll::Pending,
+        }
+    }
+}
+
+impl<Req, Resp> Responder<'_, Req, Resp> {
+    /// Completes the call with the `response`.
+    ///
+    /// If the call was abandoned by the caller, the `response` is dropped.
+    pub fn respond(self, response: Resp) {
+        let inner = &*self.server.inner;
+        unsafe { *inner.response.get() = Some(response) };
+        if inner.finish(RESPONSE) {
+            drop(unsafe { (*inner.response.get()).take() });
+        }
+        inner.caller_waker.wake();
+        core::mem::forget(self);
+    }
+}
+
+impl<Req, Resp> Drop for Responder<'_, Req, Resp> {
+    fn drop(&mut self) {
+        let inner = &*self.server.inner;
+        inner.finish(REJECTED);
+        inner.caller_waker.wake();
+    }
+}
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Call canceled.")
+    }
+}
+
+impl<Req, Resp> Inner<Req, Resp> {
+    fn new() -> Self {
+        Self {
+            state: AtomicU8::new(IDLE),
+            request: UnsafeCell::new(None),
+            response: UnsafeCell::new(None),
+            caller_waker: AtomicWaker::new(),
+            server_waker: AtomicWaker::new(),
+        }
+    }
+
+    fn set_phase(&self, phase: u8) {
+        self.state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                Some(state & !PHASE_MASK | phase)
+            })
+            .ok();
+    }
+
+    // Moves the call out of the serving phase. Returns `true` if the call was
+    // abandoned, in which case the slot is released instead.
+    fn finish(&self, phase: u8) -> bool {
+        let state = self
+            .state
+            .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |state| {
+                if state & ABANDONED == 0 {
+                    Some(state & !PHASE_MASK | phase)
+                } else {
+                    Some(state & !(PHASE_MASK | ABANDONED))
+                }
+            })
+            .unwrap();
+        state & ABANDONED != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{pin_mut, task::noop_waker_ref};
+
+    fn serve(server: &mut Server<u32, u32>, cx: &mut Context<'_>) {
+        let recv = server.recv();
+        pin_mut!(recv);
+        match recv.poll(cx) {
+            Poll::Ready(Ok((request, responder))) => responder.respond(request * 10),
+            _ => panic!("no request"),
+        }
+    }
+
+    #[test]
+    fn reuse() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let (mut caller, mut server) = channel::<u32, u32>();
+        for i in 0..3 {
+            let call = caller.call(i);
+            pin_mut!(call);
+            assert!(call.as_mut().poll(&mut cx).is_pending());
+            serve(&mut server, &mut cx);
+            assert_eq!(call.poll(&mut cx), Poll::Ready(Ok(i * 10)));
+        }
+    }
+
+    #[test]
+    fn abandon() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let (mut caller, mut server) = channel::<u32, u32>();
+        {
+            let call = caller.call(1);
+            pin_mut!(call);
+            assert!(call.as_mut().poll(&mut cx).is_pending());
+        }
+        let recv = server.recv();
+        pin_mut!(recv);
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+        let call = caller.call(2);
+        pin_mut!(call);
+        assert!(call.as_mut().poll(&mut cx).is_pending());
+        let responder = match recv.poll(&mut cx) {
+            Poll::Ready(Ok((2, responder))) => responder,
+            _ => panic!("no request"),
+        };
+        drop(responder);
+        assert_eq!(call.poll(&mut cx), Poll::Ready(Err(Canceled)));
+    }
+
+    #[test]
+    fn abandon_serving() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let (mut caller, mut server) = channel::<u32, u32>();
+        let mut call = Box::pin(caller.call(1));
+        assert!(call.as_mut().poll(&mut cx).is_pending());
+        let mut recv = Box::pin(server.recv());
+        let (request, responder) = match recv.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(x)) => x,
+            _ => panic!("no request"),
+        };
+        assert_eq!(request, 1);
+        drop(call);
+        let mut call = Box::pin(caller.call(2));
+        assert!(call.as_mut().poll(&mut cx).is_pending());
+        responder.respond(10);
+        drop(recv);
+        assert!(call.as_mut().poll(&mut cx).is_pending());
+        serve(&mut server, &mut cx);
+        assert_eq!(call.as_mut().poll(&mut cx), Poll::Ready(Ok(20)));
+    }
+
+    #[test]
+    fn close() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let (mut caller, server) = channel::<u32, u32>();
+        let call = caller.call(1);
+        pin_mut!(call);
+        assert!(call.as_mut().poll(&mut cx).is_pending());
+        drop(server);
+        assert_eq!(call.poll(&mut cx), Poll::Ready(Err(Canceled)));
+    }
+}
@@ -0,0 +1,186 @@
+use core::{
+    sync::atomic::{fence, AtomicBool, Ordering},
+    task::Waker,
+};
+use futures::task::AtomicWaker;
+
+/// A wake-up mode of a [`CoalescingWaker`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WakeMode {
+    /// Every [`wake`](CoalescingWaker::wake) wakes the registered waker, like
+    /// [`AtomicWaker`] does.
+    Immediate,
+    /// Only the first [`wake`](CoalescingWaker::wake) after
+    /// [`arm`](CoalescingWaker::arm) wakes the consumer.
+    Coalescing,
+}
+
+/// A waker slot, which can wake the consumer at most once per wait.
+///
+/// In [`WakeMode::Coalescing`] mode, the consumer [`arm`](CoalescingWaker::arm)s the slot right before it
+/// returns [`Poll::Pending`](core::task::Poll::Pending), and producers call
+/// [`wake`](CoalescingWaker::wake) after each send. Only the first `wake`
+/// after arming wakes the consumer, so a burst of sends before the consumer
+/// runs produces a single thread pend. The other sends cost a single atomic
+/// swap.
+///
+/// If the consumer finds an item after arming, it should
+/// [`disarm`](CoalescingWaker::disarm) the slot, so the next send doesn't wake
+/// a consumer which is not waiting.
+///
+/// In [`WakeMode::Immediate`] mode, the slot behaves like a plain
+/// [`AtomicWaker`], so the same consumer code works in both modes.
+///
+/// ```
+/// use core::{
+///     sync::atomic::{AtomicUsize, Ordering},
+///     task::{Context, Poll},
+/// };
+/// use drone_core::sync::CoalescingWaker;
+///
+/// static COUNT: AtomicUsize = AtomicUsize::new(0);
+/// static WAKER: CoalescingWaker = CoalescingWaker::new();
+///
+/// // In interrupt handlers.
+/// fn send() {
+///     COUNT.fetch_add(1, Ordering::Release);
+///     WAKER.wake();
+/// }
+///
+/// // In the consumer future.
+/// fn poll_recv(cx: &mut Context<'_>) -> Poll<usize> {
+///     let count = COUNT.swap(0, Ordering::Acquire);
+///     if count > 0 {
+///         return Poll::Ready(count);
+///     }
+///     WAKER.arm(cx.waker());
+///     let count = COUNT.swap(0, Ordering::Acquire);
+///     if count > 0 {
+///         WAKER.disarm();
+///         return Poll::Ready(count);
+///     }
+///     Poll::Pending
+/// }
+/// ```
+pub struct CoalescingWaker {
+    waker: AtomicWaker,
+    armed: AtomicBool,
+    mode: WakeMode,
+}
+
+impl CoalescingWaker {
+    /// Creates a new disarmed slot in [`WakeMode::Coalescing`] mode.
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_mode(WakeMode::Coalescing)
+    }
+
+    /// Creates a new disarmed slot in the given `mode`.
+    #[inline]
+    pub const fn with_mode(mode: WakeMode) -> Self {
+        Self { waker: AtomicWaker::new(), armed: AtomicBool::new(false), mode }
+    }
+
+    /// Returns the wake-up mode of the slot.
+    #[inline]
+    pub fn mode(&self) -> WakeMode {
+        self.mode
+    }
+
+    /// Stores `waker` and arms the slot.
+    ///
+    /// The consumer must check for new items after this call and before
+    /// returning `Pending`, to not miss a send made before arming.
+    #[inline]
+    pub fn arm(&self, waker: &Waker) {
+        self.waker.register(waker);
+        self.armed.store(true, Ordering::Relaxed);
+        // Orders the store above before the consumer checks for new items.
+        fence(Ordering::SeqCst);
+    }
+
+    /// Disarms the slot.
+    #[inline]
+    pub fn disarm(&self) {
+        self.armed.store(false, Ordering::Relaxed);
+    }
+
+    /// Wakes the consumer if the slot is armed, and disarms the slot.
+    ///
+    /// In [`WakeMode::Immediate`] mode, wakes the registered waker
+    /// unconditionally.
+    #[inline]
+    pub fn wake(&self) {
+        if self.mode == WakeMode::Immediate {
+            self.waker.wake();
+            return;
+        }
+        // Orders the producer's send before the armed check.
+        fence(Ordering::SeqCst);
+        if self.armed.load(Ordering::Relaxed) && self.armed.swap(false, Ordering::Acquire) {
+            self.waker.wake();
+        }
+    }
+
+    /// Returns `true` if the slot is armed.
+    #[inline]
+    pub fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for CoalescingWaker {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use core::sync::atomic::AtomicUsize;
+    use futures::task::{waker, ArcWake};
+
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn coalesce() {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let slot = CoalescingWaker::new();
+        slot.wake();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+        slot.arm(&waker);
+        slot.wake();
+        slot.wake();
+        slot.wake();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert!(!slot.is_armed());
+        slot.arm(&waker);
+        slot.disarm();
+        slot.wake();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn immediate() {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let slot = CoalescingWaker::with_mode(WakeMode::Immediate);
+        slot.arm(&waker);
+        slot.wake();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        slot.arm(&waker);
+        slot.disarm();
+        slot.wake();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    }
+}
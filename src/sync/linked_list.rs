@@ -1,5 +1,6 @@
 //! A lock-free singly-linked list.
 
+use crate::sync::Backoff;
 use core::{
     iter::{FromIterator, FusedIterator},
     marker::PhantomData,
@@ -133,6 +134,7 @@ impl<T> LinkedList<T> {
     /// [`Box::from_raw`].
     #[inline]
     pub unsafe fn push_raw(&self, node: *mut Node<T>) {
+        let mut backoff = Backoff::new();
         loop {
             let curr = self.head.load(Ordering::Relaxed);
             unsafe { (*node).next = curr };
@@ -143,6 +145,7 @@ impl<T> LinkedList<T> {
             {
                 break;
             }
+            backoff.spin();
         }
     }
 
@@ -197,6 +200,7 @@ impl<T> LinkedList<T> {
     /// It's responsibility of the caller to de-allocate the node.
     #[inline]
     pub unsafe fn pop_raw(&self) -> Option<*mut Node<T>> {
+        let mut backoff = Backoff::new();
         loop {
             let curr = self.head.load(Ordering::Acquire);
             if curr.is_null() {
@@ -210,6 +214,7 @@ impl<T> LinkedList<T> {
             {
                 break Some(curr);
             }
+            backoff.spin();
         }
     }
 
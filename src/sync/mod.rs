@@ -1,11 +1,21 @@
 //! Useful synchronization primitives.
 
+pub mod call;
 pub mod linked_list;
 pub mod spsc;
+pub mod xcore;
 
+mod backoff;
+mod coalesce;
+mod mpmc;
 mod mutex;
+mod priority;
 
+pub(crate) use self::mpmc::MpmcQueue;
 pub use self::{
+    backoff::{Backoff, BackoffWait},
+    coalesce::{CoalescingWaker, WakeMode},
     linked_list::LinkedList,
     mutex::{Mutex, MutexGuard},
+    priority::{PriorityClass, PriorityQueueChannel, PriorityRecv},
 };
@@ -0,0 +1,147 @@
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A bounded lock-free multi-producer, multi-consumer queue of `N` items.
+///
+/// This is Dmitry Vyukov's bounded MPMC queue. `N` must be a non-zero power of
+/// two, which is checked at compile time.
+pub(crate) struct MpmcQueue<T, const N: usize> {
+    enqueue: AtomicUsize,
+    dequeue: AtomicUsize,
+    // Stores the sequence number of each slot minus the slot index, so that the
+    // initial state is all zeros.
+    seqs: [AtomicUsize; N],
+    slots: UnsafeCell<MaybeUninit<[T; N]>>,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for MpmcQueue<T, N> {}
+
+impl<T, const N: usize> MpmcQueue<T, N> {
+    // Positions wrap around `usize::MAX`, so the slot index `pos % N` stays
+    // consistent only if `N` divides the `usize` range.
+    const CAPACITY_CHECK: () = assert!(N.is_power_of_two(), "capacity must be a power of two");
+
+    /// An empty queue.
+    #[allow(clippy::declare_interior_mutable_const)]
+    pub(crate) const NEW: Self = {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicUsize = AtomicUsize::new(0);
+        let () = Self::CAPACITY_CHECK;
+        Self {
+            enqueue: AtomicUsize::new(0),
+            dequeue: AtomicUsize::new(0),
+            seqs: [ZERO; N],
+            slots: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    };
+
+    /// Pushes the `item` to the back of the queue.
+    ///
+    /// If the queue is full, returns the `item` back as an error.
+    pub(crate) fn push(&self, item: T) -> Result<(), T> {
+        let mut pos = self.enqueue.load(Ordering::Relaxed);
+        loop {
+            let idx = pos % N;
+            let seq = self.seqs[idx].load(Ordering::Acquire).wrapping_add(idx);
+            let diff = seq.wrapping_sub(pos) as isize;
+            if diff == 0 {
+                match self.enqueue.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { ptr::write(self.slot(idx), item) };
+                        self.seqs[idx]
+                            .store(pos.wrapping_add(1).wrapping_sub(idx), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(next_pos) => pos = next_pos,
+                }
+            } else if diff < 0 {
+                return Err(item);
+            } else {
+                pos = self.enqueue.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pops the item from the front of the queue.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub(crate) fn pop(&self) -> Option<T> {
+        let mut pos = self.dequeue.load(Ordering::Relaxed);
+        loop {
+            let idx = pos % N;
+            let seq = self.seqs[idx].load(Ordering::Acquire).wrapping_add(idx);
+            let diff = seq.wrapping_sub(pos.wrapping_add(1)) as isize;
+            if diff == 0 {
+                match self.dequeue.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let item = unsafe { ptr::read(self.slot(idx)) };
+                        self.seqs[idx]
+                            .store(pos.wrapping_add(N).wrapping_sub(idx), Ordering::Release);
+                        return Some(item);
+                    }
+                    Err(next_pos) => pos = next_pos,
+                }
+            } else if diff < 0 {
+                return None;
+            } else {
+                pos = self.dequeue.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn slot(&self, idx: usize) -> *mut T {
+        unsafe { self.slots.get().cast::<T>().add(idx) }
+    }
+}
+
+impl<T, const N: usize> Drop for MpmcQueue<T, N> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+
+    #[test]
+    fn wrap_around() {
+        let queue = MpmcQueue::<u32, 4>::NEW;
+        for round in 0..10 {
+            for i in 0..3 {
+                assert_eq!(queue.push(round * 10 + i), Ok(()));
+            }
+            for i in 0..3 {
+                assert_eq!(queue.pop(), Some(round * 10 + i));
+            }
+        }
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn drop_items() {
+        let item = Rc::new(());
+        let queue = MpmcQueue::<_, 2>::NEW;
+        assert!(queue.push(Rc::clone(&item)).is_ok());
+        assert!(queue.push(Rc::clone(&item)).is_ok());
+        assert!(queue.push(Rc::clone(&item)).is_err());
+        assert_eq!(Rc::strong_count(&item), 3);
+        drop(queue);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+}
@@ -1,12 +1,17 @@
-use crate::sync::linked_list::{LinkedList, Node};
+use crate::{
+    sync::linked_list::{LinkedList, Node},
+    thr::{self, inherit, PriorityInheritance},
+    time::{Alarm, Duration, Instant, Sleep, Timeout, Timer},
+};
 use core::{
     cell::UnsafeCell,
     fmt,
     future::Future,
     mem::MaybeUninit,
+    num::NonZeroU32,
     ops::{Deref, DerefMut},
     pin::Pin,
-    sync::atomic::{AtomicU8, Ordering},
+    sync::atomic::{AtomicU32, AtomicU8, Ordering},
     task::{Context, Poll, Waker},
 };
 
@@ -18,18 +23,29 @@ use core::{
 /// returned from [`lock`] and [`try_lock`], which guarantees that the data is
 /// only ever accessed when the mutex is locked.
 ///
+/// A mutex created with [`with_priority_inheritance`] makes the thread
+/// holding the lock inherit the priority of the highest-priority thread
+/// awaiting it, if a [`PriorityInheritance`] hook is registered.
+///
+/// [`with_priority_inheritance`]: Self::with_priority_inheritance
+///
 /// [`new`]: Self::new
 /// [`lock`]: Self::lock
 /// [`try_lock`]: Self::try_lock
 pub struct Mutex<T: ?Sized> {
     state: AtomicU8,
     waiters: LinkedList<Waiter>,
+    inheritance: bool,
+    owner: AtomicU32,
+    inherited: AtomicU8,
     data: UnsafeCell<T>,
 }
 
 const DATA_LOCKED: u8 = 1 << 0;
 const WAITERS_LOCKED: u8 = 1 << 1;
 
+const NOT_INHERITED: u8 = 0;
+
 /// An RAII implementation of a "scoped lock" of a mutex. When this structure is
 /// dropped (falls out of scope), the lock will be unlocked.
 ///
@@ -53,6 +69,20 @@ pub struct MutexLockFuture<'a, T: ?Sized> {
     waiter: Option<*const Node<Waiter>>,
 }
 
+/// A future which resolves when the target mutex has been successfully
+/// acquired, or when the deadline has elapsed.
+///
+/// This structure is created by the [`lock_deadline`] and [`lock_for`] methods
+/// on [`Mutex`].
+///
+/// [`lock_deadline`]: Mutex::lock_deadline
+/// [`lock_for`]: Mutex::lock_for
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct MutexLockDeadline<'a, T: ?Sized, A: Timer> {
+    lock: MutexLockFuture<'a, T>,
+    sleep: Sleep<'a, A>,
+}
+
 struct Waiter {
     state: AtomicU8,
     wakers: [UnsafeCell<MaybeUninit<Waker>>; 2],
@@ -79,7 +109,37 @@ impl<T> Mutex<T> {
     /// ```
     #[inline]
     pub const fn new(data: T) -> Self {
-        Self { state: AtomicU8::new(0), waiters: LinkedList::new(), data: UnsafeCell::new(data) }
+        Self::with_inheritance(data, false)
+    }
+
+    /// Creates a new mutex with priority inheritance in an unlocked state
+    /// ready for use.
+    ///
+    /// Every acquisition of the lock records the running thread with the
+    /// [`PriorityInheritance::current`] hook. The other hooks are called only
+    /// when a thread has to wait for the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use drone_core::sync::Mutex;
+    ///
+    /// let mutex = Mutex::with_priority_inheritance(0);
+    /// ```
+    #[inline]
+    pub const fn with_priority_inheritance(data: T) -> Self {
+        Self::with_inheritance(data, true)
+    }
+
+    const fn with_inheritance(data: T, inheritance: bool) -> Self {
+        Self {
+            state: AtomicU8::new(0),
+            waiters: LinkedList::new(),
+            inheritance,
+            owner: AtomicU32::new(0),
+            inherited: AtomicU8::new(NOT_INHERITED),
+            data: UnsafeCell::new(data),
+        }
     }
 
     /// Consumes this mutex, returning the underlying data.
@@ -107,6 +167,7 @@ impl<T: ?Sized> Mutex<T> {
     #[inline]
     pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
         if self.state.fetch_or(DATA_LOCKED, Ordering::Acquire) & DATA_LOCKED == 0 {
+            self.set_owner::<inherit::Platform>();
             Some(MutexGuard { mutex: self })
         } else {
             None
@@ -122,6 +183,34 @@ impl<T: ?Sized> Mutex<T> {
         MutexLockFuture { mutex: self, waiter: None }
     }
 
+    /// Acquires this lock asynchronously, giving up at the `deadline` measured
+    /// by the `alarm`.
+    ///
+    /// This method returns a future that will resolve to the guard once the
+    /// lock has been successfully acquired, or to [`Timeout`] error if the
+    /// `deadline` elapses first.
+    #[inline]
+    pub fn lock_deadline<'a, A: Timer>(
+        &'a self,
+        alarm: &'a Alarm<A>,
+        deadline: Instant<A::Tick>,
+    ) -> MutexLockDeadline<'a, T, A> {
+        MutexLockDeadline { lock: self.lock(), sleep: alarm.sleep_until(deadline) }
+    }
+
+    /// Acquires this lock asynchronously, giving up after the `timeout` from
+    /// now.
+    ///
+    /// See [`lock_deadline`](Self::lock_deadline) for details.
+    #[inline]
+    pub fn lock_for<'a, A: Timer>(
+        &'a self,
+        alarm: &'a Alarm<A>,
+        timeout: Duration<A::Tick>,
+    ) -> MutexLockDeadline<'a, T, A> {
+        self.lock_deadline(alarm, alarm.now() + timeout)
+    }
+
     /// Returns a mutable reference to the underlying data.
     ///
     /// Since this call borrows the `Mutex` mutably, no actual locking needs to
@@ -141,7 +230,63 @@ impl<T: ?Sized> Mutex<T> {
         unsafe { &mut *self.data.get() }
     }
 
+    fn set_owner<H: PriorityInheritance>(&self) {
+        if self.inheritance {
+            self.owner.store(H::current().map_or(0, NonZeroU32::get), Ordering::Release);
+        }
+    }
+
+    /// Makes the owner thread inherit the priority of the current thread, which
+    /// is going to wait for the lock.
+    fn inherit_priority<H: PriorityInheritance>(&self) {
+        if !self.inheritance {
+            return;
+        }
+        let waiter = match H::current() {
+            Some(waiter) => waiter,
+            None => return,
+        };
+        thr::critical(|_| {
+            let owner = match NonZeroU32::new(self.owner.load(Ordering::Acquire)) {
+                Some(owner) if owner != waiter => owner,
+                _ => return,
+            };
+            let priority = H::priority(waiter);
+            if priority <= H::priority(owner) {
+                return;
+            }
+            // Inherit the new priority before releasing the previous one, so
+            // the owner never runs at a lower priority in between.
+            let inherited = self.inherited.swap(priority, Ordering::Relaxed);
+            H::inherit(owner, priority);
+            if inherited != NOT_INHERITED {
+                H::disinherit(owner, inherited);
+            }
+        });
+    }
+
+    /// Releases the priority inherited by the owner thread.
+    fn restore_priority<H: PriorityInheritance>(&self) {
+        if !self.inheritance {
+            return;
+        }
+        // Once the owner is cleared, waiters can't inherit to it anymore.
+        let owner = self.owner.swap(0, Ordering::AcqRel);
+        if self.inherited.load(Ordering::Acquire) == NOT_INHERITED {
+            return;
+        }
+        thr::critical(|_| {
+            let inherited = self.inherited.swap(NOT_INHERITED, Ordering::Relaxed);
+            if let Some(owner) = NonZeroU32::new(owner) {
+                if inherited != NOT_INHERITED {
+                    H::disinherit(owner, inherited);
+                }
+            }
+        });
+    }
+
     fn unlock(&self) {
+        self.restore_priority::<inherit::Platform>();
         let waiters_lock =
             self.state.fetch_or(WAITERS_LOCKED, Ordering::Acquire) & WAITERS_LOCKED == 0;
         if waiters_lock {
@@ -171,6 +316,17 @@ impl<T: ?Sized> MutexLockFuture<'_, T> {
             unsafe { (*waiter).disable() };
         }
     }
+
+    fn cancel(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            if unsafe { (*waiter).disable() } & WAITER_DISABLED != 0 {
+                // This future was awoken, but then cancelled before it could
+                // acquire the lock. Try to lock the mutex and then immediately
+                // unlock to wake up another thread.
+                drop(self.mutex.try_lock());
+            }
+        }
+    }
 }
 
 impl<'a, T: ?Sized> Future for MutexLockFuture<'a, T> {
@@ -192,20 +348,30 @@ impl<'a, T: ?Sized> Future for MutexLockFuture<'a, T> {
             self.disable_waiter();
             return Poll::Ready(lock);
         }
+        self.mutex.inherit_priority::<inherit::Platform>();
+        Poll::Pending
+    }
+}
+
+impl<'a, T: ?Sized, A: Timer> Future for MutexLockDeadline<'a, T, A> {
+    type Output = Result<MutexGuard<'a, T>, Timeout>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Poll::Ready(lock) = Pin::new(&mut this.lock).poll(cx) {
+            return Poll::Ready(Ok(lock));
+        }
+        if Pin::new(&mut this.sleep).poll(cx).is_ready() {
+            this.lock.cancel();
+            return Poll::Ready(Err(Timeout));
+        }
         Poll::Pending
     }
 }
 
 impl<T: ?Sized> Drop for MutexLockFuture<'_, T> {
     fn drop(&mut self) {
-        if let Some(waiter) = self.waiter {
-            if unsafe { (*waiter).disable() } & WAITER_DISABLED != 0 {
-                // This future was awoken, but then dropped before it could
-                // acquire the lock. Try to lock the mutex and then immediately
-                // unlock to wake up another thread.
-                drop(self.mutex.try_lock());
-            }
-        }
+        self.cancel();
     }
 }
 
@@ -329,6 +495,7 @@ mod tests {
     use super::*;
     use alloc::sync::Arc;
     use core::{
+        cell::{Cell, RefCell},
         future::Future,
         sync::atomic::{AtomicUsize, Ordering},
         task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
@@ -353,6 +520,105 @@ mod tests {
         }
     }
 
+    struct Hook;
+
+    std::thread_local! {
+        static CURRENT: Cell<u32> = Cell::new(0);
+        static INHERITED: RefCell<Vec<(u32, u8)>> = RefCell::new(Vec::new());
+    }
+
+    impl PriorityInheritance for Hook {
+        fn current() -> Option<NonZeroU32> {
+            NonZeroU32::new(CURRENT.with(Cell::get))
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        fn priority(thr: NonZeroU32) -> u8 {
+            INHERITED.with(|inherited| {
+                inherited
+                    .borrow()
+                    .iter()
+                    .filter(|(t, _)| *t == thr.get())
+                    .map(|&(_, priority)| priority)
+                    .fold(thr.get() as u8, u8::max)
+            })
+        }
+
+        fn inherit(thr: NonZeroU32, priority: u8) {
+            INHERITED.with(|inherited| inherited.borrow_mut().push((thr.get(), priority)));
+        }
+
+        fn disinherit(thr: NonZeroU32, priority: u8) {
+            INHERITED.with(|inherited| {
+                let mut inherited = inherited.borrow_mut();
+                let index = inherited.iter().position(|&x| x == (thr.get(), priority)).unwrap();
+                inherited.remove(index);
+            });
+        }
+    }
+
+    // The base priority of a thread in `Hook` is equal to its handle.
+    fn lock_as<T>(mutex: &Mutex<T>, thr: u32) -> MutexGuard<'_, T> {
+        CURRENT.with(|current| current.set(thr));
+        let guard = mutex.try_lock().unwrap();
+        mutex.set_owner::<Hook>();
+        guard
+    }
+
+    fn wait_as<T>(mutex: &Mutex<T>, thr: u32) {
+        CURRENT.with(|current| current.set(thr));
+        mutex.inherit_priority::<Hook>();
+    }
+
+    fn unlock(guard: MutexGuard<'_, ()>) {
+        guard.mutex.restore_priority::<Hook>();
+        drop(guard);
+    }
+
+    fn priority(thr: u32) -> u8 {
+        Hook::priority(NonZeroU32::new(thr).unwrap())
+    }
+
+    #[test]
+    fn inherit() {
+        let m = Mutex::with_priority_inheritance(());
+        let guard = lock_as(&m, 1);
+        wait_as(&m, 3);
+        assert_eq!(priority(1), 3);
+        wait_as(&m, 2);
+        assert_eq!(priority(1), 3);
+        wait_as(&m, 5);
+        assert_eq!(priority(1), 5);
+        unlock(guard);
+        assert_eq!(priority(1), 1);
+        INHERITED.with(|inherited| assert!(inherited.borrow().is_empty()));
+    }
+
+    #[test]
+    fn inherit_nested() {
+        let a = Mutex::with_priority_inheritance(());
+        let b = Mutex::with_priority_inheritance(());
+        let guard_a = lock_as(&a, 1);
+        let guard_b = lock_as(&b, 1);
+        wait_as(&a, 2);
+        assert_eq!(priority(1), 2);
+        wait_as(&b, 3);
+        assert_eq!(priority(1), 3);
+        unlock(guard_a);
+        assert_eq!(priority(1), 3);
+        unlock(guard_b);
+        assert_eq!(priority(1), 1);
+    }
+
+    #[test]
+    fn inherit_disabled() {
+        let m = Mutex::new(());
+        let guard = lock_as(&m, 1);
+        wait_as(&m, 3);
+        assert_eq!(priority(1), 1);
+        unlock(guard);
+    }
+
     #[test]
     fn try_lock() {
         let m = Mutex::new(());
@@ -396,6 +662,46 @@ mod tests {
         assert_eq!(*a.try_lock().unwrap(), 15);
     }
 
+    #[test]
+    fn lock_deadline() {
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Millis;
+        impl crate::time::Tick for Millis {
+            const FREQ: u64 = 1_000;
+        }
+        struct FakeTimer(AtomicUsize);
+        impl Timer for FakeTimer {
+            type Tick = Millis;
+
+            fn now(&self) -> Instant<Millis> {
+                Instant::from_ticks(self.0.load(Ordering::SeqCst) as u64)
+            }
+
+            fn schedule(&self, _at: Instant<Millis>) {}
+
+            fn cancel(&self) {}
+        }
+        static COUNTER: Counter = Counter(AtomicUsize::new(0));
+        let waker = COUNTER.to_waker();
+        let mut cx = Context::from_waker(&waker);
+        let alarm = Alarm::new(FakeTimer(AtomicUsize::new(0)));
+        let m = Mutex::new(1);
+        let guard = m.try_lock().unwrap();
+        let a = m.lock_for(&alarm, Duration::from_millis(10));
+        let b = m.lock_for(&alarm, Duration::from_millis(20));
+        pin_mut!(a);
+        pin_mut!(b);
+        assert!(a.as_mut().poll(&mut cx).is_pending());
+        assert!(b.as_mut().poll(&mut cx).is_pending());
+        alarm.timer().0.store(10, Ordering::SeqCst);
+        alarm.fire();
+        assert!(matches!(a.as_mut().poll(&mut cx), Poll::Ready(Err(Timeout))));
+        let wakes = COUNTER.0.load(Ordering::SeqCst);
+        drop(guard);
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), wakes + 1);
+        assert!(matches!(b.as_mut().poll(&mut cx), Poll::Ready(Ok(_))));
+    }
+
     #[test]
     fn into_inner() {
         let m = Mutex::new(NonCopy(10));
@@ -0,0 +1,154 @@
+use super::{CoalescingWaker, MpmcQueue, WakeMode};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+const CLASSES: usize = 4;
+
+/// A priority class of an item sent to a [`PriorityQueueChannel`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum PriorityClass {
+    /// Bulk data, received when no other items are pending.
+    Low,
+    /// Regular items.
+    Normal,
+    /// Items received before the regular ones.
+    High,
+    /// Control messages, received before everything else.
+    Urgent,
+}
+
+/// A bounded multi-producer, single-consumer queue with priority classes.
+///
+/// Each [`PriorityClass`] has its own lane of `N` items, where `N` must be a
+/// power of two, so bulk data can't crowd out urgent control messages. The
+/// receiver always takes the oldest item of the highest non-empty class.
+/// Sending is lock-free, and can be done from interrupt handlers.
+///
+/// ```
+/// use drone_core::sync::{PriorityClass, PriorityQueueChannel};
+///
+/// static CHANNEL: PriorityQueueChannel<&str, 8> = PriorityQueueChannel::new();
+///
+/// // In interrupt handlers:
+/// CHANNEL.send(PriorityClass::Low, "chunk").unwrap();
+/// CHANNEL.send(PriorityClass::Urgent, "stop").unwrap();
+///
+/// // In the consumer task:
+/// assert_eq!(CHANNEL.try_recv(), Some("stop"));
+/// assert_eq!(CHANNEL.try_recv(), Some("chunk"));
+/// ```
+pub struct PriorityQueueChannel<T, const N: usize> {
+    lanes: [MpmcQueue<T, N>; CLASSES],
+    waker: CoalescingWaker,
+}
+
+/// A future returned by [`PriorityQueueChannel::recv`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct PriorityRecv<'a, T, const N: usize> {
+    channel: &'a PriorityQueueChannel<T, N>,
+}
+
+impl<T, const N: usize> PriorityQueueChannel<T, N> {
+    /// Creates an empty channel, which wakes the receiver on every send.
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_wake_mode(WakeMode::Immediate)
+    }
+
+    /// Creates an empty channel with the given wake-up `mode`.
+    ///
+    /// With [`WakeMode::Coalescing`], a burst of sends before the receiver
+    /// runs wakes it only once.
+    #[inline]
+    pub const fn with_wake_mode(mode: WakeMode) -> Self {
+        Self { lanes: [MpmcQueue::NEW; CLASSES], waker: CoalescingWaker::with_mode(mode) }
+    }
+
+    /// Returns the capacity of each priority class.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Sends `item` with the priority `class` and wakes up the receiver.
+    ///
+    /// If the lane of `class` is full, returns the `item` back as an error.
+    /// This operation is lock-free.
+    pub fn send(&self, class: PriorityClass, item: T) -> Result<(), T> {
+        self.lanes[class as usize].push(item)?;
+        self.waker.wake();
+        Ok(())
+    }
+
+    /// Takes the oldest item of the highest non-empty priority class.
+    ///
+    /// Returns `None` if the channel is empty.
+    pub fn try_recv(&self) -> Option<T> {
+        self.lanes.iter().rev().find_map(MpmcQueue::pop)
+    }
+
+    /// Returns a future, which resolves to the next item by
+    /// [`try_recv`](PriorityQueueChannel::try_recv) order.
+    ///
+    /// Only one receiver should wait for the channel at a time.
+    #[inline]
+    pub fn recv(&self) -> PriorityRecv<'_, T, N> {
+        PriorityRecv { channel: self }
+    }
+}
+
+impl<T, const N: usize> Default for PriorityQueueChannel<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Future for PriorityRecv<'_, T, N> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        if let Some(item) = self.channel.try_recv() {
+            return Poll::Ready(item);
+        }
+        self.channel.waker.arm(cx.waker());
+        if let Some(item) = self.channel.try_recv() {
+            self.channel.waker.disarm();
+            return Poll::Ready(item);
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{pin_mut, task::noop_waker_ref};
+
+    #[test]
+    fn classes() {
+        let channel = PriorityQueueChannel::<u32, 2>::new();
+        assert_eq!(channel.send(PriorityClass::Low, 1), Ok(()));
+        assert_eq!(channel.send(PriorityClass::Normal, 2), Ok(()));
+        assert_eq!(channel.send(PriorityClass::Low, 3), Ok(()));
+        assert_eq!(channel.send(PriorityClass::Low, 4), Err(4));
+        assert_eq!(channel.send(PriorityClass::Urgent, 5), Ok(()));
+        assert_eq!(channel.send(PriorityClass::High, 6), Ok(()));
+        let received = core::iter::from_fn(|| channel.try_recv()).collect::<Vec<_>>();
+        assert_eq!(received, [5, 6, 2, 1, 3]);
+    }
+
+    #[test]
+    fn recv() {
+        let channel = PriorityQueueChannel::<u32, 2>::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let recv = channel.recv();
+        pin_mut!(recv);
+        assert_eq!(recv.as_mut().poll(&mut cx), Poll::Pending);
+        channel.send(PriorityClass::Normal, 1).unwrap();
+        assert_eq!(recv.poll(&mut cx), Poll::Ready(1));
+    }
+}
@@ -0,0 +1,201 @@
+//! A single-producer, single-consumer queue for sending values across
+//! asynchronous tasks, with a doorbell for out-of-band signals.
+//!
+//! This is a [`ring`](super::ring) channel fused with a
+//! [`pulse`](super::pulse) channel. The sending half can put values to the
+//! ring buffer, and also ring the doorbell to notify the receiving half about
+//! a condition which is not a value, e.g. a hardware overrun. The doorbell
+//! counter lives in the same state word as the ring buffer cursors, so both
+//! kinds of events share one waker and one channel lifetime.
+//!
+//! See [`channel`] constructor for more.
+
+mod receiver;
+mod sender;
+
+pub use self::{receiver::Receiver, sender::Sender};
+pub use crate::sync::spsc::ring::{SendError, SendErrorKind};
+
+use crate::sync::spsc::ring::{Inner, Layout, OPTION_BITS};
+use alloc::sync::Arc;
+use core::{mem::size_of, num::NonZeroUsize};
+
+/// Maximum capacity of the channel.
+pub const MAX_CAPACITY: usize = (1 << NUMBER_BITS) - 1;
+
+/// Maximum number of doorbell rings pending at once. Further rings saturate.
+pub const MAX_BELLS: usize = (1 << BELL_BITS) - 1;
+
+const NUMBER_BITS: u32 = (size_of::<usize>() as u32 * 8 - OPTION_BITS - BELL_BITS) / 2;
+const BELL_SHIFT: u32 = NUMBER_BITS * 2;
+const BELL_BITS: u32 = 4;
+
+// The ring channel state with the doorbell bits:
+//     OOOO_BBBB_CCCC_LLLL
+// Where O are option bits, B are doorbell bits, C are cursor bits, and L are
+// length bits.
+//
+// Doorbell range: [0; MAX_BELLS]
+enum Bells {}
+
+impl Layout for Bells {
+    const NUMBER_BITS: u32 = NUMBER_BITS;
+}
+
+/// An item of the [`Receiver`] stream.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event<T> {
+    /// A value from the ring buffer.
+    Value(T),
+    /// The number of doorbell rings since the last one received.
+    Bell(NonZeroUsize),
+}
+
+/// Creates a new channel, returning the sender/receiver halves.
+///
+/// `capacity` is the capacity of the underlying ring buffer.
+///
+/// The [`Sender`] half is used to write values to the ring buffer and to ring
+/// the doorbell. The [`Receiver`] half is a
+/// [`Stream`](futures::stream::Stream) that reads [`Event`]s. Pending doorbell
+/// rings are received before the buffered values.
+///
+/// ```
+/// use core::num::NonZeroUsize;
+/// use drone_core::sync::spsc::doorbell::{channel, Event};
+///
+/// let (mut tx, mut rx) = channel::<u8, ()>(4);
+///
+/// // In the interrupt handler.
+/// tx.send(1).unwrap();
+/// tx.ring().unwrap(); // The hardware reported an overrun.
+/// tx.send(2).unwrap();
+///
+/// assert_eq!(rx.try_next(), Ok(Some(Event::Bell(NonZeroUsize::new(1).unwrap()))));
+/// assert_eq!(rx.try_next(), Ok(Some(Event::Value(1))));
+/// assert_eq!(rx.try_next(), Ok(Some(Event::Value(2))));
+/// assert_eq!(rx.try_next(), Ok(None));
+/// ```
+#[inline]
+pub fn channel<T, E>(capacity: usize) -> (Sender<T, E>, Receiver<T, E>) {
+    let inner = Arc::new(Inner::new(capacity));
+    let sender = Sender::new(Arc::clone(&inner));
+    let receiver = Receiver::new(inner);
+    (sender, receiver)
+}
+
+impl<T, E> Inner<T, E, Bells> {
+    fn get_bells(state: usize) -> usize {
+        state >> BELL_SHIFT & MAX_BELLS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use core::{
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll},
+    };
+    use futures::{
+        stream::Stream,
+        task::{waker, ArcWake},
+    };
+
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn bell(count: usize) -> Event<usize> {
+        Event::Bell(NonZeroUsize::new(count).unwrap())
+    }
+
+    #[test]
+    fn bells_first() {
+        let (mut tx, mut rx) = channel::<usize, ()>(4);
+        assert_eq!(tx.send(1).unwrap(), ());
+        assert_eq!(tx.ring(), Ok(()));
+        assert_eq!(tx.ring(), Ok(()));
+        assert_eq!(tx.send(2).unwrap(), ());
+        assert_eq!(rx.try_next(), Ok(Some(bell(2))));
+        assert_eq!(rx.try_next(), Ok(Some(Event::Value(1))));
+        assert_eq!(tx.ring(), Ok(()));
+        assert_eq!(rx.try_next(), Ok(Some(bell(1))));
+        assert_eq!(rx.try_next(), Ok(Some(Event::Value(2))));
+        assert_eq!(rx.try_next(), Ok(None));
+    }
+
+    #[test]
+    fn bells_saturate() {
+        let (mut tx, mut rx) = channel::<usize, ()>(4);
+        for _ in 0..MAX_BELLS + 3 {
+            assert_eq!(tx.ring(), Ok(()));
+        }
+        assert_eq!(tx.send(1).unwrap(), ());
+        assert_eq!(rx.try_next(), Ok(Some(bell(MAX_BELLS))));
+        assert_eq!(rx.try_next(), Ok(Some(Event::Value(1))));
+        drop(rx);
+        assert_eq!(tx.ring(), Err(SendErrorKind::Canceled));
+    }
+
+    #[test]
+    fn ring_wakes() {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        let (mut tx, mut rx) = channel::<usize, ()>(4);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(tx.ring(), Ok(()));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(Ok(bell(1)))));
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(tx.send(3).unwrap(), ());
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(Ok(Event::Value(3)))));
+        drop(tx);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn close_with() {
+        let (mut tx, mut rx) = channel::<usize, u8>(4);
+        assert_eq!(tx.send(1).unwrap(), ());
+        assert_eq!(tx.ring(), Ok(()));
+        assert_eq!(tx.close_with(42).unwrap(), ());
+        assert_eq!(rx.try_next(), Ok(Some(bell(1))));
+        assert_eq!(rx.try_next(), Ok(Some(Event::Value(1))));
+        assert_eq!(rx.try_next(), Err(42));
+        assert_eq!(rx.try_next(), Ok(None));
+    }
+
+    #[test]
+    fn drop_values() {
+        let value = Rc::new(());
+        let (mut tx, mut rx) = channel::<Rc<()>, ()>(2);
+        assert!(tx.send(Rc::clone(&value)).is_ok());
+        assert!(tx.send(Rc::clone(&value)).is_ok());
+        assert!(rx.try_next().is_ok());
+        assert!(tx.send(Rc::clone(&value)).is_ok());
+        assert_eq!(Rc::strong_count(&value), 3);
+        drop(tx);
+        drop(rx);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let (mut tx, mut rx) = channel::<usize, ()>(0);
+        assert_eq!(tx.send(1).map_err(|err| err.kind), Err(SendErrorKind::Overflow));
+        assert_eq!(tx.ring(), Ok(()));
+        assert_eq!(rx.try_next(), Ok(Some(bell(1))));
+        assert_eq!(rx.try_next(), Ok(None));
+        drop(tx);
+        drop(rx);
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Bells, Event, Inner, BELL_SHIFT, MAX_BELLS};
+use crate::sync::spsc::{SpscInner, SpscInnerErr};
+use alloc::sync::Arc;
+use core::{
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+};
+use futures::stream::Stream;
+
+const IS_TX_HALF: bool = false;
+
+/// The receiving-half of [`doorbell::channel`](super::channel).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Receiver<T, E> {
+    inner: Arc<Inner<T, E, Bells>>,
+}
+
+#[derive(Clone, Copy)]
+enum Take {
+    Bells(usize),
+    Index(usize),
+}
+
+impl<T, E> Receiver<T, E> {
+    pub(super) fn new(inner: Arc<Inner<T, E, Bells>>) -> Self {
+        Self { inner }
+    }
+
+    /// Gracefully close this receiver, preventing any subsequent attempts to
+    /// send to it.
+    ///
+    /// Any `send` or `ring` operation which happens after this method returns
+    /// is guaranteed to fail. After calling this method, you can use
+    /// [`Receiver::poll`](core::future::Future::poll) to determine whether an
+    /// event had previously been sent.
+    #[inline]
+    pub fn close(&mut self) {
+        self.inner.close_half(IS_TX_HALF)
+    }
+
+    /// Attempts to receive an event outside of the context of a task.
+    ///
+    /// Does not schedule a task wakeup or have any other side effects.
+    ///
+    /// A return value of `Ok(None)` must be considered immediately stale (out
+    /// of date) unless [`close`](Receiver::close) has been called first.
+    #[inline]
+    pub fn try_next(&mut self) -> Result<Option<Event<T>>, E> {
+        self.inner.try_next()
+    }
+}
+
+impl<T, E> Stream for Receiver<T, E> {
+    type Item = Result<Event<T>, E>;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_half_with_transaction(
+            cx,
+            IS_TX_HALF,
+            Ordering::Acquire,
+            Ordering::AcqRel,
+            Inner::take_try,
+            Inner::take_finalize,
+        )
+    }
+}
+
+impl<T, E> Drop for Receiver<T, E> {
+    #[inline]
+    fn drop(&mut self) {
+        self.inner.close_half(IS_TX_HALF);
+    }
+}
+
+impl<T, E> Inner<T, E, Bells> {
+    fn try_next(&self) -> Result<Option<Event<T>>, E> {
+        let state = self.state_load(Ordering::Acquire);
+        self.transaction(state, Ordering::AcqRel, Ordering::Acquire, |state| {
+            match self.take_try(state) {
+                Some(value) => value.map_err(Ok),
+                None => Err(Err(())),
+            }
+        })
+        .map(|take| Some(unsafe { self.take_event(take) }))
+        .or_else(|value| value.map_or_else(|()| Ok(None), |()| self.take_err().transpose()))
+    }
+
+    fn take_try(&self, state: &mut usize) -> Option<Result<Take, ()>> {
+        let bells = Self::get_bells(*state);
+        let length = Self::get_length(*state);
+        if bells != 0 {
+            *state &= !(MAX_BELLS << BELL_SHIFT);
+            Some(Ok(Take::Bells(bells)))
+        } else if length != 0 {
+            Some(Ok(Take::Index(self.take_index(state, length))))
+        } else if *state & Self::COMPLETE == 0 {
+            None
+        } else {
+            Some(Err(()))
+        }
+    }
+
+    fn take_finalize(&self, value: Result<Take, ()>) -> Option<Result<Event<T>, E>> {
+        match value {
+            Ok(take) => Some(Ok(unsafe { self.take_event(take) })),
+            Err(()) => self.take_err(),
+        }
+    }
+
+    unsafe fn take_event(&self, take: Take) -> Event<T> {
+        match take {
+            Take::Bells(bells) => Event::Bell(unsafe { NonZeroUsize::new_unchecked(bells) }),
+            Take::Index(index) => Event::Value(unsafe { self.take_value(index) }),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use super::{Bells, Inner, SendError, SendErrorKind, BELL_SHIFT, MAX_BELLS};
+use crate::sync::spsc::{SpscInner, SpscInnerErr};
+use alloc::sync::Arc;
+use core::{
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+};
+
+const IS_TX_HALF: bool = true;
+
+/// The sending-half of [`doorbell::channel`](super::channel).
+pub struct Sender<T, E> {
+    inner: Arc<Inner<T, E, Bells>>,
+}
+
+impl<T, E> Sender<T, E> {
+    pub(super) fn new(inner: Arc<Inner<T, E, Bells>>) -> Self {
+        Self { inner }
+    }
+
+    /// Puts `value` to the ring buffer. The value can be immediately read by
+    /// the receiving half.
+    ///
+    /// If the value is successfully enqueued for the remote end to receive,
+    /// then `Ok(())` is returned. However if the receiving end was dropped
+    /// before this function was called or there is the ring buffer overflow,
+    /// then `Err` is returned with the value provided.
+    #[inline]
+    pub fn send(&mut self, value: T) -> Result<(), SendError<T>> {
+        self.inner.send(value)
+    }
+
+    /// Rings the doorbell. The receiving half receives
+    /// [`Event::Bell`](super::Event::Bell) before any buffered value.
+    ///
+    /// The ring buffer overflow doesn't affect the doorbell, so it can be used
+    /// to report the overflow itself. Rings received at once are counted up to
+    /// [`MAX_BELLS`](super::MAX_BELLS).
+    ///
+    /// If the receiving end was dropped before this function was called,
+    /// `Err` is returned.
+    #[inline]
+    pub fn ring(&mut self) -> Result<(), SendErrorKind> {
+        self.inner.ring()
+    }
+
+    /// Completes this channel with an `Err` result.
+    ///
+    /// This function will consume `self` and indicate to the other end, the
+    /// [`Receiver`](super::Receiver), that the channel is closed.
+    ///
+    /// If the value is successfully enqueued for the remote end to receive,
+    /// then `Ok(())` is returned. If the receiving end was dropped before this
+    /// function was called, however, then `Err` is returned with the value
+    /// provided.
+    #[inline]
+    pub fn send_err(self, err: E) -> Result<(), E> {
+        self.inner.send_err(err)
+    }
+
+    /// Closes this channel with a final `reason` value.
+    ///
+    /// This function will consume `self`. The [`Receiver`](super::Receiver)
+    /// receives all pending events first, then `Err(reason)`, and then the
+    /// stream ends.
+    ///
+    /// If the receiving end was dropped before this function was called,
+    /// `Err` is returned with the `reason` provided.
+    #[inline]
+    pub fn close_with(self, reason: E) -> Result<(), E> {
+        self.send_err(reason)
+    }
+
+    /// Polls this `Sender` half to detect whether its associated
+    /// [`Receiver`](super::Receiver) with has been dropped.
+    ///
+    /// # Return values
+    ///
+    /// If `Ok(Ready)` is returned then the associated `Receiver` has been
+    /// dropped.
+    ///
+    /// If `Ok(Pending)` is returned then the associated `Receiver` is still
+    /// alive and may be able to receive values if sent. The current task,
+    /// however, is scheduled to receive a notification if the corresponding
+    /// `Receiver` goes away.
+    #[inline]
+    pub fn poll_canceled(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.inner.poll_half(
+            cx,
+            IS_TX_HALF,
+            Ordering::Relaxed,
+            Ordering::Release,
+            Inner::take_cancel,
+        )
+    }
+
+    /// Tests to see whether this `Sender`'s corresponding `Receiver` has been
+    /// dropped.
+    ///
+    /// Unlike [`poll_canceled`](Sender::poll_canceled), this function does not
+    /// enqueue a task for wakeup upon cancellation, but merely reports the
+    /// current state, which may be subject to concurrent modification.
+    #[inline]
+    pub fn is_canceled(&self) -> bool {
+        self.inner.is_canceled(Ordering::Relaxed)
+    }
+}
+
+impl<T, E> Drop for Sender<T, E> {
+    #[inline]
+    fn drop(&mut self) {
+        self.inner.close_half(IS_TX_HALF);
+    }
+}
+
+impl<T, E> Inner<T, E, Bells> {
+    fn ring(&self) -> Result<(), SendErrorKind> {
+        let state = self.state_load(Ordering::Acquire);
+        self.publish(state, |state| {
+            if Self::get_bells(*state) < MAX_BELLS {
+                *state += 1 << BELL_SHIFT;
+            }
+        })
+        .map_err(|()| SendErrorKind::Canceled)
+    }
+}
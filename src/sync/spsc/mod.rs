@@ -1,12 +1,14 @@
 //! Single-producer, single-consumer communication primitives.
 
+use crate::sync::Backoff;
 use core::{
     mem::MaybeUninit,
     ops::{BitAnd, BitOr, BitOrAssign, BitXorAssign},
-    sync::atomic::Ordering,
+    sync::atomic::{fence, Ordering},
     task::{Context, Poll, Waker},
 };
 
+pub mod doorbell;
 pub mod oneshot;
 pub mod pulse;
 pub mod ring;
@@ -18,6 +20,7 @@ where
     const ZERO: I;
     const RX_WAKER_STORED: I;
     const TX_WAKER_STORED: I;
+    const RX_WAITING: I;
     const COMPLETE: I;
 
     fn state_load(&self, order: Ordering) -> I;
@@ -44,6 +47,7 @@ where
         failure: Ordering,
         f: impl Fn(&mut I) -> Result<R, E>,
     ) -> Result<R, E> {
+        let mut backoff = Backoff::new();
         loop {
             let mut new = old;
             let result = f(&mut new);
@@ -54,6 +58,7 @@ where
                 Ok(_) => break result,
                 Err(x) => old = x,
             }
+            backoff.spin();
         }
     }
 
@@ -92,6 +97,11 @@ where
         take(self, state)
     }
 
+    // The receiving half registers its waker only once, and then only marks
+    // itself as waiting with `RX_WAITING` bit. The sending half clears the bit
+    // together with publishing a new value, and wakes the receiver only if the
+    // bit was set. So the receiver, which is busy draining the channel, doesn't
+    // get redundant wake-ups.
     fn poll_half_with_transaction<T, U, V>(
         &self,
         cx: &mut Context<'_>,
@@ -102,28 +112,48 @@ where
         take_finalize: fn(&Self, Result<U, V>) -> T,
     ) -> Poll<T> {
         let waker_stored = if is_tx_half { Self::TX_WAKER_STORED } else { Self::RX_WAKER_STORED };
+        let waiting = if is_tx_half { Self::ZERO } else { Self::RX_WAITING };
         let state = self.state_load(read_order);
-        self.transaction(state, cas_order, read_order, |state| match take_try(self, state) {
-            Some(value) => value.map(Ok).map_err(Ok),
-            None => Err(Err(if *state & waker_stored == Self::ZERO { Ok(()) } else { Err(()) })),
-        })
-        .or_else(|value| {
-            value.map(Err).or_else(|no_waker| {
-                no_waker.and_then(|()| {
-                    unsafe {
-                        let waker =
-                            if is_tx_half { self.tx_waker_mut() } else { self.rx_waker_mut() };
-                        waker.write(cx.waker().clone());
+        let poll = self.transaction(state, cas_order, read_order, |state| {
+            let was_waiting = *state & waiting != Self::ZERO;
+            if was_waiting {
+                *state ^= waiting;
+            }
+            match take_try(self, state) {
+                Some(Ok(value)) => Ok(Poll::Ready(Ok(value))),
+                Some(Err(value)) => Err(Some(Poll::Ready(Err(value)))),
+                None if *state & waker_stored == Self::ZERO => Err(None),
+                None if was_waiting => Err(Some(Poll::Pending)),
+                None => {
+                    *state |= waiting;
+                    Ok(Poll::Pending)
+                }
+            }
+        });
+        match poll {
+            Ok(poll) | Err(Some(poll)) => poll,
+            Err(None) => {
+                unsafe {
+                    let waker = if is_tx_half { self.tx_waker_mut() } else { self.rx_waker_mut() };
+                    waker.write(cx.waker().clone());
+                }
+                let Ok(poll) = self.transaction(state, cas_order, read_order, |state| {
+                    *state |= waker_stored;
+                    if *state & waiting != Self::ZERO {
+                        *state ^= waiting;
                     }
-                    let Ok(value) = self.transaction(state, cas_order, read_order, |state| {
-                        *state |= waker_stored;
-                        Ok::<_, !>(take_try(self, state))
-                    });
-                    value.ok_or(())
-                })
-            })
-        })
-        .map_or_else(|()| Poll::Pending, |value| Poll::Ready(take_finalize(self, value)))
+                    Ok::<_, !>(take_try(self, state).map_or_else(
+                        || {
+                            *state |= waiting;
+                            Poll::Pending
+                        },
+                        Poll::Ready,
+                    ))
+                });
+                poll
+            }
+        }
+        .map(|value| take_finalize(self, value))
     }
 
     fn close_half(&self, is_tx_half: bool) {
@@ -173,7 +203,9 @@ where
             Err(err)
         } else {
             unsafe { *self.err_mut() = Some(err) };
-            // Should we do an additional synchronization here?
+            // The error is published to the receiver by the sender drop, which
+            // sets the `COMPLETE` bit right after this fence.
+            fence(Ordering::Release);
             Ok(())
         }
     }
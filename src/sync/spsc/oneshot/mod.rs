@@ -1,6 +1,7 @@
 //! A channel for sending a single message between asynchronous tasks.
 //!
-//! See [`channel`] constructor for more.
+//! See [`channel`] constructor for more. A statically allocated channel, which
+//! doesn't need the heap, can be created with [`static_channel!`].
 
 mod receiver;
 mod sender;
@@ -15,7 +16,9 @@ use alloc::sync::Arc;
 use core::{
     cell::UnsafeCell,
     mem::MaybeUninit,
-    sync::atomic::{AtomicU8, Ordering},
+    ops::Deref,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
     task::Waker,
 };
 
@@ -31,6 +34,19 @@ struct Inner<T> {
     tx_waker: UnsafeCell<MaybeUninit<Waker>>,
 }
 
+enum Shared<T> {
+    Heap(Arc<Inner<T>>),
+    Static(NonNull<Inner<T>>),
+}
+
+/// A statically allocated one-shot channel.
+///
+/// Should be created with [`static_channel!`] macro.
+pub struct StaticChannel<T> {
+    inner: Inner<T>,
+    taken: AtomicBool,
+}
+
 /// Creates a new one-shot channel, returning the sender/receiver halves.
 ///
 /// The [`Sender`] half is used to signal the end of a computation and provide
@@ -39,28 +55,101 @@ struct Inner<T> {
 #[inline]
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
     let inner = Arc::new(Inner::new());
-    let sender = Sender::new(Arc::clone(&inner));
-    let receiver = Receiver::new(inner);
+    let sender = Sender::new(Shared::Heap(Arc::clone(&inner)));
+    let receiver = Receiver::new(Shared::Heap(inner));
     (sender, receiver)
 }
 
+/// Creates a new statically allocated one-shot channel of type `$ty`,
+/// returning the sender/receiver halves.
+///
+/// The channel is allocated in a `static` item instead of the heap, so it can
+/// be used before the heap is initialized, e.g. to signal from the reset
+/// handler to the first thread.
+///
+/// ```
+/// use drone_core::sync::spsc::oneshot;
+///
+/// let (tx, mut rx) = oneshot::static_channel!(u32);
+/// assert_eq!(tx.send(314), Ok(()));
+/// assert_eq!(rx.try_recv(), Ok(Some(314)));
+/// ```
+///
+/// # Panics
+///
+/// If the same macro invocation is evaluated more than once.
+#[doc(inline)]
+pub use crate::__oneshot_static_channel as static_channel;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __oneshot_static_channel {
+    ($ty:ty) => {{
+        static CHANNEL: $crate::sync::spsc::oneshot::StaticChannel<$ty> =
+            $crate::sync::spsc::oneshot::StaticChannel::new();
+        CHANNEL.split()
+    }};
+}
+
 unsafe impl<T: Send> Send for Inner<T> {}
 unsafe impl<T: Send> Sync for Inner<T> {}
 
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
 impl<T> Inner<T> {
     #[inline]
-    fn new() -> Self {
+    const fn new() -> Self {
         Self {
             state: AtomicU8::new(0),
             data: UnsafeCell::new(None),
-            rx_waker: UnsafeCell::new(MaybeUninit::zeroed()),
-            tx_waker: UnsafeCell::new(MaybeUninit::zeroed()),
+            rx_waker: UnsafeCell::new(MaybeUninit::uninit()),
+            tx_waker: UnsafeCell::new(MaybeUninit::uninit()),
         }
     }
 }
 
+impl<T> Default for StaticChannel<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = Inner<T>;
+
+    #[inline]
+    fn deref(&self) -> &Inner<T> {
+        match self {
+            Self::Heap(inner) => inner,
+            Self::Static(inner) => unsafe { inner.as_ref() },
+        }
+    }
+}
+
+impl<T> StaticChannel<T> {
+    /// Creates a new unused channel.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { inner: Inner::new(), taken: AtomicBool::new(false) }
+    }
+
+    /// Returns the sender/receiver halves of the channel.
+    ///
+    /// # Panics
+    ///
+    /// If the halves are already taken.
+    pub fn split(&'static self) -> (Sender<T>, Receiver<T>) {
+        assert!(!self.taken.swap(true, Ordering::Relaxed), "static channel is already taken");
+        let inner = NonNull::from(&self.inner);
+        (Sender::new(Shared::Static(inner)), Receiver::new(Shared::Static(inner)))
+    }
+}
+
 impl<T> SpscInner<AtomicU8, u8> for Inner<T> {
     const COMPLETE: u8 = COMPLETE;
+    const RX_WAITING: u8 = 0;
     const RX_WAKER_STORED: u8 = RX_WAKER_STORED;
     const TX_WAKER_STORED: u8 = TX_WAKER_STORED;
     const ZERO: u8 = 0;
@@ -141,4 +230,25 @@ mod tests {
         assert_eq!(Pin::new(&mut rx).poll(&mut cx), Poll::Ready(Ok(314)));
         assert_eq!(COUNTER.0.load(Ordering::SeqCst), 1);
     }
+
+    #[test]
+    fn send_static() {
+        static COUNTER: Counter = Counter(AtomicUsize::new(0));
+        let (tx, mut rx) = static_channel!(usize);
+        let waker = COUNTER.to_waker();
+        let mut cx = Context::from_waker(&waker);
+        COUNTER.0.store(0, Ordering::SeqCst);
+        assert_eq!(Pin::new(&mut rx).poll(&mut cx), Poll::Pending);
+        assert_eq!(tx.send(314), Ok(()));
+        assert_eq!(Pin::new(&mut rx).poll(&mut cx), Poll::Ready(Ok(314)));
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "already taken")]
+    fn static_twice() {
+        static CHANNEL: StaticChannel<usize> = StaticChannel::new();
+        let _ = CHANNEL.split();
+        let _ = CHANNEL.split();
+    }
 }
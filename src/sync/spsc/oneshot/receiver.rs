@@ -1,6 +1,5 @@
-use super::{Inner, COMPLETE};
+use super::{Inner, Shared, COMPLETE};
 use crate::sync::spsc::SpscInner;
-use alloc::sync::Arc;
 use core::{
     fmt,
     future::Future,
@@ -14,7 +13,7 @@ const IS_TX_HALF: bool = false;
 /// The receiving-half of [`oneshot::channel`](super::channel).
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct Receiver<T> {
-    inner: Arc<Inner<T>>,
+    inner: Shared<T>,
 }
 
 /// Error returned from a [`Receiver`] when the corresponding
@@ -23,7 +22,7 @@ pub struct Receiver<T> {
 pub struct Canceled;
 
 impl<T> Receiver<T> {
-    pub(super) fn new(inner: Arc<Inner<T>>) -> Self {
+    pub(super) fn new(inner: Shared<T>) -> Self {
         Self { inner }
     }
 
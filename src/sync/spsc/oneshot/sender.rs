@@ -1,6 +1,5 @@
-use super::Inner;
+use super::{Inner, Shared};
 use crate::sync::spsc::SpscInner;
-use alloc::sync::Arc;
 use core::{
     sync::atomic::Ordering,
     task::{Context, Poll},
@@ -10,11 +9,11 @@ const IS_TX_HALF: bool = true;
 
 /// The sending-half of [`oneshot::channel`](super::channel).
 pub struct Sender<T> {
-    inner: Arc<Inner<T>>,
+    inner: Shared<T>,
 }
 
 impl<T> Sender<T> {
-    pub(super) fn new(inner: Arc<Inner<T>>) -> Self {
+    pub(super) fn new(inner: Shared<T>) -> Self {
         Self { inner }
     }
 
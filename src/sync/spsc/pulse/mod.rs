@@ -27,7 +27,8 @@ pub const MAX_CAPACITY: usize = 1 << size_of::<usize>() as u32 * 8 - OPTION_BITS
 const TX_WAKER_STORED: usize = 1 << 0;
 const RX_WAKER_STORED: usize = 1 << 1;
 const COMPLETE: usize = 1 << 2;
-const OPTION_BITS: u32 = 3;
+const RX_WAITING: usize = 1 << 3;
+const OPTION_BITS: u32 = 4;
 
 struct Inner<E> {
     state: AtomicUsize,
@@ -66,6 +67,7 @@ impl<E> Inner<E> {
 
 impl<E> SpscInner<AtomicUsize, usize> for Inner<E> {
     const COMPLETE: usize = COMPLETE;
+    const RX_WAITING: usize = RX_WAITING;
     const RX_WAKER_STORED: usize = RX_WAKER_STORED;
     const TX_WAKER_STORED: usize = TX_WAKER_STORED;
     const ZERO: usize = 0;
@@ -177,6 +179,23 @@ mod tests {
         assert_eq!(COUNTER.0.load(Ordering::SeqCst), 0);
     }
 
+    #[test]
+    fn close_with() {
+        static COUNTER: Counter = Counter(AtomicUsize::new(0));
+        let (mut tx, mut rx) = channel::<u8>();
+        assert_eq!(tx.send(2).unwrap(), ());
+        assert_eq!(tx.close_with(42).unwrap(), ());
+        let waker = COUNTER.to_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(
+            Pin::new(&mut rx).poll_next(&mut cx),
+            Poll::Ready(Some(Ok(NonZeroUsize::new(2).unwrap())))
+        );
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(Err(42))));
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(None));
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 0);
+    }
+
     #[test]
     fn recv_many() {
         static COUNTER: Counter = Counter(AtomicUsize::new(0));
@@ -194,6 +213,6 @@ mod tests {
         assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
         drop(tx);
         assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(None));
-        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 4);
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 2);
     }
 }
@@ -1,4 +1,4 @@
-use super::{Inner, COMPLETE, OPTION_BITS, RX_WAKER_STORED};
+use super::{Inner, COMPLETE, OPTION_BITS, RX_WAITING};
 use crate::sync::spsc::{SpscInner, SpscInnerErr};
 use alloc::sync::Arc;
 use core::{
@@ -51,6 +51,20 @@ impl<E> Sender<E> {
         self.inner.send_err(err)
     }
 
+    /// Closes this channel with a final `reason` value.
+    ///
+    /// This function will consume `self`. The [`Receiver`](super::Receiver)
+    /// receives all pending pulses first, then `Err(reason)`, and then the
+    /// stream ends. This propagates the cause of the stream ending, like an
+    /// overrun or a bus error, in-band.
+    ///
+    /// If the receiving end was dropped before this function was called,
+    /// `Err` is returned with the `reason` provided.
+    #[inline]
+    pub fn close_with(self, reason: E) -> Result<(), E> {
+        self.send_err(reason)
+    }
+
     /// Polls this `Sender` half to detect whether its associated
     /// [`Receiver`](super::Receiver) with has been dropped.
     ///
@@ -102,10 +116,12 @@ impl<E> Inner<E> {
             }
             let pulses = pulses.checked_shl(OPTION_BITS).ok_or(SendError::Overflow)?;
             *state = state.checked_add(pulses).ok_or(SendError::Overflow)?;
-            Ok(*state)
+            let waiting = *state & RX_WAITING != 0;
+            *state &= !RX_WAITING;
+            Ok(waiting)
         })
-        .map(|state| {
-            if state & RX_WAKER_STORED != 0 {
+        .map(|waiting| {
+            if waiting {
                 unsafe { (*self.rx_waker.get()).assume_init_ref().wake_by_ref() };
             }
         })
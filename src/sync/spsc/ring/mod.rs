@@ -16,6 +16,7 @@ use alloc::{raw_vec::RawVec, sync::Arc};
 use core::{
     cell::UnsafeCell,
     cmp,
+    marker::PhantomData,
     mem::{size_of, MaybeUninit},
     ptr, slice,
     sync::atomic::{AtomicUsize, Ordering},
@@ -25,27 +26,42 @@ use core::{
 /// Maximum capacity of the channel.
 pub const MAX_CAPACITY: usize = (1 << NUMBER_BITS) - 1;
 
-const NUMBER_MASK: usize = (1 << NUMBER_BITS) - 1;
 const NUMBER_BITS: u32 = (size_of::<usize>() as u32 * 8 - OPTION_BITS) / 2;
 
-const _RESERVED: usize = 1 << size_of::<usize>() * 8 - 1;
+const RX_WAITING: usize = 1 << size_of::<usize>() * 8 - 1;
 const COMPLETE: usize = 1 << size_of::<usize>() * 8 - 2;
 const RX_WAKER_STORED: usize = 1 << size_of::<usize>() * 8 - 3;
 const TX_WAKER_STORED: usize = 1 << size_of::<usize>() * 8 - 4;
-const OPTION_BITS: u32 = 4;
+pub(super) const OPTION_BITS: u32 = 4;
 
 // Layout of the state field:
 //     OOOO_CCCC_LLLL
-// Where O are option bits, C are cursor bits, and L are lenght bits.
+// Where O are option bits, C are cursor bits, and L are lenght bits. Other
+// channels built on top of the ring buffer can reserve extra bits between the
+// option bits and the cursor bits with their own `Layout`.
 //
 // Cursor range: [0; MAX_CAPACITY - 1]
 // Length range: [0; MAX_CAPACITY]
-struct Inner<T, E> {
+pub(super) struct Inner<T, E, L: Layout = Plain> {
     state: AtomicUsize,
     buffer: RawVec<T>,
     err: UnsafeCell<Option<E>>,
     rx_waker: UnsafeCell<MaybeUninit<Waker>>,
     tx_waker: UnsafeCell<MaybeUninit<Waker>>,
+    layout: PhantomData<L>,
+}
+
+/// Widths of the cursor and length fields of the state word.
+pub(super) trait Layout {
+    const NUMBER_BITS: u32;
+    const NUMBER_MASK: usize = (1 << Self::NUMBER_BITS) - 1;
+}
+
+/// The layout of the plain ring channel, without extra bits.
+pub(super) enum Plain {}
+
+impl Layout for Plain {
+    const NUMBER_BITS: u32 = NUMBER_BITS;
 }
 
 /// Creates a new channel, returning the sender/receiver halves.
@@ -63,28 +79,32 @@ pub fn channel<T, E>(capacity: usize) -> (Sender<T, E>, Receiver<T, E>) {
     (sender, receiver)
 }
 
-unsafe impl<T: Send, E: Send> Send for Inner<T, E> {}
-unsafe impl<T: Send, E: Send> Sync for Inner<T, E> {}
+unsafe impl<T: Send, E: Send, L: Layout> Send for Inner<T, E, L> {}
+unsafe impl<T: Send, E: Send, L: Layout> Sync for Inner<T, E, L> {}
 
-impl<T, E> Inner<T, E> {
+impl<T, E, L: Layout> Inner<T, E, L> {
     #[inline]
-    fn new(capacity: usize) -> Self {
-        assert!(capacity <= MAX_CAPACITY);
+    pub(super) fn new(capacity: usize) -> Self {
+        assert!(capacity <= L::NUMBER_MASK);
         Self {
             state: AtomicUsize::new(0),
             buffer: RawVec::with_capacity(capacity),
             err: UnsafeCell::new(None),
             rx_waker: UnsafeCell::new(MaybeUninit::zeroed()),
             tx_waker: UnsafeCell::new(MaybeUninit::zeroed()),
+            layout: PhantomData,
         }
     }
 }
 
-impl<T, E> Drop for Inner<T, E> {
+impl<T, E, L: Layout> Drop for Inner<T, E, L> {
     fn drop(&mut self) {
         let state = self.state_load(Ordering::Acquire);
-        let length = state & NUMBER_MASK;
-        let cursor = state >> NUMBER_BITS & NUMBER_MASK;
+        let length = Self::get_length(state);
+        if length == 0 {
+            return;
+        }
+        let cursor = state >> L::NUMBER_BITS & L::NUMBER_MASK;
         let end = cursor.wrapping_add(length).wrapping_rem(self.buffer.capacity());
         match cursor.cmp(&end) {
             cmp::Ordering::Equal => unsafe {
@@ -110,8 +130,9 @@ impl<T, E> Drop for Inner<T, E> {
     }
 }
 
-impl<T, E> SpscInner<AtomicUsize, usize> for Inner<T, E> {
+impl<T, E, L: Layout> SpscInner<AtomicUsize, usize> for Inner<T, E, L> {
     const COMPLETE: usize = COMPLETE;
+    const RX_WAITING: usize = RX_WAITING;
     const RX_WAKER_STORED: usize = RX_WAKER_STORED;
     const TX_WAKER_STORED: usize = TX_WAKER_STORED;
     const ZERO: usize = 0;
@@ -143,7 +164,7 @@ impl<T, E> SpscInner<AtomicUsize, usize> for Inner<T, E> {
     }
 }
 
-impl<T, E> SpscInnerErr<AtomicUsize, usize> for Inner<T, E> {
+impl<T, E, L: Layout> SpscInnerErr<AtomicUsize, usize> for Inner<T, E, L> {
     type Error = E;
 
     unsafe fn err_mut(&self) -> &mut Option<Self::Error> {
@@ -154,6 +175,7 @@ impl<T, E> SpscInnerErr<AtomicUsize, usize> for Inner<T, E> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::rc::Rc;
     use core::{
         pin::Pin,
         sync::atomic::AtomicUsize,
@@ -205,4 +227,99 @@ mod tests {
         assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(None));
         assert_eq!(COUNTER.0.load(Ordering::SeqCst), 2);
     }
+
+    #[test]
+    fn close_with() {
+        let (mut tx, mut rx) = channel::<usize, u8>(10);
+        assert_eq!(tx.send(1).unwrap(), ());
+        assert_eq!(tx.send(2).unwrap(), ());
+        assert_eq!(tx.close_with(42).unwrap(), ());
+        assert_eq!(rx.try_next(), Ok(Some(1)));
+        assert_eq!(rx.try_next(), Ok(Some(2)));
+        assert_eq!(rx.try_next(), Err(42));
+        assert_eq!(rx.try_next(), Ok(None));
+        let (tx, rx) = channel::<usize, u8>(10);
+        drop(rx);
+        assert_eq!(tx.close_with(42), Err(42));
+    }
+
+    #[test]
+    fn wakeups_coalesced() {
+        static COUNTER: Counter = Counter(AtomicUsize::new(0));
+        let (mut tx, mut rx) = channel::<usize, ()>(10);
+        let waker = COUNTER.to_waker();
+        let mut cx = Context::from_waker(&waker);
+        COUNTER.0.store(0, Ordering::SeqCst);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(tx.send(1).unwrap(), ());
+        assert_eq!(tx.send(2).unwrap(), ());
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(Ok(1))));
+        assert_eq!(tx.send(3).unwrap(), ());
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(Ok(2))));
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(Ok(3))));
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(tx.send(4).unwrap(), ());
+        assert_eq!(COUNTER.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn raw_parts() {
+        let (tx, rx) = channel::<usize, ()>(10);
+        let tx = tx.into_raw_parts();
+        let rx = rx.into_raw_parts();
+        let mut tx = unsafe { Sender::<usize, ()>::from_raw_parts(tx) };
+        assert_eq!(tx.send(314).unwrap(), ());
+        drop(tx);
+        let mut rx = unsafe { Receiver::<usize, ()>::from_raw_parts(rx) };
+        assert_eq!(rx.try_next(), Ok(Some(314)));
+        assert_eq!(rx.try_next(), Ok(None));
+    }
+
+    #[test]
+    fn reserve() {
+        let (mut tx, mut rx) = channel::<u8, ()>(4);
+        for &value in &[1, 2, 3] {
+            tx.send(value).unwrap();
+        }
+        assert_eq!(rx.try_next(), Ok(Some(1)));
+        assert_eq!(rx.try_next(), Ok(Some(2)));
+        let window = tx.reserve();
+        assert_eq!(window.len(), 1);
+        window[0] = MaybeUninit::new(4);
+        unsafe { tx.commit(1).unwrap() };
+        let window = tx.reserve();
+        assert_eq!(window.len(), 2);
+        window[0] = MaybeUninit::new(5);
+        window[1] = MaybeUninit::new(6);
+        unsafe { tx.commit(2).unwrap() };
+        assert_eq!(tx.reserve().len(), 0);
+        assert_eq!(rx.try_next(), Ok(Some(3)));
+        assert_eq!(rx.try_next(), Ok(Some(4)));
+        assert_eq!(rx.try_next(), Ok(Some(5)));
+        assert_eq!(rx.try_next(), Ok(Some(6)));
+        drop(rx);
+        assert_eq!(unsafe { tx.commit(0) }, Err(SendErrorKind::Canceled));
+    }
+
+    #[test]
+    fn drop_empty() {
+        let value = Rc::new(());
+        let (mut tx, mut rx) = channel::<Rc<()>, ()>(2);
+        assert!(tx.send(Rc::clone(&value)).is_ok());
+        assert!(rx.try_next().is_ok());
+        assert_eq!(Rc::strong_count(&value), 1);
+        drop(tx);
+        drop(rx);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn drop_zero_capacity() {
+        let (tx, rx) = channel::<Rc<()>, ()>(0);
+        drop(tx);
+        drop(rx);
+    }
 }
@@ -1,7 +1,8 @@
-use super::{Inner, COMPLETE, NUMBER_BITS, NUMBER_MASK};
+use super::{Inner, Layout, COMPLETE};
 use crate::sync::spsc::{SpscInner, SpscInnerErr};
 use alloc::sync::Arc;
 use core::{
+    mem::ManuallyDrop,
     pin::Pin,
     ptr,
     sync::atomic::Ordering,
@@ -22,6 +23,30 @@ impl<T, E> Receiver<T, E> {
         Self { inner }
     }
 
+    /// Consumes the receiver, returning an opaque raw pointer to the channel.
+    ///
+    /// The channel stays open until the receiver is reassembled with
+    /// [`Receiver::from_raw_parts`] and dropped. See
+    /// [`Sender::into_raw_parts`](super::Sender::into_raw_parts) for an
+    /// example.
+    #[inline]
+    pub fn into_raw_parts(self) -> *const () {
+        let receiver = ManuallyDrop::new(self);
+        Arc::into_raw(unsafe { ptr::read(&receiver.inner) }).cast()
+    }
+
+    /// Reassembles a receiver from the raw pointer returned by
+    /// [`Receiver::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be returned by [`Receiver::into_raw_parts`] of a receiver
+    /// with the same `T` and `E`, and must be reassembled only once.
+    #[inline]
+    pub unsafe fn from_raw_parts(raw: *const ()) -> Self {
+        Self::new(unsafe { Arc::from_raw(raw.cast()) })
+    }
+
     /// Gracefully close this receiver, preventing any subsequent attempts to
     /// send to it.
     ///
@@ -69,21 +94,27 @@ impl<T, E> Drop for Receiver<T, E> {
     }
 }
 
-impl<T, E> Inner<T, E> {
-    pub(super) fn take_index(&self, state: &mut usize, length: usize) -> usize {
-        let cursor = *state >> NUMBER_BITS & NUMBER_MASK;
-        *state >>= NUMBER_BITS << 1;
-        *state <<= NUMBER_BITS;
+impl<T, E, L: Layout> Inner<T, E, L> {
+    pub(in crate::sync::spsc) fn take_index(&self, state: &mut usize, length: usize) -> usize {
+        let cursor = *state >> L::NUMBER_BITS & L::NUMBER_MASK;
+        *state >>= L::NUMBER_BITS << 1;
+        *state <<= L::NUMBER_BITS;
         *state |= cursor.wrapping_add(1).wrapping_rem(self.buffer.capacity());
-        *state <<= NUMBER_BITS;
+        *state <<= L::NUMBER_BITS;
         *state |= length.wrapping_sub(1);
         cursor
     }
 
-    pub(super) fn get_length(state: usize) -> usize {
-        state & NUMBER_MASK
+    pub(in crate::sync::spsc) fn get_length(state: usize) -> usize {
+        state & L::NUMBER_MASK
     }
 
+    pub(in crate::sync::spsc) unsafe fn take_value(&self, index: usize) -> T {
+        unsafe { ptr::read(self.buffer.ptr().add(index)) }
+    }
+}
+
+impl<T, E> Inner<T, E> {
     fn try_next(&self) -> Result<Option<T>, E> {
         let state = self.state_load(Ordering::Acquire);
         self.transaction(state, Ordering::AcqRel, Ordering::Acquire, |state| {
@@ -113,8 +144,4 @@ impl<T, E> Inner<T, E> {
             Err(()) => self.take_err(),
         }
     }
-
-    unsafe fn take_value(&self, index: usize) -> T {
-        unsafe { ptr::read(self.buffer.ptr().add(index)) }
-    }
 }
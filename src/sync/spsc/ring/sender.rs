@@ -1,8 +1,13 @@
-use super::{Inner, COMPLETE, NUMBER_BITS, NUMBER_MASK, RX_WAKER_STORED};
-use crate::sync::spsc::{SpscInner, SpscInnerErr};
+use super::{Inner, Layout, COMPLETE, RX_WAITING};
+use crate::{
+    check::{self, Fault},
+    sync::spsc::{SpscInner, SpscInnerErr},
+};
 use alloc::sync::Arc;
 use core::{
-    fmt, ptr,
+    cmp, fmt,
+    mem::{ManuallyDrop, MaybeUninit},
+    ptr, slice,
     sync::atomic::Ordering,
     task::{Context, Poll},
 };
@@ -37,6 +42,56 @@ impl<T, E> Sender<T, E> {
         Self { inner }
     }
 
+    /// Consumes the sender, returning an opaque raw pointer to the channel.
+    ///
+    /// The channel stays open until the sender is reassembled with
+    /// [`Sender::from_raw_parts`] and dropped. The pointer can be stashed in a
+    /// `static`, e.g. to be accessed from an interrupt handler. The handler
+    /// stashes the sender back at the end, so that the channel stays open for
+    /// the next interrupt:
+    ///
+    /// ```
+    /// use core::sync::atomic::{AtomicPtr, Ordering};
+    /// use drone_core::sync::spsc::ring::{channel, Sender};
+    ///
+    /// static TX: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+    ///
+    /// fn interrupt_handler(value: u8) {
+    ///     let raw = TX.swap(core::ptr::null_mut(), Ordering::Acquire);
+    ///     if raw.is_null() {
+    ///         return;
+    ///     }
+    ///     let mut tx = unsafe { Sender::<u8, ()>::from_raw_parts(raw) };
+    ///     tx.send(value).ok();
+    ///     TX.store(tx.into_raw_parts() as *mut (), Ordering::Release);
+    /// }
+    ///
+    /// let (tx, mut rx) = channel::<u8, ()>(4);
+    /// TX.store(tx.into_raw_parts() as *mut (), Ordering::Release);
+    ///
+    /// interrupt_handler(1);
+    /// interrupt_handler(2);
+    /// assert_eq!(rx.try_next(), Ok(Some(1)));
+    /// assert_eq!(rx.try_next(), Ok(Some(2)));
+    /// ```
+    #[inline]
+    pub fn into_raw_parts(self) -> *const () {
+        let sender = ManuallyDrop::new(self);
+        Arc::into_raw(unsafe { ptr::read(&sender.inner) }).cast()
+    }
+
+    /// Reassembles a sender from the raw pointer returned by
+    /// [`Sender::into_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be returned by [`Sender::into_raw_parts`] of a sender with
+    /// the same `T` and `E`, and must be reassembled only once.
+    #[inline]
+    pub unsafe fn from_raw_parts(raw: *const ()) -> Self {
+        Self::new(unsafe { Arc::from_raw(raw.cast()) })
+    }
+
     /// Puts `value` to the ring buffer. The value can be immediately read by
     /// the receiving half.
     ///
@@ -46,6 +101,9 @@ impl<T, E> Sender<T, E> {
     /// then `Err` is returned with the value provided.
     #[inline]
     pub fn send(&mut self, value: T) -> Result<(), SendError<T>> {
+        if check::inject!(Fault::Overflow) {
+            return Err(SendError { value, kind: SendErrorKind::Overflow });
+        }
         self.inner.send(value)
     }
 
@@ -61,6 +119,54 @@ impl<T, E> Sender<T, E> {
         self.inner.send_overwrite(value)
     }
 
+    /// Returns the longest contiguous run of free slots in the ring buffer.
+    ///
+    /// Values can be written to the window in place, and then published with
+    /// [`Sender::commit`]. For bulk producers, like a UART receiver, this saves
+    /// a copy compared to [`Sender::send`]. The window is empty if the ring
+    /// buffer is full, and can be shorter than the free space if the free
+    /// space wraps around the end of the ring buffer.
+    ///
+    /// ```
+    /// use core::mem::MaybeUninit;
+    /// use drone_core::sync::spsc::ring::channel;
+    ///
+    /// let (mut tx, mut rx) = channel::<u8, ()>(4);
+    /// let window = tx.reserve();
+    /// assert_eq!(window.len(), 4);
+    /// window[0] = MaybeUninit::new(1);
+    /// window[1] = MaybeUninit::new(2);
+    /// unsafe { tx.commit(2).unwrap() };
+    ///
+    /// assert_eq!(rx.try_next(), Ok(Some(1)));
+    /// assert_eq!(rx.try_next(), Ok(Some(2)));
+    /// ```
+    #[inline]
+    pub fn reserve(&mut self) -> &mut [MaybeUninit<T>] {
+        let state = self.inner.state_load(Ordering::Acquire);
+        let (index, length) = self.inner.window(state);
+        unsafe { slice::from_raw_parts_mut(self.inner.buffer.ptr().add(index).cast(), length) }
+    }
+
+    /// Publishes the first `count` values of the window returned by
+    /// [`Sender::reserve`]. The values can be immediately read by the
+    /// receiving half.
+    ///
+    /// If the receiving end was dropped before this function was called, the
+    /// values are dropped, and `Err` is returned.
+    ///
+    /// # Safety
+    ///
+    /// The first `count` slots of the window must be initialized.
+    ///
+    /// # Panics
+    ///
+    /// If `count` exceeds the window length.
+    #[inline]
+    pub unsafe fn commit(&mut self, count: usize) -> Result<(), SendErrorKind> {
+        unsafe { self.inner.commit(count) }
+    }
+
     /// Completes this channel with an `Err` result.
     ///
     /// This function will consume `self` and indicate to the other end, the
@@ -75,6 +181,20 @@ impl<T, E> Sender<T, E> {
         self.inner.send_err(err)
     }
 
+    /// Closes this channel with a final `reason` value.
+    ///
+    /// This function will consume `self`. The [`Receiver`](super::Receiver)
+    /// receives all buffered values first, then `Err(reason)`, and then the
+    /// stream ends. This propagates the cause of the stream ending, like an
+    /// overrun or a bus error, in-band.
+    ///
+    /// If the receiving end was dropped before this function was called,
+    /// `Err` is returned with the `reason` provided.
+    #[inline]
+    pub fn close_with(self, reason: E) -> Result<(), E> {
+        self.send_err(reason)
+    }
+
     /// Polls this `Sender` half to detect whether its associated
     /// [`Receiver`](super::Receiver) with has been dropped.
     ///
@@ -117,9 +237,9 @@ impl<T, E> Drop for Sender<T, E> {
     }
 }
 
-impl<T, E> Inner<T, E> {
+impl<T, E, L: Layout> Inner<T, E, L> {
     #[allow(clippy::option_if_let_else)]
-    fn send(&self, value: T) -> Result<(), SendError<T>> {
+    pub(in crate::sync::spsc) fn send(&self, value: T) -> Result<(), SendError<T>> {
         let state = self.state_load(Ordering::Acquire);
         if let Some(index) = self.put_index_try(state) {
             self.put(value, state, index)
@@ -159,20 +279,55 @@ impl<T, E> Inner<T, E> {
     fn put(&self, value: T, state: usize, index: usize) -> Result<(), T> {
         let buffer_ptr = unsafe { self.buffer.ptr().add(index) };
         unsafe { ptr::write(buffer_ptr, value) };
+        self.publish(state, |state| *state = state.wrapping_add(1))
+            .map_err(|()| unsafe { ptr::read(buffer_ptr) })
+    }
+
+    unsafe fn commit(&self, count: usize) -> Result<(), SendErrorKind> {
+        let state = self.state_load(Ordering::Acquire);
+        let (index, length) = self.window(state);
+        assert!(count <= length, "commit exceeds the reserved window");
+        self.publish(state, |state| *state = state.wrapping_add(count)).map_err(|()| {
+            unsafe {
+                ptr::drop_in_place(slice::from_raw_parts_mut(self.buffer.ptr().add(index), count));
+            }
+            SendErrorKind::Canceled
+        })
+    }
+
+    /// Applies `f` to the state and wakes the receiver if it is waiting. Fails
+    /// if the channel is closed.
+    pub(in crate::sync::spsc) fn publish(
+        &self,
+        state: usize,
+        f: impl Fn(&mut usize),
+    ) -> Result<(), ()> {
         self.transaction(state, Ordering::AcqRel, Ordering::Acquire, |state| {
             if *state & COMPLETE == 0 {
-                *state = state.wrapping_add(1);
-                Ok(*state)
+                f(state);
+                let waiting = *state & RX_WAITING != 0;
+                *state &= !RX_WAITING;
+                Ok(waiting)
             } else {
                 Err(())
             }
         })
-        .map(|state| {
-            if state & RX_WAKER_STORED != 0 {
+        .map(|waiting| {
+            if waiting {
                 unsafe { (*self.rx_waker.get()).assume_init_ref().wake_by_ref() };
             }
         })
-        .map_err(|()| unsafe { ptr::read(buffer_ptr) })
+    }
+
+    /// Returns the index and the length of the contiguous free space.
+    fn window(&self, state: usize) -> (usize, usize) {
+        let length = Self::get_length(state);
+        let capacity = self.buffer.capacity();
+        if length == capacity {
+            return (0, 0);
+        }
+        let index = self.put_index(state, length);
+        (index, cmp::min(capacity - length, capacity - index))
     }
 
     fn put_index_try(&self, state: usize) -> Option<usize> {
@@ -181,7 +336,7 @@ impl<T, E> Inner<T, E> {
     }
 
     fn put_index(&self, state: usize, length: usize) -> usize {
-        let cursor = state >> NUMBER_BITS & NUMBER_MASK;
+        let cursor = state >> L::NUMBER_BITS & L::NUMBER_MASK;
         cursor.wrapping_add(length).wrapping_rem(self.buffer.capacity())
     }
 }
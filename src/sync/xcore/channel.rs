@@ -0,0 +1,186 @@
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A bounded single-producer, single-consumer queue shared across cores.
+///
+/// The queue holds up to `N - 1` items. It uses only atomic loads and stores,
+/// so it works on cores without compare-and-swap instructions.
+#[repr(C)]
+pub struct Channel<T, const N: usize> {
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    buffer: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+/// The sending half of a [`Channel`].
+pub struct Producer<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+    _marker: PhantomData<*const ()>,
+}
+
+/// The receiving half of a [`Channel`].
+pub struct Consumer<'a, T, const N: usize> {
+    channel: &'a Channel<T, N>,
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for Channel<T, N> {}
+unsafe impl<T: Send, const N: usize> Send for Producer<'_, T, N> {}
+unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+impl<T, const N: usize> Channel<T, N> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const SLOT: UnsafeCell<MaybeUninit<T>> = UnsafeCell::new(MaybeUninit::uninit());
+
+    /// Creates a new empty channel.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { head: AtomicUsize::new(0), tail: AtomicUsize::new(0), buffer: [Self::SLOT; N] }
+    }
+
+    /// Returns the sending half of the channel.
+    ///
+    /// # Safety
+    ///
+    /// At most one producer for the channel may exist across all cores at a
+    /// time.
+    #[inline]
+    pub unsafe fn producer(&self) -> Producer<'_, T, N> {
+        Producer { channel: self, _marker: PhantomData }
+    }
+
+    /// Returns the receiving half of the channel.
+    ///
+    /// # Safety
+    ///
+    /// At most one consumer for the channel may exist across all cores at a
+    /// time.
+    #[inline]
+    pub unsafe fn consumer(&self) -> Consumer<'_, T, N> {
+        Consumer { channel: self, _marker: PhantomData }
+    }
+
+    /// Returns `true` if the channel contains no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire)
+    }
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            unsafe { self.buffer[head].get_mut().as_mut_ptr().drop_in_place() };
+            head = (head + 1) % N;
+        }
+    }
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Attempts to send `value` to the other core.
+    ///
+    /// Returns `Err` with `value` if the channel is full.
+    pub fn send(&mut self, value: T) -> Result<(), T> {
+        let channel = self.channel;
+        let tail = channel.tail.load(Ordering::Relaxed);
+        let next = (tail + 1) % N;
+        if next == channel.head.load(Ordering::Acquire) {
+            return Err(value);
+        }
+        unsafe { (*channel.buffer[tail].get()).write(value) };
+        channel.tail.store(next, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Attempts to receive a value from the other core.
+    ///
+    /// Returns `None` if the channel is empty.
+    pub fn recv(&mut self) -> Option<T> {
+        let channel = self.channel;
+        let head = channel.head.load(Ordering::Relaxed);
+        if head == channel.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let value = unsafe { (*channel.buffer[head].get()).as_ptr().read() };
+        channel.head.store((head + 1) % N, Ordering::Release);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn full() {
+        let channel = Channel::<u32, 3>::new();
+        let mut tx = unsafe { channel.producer() };
+        let mut rx = unsafe { channel.consumer() };
+        assert_eq!(rx.recv(), None);
+        assert_eq!(tx.send(1), Ok(()));
+        assert_eq!(tx.send(2), Ok(()));
+        assert_eq!(tx.send(3), Err(3));
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(tx.send(3), Ok(()));
+        assert_eq!(rx.recv(), Some(2));
+        assert_eq!(rx.recv(), Some(3));
+        assert!(channel.is_empty());
+    }
+
+    #[test]
+    fn drop_queued() {
+        let value = Rc::new(());
+        let channel = Channel::<Rc<()>, 4>::new();
+        let mut tx = unsafe { channel.producer() };
+        let mut rx = unsafe { channel.consumer() };
+        for _ in 0..3 {
+            assert!(tx.send(Rc::clone(&value)).is_ok());
+        }
+        drop(rx.recv());
+        assert!(tx.send(Rc::clone(&value)).is_ok());
+        assert_eq!(Rc::strong_count(&value), 4);
+        drop(channel);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn cross_thread() {
+        static CHANNEL: Channel<usize, 4> = Channel::new();
+        let producer = thread::spawn(|| {
+            let mut tx = unsafe { CHANNEL.producer() };
+            for i in 0..1000 {
+                let mut value = i;
+                while let Err(rejected) = tx.send(value) {
+                    value = rejected;
+                    thread::yield_now();
+                }
+            }
+        });
+        let mut rx = unsafe { CHANNEL.consumer() };
+        let mut expected = 0;
+        while expected < 1000 {
+            if let Some(value) = rx.recv() {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+        }
+        producer.join().unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+//! Inter-processor communication for multi-core MCUs.
+//!
+//! Cores of an asymmetric multi-core MCU (e.g. Cortex-M4 + Cortex-M0+) usually
+//! run separate images, which share a region of RAM. The types in this module
+//! are `#[repr(C)]`, so both images can place them at the same address in the
+//! shared memory, and use only atomic loads and stores with acquire/release
+//! ordering, which are compiled to proper memory barriers.
+//!
+//! * [`SpinLock`] protects shared data. The lock itself is pluggable through
+//!   [`RawSpinLock`], because some cores, like Cortex-M0, lack atomic
+//!   compare-and-swap instructions, and should use a hardware semaphore
+//!   instead.
+//! * [`Channel`] is a bounded single-producer, single-consumer queue.
+//! * [`Mailbox`] abstracts a hardware mechanism to interrupt the other core,
+//!   so it doesn't need to poll the shared memory.
+//!
+//! ```
+//! use drone_core::sync::xcore::{Channel, Mailbox};
+//!
+//! // Normally placed in a shared memory section known to both images.
+//! static CHANNEL: Channel<u32, 8> = Channel::new();
+//!
+//! struct Ipcc;
+//!
+//! impl Mailbox for Ipcc {
+//!     fn signal(&self) {
+//!         // Raise an interrupt on the other core here.
+//!     }
+//! }
+//!
+//! // On the first core.
+//! let mut tx = unsafe { CHANNEL.producer() };
+//! tx.send(314).unwrap();
+//! Ipcc.signal();
+//!
+//! // On the second core, in the mailbox interrupt handler.
+//! let mut rx = unsafe { CHANNEL.consumer() };
+//! assert_eq!(rx.recv(), Some(314));
+//! ```
+
+mod channel;
+mod spin;
+
+pub use self::{
+    channel::{Channel, Consumer, Producer},
+    spin::{AtomicSpinLock, RawSpinLock, SpinLock, SpinLockGuard},
+};
+
+/// A hardware mechanism to signal an event to another core.
+///
+/// Examples are IPCC on STM32WB/MP1, the inter-processor FIFO on RP2040, or a
+/// plain `SEV` instruction.
+pub trait Mailbox: Sync {
+    /// Signals the event to the other core.
+    fn signal(&self);
+}
@@ -0,0 +1,202 @@
+use crate::sync::Backoff;
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A lock primitive shared across cores.
+///
+/// # Safety
+///
+/// A successful [`try_lock`](RawSpinLock::try_lock) must synchronize-with the
+/// previous [`unlock`](RawSpinLock::unlock), i.e. have acquire and release
+/// semantics respectively.
+pub unsafe trait RawSpinLock: Sync {
+    /// Attempts to acquire the lock. Returns `true` on success.
+    fn try_lock(&self) -> bool;
+
+    /// Releases the lock.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by the caller.
+    unsafe fn unlock(&self);
+}
+
+/// A [`RawSpinLock`] implementation based on atomic compare-and-swap.
+///
+/// Not available on cores without the instruction, e.g. Cortex-M0.
+#[repr(C)]
+pub struct AtomicSpinLock(AtomicBool);
+
+/// A spinning mutual exclusion primitive safe to use across cores.
+///
+/// Unlike [`Mutex`](crate::sync::Mutex), the lock doesn't yield to other
+/// fibers, so it should be held only for short periods. Waiting is done with
+/// [`Backoff::snooze`], which may put the core to sleep until the other core
+/// releases the lock.
+#[repr(C)]
+pub struct SpinLock<T: ?Sized, R: RawSpinLock = AtomicSpinLock> {
+    raw: R,
+    data: UnsafeCell<T>,
+}
+
+/// An RAII implementation of a "scoped lock" of a [`SpinLock`]. When this
+/// structure is dropped (falls out of scope), the lock will be unlocked.
+#[must_use = "if unused the SpinLock will immediately unlock"]
+pub struct SpinLockGuard<'a, T: ?Sized, R: RawSpinLock> {
+    lock: &'a SpinLock<T, R>,
+}
+
+unsafe impl<T: ?Sized + Send, R: RawSpinLock> Send for SpinLock<T, R> {}
+unsafe impl<T: ?Sized + Send, R: RawSpinLock> Sync for SpinLock<T, R> {}
+unsafe impl<T: ?Sized + Sync, R: RawSpinLock> Sync for SpinLockGuard<'_, T, R> {}
+
+impl AtomicSpinLock {
+    /// Creates a new unlocked lock.
+    #[inline]
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+}
+
+impl Default for AtomicSpinLock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl RawSpinLock for AtomicSpinLock {
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.0.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl<T> SpinLock<T> {
+    /// Creates a new spin lock in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(data: T) -> Self {
+        Self::with_raw(AtomicSpinLock::new(), data)
+    }
+}
+
+impl<T, R: RawSpinLock> SpinLock<T, R> {
+    /// Creates a new spin lock backed by `raw`.
+    #[inline]
+    pub const fn with_raw(raw: R, data: T) -> Self {
+        Self { raw, data: UnsafeCell::new(data) }
+    }
+
+    /// Consumes this lock, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized, R: RawSpinLock> SpinLock<T, R> {
+    /// Acquires the lock, spinning until it is able to do so.
+    pub fn lock(&self) -> SpinLockGuard<'_, T, R> {
+        let mut backoff = Backoff::new();
+        while !self.raw.try_lock() {
+            backoff.snooze();
+        }
+        SpinLockGuard { lock: self }
+    }
+
+    /// Attempts to acquire the lock without spinning.
+    ///
+    /// If the lock could not be acquired at this time, then `None` is returned.
+    #[inline]
+    pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T, R>> {
+        if self.raw.try_lock() { Some(SpinLockGuard { lock: self }) } else { None }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the lock mutably, no actual locking needs to
+    /// take place.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data.get() }
+    }
+}
+
+impl<T: Default> Default for SpinLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, R: RawSpinLock> fmt::Debug for SpinLock<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_lock() {
+            Some(guard) => f.debug_struct("SpinLock").field("data", &&*guard).finish(),
+            None => f.debug_struct("SpinLock").field("data", &"<locked>").finish(),
+        }
+    }
+}
+
+impl<T: ?Sized, R: RawSpinLock> Deref for SpinLockGuard<'_, T, R> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized, R: RawSpinLock> DerefMut for SpinLockGuard<'_, T, R> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized, R: RawSpinLock> Drop for SpinLockGuard<'_, T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.lock.raw.unlock() };
+        Backoff::notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn contention() {
+        let lock = Arc::new(SpinLock::new(0_usize));
+        let threads = (0..4)
+            .map(|_| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        *lock.lock() += 1;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*lock.lock(), 4000);
+        let guard = lock.lock();
+        assert!(lock.try_lock().is_none());
+        drop(guard);
+        assert!(lock.try_lock().is_some());
+    }
+}
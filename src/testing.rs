@@ -0,0 +1,214 @@
+//! On-target test harness.
+//!
+//! Host-side `cargo test` can't exercise interrupt priorities, peripherals, and
+//! timing of the real hardware. Functions marked with
+//! [`#[drone_test]`](drone_test) are registered in a linker section, and
+//! executed sequentially on the target by [`run`]. Test functions take no
+//! arguments and return either `()` or `Result<(), E>` where `E: Debug`. Async
+//! test functions are awaited, so the runner future must be executed by a
+//! thread, and the tests can wait for interrupts.
+//!
+//! The linker script must keep the `.drone_tests` section and surround it with
+//! `DRONE_TESTS_START` and `DRONE_TESTS_END` symbols.
+//!
+//! ```
+//! use drone_core::{
+//!     crc::{Crc32, CRC32_ISO_HDLC},
+//!     testing::drone_test,
+//! };
+//!
+//! #[drone_test]
+//! fn checksum() {
+//!     assert_eq!(Crc32::new(CRC32_ISO_HDLC).checksum(b"123456789"), 0xCBF4_3926);
+//! }
+//!
+//! #[drone_test]
+//! async fn timer_fires() -> Result<(), &'static str> {
+//!     // Await a timer interrupt here.
+//!     Ok(())
+//! }
+//!
+//! #[drone_test(ignore)]
+//! fn flaky() {}
+//! # fn main() {}
+//! ```
+//!
+//! # Protocol
+//!
+//! The runner writes one line per step to the log port [`PORT`]:
+//!
+//! ```text
+//! drone-test start 3
+//! drone-test run app::tests::checksum
+//! drone-test ok app::tests::checksum
+//! drone-test run app::tests::timer_fires
+//! drone-test fail app::tests::timer_fires: "timeout"
+//! drone-test ignored app::tests::flaky
+//! drone-test done passed=1 failed=1 ignored=1
+//! ```
+//!
+//! A failed assertion panics, which stops the run. Register [`PanicFail`] as
+//! the panic strategy to report the panic as a failure of the current test.
+
+use crate::{
+    log::{self, Port},
+    panic::{Halt, PanicHandler},
+};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    fmt::Write,
+    future::Future,
+    mem::size_of,
+    panic::PanicInfo,
+    pin::Pin,
+    ptr, slice,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// Marks a function as an on-target test.
+///
+/// See [the module-level documentation](self) for details.
+#[doc(inline)]
+pub use drone_core_macros::drone_test;
+
+extern "C" {
+    static DRONE_TESTS_START: UnsafeCell<usize>;
+    static DRONE_TESTS_END: UnsafeCell<usize>;
+}
+
+/// The log port number for test reports.
+pub const PORT: u8 = 27;
+
+static CURRENT: AtomicPtr<Test> = AtomicPtr::new(ptr::null_mut());
+
+/// A test registered with [`#[drone_test]`](drone_test).
+pub struct Test {
+    /// The test path.
+    pub name: &'static str,
+    /// Whether the test is skipped.
+    pub ignore: bool,
+    /// The test function.
+    pub run: TestFn,
+}
+
+/// A test function.
+pub enum TestFn {
+    /// A synchronous test function.
+    Sync(fn() -> Outcome),
+    /// An asynchronous test function.
+    Async(fn() -> Pin<Box<dyn Future<Output = Outcome> + Send>>),
+}
+
+/// A result of a test function.
+pub type Outcome = Result<(), Box<dyn fmt::Debug + Send>>;
+
+/// A return type of a test function.
+pub trait TestResult {
+    /// Converts the value into an [`Outcome`].
+    fn into_outcome(self) -> Outcome;
+}
+
+/// Numbers of tests by result.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Summary {
+    /// The number of passed tests.
+    pub passed: u32,
+    /// The number of failed tests.
+    pub failed: u32,
+    /// The number of ignored tests.
+    pub ignored: u32,
+}
+
+/// A panic strategy, which reports the panic as a failure of the current test,
+/// and halts.
+pub struct PanicFail;
+
+impl TestResult for () {
+    #[inline]
+    fn into_outcome(self) -> Outcome {
+        Ok(())
+    }
+}
+
+impl<E: fmt::Debug + Send + 'static> TestResult for Result<(), E> {
+    #[inline]
+    fn into_outcome(self) -> Outcome {
+        self.map_err(|err| Box::new(err) as Box<dyn fmt::Debug + Send>)
+    }
+}
+
+impl PanicHandler for PanicFail {
+    fn handle(info: &PanicInfo<'_>) -> ! {
+        if let Some(test) = current() {
+            report(format_args!("fail {}: {}", test.name, info));
+        }
+        Halt::handle(info)
+    }
+}
+
+/// Returns an iterator over all registered tests.
+pub fn tests() -> impl Iterator<Item = &'static Test> {
+    let tests = unsafe {
+        let count =
+            (DRONE_TESTS_END.get() as usize - DRONE_TESTS_START.get() as usize) / size_of::<Test>();
+        slice::from_raw_parts(DRONE_TESTS_START.get().cast::<Test>(), count)
+    };
+    tests.iter()
+}
+
+/// Returns the currently running test, if any.
+pub fn current() -> Option<&'static Test> {
+    unsafe { CURRENT.load(Ordering::Acquire).as_ref() }
+}
+
+/// Runs all registered tests sequentially, and reports the results to the log
+/// port [`PORT`].
+pub async fn run() -> Summary {
+    let mut summary = Summary::default();
+    report(format_args!("start {}", tests().count()));
+    for test in tests() {
+        if test.ignore {
+            report(format_args!("ignored {}", test.name));
+            summary.ignored += 1;
+            continue;
+        }
+        report(format_args!("run {}", test.name));
+        CURRENT.store(test as *const Test as *mut Test, Ordering::Release);
+        let outcome = match test.run {
+            TestFn::Sync(f) => f(),
+            TestFn::Async(f) => f().await,
+        };
+        CURRENT.store(ptr::null_mut(), Ordering::Release);
+        match outcome {
+            Ok(()) => {
+                report(format_args!("ok {}", test.name));
+                summary.passed += 1;
+            }
+            Err(err) => {
+                report(format_args!("fail {}: {:?}", test.name, err));
+                summary.failed += 1;
+            }
+        }
+    }
+    let Summary { passed, failed, ignored } = summary;
+    report(format_args!("done passed={} failed={} ignored={}", passed, failed, ignored));
+    log::flush();
+    summary
+}
+
+#[doc(hidden)]
+pub fn box_async<F>(future: F) -> Pin<Box<dyn Future<Output = Outcome> + Send>>
+where
+    F: Future + Send + 'static,
+    F::Output: TestResult,
+{
+    Box::pin(async move { future.await.into_outcome() })
+}
+
+fn report(args: fmt::Arguments<'_>) {
+    let mut port = Port::new(PORT);
+    if port.is_enabled() {
+        writeln!(port, "drone-test {}", args).ok();
+    }
+}
@@ -0,0 +1,179 @@
+#![cfg_attr(feature = "std", allow(unreachable_code))]
+
+use core::marker::PhantomData;
+
+/// A token proving that the code runs inside a critical section.
+///
+/// Instances are created only by [`critical`] function, and can't outlive the
+/// critical section.
+#[derive(Clone, Copy, Debug)]
+pub struct CriticalSection<'cs> {
+    _marker: PhantomData<&'cs ()>,
+}
+
+/// A platform implementation of critical sections.
+///
+/// A critical section must prevent all threads, which may access the protected
+/// data, from preempting the current context. Usually it is implemented by
+/// masking interrupts. The implementation is registered with the
+/// [`set_critical!`](crate::set_critical) macro.
+///
+/// With the `critical-section` feature enabled, the registered implementation
+/// is also used by the `critical-section` crate, so that the crates of this
+/// ecosystem interoperate with Drone threads.
+///
+/// # Safety
+///
+/// The implementation must provide mutual exclusion between all contexts.
+/// Nested critical sections must be supported.
+pub unsafe trait Critical {
+    /// Enters a critical section and returns the state to restore on exit.
+    ///
+    /// # Safety
+    ///
+    /// Each call must be paired with a call to [`Critical::release`].
+    unsafe fn acquire() -> u32;
+
+    /// Exits a critical section and restores the state returned by the paired
+    /// [`Critical::acquire`] call.
+    ///
+    /// # Safety
+    ///
+    /// Critical sections must be exited in the reverse order of entering.
+    unsafe fn release(state: u32);
+}
+
+/// Registers `$critical` type as the platform implementation of critical
+/// sections.
+///
+/// The type must implement [`Critical`](crate::thr::Critical).
+#[macro_export]
+macro_rules! set_critical {
+    ($critical:ty) => {
+        #[no_mangle]
+        unsafe fn drone_critical_acquire() -> u32 {
+            unsafe { <$critical as $crate::thr::Critical>::acquire() }
+        }
+
+        #[no_mangle]
+        unsafe fn drone_critical_release(state: u32) {
+            unsafe { <$critical as $crate::thr::Critical>::release(state) }
+        }
+
+        #[no_mangle]
+        fn drone_critical_available() -> bool {
+            true
+        }
+    };
+}
+
+struct Guard(u32);
+
+/// Executes the closure `f` inside a critical section.
+///
+/// ```
+/// use core::cell::Cell;
+/// use drone_core::thr;
+///
+/// let counter = Cell::new(0);
+/// thr::critical(|_cs| counter.set(counter.get() + 1));
+/// assert_eq!(counter.get(), 1);
+/// ```
+#[inline]
+pub fn critical<R>(f: impl FnOnce(CriticalSection<'_>) -> R) -> R {
+    let _guard = Guard(unsafe { drone_critical_acquire() });
+    f(CriticalSection { _marker: PhantomData })
+}
+
+/// Returns `true` if the platform registered a critical section
+/// implementation with [`set_critical!`](crate::set_critical).
+///
+/// Without the implementation [`critical`] panics, so the code, which must not
+/// panic, e.g. the error output path, can check this first and fall back to a
+/// lock-free alternative.
+#[inline]
+pub fn critical_available() -> bool {
+    drone_critical_available()
+}
+
+impl Drop for Guard {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { drone_critical_release(self.0) };
+    }
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+pub(super) unsafe fn drone_critical_acquire() -> u32 {
+    #[cfg(feature = "std")]
+    return host::acquire();
+    panic!("critical sections are not implemented by the platform");
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_critical_available() -> bool {
+    cfg!(feature = "std")
+}
+
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+#[linkage = "weak"]
+#[no_mangle]
+pub(super) unsafe fn drone_critical_release(state: u32) {
+    #[cfg(feature = "std")]
+    host::release(state);
+}
+
+#[cfg(feature = "std")]
+mod host {
+    use core::{
+        cell::Cell,
+        hint,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    static LOCKED: AtomicBool = AtomicBool::new(false);
+
+    std::thread_local! {
+        static HELD: Cell<bool> = Cell::new(false);
+    }
+
+    pub(super) fn acquire() -> u32 {
+        if HELD.with(Cell::get) {
+            return 0;
+        }
+        while LOCKED
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+        HELD.with(|held| held.set(true));
+        1
+    }
+
+    pub(super) fn release(state: u32) {
+        if state != 0 {
+            HELD.with(|held| held.set(false));
+            LOCKED.store(false, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(feature = "critical-section")]
+mod ecosystem {
+    struct DroneCritical;
+
+    critical_section::set_impl!(DroneCritical);
+
+    unsafe impl critical_section::Impl for DroneCritical {
+        unsafe fn acquire() -> u32 {
+            unsafe { super::drone_critical_acquire() }
+        }
+
+        unsafe fn release(state: u32) {
+            unsafe { super::drone_critical_release(state) };
+        }
+    }
+}
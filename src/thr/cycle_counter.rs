@@ -0,0 +1,32 @@
+/// A free-running cycle counter for thread statistics.
+pub trait CycleCounter {
+    /// Returns the current counter value. The counter is allowed to wrap
+    /// around.
+    fn cycles() -> u32;
+}
+
+/// Registers `$counter` type as the cycle counter for thread statistics.
+///
+/// The type must implement [`CycleCounter`](crate::thr::CycleCounter).
+#[macro_export]
+macro_rules! set_cycle_counter {
+    ($counter:ty) => {
+        #[no_mangle]
+        fn drone_thr_cycles() -> u32 {
+            <$counter as $crate::thr::CycleCounter>::cycles()
+        }
+    };
+}
+
+/// Returns the current value of the registered cycle counter, or zero if no
+/// counter is registered.
+#[inline]
+pub(crate) fn cycles() -> u32 {
+    drone_thr_cycles()
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_thr_cycles() -> u32 {
+    0
+}
@@ -1,4 +1,4 @@
-use crate::{fib, thr::prelude::*};
+use crate::{fib, sync::spsc::oneshot, thr::prelude::*};
 use core::{
     fmt::Display,
     future::Future,
@@ -38,6 +38,26 @@ pub trait ThrExec: ThrToken {
         self.wakeup();
     }
 
+    /// Spawns the future `fut` on the thread and wakes up the thread
+    /// immediately.
+    ///
+    /// Unlike [`ThrExec::exec`], the output of `fut` is not restricted to
+    /// [`ExecOutput`] types. The returned receiver resolves to the output when
+    /// `fut` completes. If the receiver is dropped, `fut` still runs to
+    /// completion, and the output is discarded.
+    #[inline]
+    fn spawn<F>(self, fut: F) -> oneshot::Receiver<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.exec(async move {
+            tx.send(fut.await).ok();
+        });
+        rx
+    }
+
     /// Adds an executor for the future `fut` to the fiber chain.
     ///
     /// The future `fut` will start polling on the next thread wake-up.
@@ -0,0 +1,158 @@
+use crate::thr::{SoftThread, Thread};
+use core::sync::atomic::Ordering;
+
+/// A group of thread tokens from the same thread pool.
+///
+/// Groups are defined with [`thr::group!`](crate::thr::group) macro. They
+/// bundle thread tokens of a subsystem, so that its bring-up and teardown can
+/// manipulate all its threads in one place. Platform crates may add their own
+/// bulk operations (e.g. enabling all interrupts of the group) by extending
+/// this trait.
+///
+/// # Safety
+///
+/// [`ThrGroup::THR_IDXS`] must contain the indices of exactly the threads of
+/// the group tokens.
+pub unsafe trait ThrGroup: Sized + Copy + 'static {
+    /// The thread type.
+    type Thread: Thread;
+
+    /// Positions of the threads within [`Thread::pool`] array.
+    const THR_IDXS: &'static [u16];
+
+    /// Returns an iterator over the thread objects of the group.
+    #[inline]
+    fn threads(self) -> Threads<Self::Thread> {
+        Threads { thr_idxs: Self::THR_IDXS.iter(), pool: Self::Thread::pool() }
+    }
+}
+
+/// Bulk operations for a group of software-managed threads.
+pub trait SoftThrGroup: ThrGroup
+where
+    Self::Thread: SoftThread,
+{
+    /// Writes the priority of every thread in the group.
+    ///
+    /// # Panics
+    ///
+    /// If `priority` is greater than or equals to
+    /// [`PRIORITY_LEVELS`](crate::thr::PRIORITY_LEVELS).
+    #[inline]
+    fn set_priority_batch(self, priority: u8) {
+        assert!(priority < crate::thr::PRIORITY_LEVELS);
+        for thr in self.threads() {
+            unsafe { (*thr.priority()).store(priority, Ordering::Relaxed) };
+        }
+    }
+
+    /// Sets every thread in the group pending.
+    ///
+    /// All threads are marked pending before any of them is run, so the
+    /// threads are resumed in the order of their priorities.
+    #[inline]
+    fn set_pending_batch(self) {
+        let mut preempt = false;
+        for &thr_idx in Self::THR_IDXS {
+            preempt |= unsafe { Self::Thread::will_preempt(thr_idx) };
+        }
+        if preempt {
+            Self::Thread::preempt();
+        }
+    }
+}
+
+impl<G: ThrGroup> SoftThrGroup for G where G::Thread: SoftThread {}
+
+/// An iterator over the thread objects of a [`ThrGroup`].
+pub struct Threads<T: Thread> {
+    thr_idxs: core::slice::Iter<'static, u16>,
+    pool: *const T,
+}
+
+impl<T: Thread> Iterator for Threads<T> {
+    type Item = &'static T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.thr_idxs.next().map(|&thr_idx| unsafe { &*self.pool.add(usize::from(thr_idx)) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.thr_idxs.size_hint()
+    }
+}
+
+/// Defines a group of thread tokens.
+///
+/// All tokens must belong to the thread pool given in parentheses. See
+/// [`ThrGroup`] for details.
+///
+/// ```
+/// use drone_core::{thr, thr::SoftThrGroup, token::Token};
+///
+/// thr::soft! {
+///     thread => pub Thr {};
+///     local => pub ThrLocal {};
+///     index => pub Thrs;
+///     threads => { uart_rx; uart_tx; };
+/// }
+///
+/// thr::group! {
+///     /// The UART threads.
+///     pub struct Uart(Thr) {
+///         pub rx: UartRx,
+///         pub tx: UartTx,
+///     }
+/// }
+///
+/// fn main() {
+///     let thr = unsafe { Thrs::take() };
+///     let uart = Uart { rx: thr.uart_rx, tx: thr.uart_tx };
+///     uart.set_priority_batch(3);
+/// }
+/// ```
+#[doc(inline)]
+pub use crate::__thr_group as group;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __thr_group {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $ident:ident($thr:ty) {
+            $(
+                $(#[$field_attr:meta])*
+                $field_vis:vis $field:ident: $token:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy)]
+        $vis struct $ident {
+            $(
+                $(#[$field_attr])*
+                $field_vis $field: $token,
+            )*
+        }
+
+        unsafe impl $crate::token::Token for $ident {
+            #[inline]
+            unsafe fn take() -> Self {
+                Self { $($field: $crate::token::Token::take(),)* }
+            }
+        }
+
+        unsafe impl $crate::thr::ThrGroup for $ident {
+            type Thread = $thr;
+
+            const THR_IDXS: &'static [u16] = &[$(<$token as $crate::thr::ThrToken>::THR_IDX),*];
+        }
+
+        const _: fn() = || {
+            fn assert_thread<T: $crate::thr::ThrToken<Thread = $thr>>() {}
+            $(assert_thread::<$token>();)*
+        };
+    };
+}
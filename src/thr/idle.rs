@@ -0,0 +1,76 @@
+use core::{cell::UnsafeCell, mem::size_of, slice};
+
+extern "C" {
+    static DRONE_IDLE_HOOKS_START: UnsafeCell<usize>;
+    static DRONE_IDLE_HOOKS_END: UnsafeCell<usize>;
+}
+
+/// A registry entry for [`thr::idle_hook!`](crate::thr::idle_hook).
+#[doc(hidden)]
+#[repr(C)]
+pub struct IdleHook {
+    pub order: u16,
+    pub hook: fn(),
+}
+
+/// Registers a function to run when the idle thread executes.
+///
+/// The first argument is the order of the hook: hooks with lower orders run
+/// first, and hooks with equal orders run in unspecified order. By convention
+/// hooks which put the processor to sleep (e.g. `WFI`) should use `u16::MAX`,
+/// so that all other hooks run before the sleep.
+///
+/// The platform idle loop calls [`run_idle_hooks`] to execute the registered
+/// hooks. The linker script must keep the `.drone_idle_hooks` section and
+/// surround it with `DRONE_IDLE_HOOKS_START` and `DRONE_IDLE_HOOKS_END`
+/// symbols.
+///
+/// ```
+/// use drone_core::thr;
+///
+/// fn kick_watchdog() {
+///     // Reload the watchdog counter.
+/// }
+///
+/// thr::idle_hook!(100, kick_watchdog);
+/// # fn main() {}
+/// ```
+#[doc(inline)]
+pub use crate::__thr_idle_hook as idle_hook;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __thr_idle_hook {
+    ($order:expr, $hook:path $(,)?) => {
+        const _: () = {
+            #[used]
+            #[link_section = ".drone_idle_hooks"]
+            static IDLE_HOOK: $crate::thr::IdleHook =
+                $crate::thr::IdleHook { order: $order, hook: $hook };
+        };
+    };
+}
+
+/// Runs all hooks registered with [`thr::idle_hook!`](crate::thr::idle_hook)
+/// in their order.
+///
+/// This function should be called by the platform idle loop on each
+/// iteration.
+pub fn run_idle_hooks() {
+    let hooks = unsafe {
+        let count = (DRONE_IDLE_HOOKS_END.get() as usize - DRONE_IDLE_HOOKS_START.get() as usize)
+            / size_of::<IdleHook>();
+        slice::from_raw_parts(DRONE_IDLE_HOOKS_START.get().cast::<IdleHook>(), count)
+    };
+    // Hooks are few, so a selection without allocation is cheaper than sorting.
+    let mut last: Option<(u16, usize)> = None;
+    while let Some((index, hook)) = hooks
+        .iter()
+        .enumerate()
+        .filter(|&(index, hook)| last.map_or(true, |last| (hook.order, index) > last))
+        .min_by_key(|&(index, hook)| (hook.order, index))
+    {
+        (hook.hook)();
+        last = Some((hook.order, index));
+    }
+}
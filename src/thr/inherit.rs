@@ -0,0 +1,111 @@
+use core::num::NonZeroU32;
+
+/// A platform hook for priority inheritance in [`sync`](crate::sync)
+/// primitives.
+///
+/// When a [`Mutex`](crate::sync::Mutex) created with
+/// [`with_priority_inheritance`](crate::sync::Mutex::with_priority_inheritance)
+/// is held by a fiber running on a lower-priority thread and a higher-priority
+/// thread awaits it, the holder's thread inherits the priority of the waiter
+/// until the mutex is unlocked. This avoids unbounded priority inversion.
+///
+/// A thread can hold several mutexes and release them in any order, so the
+/// platform keeps track of all priorities a thread inherited: the effective
+/// priority of a thread is the maximum of its own priority and the priorities
+/// it inherited and not yet [`disinherit`](Self::disinherit)ed. A simple
+/// implementation keeps a counter for each priority level per thread.
+///
+/// Only the platform knows how to identify the running thread and how to change
+/// its priority, so the mechanism is disabled by default. It is enabled by
+/// implementing this trait and registering it with the
+/// [`set_priority_inheritance!`](crate::set_priority_inheritance) macro. The
+/// hooks are called within [`thr::critical`](crate::thr::critical) sections.
+pub trait PriorityInheritance {
+    /// Returns a non-zero opaque handle of the currently running thread, or
+    /// `None` if it is not known.
+    fn current() -> Option<NonZeroU32>;
+
+    /// Reads the effective priority of the thread `thr`.
+    fn priority(thr: NonZeroU32) -> u8;
+
+    /// Makes the thread `thr` inherit the `priority`.
+    fn inherit(thr: NonZeroU32, priority: u8);
+
+    /// Releases the `priority` previously inherited by the thread `thr`.
+    fn disinherit(thr: NonZeroU32, priority: u8);
+}
+
+/// Registers `$hook` type as the priority inheritance mechanism.
+///
+/// The type must implement
+/// [`PriorityInheritance`](crate::thr::PriorityInheritance).
+#[macro_export]
+macro_rules! set_priority_inheritance {
+    ($hook:ty) => {
+        #[no_mangle]
+        fn drone_thr_current() -> ::core::option::Option<::core::num::NonZeroU32> {
+            <$hook as $crate::thr::PriorityInheritance>::current()
+        }
+
+        #[no_mangle]
+        fn drone_thr_priority(thr: ::core::num::NonZeroU32) -> u8 {
+            <$hook as $crate::thr::PriorityInheritance>::priority(thr)
+        }
+
+        #[no_mangle]
+        fn drone_thr_inherit(thr: ::core::num::NonZeroU32, priority: u8) {
+            <$hook as $crate::thr::PriorityInheritance>::inherit(thr, priority)
+        }
+
+        #[no_mangle]
+        fn drone_thr_disinherit(thr: ::core::num::NonZeroU32, priority: u8) {
+            <$hook as $crate::thr::PriorityInheritance>::disinherit(thr, priority)
+        }
+    };
+}
+
+/// The hook registered with
+/// [`set_priority_inheritance!`](crate::set_priority_inheritance).
+pub(crate) struct Platform;
+
+impl PriorityInheritance for Platform {
+    #[inline]
+    fn current() -> Option<NonZeroU32> {
+        drone_thr_current()
+    }
+
+    #[inline]
+    fn priority(thr: NonZeroU32) -> u8 {
+        drone_thr_priority(thr)
+    }
+
+    #[inline]
+    fn inherit(thr: NonZeroU32, priority: u8) {
+        drone_thr_inherit(thr, priority);
+    }
+
+    #[inline]
+    fn disinherit(thr: NonZeroU32, priority: u8) {
+        drone_thr_disinherit(thr, priority);
+    }
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_thr_current() -> Option<NonZeroU32> {
+    None
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_thr_priority(_thr: NonZeroU32) -> u8 {
+    0
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_thr_inherit(_thr: NonZeroU32, _priority: u8) {}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_thr_disinherit(_thr: NonZeroU32, _priority: u8) {}
@@ -0,0 +1,81 @@
+use alloc::boxed::Box;
+use core::{
+    any::TypeId,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// Type-erased dynamic thread-local storage.
+///
+/// Each thread object owns one instance of this type, which holds at most one
+/// value per type. Values are allocated lazily on the first access through
+/// [`ThrToken::local_cell`](crate::thr::ThrToken::local_cell), and live as
+/// long as the thread object, i.e. forever.
+pub struct LocalCells {
+    head: AtomicPtr<Cell>,
+}
+
+struct Cell {
+    next: *mut Cell,
+    type_id: TypeId,
+    value: *const (),
+}
+
+unsafe impl Sync for LocalCells {}
+
+impl LocalCells {
+    /// Creates an empty storage.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { head: AtomicPtr::new(ptr::null_mut()) }
+    }
+
+    /// Returns a reference to the value of type `T`, inserting
+    /// `T::default()` if it is not present.
+    pub fn get_or_default<T: Default + Send + Sync + 'static>(&self) -> &T {
+        let type_id = TypeId::of::<T>();
+        let mut head = self.head.load(Ordering::Acquire);
+        if let Some(value) = unsafe { find(head, ptr::null_mut(), type_id) } {
+            return unsafe { &*value.cast::<T>() };
+        }
+        let value = Box::into_raw(Box::new(T::default()));
+        let cell = Box::into_raw(Box::new(Cell { next: head, type_id, value: value.cast() }));
+        loop {
+            match self.head.compare_exchange_weak(head, cell, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return unsafe { &*value },
+                Err(next_head) => {
+                    // Another context could insert the same type in between.
+                    if let Some(found) = unsafe { find(next_head, head, type_id) } {
+                        unsafe {
+                            drop(Box::from_raw(cell));
+                            drop(Box::from_raw(value));
+                            return &*found.cast::<T>();
+                        }
+                    }
+                    head = next_head;
+                    unsafe { (*cell).next = head };
+                }
+            }
+        }
+    }
+}
+
+impl Default for LocalCells {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Searches cells starting from `cell` until `end` for the `type_id`.
+unsafe fn find(mut cell: *mut Cell, end: *mut Cell, type_id: TypeId) -> Option<*const ()> {
+    while cell != end {
+        unsafe {
+            if (*cell).type_id == type_id {
+                return Some((*cell).value);
+            }
+            cell = (*cell).next;
+        }
+    }
+    None
+}
@@ -48,17 +48,95 @@
 //!     };
 //! }
 //! ```
+//!
+//! # Software-managed Threads
+//!
+//! Threads defined with `thr::soft!` macro are not bound to hardware
+//! interrupts. Such a thread has a run-time priority and can be pended from any
+//! context with [`SoftThrToken::set_pending`]. A pending thread preempts the
+//! current context as soon as its priority is higher than the priority of the
+//! running thread; threads with equal priorities run cooperatively.
+//!
+//! By default the preemption happens synchronously inside `set_pending`.
+//! Platform crates may provide a different mechanism (e.g. deferring to a
+//! dedicated low-priority exception) with the `set_pending` option, which
+//! accepts a path to an `unsafe fn(thr_idx: u16)`. Such a function should call
+//! [`SoftThread::will_preempt`] and arrange for [`SoftThread::preempt`] to be
+//! called later.
+//!
+//! ```
+//! use drone_core::{
+//!     thr,
+//!     thr::{SoftThrToken, ThrExec},
+//!     token::Token,
+//! };
+//!
+//! thr::soft! {
+//!     /// The software-managed thread object.
+//!     thread => pub Thr {};
+//!
+//!     /// The thread-local storage.
+//!     local => pub ThrLocal {};
+//!
+//!     /// Thread token set.
+//!     index => pub Thrs;
+//!
+//!     threads => {
+//!         /// A background task thread.
+//!         pub background;
+//!     };
+//! }
+//!
+//! fn main() {
+//!     let thr = unsafe { Thrs::take() };
+//!     thr.background.set_priority(1);
+//!     thr.background.add_exec(async {
+//!         // Do the background work.
+//!     });
+//!     thr.background.set_pending();
+//! }
+//! ```
 
 pub mod prelude;
+pub mod timer_wheel;
 
+mod critical;
+mod cycle_counter;
 mod exec;
+mod group;
+mod idle;
+pub(crate) mod inherit;
+mod local_cell;
+mod preempt;
+mod shutdown;
 mod soft;
+#[cfg(feature = "thr-stats")]
+mod stats;
+mod wake;
+mod work_queue;
 
 pub use self::{
+    critical::{critical, critical_available, Critical, CriticalSection},
+    cycle_counter::CycleCounter,
     exec::{ExecOutput, ThrExec},
+    group::{group, SoftThrGroup, ThrGroup, Threads},
+    idle::{idle_hook, run_idle_hooks, IdleHook},
+    inherit::PriorityInheritance,
+    local_cell::LocalCells,
+    preempt::{with_preempt_disabled, PreemptLock},
+    shutdown::{
+        is_shutdown_requested, shutdown, ShutdownAware, ShutdownComplete, ShutdownRequested,
+    },
     soft::{pending_size, SoftThrToken, SoftThread, PRIORITY_LEVELS},
+    wake::{static_waker, StaticWake},
+    work_queue::{WorkQueue, WorkQueueNext},
 };
 
+#[cfg(feature = "thr-stats")]
+pub use self::stats::{StormHandler, ThrStats};
+
+pub(crate) use self::cycle_counter::cycles;
+
 /// Defines a thread pool.
 ///
 /// See [the module level documentation](self) for details.
@@ -109,6 +187,17 @@ pub unsafe trait Thread: Sized + Sync + 'static {
     /// [`Thread::local`] function.
     fn local_opaque(&self) -> &LocalOpaque<Self>;
 
+    /// Returns a reference to the dynamic thread-local storage.
+    ///
+    /// See [`ThrToken::local_cell`] for details.
+    fn local_cells(&self) -> &LocalCells;
+
+    /// Returns a reference to the thread execution statistics.
+    ///
+    /// See [`ThrToken::stats`] for details.
+    #[cfg(feature = "thr-stats")]
+    fn stats(&self) -> &ThrStats;
+
     /// Returns a reference to the thread-local storage for the current thread.
     ///
     /// The contents of this object can be customized with `thr::pool!`
@@ -161,7 +250,11 @@ pub unsafe trait Thread: Sized + Sync + 'static {
         unsafe {
             let preempted = (*Self::current()).load(Ordering::Relaxed);
             (*Self::current()).store(thr_idx + 1, Ordering::Relaxed);
-            f(&*Self::pool().add(usize::from(thr_idx)));
+            let thr = &*Self::pool().add(usize::from(thr_idx));
+            #[cfg(feature = "thr-stats")]
+            thr.stats().record(thr_idx, || f(thr));
+            #[cfg(not(feature = "thr-stats"))]
+            f(thr);
             (*Self::current()).store(preempted, Ordering::Relaxed);
         }
     }
@@ -217,6 +310,42 @@ where
     fn is_empty(self) -> bool {
         self.to_thr().fib_chain().is_empty()
     }
+
+    /// Returns a reference to the thread-local value of type `T`.
+    ///
+    /// Unlike the fields defined in `thr::pool!` macro, these values don't
+    /// need to be known to the application. This lets middleware crates attach
+    /// their own state to every thread. The value is initialized with
+    /// `T::default()` on the first access, which allocates memory.
+    ///
+    /// ```
+    /// # fn main() {}
+    /// use core::sync::atomic::{AtomicU32, Ordering};
+    /// use drone_core::thr::ThrToken;
+    ///
+    /// #[derive(Default)]
+    /// struct Stats {
+    ///     wakeups: AtomicU32,
+    /// }
+    ///
+    /// fn count_wakeup<T: ThrToken>(thr: T) {
+    ///     thr.local_cell::<Stats>().wakeups.fetch_add(1, Ordering::Relaxed);
+    /// }
+    /// ```
+    #[inline]
+    fn local_cell<T: Default + Send + Sync + 'static>(self) -> &'static T {
+        self.to_thr().local_cells().get_or_default()
+    }
+
+    /// Returns a reference to the thread execution statistics.
+    ///
+    /// The statistics can be used to validate worst-case execution time
+    /// budgets empirically on hardware. See [`ThrStats`] for details.
+    #[cfg(feature = "thr-stats")]
+    #[inline]
+    fn stats(self) -> &'static ThrStats {
+        self.to_thr().stats()
+    }
 }
 
 /// Thread-local storage wrapper for thread `T`.
@@ -0,0 +1,116 @@
+use super::critical::{drone_critical_acquire, drone_critical_release};
+
+/// A platform implementation of the preemption lock.
+///
+/// The lock raises the execution priority of the current context to a ceiling,
+/// so that only the threads with priorities above the ceiling can preempt it.
+/// Usually it is implemented with a base priority register of the interrupt
+/// controller. The implementation is registered with the
+/// [`set_preempt_lock!`](crate::set_preempt_lock) macro. Without an
+/// implementation, the lock falls back to a [`critical`](super::critical)
+/// section.
+///
+/// # Safety
+///
+/// The implementation must prevent all threads with priorities less than or
+/// equal to the ceiling from preempting the current context. Nested locks must
+/// be supported.
+pub unsafe trait PreemptLock {
+    /// Raises the execution priority to `ceiling`, if it is not already higher,
+    /// and returns the state to restore on exit.
+    ///
+    /// # Safety
+    ///
+    /// Each call must be paired with a call to [`PreemptLock::restore`].
+    unsafe fn raise(ceiling: u8) -> u32;
+
+    /// Restores the execution priority from the state returned by the paired
+    /// [`PreemptLock::raise`] call.
+    ///
+    /// # Safety
+    ///
+    /// Locks must be exited in the reverse order of entering.
+    unsafe fn restore(state: u32);
+}
+
+/// Registers `$lock` type as the platform implementation of the preemption
+/// lock.
+///
+/// The type must implement [`PreemptLock`](crate::thr::PreemptLock).
+#[macro_export]
+macro_rules! set_preempt_lock {
+    ($lock:ty) => {
+        #[no_mangle]
+        unsafe fn drone_preempt_raise(ceiling: u8) -> u32 {
+            unsafe { <$lock as $crate::thr::PreemptLock>::raise(ceiling) }
+        }
+
+        #[no_mangle]
+        unsafe fn drone_preempt_restore(state: u32) {
+            unsafe { <$lock as $crate::thr::PreemptLock>::restore(state) }
+        }
+    };
+}
+
+struct Guard(u32);
+
+/// Executes the closure `f` with the execution priority of the current context
+/// raised to `ceiling`.
+///
+/// The priority is restored when `f` returns, so nested calls always restore
+/// in the reverse order of raising.
+///
+/// ```
+/// use drone_core::thr;
+///
+/// const DMA_CEILING: u8 = 3;
+///
+/// thr::with_preempt_disabled(DMA_CEILING, || {
+///     // Threads with priorities up to `DMA_CEILING` can't preempt this code.
+/// });
+/// ```
+#[inline]
+pub fn with_preempt_disabled<R>(ceiling: u8, f: impl FnOnce() -> R) -> R {
+    let _guard = Guard(unsafe { drone_preempt_raise(ceiling) });
+    f()
+}
+
+impl Drop for Guard {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { drone_preempt_restore(self.0) };
+    }
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+unsafe fn drone_preempt_raise(_ceiling: u8) -> u32 {
+    unsafe { drone_critical_acquire() }
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+unsafe fn drone_preempt_restore(state: u32) {
+    unsafe { drone_critical_release(state) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thr::critical;
+    use std::{sync::mpsc, thread, time::Duration};
+
+    #[test]
+    fn nested() {
+        let (tx, rx) = mpsc::channel();
+        let other = with_preempt_disabled(2, || {
+            assert_eq!(with_preempt_disabled(5, || 1), 1);
+            // The outer lock is still held after the inner one is restored.
+            let other = thread::spawn(move || critical(|_| tx.send(()).unwrap()));
+            assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+            other
+        });
+        other.join().unwrap();
+        assert!(rx.try_recv().is_ok());
+    }
+}
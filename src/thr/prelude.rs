@@ -18,5 +18,5 @@ pub use crate::{
         ThrFiberClosure as _, ThrFiberFuture as _, ThrFiberGen as _, ThrFiberStreamPulse as _,
         ThrFiberStreamRing as _,
     },
-    thr::{SoftThrToken as _, ThrExec as _, Thread as _},
+    thr::{SoftThrGroup as _, SoftThrToken as _, ThrExec as _, ThrGroup as _, Thread as _},
 };
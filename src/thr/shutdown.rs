@@ -0,0 +1,235 @@
+use super::critical;
+use core::{
+    cell::UnsafeCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+static STATE: StateCell = StateCell(UnsafeCell::new(State::new()));
+
+struct StateCell(UnsafeCell<State>);
+
+unsafe impl Sync for StateCell {}
+
+struct State {
+    requested: bool,
+    participants: Vec<Option<Participant>>,
+    waiters: Vec<Waker>,
+}
+
+struct Participant {
+    waker: Option<Waker>,
+}
+
+/// A registration of a subsystem in the shutdown protocol.
+///
+/// A subsystem, which needs to flush its state before the device is reset or
+/// enters a deep power-down mode, registers itself with
+/// [`ShutdownAware::register`] and awaits [`ShutdownAware::requested`]. After
+/// the state is flushed, the subsystem acknowledges the shutdown by calling
+/// [`ShutdownAware::acknowledge`] or dropping the registration. The initiator
+/// calls [`shutdown`] and awaits until all registered subsystems acknowledge:
+///
+/// ```
+/// use drone_core::thr::{self, ShutdownAware};
+///
+/// async fn logger() {
+///     let mut shutdown = ShutdownAware::register();
+///     shutdown.requested().await;
+///     // Flush the log buffer here.
+///     shutdown.acknowledge();
+/// }
+///
+/// async fn power_down() {
+///     thr::shutdown().await;
+///     // Enter the deep power-down mode here.
+/// }
+/// ```
+#[must_use = "the registration is acknowledged immediately if unused"]
+pub struct ShutdownAware {
+    index: usize,
+}
+
+/// A future which resolves when a shutdown is requested.
+///
+/// This structure is created by the [`ShutdownAware::requested`] method.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ShutdownRequested<'a> {
+    aware: &'a mut ShutdownAware,
+}
+
+/// A future which resolves when all registered [`ShutdownAware`] subsystems
+/// acknowledge the shutdown.
+///
+/// This structure is created by the [`shutdown`] function.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ShutdownComplete {
+    _private: (),
+}
+
+impl State {
+    const fn new() -> Self {
+        Self { requested: false, participants: Vec::new(), waiters: Vec::new() }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.participants.iter().all(Option::is_none)
+    }
+}
+
+impl ShutdownAware {
+    /// Registers a new subsystem in the shutdown protocol.
+    ///
+    /// If a shutdown is already requested, the registration is immediately
+    /// notified.
+    pub fn register() -> Self {
+        critical(|_| {
+            let state = unsafe { &mut *STATE.0.get() };
+            let index = if let Some(index) = state.participants.iter().position(Option::is_none) {
+                state.participants[index] = Some(Participant { waker: None });
+                index
+            } else {
+                state.participants.push(Some(Participant { waker: None }));
+                state.participants.len() - 1
+            };
+            Self { index }
+        })
+    }
+
+    /// Returns `true` if a shutdown is requested.
+    #[inline]
+    pub fn is_requested(&self) -> bool {
+        is_shutdown_requested()
+    }
+
+    /// Returns a future, which resolves when a shutdown is requested.
+    #[inline]
+    pub fn requested(&mut self) -> ShutdownRequested<'_> {
+        ShutdownRequested { aware: self }
+    }
+
+    /// Acknowledges the shutdown and unregisters the subsystem.
+    ///
+    /// Equivalent to dropping the registration.
+    #[inline]
+    pub fn acknowledge(self) {}
+}
+
+impl Drop for ShutdownAware {
+    fn drop(&mut self) {
+        let waiters = critical(|_| {
+            let state = unsafe { &mut *STATE.0.get() };
+            state.participants[self.index] = None;
+            if state.requested && state.is_complete() {
+                core::mem::take(&mut state.waiters)
+            } else {
+                Vec::new()
+            }
+        });
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl Future for ShutdownRequested<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let index = self.aware.index;
+        critical(|_| {
+            let state = unsafe { &mut *STATE.0.get() };
+            if state.requested {
+                Poll::Ready(())
+            } else {
+                state.participants[index] = Some(Participant { waker: Some(cx.waker().clone()) });
+                Poll::Pending
+            }
+        })
+    }
+}
+
+impl Future for ShutdownComplete {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        critical(|_| {
+            let state = unsafe { &mut *STATE.0.get() };
+            if state.is_complete() {
+                Poll::Ready(())
+            } else {
+                if !state.waiters.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    state.waiters.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        })
+    }
+}
+
+/// Broadcasts a shutdown request to all registered [`ShutdownAware`]
+/// subsystems.
+///
+/// Returns a future, which resolves when all of them acknowledge the shutdown.
+/// The request can't be withdrawn.
+pub fn shutdown() -> ShutdownComplete {
+    let listeners = critical(|_| {
+        let state = unsafe { &mut *STATE.0.get() };
+        state.requested = true;
+        state
+            .participants
+            .iter_mut()
+            .filter_map(|participant| participant.as_mut().and_then(|p| p.waker.take()))
+            .collect::<Vec<_>>()
+    });
+    listeners.into_iter().for_each(Waker::wake);
+    ShutdownComplete { _private: () }
+}
+
+/// Returns `true` if a shutdown is requested.
+pub fn is_shutdown_requested() -> bool {
+    critical(|_| unsafe { (*STATE.0.get()).requested })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use futures::{pin_mut, task::waker_ref};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl futures::task::ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn protocol() {
+        let counter = Arc::new(Counter::default());
+        let waker = waker_ref(&counter);
+        let mut cx = Context::from_waker(&waker);
+        let mut a = ShutdownAware::register();
+        let b = ShutdownAware::register();
+        let complete = {
+            let requested = a.requested();
+            pin_mut!(requested);
+            assert_eq!(requested.as_mut().poll(&mut cx), Poll::Pending);
+            let complete = shutdown();
+            assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+            assert_eq!(requested.poll(&mut cx), Poll::Ready(()));
+            complete
+        };
+        pin_mut!(complete);
+        assert_eq!(complete.as_mut().poll(&mut cx), Poll::Pending);
+        assert!(b.is_requested());
+        b.acknowledge();
+        assert_eq!(complete.as_mut().poll(&mut cx), Poll::Pending);
+        drop(a);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(complete.poll(&mut cx), Poll::Ready(()));
+        assert!(is_shutdown_requested());
+    }
+}
@@ -1,7 +1,7 @@
 mod wake;
 
-use self::wake::SoftWaker;
-use crate::thr::{ThrExec, ThrToken, Thread};
+use self::wake::SoftWake;
+use crate::thr::{static_waker, ThrExec, ThrToken, Thread};
 use core::{
     sync::atomic::{AtomicU32, AtomicU8, Ordering},
     task::Waker,
@@ -184,12 +184,12 @@ impl<S: SoftThread, T: ThrToken<Thread = S>> SoftThrToken for T {
 impl<T: SoftThrToken> ThrExec for T {
     #[inline]
     fn wakeup(self) {
-        SoftWaker::<T::SoftThread>::new(T::THR_IDX).wakeup();
+        unsafe { T::SoftThread::set_pending(T::THR_IDX) };
     }
 
     #[inline]
     fn waker(self) -> Waker {
-        SoftWaker::<T::SoftThread>::new(T::THR_IDX).to_waker()
+        unsafe { static_waker::<SoftWake<T::SoftThread>>(T::THR_IDX) }
     }
 }
 
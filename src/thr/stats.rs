@@ -0,0 +1,287 @@
+use super::cycles;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Per-thread execution statistics.
+///
+/// Available with `thr-stats` feature enabled. Each thread object owns one
+/// instance of this type, which is updated on every
+/// thread activation through [`Thread::call`](crate::thr::Thread::call). The
+/// durations are measured with a platform cycle counter registered with the
+/// [`set_cycle_counter!`](crate::set_cycle_counter) macro. If no counter is
+/// registered, only activations are counted.
+///
+/// The measured duration of an activation includes the time spent in
+/// higher-priority threads which preempted it.
+///
+/// Thread activations also track the nesting of preempting threads, which
+/// allows to validate priority assignments against the real behavior. See
+/// [`preemptions`](ThrStats::preemptions) and
+/// [`max_nesting`](ThrStats::max_nesting).
+///
+/// # Interrupt storms
+///
+/// A stuck peripheral line can activate a thread over and over, starving all
+/// lower-priority threads. The activation rate of a thread can be limited with
+/// [`set_storm_limit`](ThrStats::set_storm_limit). When the thread is
+/// activated more times than the limit within the window, the handler
+/// registered with the [`set_storm_handler!`](crate::set_storm_handler) macro
+/// is called from the offending activation, e.g. to disable the interrupt and
+/// notify a supervisor task. The window is measured with the cycle counter, so
+/// without a registered counter the activations are never forgotten.
+pub struct ThrStats {
+    activations: AtomicU32,
+    max_cycles: AtomicU32,
+    preemptions: AtomicU32,
+    max_nesting: AtomicU32,
+    storm_limit: AtomicU32,
+    storm_window: AtomicU32,
+    window_start: AtomicU32,
+    window_activations: AtomicU32,
+    storms: AtomicU32,
+}
+
+/// The state saved on a thread activation to restore on its exit.
+#[derive(Clone, Copy)]
+struct Nesting {
+    depth: u32,
+    peak: u32,
+}
+
+/// Tracks the nesting of thread activations.
+struct NestingTracker {
+    /// The number of nested thread activations at the moment.
+    depth: AtomicU32,
+    /// The deepest nesting reached since the current activation started.
+    peak: AtomicU32,
+}
+
+static NESTING: NestingTracker = NestingTracker::new();
+
+/// A handler of thread activation rate violations.
+///
+/// See [the `ThrStats` documentation](ThrStats#interrupt-storms) for details.
+pub trait StormHandler {
+    /// Called from the activation of the thread number `thr_idx`, which
+    /// exceeded the limit set with
+    /// [`set_storm_limit`](ThrStats::set_storm_limit). `activations` is the
+    /// number of activations within the current window.
+    ///
+    /// The handler is called once per window.
+    fn storm(thr_idx: u16, activations: u32);
+}
+
+/// Registers `$handler` type as the handler of thread activation rate
+/// violations.
+///
+/// The type must implement [`StormHandler`](crate::thr::StormHandler).
+#[macro_export]
+macro_rules! set_storm_handler {
+    ($handler:ty) => {
+        #[no_mangle]
+        fn drone_thr_storm(thr_idx: u16, activations: u32) {
+            <$handler as $crate::thr::StormHandler>::storm(thr_idx, activations)
+        }
+    };
+}
+
+impl ThrStats {
+    /// Creates a new zeroed statistics.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            activations: AtomicU32::new(0),
+            max_cycles: AtomicU32::new(0),
+            preemptions: AtomicU32::new(0),
+            max_nesting: AtomicU32::new(0),
+            storm_limit: AtomicU32::new(0),
+            storm_window: AtomicU32::new(0),
+            window_start: AtomicU32::new(0),
+            window_activations: AtomicU32::new(0),
+            storms: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns the number of thread activations.
+    #[inline]
+    pub fn activations(&self) -> u32 {
+        self.activations.load(Ordering::Relaxed)
+    }
+
+    /// Returns the longest observed activation duration in cycles.
+    #[inline]
+    pub fn max_cycles(&self) -> u32 {
+        self.max_cycles.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of thread activations, which were preempted by
+    /// higher-priority threads.
+    #[inline]
+    pub fn preemptions(&self) -> u32 {
+        self.preemptions.load(Ordering::Relaxed)
+    }
+
+    /// Returns the maximal observed depth of threads nested on top of a thread
+    /// activation.
+    ///
+    /// Zero means the thread has never been preempted. One means the thread
+    /// has been preempted only by threads, which weren't preempted themselves.
+    #[inline]
+    pub fn max_nesting(&self) -> u32 {
+        self.max_nesting.load(Ordering::Relaxed)
+    }
+
+    /// Limits the activation rate of the thread to `limit` activations per
+    /// `window` cycles. Zero `limit` disables the limit.
+    ///
+    /// See [the type level documentation](ThrStats#interrupt-storms) for
+    /// details.
+    #[inline]
+    pub fn set_storm_limit(&self, limit: u32, window: u32) {
+        self.storm_limit.store(0, Ordering::Relaxed);
+        self.storm_window.store(window, Ordering::Relaxed);
+        self.window_start.store(cycles(), Ordering::Relaxed);
+        self.window_activations.store(0, Ordering::Relaxed);
+        self.storm_limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Returns the number of windows, in which the thread exceeded the
+    /// activation rate limit.
+    #[inline]
+    pub fn storms(&self) -> u32 {
+        self.storms.load(Ordering::Relaxed)
+    }
+
+    /// Resets the statistics. The activation rate limit is kept.
+    #[inline]
+    pub fn reset(&self) {
+        self.activations.store(0, Ordering::Relaxed);
+        self.max_cycles.store(0, Ordering::Relaxed);
+        self.preemptions.store(0, Ordering::Relaxed);
+        self.max_nesting.store(0, Ordering::Relaxed);
+        self.storms.store(0, Ordering::Relaxed);
+    }
+
+    /// Runs `f` as an activation of the thread number `thr_idx`.
+    pub(crate) fn record(&self, thr_idx: u16, f: impl FnOnce()) {
+        let nesting = NESTING.enter();
+        let start = self.start();
+        self.check_rate(thr_idx, start);
+        f();
+        self.finish(start);
+        NESTING.exit(self, nesting);
+    }
+
+    pub(crate) fn start(&self) -> u32 {
+        self.activations.fetch_add(1, Ordering::Relaxed);
+        cycles()
+    }
+
+    pub(crate) fn finish(&self, start: u32) {
+        let elapsed = cycles().wrapping_sub(start);
+        self.max_cycles.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    fn check_rate(&self, thr_idx: u16, now: u32) {
+        let limit = self.storm_limit.load(Ordering::Relaxed);
+        if limit == 0 {
+            return;
+        }
+        let elapsed = now.wrapping_sub(self.window_start.load(Ordering::Relaxed));
+        let activations = if elapsed >= self.storm_window.load(Ordering::Relaxed) {
+            self.window_start.store(now, Ordering::Relaxed);
+            1
+        } else {
+            self.window_activations.load(Ordering::Relaxed).saturating_add(1)
+        };
+        self.window_activations.store(activations, Ordering::Relaxed);
+        if activations - 1 == limit {
+            self.storms.fetch_add(1, Ordering::Relaxed);
+            drone_thr_storm(thr_idx, activations);
+        }
+    }
+}
+
+// Thread activations are strictly nested, so the counters don't need
+// read-modify-write operations.
+impl NestingTracker {
+    const fn new() -> Self {
+        Self { depth: AtomicU32::new(0), peak: AtomicU32::new(0) }
+    }
+
+    fn enter(&self) -> Nesting {
+        let depth = self.depth.load(Ordering::Relaxed) + 1;
+        self.depth.store(depth, Ordering::Relaxed);
+        let peak = self.peak.load(Ordering::Relaxed);
+        self.peak.store(depth, Ordering::Relaxed);
+        Nesting { depth, peak }
+    }
+
+    fn exit(&self, stats: &ThrStats, nesting: Nesting) {
+        let Nesting { depth, peak } = nesting;
+        let nested = self.peak.load(Ordering::Relaxed);
+        if nested > depth {
+            stats.preemptions.fetch_add(1, Ordering::Relaxed);
+            stats.max_nesting.fetch_max(nested - depth, Ordering::Relaxed);
+        }
+        self.peak.store(peak.max(nested), Ordering::Relaxed);
+        self.depth.store(depth - 1, Ordering::Relaxed);
+    }
+}
+
+impl Default for ThrStats {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn drone_thr_storm(_thr_idx: u16, _activations: u32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting() {
+        let tracker = NestingTracker::new();
+        let (low, mid, high) = (ThrStats::new(), ThrStats::new(), ThrStats::new());
+        let low_nesting = tracker.enter();
+        let mid_nesting = tracker.enter();
+        let high_nesting = tracker.enter();
+        tracker.exit(&high, high_nesting);
+        tracker.exit(&mid, mid_nesting);
+        let high_nesting = tracker.enter();
+        tracker.exit(&high, high_nesting);
+        tracker.exit(&low, low_nesting);
+        let low_nesting = tracker.enter();
+        tracker.exit(&low, low_nesting);
+        assert_eq!((low.preemptions(), low.max_nesting()), (1, 2));
+        assert_eq!((mid.preemptions(), mid.max_nesting()), (1, 1));
+        assert_eq!((high.preemptions(), high.max_nesting()), (0, 0));
+        assert_eq!(tracker.depth.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn storm() {
+        let stats = ThrStats::new();
+        stats.check_rate(0, 0);
+        stats.set_storm_limit(3, 100);
+        for now in 0..5 {
+            stats.check_rate(0, now);
+        }
+        assert_eq!(stats.storms(), 1);
+        for now in 100..103 {
+            stats.check_rate(0, now);
+        }
+        assert_eq!(stats.storms(), 1);
+        stats.check_rate(0, 199);
+        assert_eq!(stats.storms(), 2);
+        stats.set_storm_limit(0, 0);
+        for now in 200..210 {
+            stats.check_rate(0, now);
+        }
+        assert_eq!(stats.storms(), 2);
+    }
+}
@@ -0,0 +1,566 @@
+//! Software timers multiplexed onto a periodic tick.
+//!
+//! A [`TimerWheel`] drives any number of lightweight one-shot and periodic
+//! timers from a single periodic interrupt, like `SysTick`. Unlike
+//! [`time::Alarm`](crate::time::Alarm), which keeps a sorted queue and
+//! reprograms the hardware timer for the nearest deadline, the wheel doesn't
+//! need a compare-capable timer, and both inserting a timer and handling a
+//! tick take constant time regardless of the number of pending timers.
+//!
+//! The timers are kept in a hierarchical timing wheel. Each level has 16
+//! slots, and each slot of a level spans 16 times more ticks than a slot of
+//! the level below. A timer is placed in the lowest level, which can hold its
+//! deadline, and cascades down to the lower levels as the time advances. Six
+//! levels cover 2<sup>24</sup> ticks, and timers further in the future are
+//! re-inserted as the top level wraps around.
+//!
+//! The wheel never allocates. Each pending timer is a node of an intrusive
+//! list stored inside its pinned [`Sleep`] future, so [`TimerWheel::tick`] can
+//! be called from an interrupt handler without touching the heap. Expired
+//! timers wake their fibers with the stored [`Waker`]s.
+//!
+//! ```
+//! use drone_core::{
+//!     thr::timer_wheel::TimerWheel,
+//!     time::{Duration, Tick},
+//! };
+//! use futures::{pin_mut, prelude::*};
+//!
+//! /// A 1 kHz SysTick.
+//! #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+//! pub struct SysTickRate;
+//!
+//! impl Tick for SysTickRate {
+//!     const FREQ: u64 = 1_000;
+//! }
+//!
+//! static WHEEL: TimerWheel<SysTickRate> = TimerWheel::new();
+//!
+//! // The SysTick interrupt handler.
+//! fn sys_tick_handler() {
+//!     WHEEL.tick();
+//! }
+//!
+//! async fn blink() {
+//!     let interval = WHEEL.interval(Duration::from_millis(500));
+//!     pin_mut!(interval);
+//!     while let Some(_deadline) = interval.next().await {
+//!         // Toggle the LED here.
+//!     }
+//! }
+//!
+//! async fn debounce() {
+//!     WHEEL.sleep(Duration::from_millis(20)).await;
+//! }
+//! ```
+
+use crate::{
+    thr,
+    time::{Duration, Instant, Tick},
+};
+use core::{
+    cell::UnsafeCell,
+    future::Future,
+    marker::{PhantomData, PhantomPinned},
+    pin::Pin,
+    ptr,
+    task::{Context, Poll, Waker},
+};
+use futures::stream::{FusedStream, Stream};
+
+/// The number of bits of a slot index.
+const SLOT_BITS: u32 = 4;
+
+/// The number of slots in each level.
+const SLOTS: usize = 1 << SLOT_BITS;
+
+/// The number of levels.
+const LEVELS: usize = 6;
+
+const EMPTY_LEVEL: [*mut Node; SLOTS] = [ptr::null_mut(); SLOTS];
+
+/// A hierarchical timing wheel of software timers.
+///
+/// The wheel is protected by [`thr::critical`] sections. It doesn't allocate:
+/// the slots are intrusive lists of the timer nodes, which are stored inside
+/// the pinned [`Sleep`] futures. See [the module-level documentation](self)
+/// for details.
+pub struct TimerWheel<T: Tick> {
+    inner: UnsafeCell<Inner>,
+    _tick: PhantomData<T>,
+}
+
+struct Inner {
+    now: u64,
+    len: usize,
+    levels: [[*mut Node; SLOTS]; LEVELS],
+    expired: *mut Node,
+}
+
+/// An intrusive doubly-linked list node of a pending timer.
+struct Node {
+    deadline: u64,
+    waker: Option<Waker>,
+    next: *mut Node,
+    /// The link, which points to this node, or null if the node is not in a
+    /// list.
+    prev: *mut *mut Node,
+}
+
+/// A future returned by [`TimerWheel::sleep_until`] and [`TimerWheel::sleep`].
+///
+/// The future must be pinned to be polled. Once polled, it is linked into the
+/// wheel, and unlinked when it resolves or is dropped.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep<'a, T: Tick> {
+    wheel: &'a TimerWheel<T>,
+    node: UnsafeCell<Node>,
+    _pinned: PhantomPinned,
+}
+
+/// A stream returned by [`TimerWheel::interval`].
+#[must_use = "streams do nothing unless polled"]
+pub struct Interval<'a, T: Tick> {
+    wheel: &'a TimerWheel<T>,
+    period: Duration<T>,
+    next: Instant<T>,
+    sleep: Sleep<'a, T>,
+    overruns: u32,
+}
+
+unsafe impl<T: Tick> Sync for TimerWheel<T> {}
+
+unsafe impl<T: Tick> Send for Sleep<'_, T> {}
+
+unsafe impl<T: Tick> Sync for Sleep<'_, T> {}
+
+impl<T: Tick> TimerWheel<T> {
+    /// Creates a new empty wheel at the tick zero.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            inner: UnsafeCell::new(Inner {
+                now: 0,
+                len: 0,
+                levels: [EMPTY_LEVEL; LEVELS],
+                expired: ptr::null_mut(),
+            }),
+            _tick: PhantomData,
+        }
+    }
+
+    /// Returns the current time, which is the number of ticks passed.
+    pub fn now(&self) -> Instant<T> {
+        thr::critical(|_| Instant::from_ticks(unsafe { &*self.inner.get() }.now))
+    }
+
+    /// Returns the number of pending timers.
+    pub fn len(&self) -> usize {
+        thr::critical(|_| unsafe { &*self.inner.get() }.len)
+    }
+
+    /// Returns `true` if there are no pending timers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a future, which resolves at the `deadline`.
+    #[inline]
+    pub fn sleep_until(&self, deadline: Instant<T>) -> Sleep<'_, T> {
+        Sleep {
+            wheel: self,
+            node: UnsafeCell::new(Node {
+                deadline: deadline.ticks(),
+                waker: None,
+                next: ptr::null_mut(),
+                prev: ptr::null_mut(),
+            }),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Returns a future, which resolves after the `duration` from now.
+    #[inline]
+    pub fn sleep(&self, duration: Duration<T>) -> Sleep<'_, T> {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Returns a stream, which yields every `period` starting from now.
+    ///
+    /// The stream yields the deadline instants and never ends. If the consumer
+    /// falls behind by one or more whole periods, the missed deadlines are
+    /// skipped, and counted as overruns.
+    ///
+    /// # Panics
+    ///
+    /// If `period` is zero.
+    pub fn interval(&self, period: Duration<T>) -> Interval<'_, T> {
+        assert!(period.ticks() > 0, "period must be non-zero");
+        let next = self.now() + period;
+        Interval { wheel: self, period, next, sleep: self.sleep_until(next), overruns: 0 }
+    }
+
+    /// Advances the time by one tick, and wakes all fibers with expired
+    /// timers.
+    ///
+    /// This method must be called by the tick interrupt handler. It doesn't
+    /// allocate.
+    pub fn tick(&self) {
+        thr::critical(|_| {
+            let inner = unsafe { &mut *self.inner.get() };
+            inner.now += 1;
+            inner.advance();
+        });
+        // Wakers are called outside of the critical section, because waking a
+        // thread may resume it immediately. Each expired timer is taken in its
+        // own short critical section, so that a dropped `Sleep` is never woken.
+        while let Some(waker) = thr::critical(|_| unsafe { (*self.inner.get()).pop_expired() }) {
+            waker.wake();
+        }
+    }
+
+    /// Links the `node` into the wheel, or returns `false` if its deadline has
+    /// passed.
+    fn register(&self, node: *mut Node, waker: &Waker) -> bool {
+        thr::critical(|_| unsafe {
+            let inner = &mut *self.inner.get();
+            if (*node).deadline <= inner.now {
+                inner.remove(node);
+                return false;
+            }
+            match &mut (*node).waker {
+                Some(stored) if stored.will_wake(waker) => {}
+                stored => *stored = Some(waker.clone()),
+            }
+            if (*node).prev.is_null() {
+                let slot = inner.slot((*node).deadline);
+                link(slot, node);
+                inner.len += 1;
+            }
+            true
+        })
+    }
+
+    /// Unlinks the `node` from the wheel if it is linked.
+    fn unregister(&self, node: *mut Node) {
+        thr::critical(|_| unsafe { (*self.inner.get()).remove(node) });
+    }
+}
+
+impl<T: Tick> Default for TimerWheel<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inner {
+    /// Returns the slot, which holds timers with the `deadline` at the current
+    /// time.
+    fn slot(&mut self, deadline: u64) -> &mut *mut Node {
+        let (level, slot) = position(deadline, self.now);
+        &mut self.levels[level][slot]
+    }
+
+    /// Cascades the slots reached by the current time, and moves the expired
+    /// timers to the expired list.
+    #[allow(clippy::cast_possible_truncation)]
+    fn advance(&mut self) {
+        let now = self.now;
+        // Higher levels go first, so timers cascaded down to the current slot
+        // of a lower level are handled in the same tick.
+        for level in (0..LEVELS).rev() {
+            let shift = SLOT_BITS * level as u32;
+            if now & ((1 << shift) - 1) != 0 {
+                continue;
+            }
+            let slot = (now >> shift) as usize & (SLOTS - 1);
+            let mut node = self.levels[level][slot];
+            self.levels[level][slot] = ptr::null_mut();
+            while !node.is_null() {
+                unsafe {
+                    let next = (*node).next;
+                    if (*node).deadline <= now {
+                        self.len -= 1;
+                        link(&mut self.expired, node);
+                    } else {
+                        link(self.slot((*node).deadline), node);
+                    }
+                    node = next;
+                }
+            }
+        }
+    }
+
+    /// Unlinks the first expired timer, and returns its waker.
+    fn pop_expired(&mut self) -> Option<Waker> {
+        let node = self.expired;
+        if node.is_null() {
+            return None;
+        }
+        unsafe {
+            unlink(node);
+            (*node).waker.take()
+        }
+    }
+
+    /// Unlinks the `node` from a slot or from the expired list.
+    unsafe fn remove(&mut self, node: *mut Node) {
+        unsafe {
+            if (*node).prev.is_null() {
+                return;
+            }
+            // Timers in the slots are always in the future, while the expired
+            // timers are already accounted for.
+            if (*node).deadline > self.now {
+                self.len -= 1;
+            }
+            unlink(node);
+        }
+    }
+}
+
+/// Pushes the unlinked `node` to the front of the `list`.
+unsafe fn link(list: *mut *mut Node, node: *mut Node) {
+    unsafe {
+        let head = *list;
+        if !head.is_null() {
+            (*head).prev = &mut (*node).next;
+        }
+        (*node).next = head;
+        (*node).prev = list;
+        *list = node;
+    }
+}
+
+/// Removes the linked `node` from its list.
+unsafe fn unlink(node: *mut Node) {
+    unsafe {
+        let next = (*node).next;
+        if !next.is_null() {
+            (*next).prev = (*node).prev;
+        }
+        *(*node).prev = next;
+        (*node).next = ptr::null_mut();
+        (*node).prev = ptr::null_mut();
+    }
+}
+
+impl<T: Tick> Sleep<'_, T> {
+    /// Returns the deadline.
+    #[inline]
+    pub fn deadline(&self) -> Instant<T> {
+        Instant::from_ticks(unsafe { (*self.node.get()).deadline })
+    }
+
+    /// Resets the deadline, unlinking the timer from the wheel until the next
+    /// poll.
+    pub fn reset(self: Pin<&mut Self>, deadline: Instant<T>) {
+        let node = self.node.get();
+        self.wheel.unregister(node);
+        unsafe { (*node).deadline = deadline.ticks() };
+    }
+}
+
+impl<T: Tick> Future for Sleep<'_, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.wheel.register(self.node.get(), cx.waker()) {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    }
+}
+
+impl<T: Tick> Drop for Sleep<'_, T> {
+    fn drop(&mut self) {
+        // The node can be linked only after the future was pinned, so it is
+        // still at the same address.
+        self.wheel.unregister(self.node.get());
+    }
+}
+
+impl<T: Tick> Interval<'_, T> {
+    /// Returns the period.
+    #[inline]
+    pub fn period(&self) -> Duration<T> {
+        self.period
+    }
+
+    /// Returns the next deadline.
+    #[inline]
+    pub fn next_deadline(&self) -> Instant<T> {
+        self.next
+    }
+
+    /// Returns the number of deadlines skipped because the consumer was late.
+    #[inline]
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
+
+impl<T: Tick> Stream for Interval<'_, T> {
+    type Item = Instant<T>;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let mut sleep = unsafe { Pin::new_unchecked(&mut this.sleep) };
+        if sleep.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let deadline = this.next;
+        let period = this.period.ticks();
+        let missed = this.wheel.now().saturating_duration_since(deadline).ticks() / period;
+        this.overruns = this.overruns.saturating_add(missed as u32);
+        this.next = deadline + Duration::from_ticks(period * (missed + 1));
+        sleep.reset(this.next);
+        Poll::Ready(Some(deadline))
+    }
+}
+
+impl<T: Tick> FusedStream for Interval<'_, T> {
+    #[inline]
+    fn is_terminated(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the level and the slot of a timer with the `deadline` at the time
+/// `now`.
+///
+/// The level is the highest slot index digit, in which `deadline` and `now`
+/// differ. A timer stays in its slot until the time reaches the start of the
+/// slot, at which point the digit becomes equal, and the timer is moved to a
+/// lower level.
+#[allow(clippy::cast_possible_truncation)]
+fn position(deadline: u64, now: u64) -> (usize, usize) {
+    let masked = (deadline ^ now) | (SLOTS as u64 - 1);
+    let significant = 63 - masked.leading_zeros();
+    let level = ((significant / SLOT_BITS) as usize).min(LEVELS - 1);
+    let slot = (deadline >> (SLOT_BITS * level as u32)) as usize & (SLOTS - 1);
+    (level, slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use futures::{
+        pin_mut,
+        task::{waker, ArcWake},
+    };
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Millis;
+
+    impl Tick for Millis {
+        const FREQ: u64 = 1_000;
+    }
+
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn advance(wheel: &TimerWheel<Millis>, ticks: u64) {
+        for _ in 0..ticks {
+            wheel.tick();
+        }
+    }
+
+    #[test]
+    fn sleep() {
+        let wheel = TimerWheel::<Millis>::new();
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        let deadlines = [1, 15, 16, 17, 255, 256, 4_097, 70_000];
+        let mut sleeps = deadlines
+            .iter()
+            .map(|&ms| Box::pin(wheel.sleep(Duration::from_ticks(ms))))
+            .collect::<Vec<_>>();
+        for sleep in &mut sleeps {
+            assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert_eq!(wheel.len(), deadlines.len());
+        let mut now = 0;
+        for (i, &deadline) in deadlines.iter().enumerate() {
+            advance(&wheel, deadline - 1 - now);
+            assert_eq!(counter.0.load(Ordering::SeqCst), i);
+            wheel.tick();
+            now = deadline;
+            assert_eq!(counter.0.load(Ordering::SeqCst), i + 1);
+            assert_eq!(sleeps[i].as_mut().poll(&mut cx), Poll::Ready(()));
+        }
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn far_deadline() {
+        let wheel = TimerWheel::<Millis>::new();
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        let deadline = (1 << (SLOT_BITS * LEVELS as u32)) + 5;
+        let sleep = wheel.sleep(Duration::from_ticks(deadline));
+        pin_mut!(sleep);
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        advance(&wheel, deadline - 1);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+        wheel.tick();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn cancel() {
+        let wheel = TimerWheel::<Millis>::new();
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        advance(&wheel, 30);
+        let mut sleep = Box::pin(wheel.sleep(Duration::from_ticks(300)));
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        advance(&wheel, 100);
+        assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(wheel.len(), 1);
+        drop(sleep);
+        assert!(wheel.is_empty());
+        advance(&wheel, 300);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn interval() {
+        let wheel = TimerWheel::<Millis>::new();
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        let interval = wheel.interval(Duration::from_ticks(10));
+        pin_mut!(interval);
+        assert_eq!(interval.as_mut().poll_next(&mut cx), Poll::Pending);
+        advance(&wheel, 10);
+        assert_eq!(
+            interval.as_mut().poll_next(&mut cx),
+            Poll::Ready(Some(Instant::from_ticks(10)))
+        );
+        assert_eq!(interval.as_mut().poll_next(&mut cx), Poll::Pending);
+        advance(&wheel, 25);
+        assert_eq!(
+            interval.as_mut().poll_next(&mut cx),
+            Poll::Ready(Some(Instant::from_ticks(20)))
+        );
+        assert_eq!(interval.overruns(), 1);
+        assert_eq!(interval.next_deadline(), Instant::from_ticks(40));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    }
+}
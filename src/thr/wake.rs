@@ -0,0 +1,49 @@
+use core::{
+    marker::PhantomData,
+    task::{RawWaker, RawWakerVTable, Waker},
+};
+
+/// A thread wake-up routine addressable by the thread index.
+///
+/// Implementing this trait lets a platform construct [`Waker`]s with
+/// [`static_waker`], which don't allocate memory. The thread index is packed
+/// into the waker data pointer, and the routine is dispatched statically.
+pub trait StaticWake: 'static {
+    /// Wakes up the thread number `thr_idx`.
+    ///
+    /// # Safety
+    ///
+    /// `thr_idx` must be a valid thread index for the implementer.
+    unsafe fn wake(thr_idx: u16);
+}
+
+struct StaticWaker<W: StaticWake>(PhantomData<W>);
+
+/// Creates an allocation-free [`Waker`], which calls [`StaticWake::wake`] with
+/// `thr_idx`.
+///
+/// Cloning and dropping the returned waker are no-ops.
+///
+/// # Safety
+///
+/// `thr_idx` must be a valid thread index for `W`.
+#[inline]
+pub unsafe fn static_waker<W: StaticWake>(thr_idx: u16) -> Waker {
+    unsafe { Waker::from_raw(StaticWaker::<W>::raw(thr_idx)) }
+}
+
+impl<W: StaticWake> StaticWaker<W> {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(Self::clone, Self::wake, Self::wake, drop);
+
+    fn raw(thr_idx: u16) -> RawWaker {
+        RawWaker::new(usize::from(thr_idx) as *const (), &Self::VTABLE)
+    }
+
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        Self::raw(data as u16)
+    }
+
+    unsafe fn wake(data: *const ()) {
+        unsafe { W::wake(data as u16) };
+    }
+}
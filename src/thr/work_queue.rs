@@ -0,0 +1,180 @@
+use crate::{
+    sync::{CoalescingWaker, MpmcQueue, WakeMode},
+    thr::ThrExec,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+
+/// A bounded queue of deferred work items drained by a thread.
+///
+/// Items are pushed from any context, including interrupt handlers, and
+/// processed later by a designated lower-priority thread — the classic "bottom
+/// half" mechanism. The queue has a fixed capacity of `N` items, which must be a
+/// power of two, and counts items rejected because of overflow.
+///
+/// Items can be typed jobs, or function pointers for small closures without
+/// captures.
+///
+/// ```
+/// use drone_core::{thr, thr::WorkQueue, token::Token};
+///
+/// thr::soft! {
+///     thread => pub Thr {};
+///     local => pub ThrLocal {};
+///     index => pub Thrs;
+///     threads => { bottom_half; };
+/// }
+///
+/// enum Job {
+///     Received(u8),
+///     Flush,
+/// }
+///
+/// static QUEUE: WorkQueue<Job, 16> = WorkQueue::new();
+///
+/// fn main() {
+///     let thr = unsafe { Thrs::take() };
+///     QUEUE.attach(thr.bottom_half, |job| match job {
+///         Job::Received(_byte) => {}
+///         Job::Flush => {}
+///     });
+///     // In an interrupt handler:
+///     if QUEUE.push(Job::Received(0x42)).is_err() {
+///         // The item is dropped, and counted in `QUEUE.overflows()`.
+///     }
+/// }
+/// ```
+pub struct WorkQueue<T, const N: usize> {
+    queue: MpmcQueue<T, N>,
+    overflows: AtomicUsize,
+    waker: CoalescingWaker,
+}
+
+/// A future returned by [`WorkQueue::next`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WorkQueueNext<'a, T, const N: usize> {
+    queue: &'a WorkQueue<T, N>,
+}
+
+impl<T, const N: usize> WorkQueue<T, N> {
+    /// Creates an empty queue, which wakes the attached thread on every push.
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_wake_mode(WakeMode::Immediate)
+    }
+
+    /// Creates an empty queue with the given wake-up `mode`.
+    ///
+    /// With [`WakeMode::Coalescing`], a burst of pushes before the attached
+    /// thread runs pends it only once.
+    #[inline]
+    pub const fn with_wake_mode(mode: WakeMode) -> Self {
+        Self {
+            queue: MpmcQueue::NEW,
+            overflows: AtomicUsize::new(0),
+            waker: CoalescingWaker::with_mode(mode),
+        }
+    }
+
+    /// Returns the capacity of the queue.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of items rejected by [`WorkQueue::push`] because the
+    /// queue was full.
+    #[inline]
+    pub fn overflows(&self) -> usize {
+        self.overflows.load(Ordering::Relaxed)
+    }
+
+    /// Pushes the `item` to the queue and wakes up the attached thread.
+    ///
+    /// If the queue is full, returns the `item` back as an error. This
+    /// operation is lock-free.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        if let Err(item) = self.queue.push(item) {
+            self.overflows.fetch_add(1, Ordering::Relaxed);
+            return Err(item);
+        }
+        self.waker.wake();
+        Ok(())
+    }
+
+    /// Pops the oldest item from the queue.
+    ///
+    /// Returns `None` if the queue is empty. This operation is lock-free.
+    #[inline]
+    pub fn pop(&self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    /// Returns a future, which resolves to the next item of the queue.
+    #[inline]
+    pub fn next(&self) -> WorkQueueNext<'_, T, N> {
+        WorkQueueNext { queue: self }
+    }
+
+    /// Attaches the queue to the thread `thr`, which will drain the queue
+    /// calling `handler` for each item.
+    pub fn attach<H, F>(&'static self, thr: H, mut handler: F)
+    where
+        T: Send,
+        H: ThrExec,
+        F: FnMut(T) + Send + 'static,
+    {
+        thr.add_exec(async move {
+            loop {
+                handler(self.next().await);
+            }
+        });
+    }
+}
+
+impl<T, const N: usize> Default for WorkQueue<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Future for WorkQueueNext<'_, T, N> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        if let Some(item) = self.queue.pop() {
+            return Poll::Ready(item);
+        }
+        self.queue.waker.arm(cx.waker());
+        if let Some(item) = self.queue.pop() {
+            self.queue.waker.disarm();
+            return Poll::Ready(item);
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_pop() {
+        let queue = WorkQueue::<u32, 2>::new();
+        assert_eq!(queue.pop(), None);
+        for round in 0..3 {
+            assert_eq!(queue.push(round), Ok(()));
+            assert_eq!(queue.push(round + 10), Ok(()));
+            assert_eq!(queue.push(round + 20), Err(round + 20));
+            assert_eq!(queue.pop(), Some(round));
+            assert_eq!(queue.pop(), Some(round + 10));
+            assert_eq!(queue.pop(), None);
+        }
+        assert_eq!(queue.overflows(), 3);
+    }
+}
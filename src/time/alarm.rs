@@ -0,0 +1,226 @@
+use super::{Duration, Instant, Timer};
+use crate::thr;
+use core::{
+    cell::UnsafeCell,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+/// A multiplexer of one hardware [`Timer`] between any number of tasks.
+///
+/// The pending deadlines are kept in a priority queue protected by
+/// [`thr::critical`] sections. See [the module-level documentation](super) for
+/// details.
+pub struct Alarm<T: Timer> {
+    timer: T,
+    // Sorted by deadline in descending order, so the nearest one is the last.
+    queue: UnsafeCell<Vec<Entry<T>>>,
+    next_id: AtomicUsize,
+}
+
+struct Entry<T: Timer> {
+    deadline: Instant<T::Tick>,
+    id: usize,
+    waker: Waker,
+}
+
+/// A future returned by [`Alarm::sleep_until`] and [`Alarm::sleep`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep<'a, T: Timer> {
+    alarm: &'a Alarm<T>,
+    deadline: Instant<T::Tick>,
+    id: Option<usize>,
+}
+
+unsafe impl<T: Timer> Sync for Alarm<T> {}
+
+impl<T: Timer> Unpin for Sleep<'_, T> {}
+
+impl<T: Timer> Alarm<T> {
+    /// Creates a new alarm multiplexer for the `timer`.
+    #[inline]
+    pub const fn new(timer: T) -> Self {
+        Self { timer, queue: UnsafeCell::new(Vec::new()), next_id: AtomicUsize::new(0) }
+    }
+
+    /// Returns a reference to the underlying timer.
+    #[inline]
+    pub fn timer(&self) -> &T {
+        &self.timer
+    }
+
+    /// Returns the current time.
+    #[inline]
+    pub fn now(&self) -> Instant<T::Tick> {
+        self.timer.now()
+    }
+
+    /// Returns a future, which resolves at the `deadline`.
+    #[inline]
+    pub fn sleep_until(&self, deadline: Instant<T::Tick>) -> Sleep<'_, T> {
+        Sleep { alarm: self, deadline, id: None }
+    }
+
+    /// Returns a future, which resolves after the `duration` from now.
+    #[inline]
+    pub fn sleep(&self, duration: Duration<T::Tick>) -> Sleep<'_, T> {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Wakes all tasks with expired deadlines, and reprograms the timer for the
+    /// nearest pending deadline.
+    ///
+    /// This method must be called by the timer interrupt handler.
+    pub fn fire(&self) {
+        loop {
+            // Wakers are called outside of the critical section, because waking
+            // a thread may resume it immediately.
+            let waker = thr::critical(|_| {
+                let queue = unsafe { &mut *self.queue.get() };
+                let now = self.timer.now();
+                if queue.last().map_or(false, |entry| entry.deadline <= now) {
+                    queue.pop().map(|entry| entry.waker)
+                } else {
+                    self.reschedule(queue);
+                    None
+                }
+            });
+            match waker {
+                Some(waker) => waker.wake(),
+                None => break,
+            }
+        }
+    }
+
+    fn register(&self, id: usize, deadline: Instant<T::Tick>, waker: &Waker) {
+        thr::critical(|_| {
+            let queue = unsafe { &mut *self.queue.get() };
+            if let Some(entry) = queue.iter_mut().find(|entry| entry.id == id) {
+                if !entry.waker.will_wake(waker) {
+                    entry.waker = waker.clone();
+                }
+                return;
+            }
+            let index = queue.partition_point(|entry| entry.deadline > deadline);
+            queue.insert(index, Entry { deadline, id, waker: waker.clone() });
+            self.reschedule(queue);
+        });
+    }
+
+    fn unregister(&self, id: usize) {
+        thr::critical(|_| {
+            let queue = unsafe { &mut *self.queue.get() };
+            if let Some(index) = queue.iter().position(|entry| entry.id == id) {
+                queue.remove(index);
+                self.reschedule(queue);
+            }
+        });
+    }
+
+    fn reschedule(&self, queue: &[Entry<T>]) {
+        match queue.last() {
+            Some(entry) => self.timer.schedule(entry.deadline),
+            None => self.timer.cancel(),
+        }
+    }
+}
+
+impl<T: Timer> Future for Sleep<'_, T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.alarm.now() >= self.deadline {
+            if let Some(id) = self.id.take() {
+                self.alarm.unregister(id);
+            }
+            return Poll::Ready(());
+        }
+        let alarm = self.alarm;
+        let id = *self.id.get_or_insert_with(|| alarm.next_id.fetch_add(1, Ordering::Relaxed));
+        alarm.register(id, self.deadline, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<T: Timer> Drop for Sleep<'_, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.alarm.unregister(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Tick;
+    use alloc::sync::Arc;
+    use core::{cell::Cell, task::Context};
+    use futures::{pin_mut, task::ArcWake};
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Millis;
+
+    impl Tick for Millis {
+        const FREQ: u64 = 1_000;
+    }
+
+    struct FakeTimer {
+        now: Cell<u64>,
+        scheduled: Cell<Option<u64>>,
+    }
+
+    unsafe impl Sync for FakeTimer {}
+
+    impl Timer for FakeTimer {
+        type Tick = Millis;
+
+        fn now(&self) -> Instant<Millis> {
+            Instant::from_ticks(self.now.get())
+        }
+
+        fn schedule(&self, at: Instant<Millis>) {
+            self.scheduled.set(Some(at.ticks()));
+        }
+
+        fn cancel(&self) {
+            self.scheduled.set(None);
+        }
+    }
+
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn sleep() {
+        let alarm = Alarm::new(FakeTimer { now: Cell::new(0), scheduled: Cell::new(None) });
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = futures::task::waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+        let a = alarm.sleep(Duration::from_millis(20));
+        let b = alarm.sleep(Duration::from_millis(10));
+        pin_mut!(a);
+        pin_mut!(b);
+        assert_eq!(a.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(alarm.timer().scheduled.get(), Some(20));
+        assert_eq!(b.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(alarm.timer().scheduled.get(), Some(10));
+        alarm.timer().now.set(15);
+        alarm.fire();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(alarm.timer().scheduled.get(), Some(20));
+        assert_eq!(b.as_mut().poll(&mut cx), Poll::Ready(()));
+        alarm.timer().now.set(20);
+        alarm.fire();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(alarm.timer().scheduled.get(), None);
+        assert_eq!(a.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+}
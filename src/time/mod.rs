@@ -0,0 +1,292 @@
+//! Time keeping and asynchronous delays.
+//!
+//! A Drone platform provides a hardware timer by implementing the [`Timer`]
+//! trait. Time is measured in ticks of the timer, which rate is described by
+//! a [`Tick`] type. [`Instant`] and [`Duration`] are tick counts tagged with
+//! the tick type, so values of different timers can't be mixed up.
+//!
+//! An [`Alarm`] multiplexes one hardware timer between any number of tasks.
+//! Pending deadlines are kept in a priority queue, and the timer is always
+//! programmed to fire at the nearest one.
+//!
+//! ```
+//! use drone_core::time::{Alarm, Duration, Instant, Tick, Timer};
+//!
+//! /// A 32 kHz low-power timer.
+//! #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+//! pub struct LptimTick;
+//!
+//! impl Tick for LptimTick {
+//!     const FREQ: u64 = 32_768;
+//! }
+//!
+//! pub struct Lptim;
+//!
+//! impl Timer for Lptim {
+//!     type Tick = LptimTick;
+//!
+//!     fn now(&self) -> Instant<LptimTick> {
+//!         Instant::from_ticks(0) // Read the counter register here.
+//!     }
+//!
+//!     fn schedule(&self, _at: Instant<LptimTick>) {
+//!         // Program the compare register and enable the interrupt here.
+//!     }
+//!
+//!     fn cancel(&self) {
+//!         // Disable the compare interrupt here.
+//!     }
+//! }
+//!
+//! async fn blink(alarm: &Alarm<Lptim>) {
+//!     loop {
+//!         // Toggle the LED here.
+//!         alarm.sleep(Duration::from_millis(500)).await;
+//!     }
+//! }
+//! ```
+//!
+//! The interrupt handler of the timer must call [`Alarm::fire`].
+
+mod alarm;
+
+pub use self::alarm::{Alarm, Sleep};
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+
+/// The error type returned when a deadline elapses before an operation
+/// completes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Timeout;
+
+/// A tick rate of a [`Timer`].
+pub trait Tick: Copy + Ord + fmt::Debug + Send + Sync + 'static {
+    /// The number of ticks per second.
+    const FREQ: u64;
+}
+
+/// A hardware timer.
+pub trait Timer: Sync {
+    /// The tick rate of the timer.
+    type Tick: Tick;
+
+    /// Returns the current time.
+    ///
+    /// The counter must be monotonic and must not wrap around, which can be
+    /// achieved by extending a hardware counter in software.
+    fn now(&self) -> Instant<Self::Tick>;
+
+    /// Programs the timer to fire at `at`, replacing the previous schedule.
+    ///
+    /// If `at` is already in the past, the timer must fire as soon as
+    /// possible.
+    fn schedule(&self, at: Instant<Self::Tick>);
+
+    /// Cancels the previous schedule.
+    fn cancel(&self);
+}
+
+/// A point in time measured in ticks of `T`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant<T: Tick> {
+    ticks: u64,
+    _tick: PhantomData<T>,
+}
+
+/// A span of time measured in ticks of `T`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration<T: Tick> {
+    ticks: u64,
+    _tick: PhantomData<T>,
+}
+
+impl<T: Tick> Instant<T> {
+    /// Creates an instant from the raw number of ticks.
+    #[inline]
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self { ticks, _tick: PhantomData }
+    }
+
+    /// Returns the raw number of ticks.
+    #[inline]
+    pub const fn ticks(self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the amount of time elapsed from `earlier` to `self`, or `None`
+    /// if `earlier` is later than `self`.
+    #[inline]
+    pub fn checked_duration_since(self, earlier: Self) -> Option<Duration<T>> {
+        self.ticks.checked_sub(earlier.ticks).map(Duration::from_ticks)
+    }
+
+    /// Returns the amount of time elapsed from `earlier` to `self`, or zero
+    /// duration if `earlier` is later than `self`.
+    #[inline]
+    pub fn saturating_duration_since(self, earlier: Self) -> Duration<T> {
+        Duration::from_ticks(self.ticks.saturating_sub(earlier.ticks))
+    }
+}
+
+impl<T: Tick> Duration<T> {
+    /// A zero duration.
+    pub const ZERO: Self = Self::from_ticks(0);
+
+    /// Creates a duration from the raw number of ticks.
+    #[inline]
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self { ticks, _tick: PhantomData }
+    }
+
+    /// Creates a duration from the number of seconds.
+    #[inline]
+    pub const fn from_secs(secs: u64) -> Self {
+        Self::from_ticks(secs * T::FREQ)
+    }
+
+    /// Creates a duration from the number of milliseconds, rounding up to the
+    /// nearest tick.
+    #[inline]
+    pub const fn from_millis(millis: u64) -> Self {
+        Self::from_ticks(div_ceil(millis * T::FREQ, 1_000))
+    }
+
+    /// Creates a duration from the number of microseconds, rounding up to the
+    /// nearest tick.
+    #[inline]
+    pub const fn from_micros(micros: u64) -> Self {
+        Self::from_ticks(div_ceil(micros * T::FREQ, 1_000_000))
+    }
+
+    /// Returns the raw number of ticks.
+    #[inline]
+    pub const fn ticks(self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the number of whole seconds.
+    #[inline]
+    pub const fn as_secs(self) -> u64 {
+        self.ticks / T::FREQ
+    }
+
+    /// Returns the number of whole milliseconds.
+    #[inline]
+    pub const fn as_millis(self) -> u64 {
+        self.ticks * 1_000 / T::FREQ
+    }
+
+    /// Returns the number of whole microseconds.
+    #[inline]
+    pub const fn as_micros(self) -> u64 {
+        self.ticks * 1_000_000 / T::FREQ
+    }
+}
+
+const fn div_ceil(lhs: u64, rhs: u64) -> u64 {
+    (lhs + rhs - 1) / rhs
+}
+
+impl<T: Tick> Add<Duration<T>> for Instant<T> {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Duration<T>) -> Self {
+        Self::from_ticks(self.ticks + rhs.ticks)
+    }
+}
+
+impl<T: Tick> AddAssign<Duration<T>> for Instant<T> {
+    #[inline]
+    fn add_assign(&mut self, rhs: Duration<T>) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Tick> Sub<Duration<T>> for Instant<T> {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Duration<T>) -> Self {
+        Self::from_ticks(self.ticks - rhs.ticks)
+    }
+}
+
+impl<T: Tick> SubAssign<Duration<T>> for Instant<T> {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Duration<T>) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: Tick> Sub for Instant<T> {
+    type Output = Duration<T>;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Duration<T> {
+        Duration::from_ticks(self.ticks - rhs.ticks)
+    }
+}
+
+impl<T: Tick> Add for Duration<T> {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::from_ticks(self.ticks + rhs.ticks)
+    }
+}
+
+impl<T: Tick> Sub for Duration<T> {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_ticks(self.ticks - rhs.ticks)
+    }
+}
+
+impl<T: Tick> fmt::Debug for Instant<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instant({} ticks)", self.ticks)
+    }
+}
+
+impl<T: Tick> fmt::Debug for Duration<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Duration({} ticks)", self.ticks)
+    }
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Deadline has elapsed.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct LowPower;
+
+    impl Tick for LowPower {
+        const FREQ: u64 = 32_768;
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Duration::<LowPower>::from_secs(2).ticks(), 65_536);
+        assert_eq!(Duration::<LowPower>::from_millis(1).ticks(), 33);
+        assert_eq!(Duration::<LowPower>::from_micros(1).ticks(), 1);
+        assert_eq!(Duration::<LowPower>::from_ticks(65_536).as_millis(), 2_000);
+        let instant = Instant::<LowPower>::from_ticks(100);
+        assert_eq!((instant + Duration::from_ticks(5)).ticks(), 105);
+        assert_eq!(instant.checked_duration_since(instant + Duration::from_ticks(1)), None);
+    }
+}
@@ -85,6 +85,73 @@
 //! }
 //! ```
 //!
+//! # Init Token Trees
+//!
+//! In a large firmware handing every subsystem its own set of one-time
+//! initializers results in one giant token struct. `unsafe_init_tokens!` macro
+//! defines a tree of token groups instead. Each group is a token itself, which
+//! can be [`split`](Split::split) into its parts and [`join`](Split::join)ed
+//! back. Since the leaves are affine, each of them can still be consumed
+//! exactly once.
+//!
+//! ```
+//! use drone_core::token::{simple_token, unsafe_init_tokens, Split, Token};
+//!
+//! simple_token!(pub struct EthInitToken);
+//! simple_token!(pub struct IpInitToken);
+//! simple_token!(pub struct FlashInitToken);
+//!
+//! unsafe_init_tokens! {
+//!     /// The root group token.
+//!     pub struct Inits {
+//!         /// The network subsystem tokens.
+//!         Net {
+//!             EthInitToken,
+//!             IpInitToken,
+//!         },
+//!         FlashInitToken,
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let ini = unsafe { Inits::take() };
+//!     let (net, flash_init) = ini.split();
+//!     init_net(net);
+//!     init_flash(flash_init);
+//! }
+//!
+//! fn init_net(net: Net) {
+//!     let (eth_init, ip_init) = net.split();
+//!     // Recombine the group to pass it further.
+//!     let net = Net::join((eth_init, ip_init));
+//!     let Net { eth_init, ip_init, .. } = net;
+//! }
+//!
+//! fn init_flash(token: FlashInitToken) {}
+//! ```
+//!
+//! # Testing
+//!
+//! Host unit tests often need a token to call a function under test. Instead of
+//! calling [`Token::take`] directly, tests should use [`forge`], which is
+//! available only under `cfg(test)` or with the `std` feature. This way
+//! forging a token in production code is a compile error.
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use drone_core::token::{self, simple_token};
+//!
+//! simple_token!(pub struct FooInitToken);
+//!
+//! fn init_foo(token: FooInitToken) -> u32 {
+//!     42
+//! }
+//!
+//! let token = unsafe { token::forge::<FooInitToken>() };
+//! assert_eq!(init_foo(token), 42);
+//! # }
+//! ```
+//!
 //! # Static Tokens
 //!
 //! Mutable statics are unsafe in Rust. One way to make them safe is to use
@@ -155,6 +222,16 @@ pub use drone_core_macros::simple_token;
 #[doc(inline)]
 pub use drone_core_macros::unsafe_simple_tokens;
 
+/// Defines a tree of token groups for the set of simple [`Token`]s.
+///
+/// See [the module-level documentation](self) for details.
+///
+/// # Safety
+///
+/// The tokens must not be instantiated anywhere else.
+#[doc(inline)]
+pub use drone_core_macros::unsafe_init_tokens;
+
 /// Defines a new token for the set of [`StaticToken`]s.
 ///
 /// See [the module-level documentation](self) for details.
@@ -212,6 +289,42 @@ pub unsafe trait StaticToken: Token + Sized + Send + 'static {
     fn into_static(self) -> &'static mut Self::Target;
 }
 
+/// Forges an instance of the token `T` for host unit tests.
+///
+/// This lets driver crates test their logic with real register and thread
+/// tokens instead of making every function generic over a mock layer. The
+/// function is available only in tests or with the `std` feature, and must
+/// never be used in production code.
+///
+/// See [the module-level documentation](self) for details.
+///
+/// # Safety
+///
+/// This function breaks the token uniqueness guarantee. It is the caller
+/// responsibility to not forge more than one instance of the same token at
+/// the same time, and to not access the real hardware through the forged
+/// register tokens.
+#[cfg(any(test, feature = "std"))]
+#[inline]
+pub unsafe fn forge<T: Token>() -> T {
+    unsafe { T::take() }
+}
+
+/// A token composed of other tokens, which can be split into its parts and
+/// recombined.
+///
+/// See [the module-level documentation](self) for details.
+pub trait Split: Token {
+    /// A tuple of the constituent tokens.
+    type Parts;
+
+    /// Splits the token into its parts.
+    fn split(self) -> Self::Parts;
+
+    /// Recombines the token from its parts.
+    fn join(parts: Self::Parts) -> Self;
+}
+
 mod compile_tests {
     //! ```compile_fail
     //! drone_core::token::simple_token!(struct Foo);
@@ -239,6 +352,22 @@ mod compile_tests {
     //! ```
     //!
     //! ```compile_fail
+    //! use drone_core::token::{simple_token, unsafe_init_tokens, Split, Token};
+    //! simple_token!(struct FooToken);
+    //! unsafe_init_tokens! {
+    //!     struct Foo {
+    //!         FooToken,
+    //!     }
+    //! }
+    //! fn main() {
+    //!     let foo = unsafe { Foo::take() };
+    //!     let (a,) = foo.split();
+    //!     drop(a);
+    //!     drop(foo.foo);
+    //! }
+    //! ```
+    //!
+    //! ```compile_fail
     //! use drone_core::token::Token;
     //! static mut FOO: usize = 0;
     //! drone_core::token::unsafe_static_tokens! {
@@ -251,3 +380,21 @@ mod compile_tests {
     //! }
     //! ```
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FooToken(());
+
+    unsafe impl Token for FooToken {
+        unsafe fn take() -> Self {
+            Self(())
+        }
+    }
+
+    #[test]
+    fn forge_token() {
+        let FooToken(()) = unsafe { forge::<FooToken>() };
+    }
+}
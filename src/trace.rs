@@ -0,0 +1,189 @@
+//! Instrumented tracing of spans and events.
+//!
+//! A span marks a region of code, such as a fiber step or an interrupt
+//! handler. [`trace::span!`](crate::trace::span) returns a guard, which writes
+//! an enter record when created and an exit record when dropped. Every span
+//! gets a unique non-zero ID, so that the host tools can match the exit records
+//! to the enter records. [`trace::event!`](crate::trace::event) writes a single
+//! point-in-time record with an optional value.
+//!
+//! ```
+//! use drone_core::trace;
+//!
+//! fn adc_handler(sample: u16) {
+//!     let _span = trace::span!("adc_handler");
+//!     trace::event!("sample", u32::from(sample));
+//!     // Process the sample.
+//! }
+//! ```
+//!
+//! # Records
+//!
+//! Records are written to the log port [`PORT`] only if a debug probe is
+//! listening to it. Each record starts with a tag byte followed by big-endian
+//! `u32` words:
+//!
+//! * [`ENTER`] - span ID, name address, timestamp
+//! * [`EXIT`] - span ID, timestamp
+//! * [`EVENT`] - name address, value, timestamp
+//!
+//! Names are `&'static str`s, which are not transmitted. Instead their
+//! addresses are, and the host tools resolve them from the firmware image.
+//! Timestamps are read from the cycle counter registered with
+//! [`set_cycle_counter!`](crate::set_cycle_counter). Records are written inside
+//! [`thr::critical`] sections, so preempting spans always nest, which is
+//! enough to reconstruct flamegraphs of threads and interrupts.
+//!
+//! # Compiling Out
+//!
+//! With `trace-off` feature, the macros expand to no-ops, and neither the
+//! records nor the names are included into the binary.
+
+use crate::{log::Port, thr};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// The log port number for trace records.
+pub const PORT: u8 = 29;
+
+/// The tag of a span enter record.
+pub const ENTER: u8 = 0x01;
+
+/// The tag of a span exit record.
+pub const EXIT: u8 = 0x02;
+
+/// The tag of an event record.
+pub const EVENT: u8 = 0x03;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A guard returned by [`trace::span!`](crate::trace::span), which writes the
+/// exit record when dropped.
+#[must_use = "the span exits immediately if the guard is not held"]
+pub struct Span {
+    id: u32,
+}
+
+/// Enters a new span with the given `&'static str` name, and returns a
+/// [`Span`] guard.
+///
+/// See [the module-level documentation](self) for details.
+#[doc(inline)]
+pub use crate::__trace_span as span;
+
+/// Writes an event record with the given `&'static str` name and an optional
+/// `u32` value.
+///
+/// See [the module-level documentation](self) for details.
+#[doc(inline)]
+pub use crate::__trace_event as event;
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_span {
+    ($name:expr $(,)?) => {
+        $crate::trace::Span::enter($name)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_event {
+    ($name:expr $(,)?) => {
+        $crate::trace::event!($name, 0)
+    };
+    ($name:expr, $value:expr $(,)?) => {
+        $crate::trace::emit_event($name, $value)
+    };
+}
+
+/// Returns `true` if trace records are compiled in and a debug probe is
+/// listening to the trace port.
+#[inline(always)]
+pub fn is_enabled() -> bool {
+    #[cfg(feature = "trace-off")]
+    return false;
+    #[cfg(not(feature = "trace-off"))]
+    {
+        Port::new(PORT).is_enabled()
+    }
+}
+
+impl Span {
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn enter(name: &'static str) -> Self {
+        if !is_enabled() {
+            return Self { id: 0 };
+        }
+        let id = next_id();
+        thr::critical(|_| {
+            Port::new(PORT)
+                .write::<u8>(ENTER)
+                .write::<u32>(id)
+                .write::<u32>(address(name))
+                .write::<u32>(thr::cycles());
+        });
+        Self { id }
+    }
+
+    /// Returns the span ID, or zero if the span is not recorded.
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Drop for Span {
+    #[inline(always)]
+    fn drop(&mut self) {
+        if self.id != 0 {
+            thr::critical(|_| {
+                Port::new(PORT).write::<u8>(EXIT).write::<u32>(self.id).write::<u32>(thr::cycles());
+            });
+        }
+    }
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn emit_event(name: &'static str, value: u32) {
+    if is_enabled() {
+        thr::critical(|_| {
+            Port::new(PORT)
+                .write::<u8>(EVENT)
+                .write::<u32>(address(name))
+                .write::<u32>(value)
+                .write::<u32>(thr::cycles());
+        });
+    }
+}
+
+fn next_id() -> u32 {
+    // Zero is reserved for unrecorded spans.
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    if id == 0 {
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    } else {
+        id
+    }
+}
+
+fn address(name: &'static str) -> u32 {
+    name.as_ptr() as usize as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled() {
+        assert!(!is_enabled());
+        let span = span!("disabled");
+        assert_eq!(span.id(), 0);
+        event!("disabled");
+        event!("disabled", 1);
+        drop(span);
+        assert_eq!(NEXT_ID.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,238 @@
+//! Software watchdog supervisor.
+//!
+//! A hardware watchdog only proves that *something* in the application is still
+//! running. The [`Supervisor`] turns it into a per-task liveness monitor. Each
+//! monitored task registers a [`Heartbeat`] with its declared period, and must
+//! [`feed`](Heartbeat::feed) it within this period. The supervisor fiber kicks
+//! the hardware watchdog only if all heartbeats are fresh, and reports the
+//! starving task otherwise, so that the device is reset by the hardware.
+//!
+//! ```
+//! use drone_core::{
+//!     time::{Alarm, Duration, Timer},
+//!     watchdog::{Supervisor, Watchdog},
+//! };
+//!
+//! pub struct Iwdg;
+//!
+//! impl Watchdog for Iwdg {
+//!     fn kick(&self) {
+//!         // Reload the hardware watchdog counter here.
+//!     }
+//! }
+//!
+//! async fn control_loop<T: Timer>(supervisor: &'static Supervisor<T, 4>) {
+//!     let heartbeat = supervisor.register("control", Duration::from_millis(100)).unwrap();
+//!     loop {
+//!         // Do the control work here.
+//!         heartbeat.feed();
+//!         supervisor.alarm().sleep(Duration::from_millis(50)).await;
+//!     }
+//! }
+//!
+//! async fn supervise<T: Timer>(supervisor: &'static Supervisor<T, 4>) {
+//!     supervisor.run(Iwdg, Duration::from_millis(200)).await;
+//! }
+//! ```
+
+use crate::{
+    eprintln, thr,
+    time::{Alarm, Duration, Instant, Timer},
+};
+use core::{cell::UnsafeCell, fmt};
+
+/// A hardware watchdog.
+pub trait Watchdog {
+    /// Reloads the watchdog counter.
+    fn kick(&self);
+}
+
+/// A software watchdog supervisor for up to `N` tasks.
+///
+/// See [the module-level documentation](self) for details.
+pub struct Supervisor<T: Timer + 'static, const N: usize> {
+    alarm: &'static Alarm<T>,
+    slots: UnsafeCell<[Option<Slot<T>>; N]>,
+}
+
+/// A liveness handle of a monitored task.
+///
+/// The task is unregistered when the handle is dropped.
+pub struct Heartbeat<'a, T: Timer + 'static, const N: usize> {
+    supervisor: &'a Supervisor<T, N>,
+    index: usize,
+}
+
+/// The error type returned from [`Supervisor::register`] when all slots are
+/// taken.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SupervisorFull;
+
+/// A task which didn't feed its heartbeat in time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Starving {
+    /// The identifier of the task.
+    pub id: &'static str,
+}
+
+struct Slot<T: Timer> {
+    id: &'static str,
+    period: Duration<T::Tick>,
+    last_fed: Instant<T::Tick>,
+}
+
+unsafe impl<T: Timer, const N: usize> Sync for Supervisor<T, N> {}
+
+impl<T: Timer, const N: usize> Supervisor<T, N> {
+    /// Creates a new supervisor using the `alarm` as the time source.
+    #[inline]
+    pub const fn new(alarm: &'static Alarm<T>) -> Self {
+        Self { alarm, slots: UnsafeCell::new([None; N]) }
+    }
+
+    /// Returns a reference to the alarm used as the time source.
+    #[inline]
+    pub fn alarm(&self) -> &'static Alarm<T> {
+        self.alarm
+    }
+
+    /// Registers a task identified by `id`, which promises to feed the returned
+    /// heartbeat at least once per `period`.
+    ///
+    /// The heartbeat is considered fed at the moment of registration.
+    pub fn register(
+        &self,
+        id: &'static str,
+        period: Duration<T::Tick>,
+    ) -> Result<Heartbeat<'_, T, N>, SupervisorFull> {
+        let last_fed = self.alarm.now();
+        thr::critical(|_| {
+            let slots = unsafe { &mut *self.slots.get() };
+            let index = slots.iter().position(Option::is_none).ok_or(SupervisorFull)?;
+            slots[index] = Some(Slot { id, period, last_fed });
+            Ok(Heartbeat { supervisor: self, index })
+        })
+    }
+
+    /// Checks that all registered heartbeats are fresh.
+    ///
+    /// Returns the first starving task otherwise.
+    pub fn check(&self) -> Result<(), Starving> {
+        let now = self.alarm.now();
+        thr::critical(|_| {
+            let slots = unsafe { &*self.slots.get() };
+            for slot in slots.iter().flatten() {
+                if now.saturating_duration_since(slot.last_fed) > slot.period {
+                    return Err(Starving { id: slot.id });
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs the supervisor loop, which checks the heartbeats every `interval`
+    /// and kicks the `watchdog` if all of them are fresh.
+    ///
+    /// If a task is starving, it is reported to the standard error log port,
+    /// and the watchdog is not kicked anymore.
+    pub async fn run<W: Watchdog>(&self, watchdog: W, interval: Duration<T::Tick>) -> ! {
+        loop {
+            match self.check() {
+                Ok(()) => watchdog.kick(),
+                Err(starving) => {
+                    eprintln!("{}", starving);
+                    loop {
+                        self.alarm.sleep(interval).await;
+                    }
+                }
+            }
+            self.alarm.sleep(interval).await;
+        }
+    }
+}
+
+impl<T: Timer, const N: usize> Heartbeat<'_, T, N> {
+    /// Reports that the task is alive.
+    pub fn feed(&self) {
+        let now = self.supervisor.alarm.now();
+        thr::critical(|_| {
+            let slots = unsafe { &mut *self.supervisor.slots.get() };
+            if let Some(slot) = &mut slots[self.index] {
+                slot.last_fed = now;
+            }
+        });
+    }
+}
+
+impl<T: Timer, const N: usize> Drop for Heartbeat<'_, T, N> {
+    fn drop(&mut self) {
+        thr::critical(|_| {
+            let slots = unsafe { &mut *self.supervisor.slots.get() };
+            slots[self.index] = None;
+        });
+    }
+}
+
+impl<T: Timer> Clone for Slot<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Timer> Copy for Slot<T> {}
+
+impl fmt::Display for SupervisorFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no free watchdog supervisor slots")
+    }
+}
+
+impl fmt::Display for Starving {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "watchdog: task `{}` is starving", self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Tick;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Millis;
+
+    impl Tick for Millis {
+        const FREQ: u64 = 1_000;
+    }
+
+    struct ManualTimer(AtomicU64);
+
+    impl Timer for ManualTimer {
+        type Tick = Millis;
+
+        fn now(&self) -> Instant<Millis> {
+            Instant::from_ticks(self.0.load(Ordering::SeqCst))
+        }
+
+        fn schedule(&self, _at: Instant<Millis>) {}
+
+        fn cancel(&self) {}
+    }
+
+    #[test]
+    fn starving() {
+        static ALARM: Alarm<ManualTimer> = Alarm::new(ManualTimer(AtomicU64::new(0)));
+        let supervisor = Supervisor::<_, 2>::new(&ALARM);
+        let a = supervisor.register("a", Duration::from_millis(10)).unwrap();
+        let b = supervisor.register("b", Duration::from_millis(20)).unwrap();
+        assert!(supervisor.register("c", Duration::from_millis(30)).is_err());
+        ALARM.timer().0.store(15, Ordering::SeqCst);
+        assert_eq!(supervisor.check(), Err(Starving { id: "a" }));
+        a.feed();
+        assert_eq!(supervisor.check(), Ok(()));
+        ALARM.timer().0.store(25, Ordering::SeqCst);
+        drop(b);
+        assert_eq!(supervisor.check(), Ok(()));
+    }
+}
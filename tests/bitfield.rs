@@ -1,7 +1,7 @@
 #![no_implicit_prelude]
 
-use ::drone_core::bitfield::Bitfield;
-use ::std::assert_eq;
+use ::drone_core::bitfield::{Bitfield, Packed};
+use ::std::{assert, assert_eq, iter::Iterator, vec::Vec};
 
 #[derive(Bitfield, Copy, Clone)]
 #[bitfield(
@@ -11,6 +11,29 @@ use ::std::assert_eq;
 )]
 pub struct Byte(u8);
 
+#[derive(Bitfield, Copy, Clone)]
+#[bitfield(
+    txe(r, 7, "Test flag with the highest priority."),
+    rxne(r, 1, "Test flag with a lower priority."),
+    mode(rw, 2, 2, "Test multiple-bits field."),
+    ore(rw, 0, 1, "Test read-write flag."),
+    clear(w, 4, 1, "Test write-only flag.")
+)]
+#[bitfield_flags(Flag)]
+pub struct Status(u16);
+
+#[derive(Packed)]
+#[packed(size = 3)]
+pub struct Header(Byte, u16);
+
+#[derive(Packed)]
+#[packed(size = 9)]
+pub struct Packet {
+    header: Header,
+    status: Status,
+    crc: u32,
+}
+
 #[test]
 fn read_bit() {
     let x = Byte(0b1010_1010);
@@ -118,3 +141,41 @@ fn write_bits() {
     unsafe { x.write_bits(0, 8, 0b1111_1111) };
     assert_eq!(x.bits(), 0b1111_1111);
 }
+
+#[test]
+fn iter_set() {
+    let x = Status(0b1001_1111);
+    assert_eq!(x.iter_set().collect::<Vec<_>>(), [Flag::Txe, Flag::Rxne, Flag::Ore]);
+    let x = Status(0b0110_1100);
+    assert_eq!(x.iter_set().next(), ::std::option::Option::None);
+}
+
+#[test]
+fn masks() {
+    let x = Byte(0b1010_0110);
+    assert!(x.any(0b0000_0011));
+    assert!(!x.any(0b0101_0001));
+    assert!(x.all(0b1000_0110));
+    assert!(!x.all(0b1000_0111));
+    assert_eq!(x.count_ones(), 4);
+    assert_eq!(x.leading_zeros(), 0);
+    assert_eq!(Byte(0b0001_0000).leading_zeros(), 3);
+}
+
+#[test]
+fn packed() {
+    let packet =
+        Packet { header: Header(Byte(0xA5), 0x0102), status: Status(0x0304), crc: 0x0506_0708 };
+    assert_eq!(<Packet as Packed>::SIZE, 9);
+    assert_eq!(packet.to_le_bytes(), [0xA5, 0x02, 0x01, 0x04, 0x03, 0x08, 0x07, 0x06, 0x05]);
+    assert_eq!(packet.to_be_bytes(), [0xA5, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+    let packet = Packet::from_be_bytes(packet.to_be_bytes());
+    assert_eq!(packet.header.0.bits(), 0xA5);
+    assert_eq!(packet.header.1, 0x0102);
+    assert_eq!(packet.status.bits(), 0x0304);
+    assert_eq!(packet.crc, 0x0506_0708);
+    let packet = Packet::from_le_bytes([0xA5, 0x02, 0x01, 0x04, 0x03, 0x08, 0x07, 0x06, 0x05]);
+    assert_eq!(packet.header.1, 0x0102);
+    assert_eq!(packet.status.bits(), 0x0304);
+    assert_eq!(packet.crc, 0x0506_0708);
+}
@@ -1,10 +1,17 @@
 #![feature(proc_macro_hygiene)]
 #![no_implicit_prelude]
 
-use ::drone_core::{bitfield::Bitfield, reg, reg::prelude::*, token::Token};
+use ::drone_core::{
+    bitfield::Bitfield,
+    reg,
+    reg::{prelude::*, DynField, DynFieldMut, RegTransport},
+    token::Token,
+};
 use ::std::{
     assert_eq,
     mem::{size_of, size_of_val},
+    option::Option::None,
+    result::Result::{self, Ok},
 };
 
 reg! {
@@ -14,12 +21,14 @@ reg! {
         size => 0x20;
         reset => 0x410F_C241;
         traits => { RReg RoReg };
+        assert => { mask => 0xFFFF_FFFF };
         fields => {
             /// Implementer code assigned by ARM.
             IMPLEMENTER => {
                 offset => 24;
                 width => 8;
                 traits => { RRRegField RoRRegField };
+                assert => { mask => 0xFF00_0000 };
             };
             /// Variant number.
             VARIANT => {
@@ -38,6 +47,7 @@ reg! {
                 offset => 4;
                 width => 12;
                 traits => { RRRegField RoRRegField };
+                assert => { mask => 0x0000_FFF0; offset => 4; width => 12 };
             };
             /// Revision number.
             REVISION => {
@@ -119,6 +129,50 @@ reg! {
     };
 }
 
+reg! {
+    /// Data register.
+    pub USART1 DR => {
+        address => 0x4001_3804;
+        size => 0x20;
+        reset => 0x0000_0000;
+        read_side_effects => true;
+        traits => { RReg WReg };
+        fields => {
+            /// Data value.
+            DR => {
+                offset => 0;
+                width => 9;
+                traits => { RRRegField WWRegField };
+            };
+        };
+    };
+}
+
+reg! {
+    /// APB2 peripheral clock enable register.
+    pub RCC APB2ENR => {
+        address => 0x4002_1018;
+        size => 0x20;
+        reset => 0x0000_0000;
+        shared => true;
+        traits => { RReg WReg };
+        fields => {
+            /// I/O port A clock enable.
+            IOPAEN => {
+                offset => 2;
+                width => 1;
+                traits => { RRRegField WWRegField };
+            };
+            /// USART1 clock enable.
+            USART1EN => {
+                offset => 14;
+                width => 1;
+                traits => { RRRegField WWRegField };
+            };
+        };
+    };
+}
+
 reg::tokens! {
     /// Intermediate register tokens macro.
     pub macro reg_tokens_intermediate;
@@ -143,6 +197,11 @@ reg::tokens! {
         CCMR1_Input;
         !CCMR1_Output;
     }
+
+    /// Reset and clock control.
+    pub mod RCC {
+        APB2ENR;
+    }
 }
 
 reg_tokens! {
@@ -171,9 +230,63 @@ fn tokens() {
     assert_eq!(size_of_val(&reg.tim1_ccmr1_input), 0);
 }
 
+#[test]
+fn shared() {
+    let reg = unsafe { Regs::take() };
+    let rcc_apb2enr::Fields { iopaen, usart1en } = reg.rcc_apb2enr;
+    assert_eq!(size_of_val(&iopaen), 0);
+    assert_eq!(size_of_val(&usart1en), 0);
+}
+
 #[test]
 fn variants() {
     let input: tim1::Ccmr1Input<Srt> = unsafe { Token::take() };
     let output: tim1::Ccmr1Output<Srt> = input.into_tim1_ccmr1_output();
     let _input: tim1::Ccmr1Input<Srt> = output.into_tim1_ccmr1_input();
 }
+
+#[test]
+fn read_debug() {
+    assert_eq!(unsafe { usart1_dr::read_debug() }, None);
+}
+
+#[test]
+fn remote() {
+    struct Bus {
+        address: usize,
+        bits: u32,
+    }
+    impl RegTransport<u32> for Bus {
+        type Error = ();
+
+        fn read(&mut self, address: usize) -> Result<u32, ()> {
+            assert_eq!(address, self.address);
+            Ok(self.bits)
+        }
+
+        fn write(&mut self, address: usize, bits: u32) -> Result<(), ()> {
+            assert_eq!(address, self.address);
+            self.bits = bits;
+            Ok(())
+        }
+    }
+    let reg: tim1::Ccmr1Output<Srt> = unsafe { Token::take() };
+    let mut bus = Bus { address: 0x4001_0018, bits: 0x0000_0300 };
+    assert_eq!(reg.modify_via(&mut bus, |r| r.set_oc1pe().write_oc1m(0b110)), Ok(()));
+    assert_eq!(bus.bits, 0x0000_6B00);
+    assert_eq!(reg.load_via(&mut bus).map(|r| r.oc1m()), Ok(0b110));
+    assert_eq!(reg.store_via(&mut bus, |r| r.set_oc1ce()), Ok(()));
+    assert_eq!(bus.bits, 0x0000_8000);
+}
+
+#[test]
+fn dyn_fields() {
+    let reg = unsafe { Regs::take() };
+    let partno = DynField::new(&reg.scb_cpuid.partno);
+    assert_eq!((partno.address(), partno.offset(), partno.width()), (0xE000_ED00, 4, 12));
+    let mut output: tim1::Ccmr1Output<Urt> = unsafe { Token::take() };
+    let fields = [DynFieldMut::new(&mut output.oc1m), DynFieldMut::new(&mut output.oc1pe)];
+    assert_eq!((fields[0].offset(), fields[0].width()), (12, 3));
+    assert_eq!((fields[1].offset(), fields[1].width()), (11, 1));
+    assert_eq!(fields[1].as_dyn_field().address(), 0x4001_0018);
+}
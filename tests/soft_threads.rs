@@ -2,7 +2,7 @@
 
 use ::drone_core::{
     thr,
-    thr::{pending_size, SoftThrToken, SoftThread, ThrExec, PRIORITY_LEVELS},
+    thr::{pending_size, SoftThrToken, SoftThread, ThrExec, ThrToken, PRIORITY_LEVELS},
     token::Token,
 };
 use ::std::{
@@ -112,3 +112,35 @@ fn test_priorities() {
         assert_eq!(unsafe { &*Thr::pending().add(i) }.load(Ordering::Relaxed), 0);
     }
 }
+
+#[test]
+fn test_spawn() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; };
+    }
+    let Thrs { thr_0 } = unsafe { Thrs::take() };
+    let mut rx = thr_0.spawn(async { 42 });
+    assert_eq!(rx.try_recv(), ::std::result::Result::Ok(::std::option::Option::Some(42)));
+}
+
+#[cfg(feature = "thr-stats")]
+#[test]
+fn test_stats() {
+    thr::soft! {
+        thread => Thr {};
+        local => ThrLocal {};
+        index => Thrs;
+        threads => { thr_0; };
+    }
+    let Thrs { thr_0 } = unsafe { Thrs::take() };
+    assert_eq!(thr_0.stats().activations(), 0);
+    thr_0.set_pending();
+    thr_0.set_pending();
+    assert_eq!(thr_0.stats().activations(), 2);
+    assert_eq!(thr_0.stats().max_cycles(), 0);
+    thr_0.stats().reset();
+    assert_eq!(thr_0.stats().activations(), 0);
+}
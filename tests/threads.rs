@@ -3,17 +3,22 @@
 
 use ::drone_core::{
     fib, thr,
-    thr::{prelude::*, Thread},
+    thr::{prelude::*, ThrExec, Thread},
     token::Token,
 };
+use ::futures::task::{noop_waker_ref, waker, ArcWake};
 use ::std::{
     assert_eq,
     clone::Clone,
+    default::Default,
+    future::Future,
     ops::Drop,
+    pin::Pin,
     sync::{
-        atomic::{AtomicI8, Ordering::*},
+        atomic::{AtomicI8, AtomicUsize, Ordering::*},
         Arc,
     },
+    task::{Context, Poll, Waker},
 };
 
 thr::pool! {
@@ -39,6 +44,7 @@ thr::pool! {
         thr0;
         thr1;
         thr2;
+        thr3;
     }
 }
 
@@ -110,3 +116,83 @@ fn fiber_once() {
         assert_eq!(counter.load(Relaxed), -2);
     }
 }
+
+#[test]
+fn future_then() {
+    let thr = unsafe { Thr3::take() };
+    let mut future = thr.add_future_then(
+        fib::new(|| {
+            yield;
+            2
+        }),
+        |x| {
+            fib::new(move || {
+                yield;
+                x * 3
+            })
+        },
+    );
+    let mut cx = Context::from_waker(noop_waker_ref());
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+    thr.to_thr().fib_chain().drain();
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+    thr.to_thr().fib_chain().drain();
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+    thr.to_thr().fib_chain().drain();
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(6));
+}
+
+static THR1_WAKEUPS: AtomicUsize = AtomicUsize::new(0);
+
+struct Thr1Wakeup;
+
+impl ArcWake for Thr1Wakeup {
+    fn wake_by_ref(_arc_self: &Arc<Self>) {
+        THR1_WAKEUPS.fetch_add(1, Relaxed);
+    }
+}
+
+impl ThrExec for Thr1 {
+    fn wakeup(self) {
+        THR1_WAKEUPS.fetch_add(1, Relaxed);
+    }
+
+    fn waker(self) -> Waker {
+        waker(Arc::new(Thr1Wakeup))
+    }
+}
+
+#[test]
+fn future_donate() {
+    let thr = unsafe { Thr1::take() };
+    let mut future = thr.add_future_donate(fib::new(|| {
+        yield;
+        2
+    }));
+    let mut cx = Context::from_waker(noop_waker_ref());
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+    assert_eq!(THR1_WAKEUPS.load(Relaxed), 1);
+    thr.to_thr().fib_chain().drain();
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+    assert_eq!(THR1_WAKEUPS.load(Relaxed), 2);
+    thr.to_thr().fib_chain().drain();
+    assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(2));
+    assert_eq!(THR1_WAKEUPS.load(Relaxed), 2);
+}
+
+#[test]
+fn local_cell() {
+    #[derive(Default)]
+    struct Foo(AtomicI8);
+    #[derive(Default)]
+    struct Bar(AtomicI8);
+    let thr0 = unsafe { Thr0::take() };
+    let thr1 = unsafe { Thr1::take() };
+    thr0.local_cell::<Foo>().0.store(1, Relaxed);
+    thr0.local_cell::<Bar>().0.store(2, Relaxed);
+    thr1.local_cell::<Foo>().0.store(3, Relaxed);
+    assert_eq!(thr0.local_cell::<Foo>().0.load(Relaxed), 1);
+    assert_eq!(thr0.local_cell::<Bar>().0.load(Relaxed), 2);
+    assert_eq!(thr1.local_cell::<Foo>().0.load(Relaxed), 3);
+    assert_eq!(thr1.local_cell::<Bar>().0.load(Relaxed), 0);
+}